@@ -0,0 +1,66 @@
+use rocket::figment::providers::Env;
+use rocket::figment::Figment;
+use serde::Deserialize;
+use std::fmt;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    #[default]
+    Memory,
+    File,
+    Sqlite,
+}
+
+impl fmt::Display for StorageBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageBackend::Memory => write!(f, "memory"),
+            StorageBackend::File => write!(f, "file"),
+            StorageBackend::Sqlite => write!(f, "sqlite"),
+        }
+    }
+}
+
+// Sección `[storage]` de Rocket.toml
+#[derive(Debug, Clone, Deserialize)]
+pub struct StorageConfig {
+    #[serde(default)]
+    pub backend: StorageBackend,
+    #[serde(default = "default_data_dir")]
+    pub data_dir: PathBuf,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            backend: StorageBackend::default(),
+            data_dir: default_data_dir(),
+        }
+    }
+}
+
+fn default_data_dir() -> PathBuf {
+    PathBuf::from("data")
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AppConfig {
+    #[serde(default)]
+    storage: StorageConfig,
+}
+
+// Configuración de Rocket más las variables CENTRALVET_STORAGE y CENTRALVET_DATA_DIR,
+// que tienen prioridad sobre Rocket.toml y ROCKET_STORAGE.
+pub fn figment() -> Figment {
+    rocket::Config::figment()
+        .merge(Env::raw().only(&["CENTRALVET_STORAGE"]).map(|_| "storage.backend".into()))
+        .merge(Env::raw().only(&["CENTRALVET_DATA_DIR"]).map(|_| "storage.data_dir".into()))
+}
+
+pub fn storage_config(figment: &Figment) -> Result<StorageConfig, String> {
+    figment.extract::<AppConfig>()
+        .map(|config| config.storage)
+        .map_err(|e| format!("Configuración de almacenamiento inválida: {}", e))
+}
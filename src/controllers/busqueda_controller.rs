@@ -0,0 +1,34 @@
+use rocket::serde::json::Json;
+use rocket::State;
+use crate::error::DomainError;
+use crate::services::busqueda::{self, ResultadoBusqueda};
+use crate::controllers::cliente_controller::ClienteServiceType;
+use crate::controllers::mascota_controller::MascotaServiceType;
+
+const LIMITE_PREDETERMINADO: usize = 20;
+const LIMITE_MAXIMO: usize = 100;
+
+// Búsqueda por prefijo, sin distinguir mayúsculas ni acentos, sobre nombre,
+// apellido, teléfono y correo de los clientes y nombre y raza de las mascotas.
+#[get("/buscar?<q>&<limite>")]
+pub async fn buscar(
+    q: Option<String>,
+    limite: Option<usize>,
+    clientes: &State<ClienteServiceType>,
+    mascotas: &State<MascotaServiceType>
+) -> Result<Json<Vec<ResultadoBusqueda>>, DomainError> {
+    let q = q.unwrap_or_default();
+    let limite = limite.unwrap_or(LIMITE_PREDETERMINADO);
+    if !(1..=LIMITE_MAXIMO).contains(&limite) {
+        return Err(DomainError::BadRequest(format!(
+            "`limite` debe estar entre 1 y {}", LIMITE_MAXIMO
+        )));
+    }
+
+    // Orden de bloqueo: clientes, mascotas
+    let clientes = clientes.lock()?;
+    let mascotas = mascotas.lock()?;
+    let resultados = busqueda::buscar(&clientes, &mascotas, &q, limite)?;
+
+    Ok(Json(resultados))
+}
@@ -1,104 +1,262 @@
 use rocket::serde::json::Json;
 use rocket::State;
 use rocket::http::Status;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use uuid::Uuid;
+use validator::Validate;
+use crate::error::DomainError;
 use crate::models::Cliente;
 use crate::services::ClienteService;
-use crate::repositories::cliente_repository::InMemoryClienteRepository;
+use crate::services::cliente_service::CambiosCliente;
+use crate::services::integridad_referencial;
+use crate::services::eliminacion;
+use crate::repositories::cliente_repository::{ClienteRepository, FiltroClientes};
+use crate::controllers::clinica_controller::ClinicaServiceType;
+use crate::controllers::mascota_controller::MascotaServiceType;
+use crate::controllers::historia_clinica_controller::HistoriaClinicaServiceType;
+use crate::controllers::turno_controller::TurnoServiceType;
+use crate::controllers::vacunacion_controller::VacunacionServiceType;
+use crate::controllers::prescripcion_controller::PrescripcionServiceType;
+use crate::controllers::validacion::{self, Normalizable};
+use crate::controllers::paginacion::{Paginado, ParametrosLista};
+use crate::controllers::parche;
+use crate::controllers::usuario::Usuario;
+use crate::controllers::concurrencia::{ConEtag, IfMatch, IfNoneMatch};
 use std::sync::Mutex;
-use log::{error, info, warn};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize, Validate)]
+#[serde(default)]
 pub struct ClienteCreateDto {
+    #[validate(length(min = 1, max = 80))]
     pub nombre: String,
+    #[validate(length(min = 1, max = 80))]
     pub apellido: String,
+    #[validate(email)]
     pub correo: String,
+    #[validate(custom(function = "validacion::validar_telefono"))]
     pub telefono: String,
+    #[validate(length(min = 1, max = 200))]
     pub direccion: String,
+    #[validate(custom(function = "validacion::validar_uuid"))]
     pub id_clinica: String,
 }
 
-type ClienteServiceType = Mutex<ClienteService<InMemoryClienteRepository>>;
-
-#[get("/clientes")]
-pub async fn listar_clientes(service: &State<ClienteServiceType>) -> Result<Json<Vec<Cliente>>, Status> {
-    let clientes = service.lock()
-        .map_err(|_| Status::InternalServerError)?
-        .listar_clientes()
-        .into_iter()
-        .cloned()
-        .collect();
-    Ok(Json(clientes))
+impl Normalizable for ClienteCreateDto {
+    fn normalizar(&mut self) {
+        validacion::normalizar_texto(&mut self.nombre);
+        validacion::normalizar_texto(&mut self.apellido);
+        validacion::normalizar_correo(&mut self.correo);
+        validacion::normalizar_telefono(&mut self.telefono);
+        validacion::normalizar_texto(&mut self.direccion);
+        validacion::normalizar_texto(&mut self.id_clinica);
+    }
+}
+
+impl From<&Cliente> for ClienteCreateDto {
+    fn from(cliente: &Cliente) -> Self {
+        Self {
+            nombre: cliente.nombre.clone(),
+            apellido: cliente.apellido.clone(),
+            correo: cliente.correo.clone(),
+            telefono: cliente.telefono.clone(),
+            direccion: cliente.direccion.clone(),
+            id_clinica: cliente.id_clinica.to_string(),
+        }
+    }
+}
+
+pub type ClienteServiceType = Mutex<ClienteService<dyn ClienteRepository + Send>>;
+
+#[get("/clientes?<nombre>&<correo>&<id_clinica>&<archivados>&<lista..>")]
+pub async fn listar_clientes(
+    nombre: Option<String>,
+    correo: Option<String>,
+    id_clinica: Option<String>,
+    archivados: Option<bool>,
+    lista: ParametrosLista,
+    service: &State<ClienteServiceType>
+) -> Result<Paginado<Cliente>, DomainError> {
+    let consulta = lista.consulta(FiltroClientes {
+        id_clinica: id_clinica.as_deref().map(Uuid::parse_str).transpose()?,
+        nombre,
+        correo,
+        incluir_archivados: archivados.unwrap_or(false),
+    })?;
+
+    let clientes = service.lock()?
+        .listar_clientes(&consulta)?;
+    Ok(Paginado::new(clientes, &consulta))
 }
 
 #[get("/clientes/<id>")]
 pub async fn obtener_cliente(
     id: String,
+    condicion: IfNoneMatch,
     service: &State<ClienteServiceType>
-) -> Result<Json<Cliente>, Status> {
-    let uuid = Uuid::parse_str(&id)
-        .map_err(|err| {
-            error!("Error parsing UUID '{}': {}", id, err);
-            Status::BadRequest
-        })?;
+) -> Result<ConEtag<Cliente>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
     
-    service.lock()
-        .map_err(|_| Status::InternalServerError)?
-        .obtener_cliente(uuid)
-        .map(|cliente| Json(cliente.clone()))
-        .ok_or(Status::NotFound)
+    service.lock()?
+        .obtener_cliente(uuid)?
+        .map(|cliente| ConEtag::leer(cliente, &condicion))
+        .ok_or_else(|| DomainError::NotFound(format!("El cliente {} no existe", uuid)))
 }
 
 #[post("/clientes", data = "<cliente_dto>")]
 pub async fn crear_cliente(
     cliente_dto: Json<ClienteCreateDto>,
-    service: &State<ClienteServiceType>
-) -> Result<Json<Cliente>, Status> {
-    let id_clinica = Uuid::parse_str(&cliente_dto.id_clinica)
-        .map_err(|_| Status::BadRequest)?;
+    usuario: Usuario,
+    service: &State<ClienteServiceType>,
+    clinicas: &State<ClinicaServiceType>
+) -> Result<ConEtag<Cliente>, DomainError> {
+    let cliente_dto = validacion::validar(cliente_dto)?;
+    let id_clinica = Uuid::parse_str(&cliente_dto.id_clinica)?;
+
+    // La clínica queda bloqueada hasta guardar el cliente
+    let clinicas = clinicas.lock()?;
+    integridad_referencial::validar_clinica(&clinicas, id_clinica)?;
 
-    let result = service.lock()
-        .map_err(|_| Status::InternalServerError)?
+    let cliente = service.lock()?
         .crear_cliente(
-            cliente_dto.nombre.clone(),
-            cliente_dto.apellido.clone(),
-            cliente_dto.correo.clone(),
-            cliente_dto.telefono.clone(),
-            cliente_dto.direccion.clone(),
+            cliente_dto.nombre,
+            cliente_dto.apellido,
+            cliente_dto.correo,
+            cliente_dto.telefono,
+            cliente_dto.direccion,
             id_clinica,
-        );
+            usuario.autor(),
+        )?;
 
-    match result {
-        Ok(cliente) => Ok(Json(cliente)),
-        Err(_) => Err(Status::InternalServerError),
-    }
+    Ok(ConEtag::new(cliente))
 }
 
 #[put("/clientes/<id>", data = "<cliente_dto>")]
 pub async fn actualizar_cliente(
     id: String,
     cliente_dto: Json<ClienteCreateDto>,
-    service: &State<ClienteServiceType>
-) -> Result<Json<Cliente>, Status> {
-    let uuid = Uuid::parse_str(&id).map_err(|_| Status::BadRequest)?;
-    let id_clinica = Uuid::parse_str(&cliente_dto.id_clinica)
-        .map_err(|_| Status::BadRequest)?;
+    if_match: IfMatch,
+    usuario: Usuario,
+    service: &State<ClienteServiceType>,
+    clinicas: &State<ClinicaServiceType>
+) -> Result<ConEtag<Cliente>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+    let esperada = if_match.version_esperada()?;
+    let cliente_dto = validacion::validar(cliente_dto)?;
+    let id_clinica = Uuid::parse_str(&cliente_dto.id_clinica)?;
 
-    let result = service.lock()
-        .map_err(|_| Status::InternalServerError)?
+    let clinicas = clinicas.lock()?;
+    integridad_referencial::validar_clinica(&clinicas, id_clinica)?;
+
+    let cliente = service.lock()?
         .actualizar_cliente(
             uuid,
-            cliente_dto.nombre.clone(),
-            cliente_dto.apellido.clone(),
-            cliente_dto.correo.clone(),
-            cliente_dto.telefono.clone(),
-            cliente_dto.direccion.clone(),
+            cliente_dto.nombre,
+            cliente_dto.apellido,
+            cliente_dto.correo,
+            cliente_dto.telefono,
+            cliente_dto.direccion,
             id_clinica,
-        );
+            &esperada,
+            usuario.autor(),
+        )?;
+
+    Ok(ConEtag::new(cliente))
+}
+
+// JSON Merge Patch: solo se modifican los campos enviados
+#[patch("/clientes/<id>", data = "<parche>")]
+pub async fn modificar_cliente(
+    id: String,
+    parche: Json<Value>,
+    if_match: IfMatch,
+    usuario: Usuario,
+    service: &State<ClienteServiceType>,
+    clinicas: &State<ClinicaServiceType>
+) -> Result<ConEtag<Cliente>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+    let esperada = if_match.version_esperada()?;
+
+    // Orden de bloqueo: clínicas, clientes
+    let clinicas = clinicas.lock()?;
+    let mut clientes = service.lock()?;
+    let actual = clientes.obtener_cliente(uuid)?
+        .ok_or_else(|| DomainError::NotFound(format!("El cliente {} no existe", uuid)))?;
+    let (dto, campos) = parche::aplicar(&ClienteCreateDto::from(&actual), parche.into_inner())?;
 
-    match result {
-        Ok(cliente) => Ok(Json(cliente)),
-        Err(_) => Err(Status::NotFound),
+    let id_clinica = campos.tomar("id_clinica", Uuid::parse_str(&dto.id_clinica)?);
+    if let Some(id_clinica) = id_clinica {
+        integridad_referencial::validar_clinica(&clinicas, id_clinica)?;
     }
+
+    let cliente = clientes.modificar_cliente(uuid, CambiosCliente {
+        nombre: campos.tomar("nombre", dto.nombre),
+        apellido: campos.tomar("apellido", dto.apellido),
+        correo: campos.tomar("correo", dto.correo),
+        telefono: campos.tomar("telefono", dto.telefono),
+        direccion: campos.tomar("direccion", dto.direccion),
+        id_clinica,
+    }, &esperada, usuario.autor())?;
+
+    Ok(ConEtag::new(cliente))
+}
+
+// Por defecto archiva el cliente; con `permanente=true` lo elimina, y con
+// `cascada=true` también a sus turnos, mascotas, historias y entradas.
+#[delete("/clientes/<id>?<permanente>&<cascada>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn eliminar_cliente(
+    id: String,
+    permanente: Option<bool>,
+    cascada: Option<bool>,
+    if_match: IfMatch,
+    usuario: Usuario,
+    service: &State<ClienteServiceType>,
+    mascotas: &State<MascotaServiceType>,
+    historias: &State<HistoriaClinicaServiceType>,
+    turnos: &State<TurnoServiceType>,
+    vacunaciones: &State<VacunacionServiceType>,
+    prescripciones: &State<PrescripcionServiceType>
+) -> Result<Status, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+    let esperada = if_match.version_esperada()?;
+
+    let mut clientes = service.lock()?;
+
+    if !permanente.unwrap_or(false) {
+        return clientes.archivar_cliente(uuid, &esperada, usuario.autor())
+            .map(|_| Status::NoContent);
+    }
+
+    let mut mascotas = mascotas.lock()?;
+    let mut historias = historias.lock()?;
+    let mut turnos = turnos.lock()?;
+    let mut vacunaciones = vacunaciones.lock()?;
+    let mut prescripciones = prescripciones.lock()?;
+
+    eliminacion::eliminar_cliente(
+        &mut clientes,
+        &mut mascotas,
+        &mut historias,
+        &mut turnos,
+        &mut vacunaciones,
+        &mut prescripciones,
+        uuid,
+        cascada.unwrap_or(false),
+        &esperada,
+    )?;
+    Ok(Status::NoContent)
+}
+
+#[post("/clientes/<id>/restaurar")]
+pub async fn restaurar_cliente(
+    id: String,
+    usuario: Usuario,
+    service: &State<ClienteServiceType>
+) -> Result<ConEtag<Cliente>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+
+    service.lock()?
+        .restaurar_cliente(uuid, usuario.autor())
+        .map(ConEtag::new)
 }
@@ -1,106 +1,359 @@
 use rocket::serde::json::Json;
 use rocket::State;
 use rocket::http::Status;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use uuid::Uuid;
-use crate::models::{Cliente, Clinica};
+use validator::Validate;
+use crate::error::DomainError;
+use crate::models::{Cliente, Clinica, Personal};
 use crate::services::ClinicaService;
-use crate::repositories::clinica_repository::InMemoryClinicaRepository;
+use crate::services::clinica_service::CambiosClinica;
+use crate::services::eliminacion;
+use crate::services::turno_service::Agenda;
+use crate::services::vencimientos::{self, VacunaPendiente};
+use crate::repositories::clinica_repository::{ClinicaRepository, FiltroClinicas};
+use crate::repositories::cliente_repository::FiltroClientes;
+use crate::repositories::personal_repository::FiltroPersonal;
+use crate::controllers::cliente_controller::ClienteServiceType;
+use crate::controllers::mascota_controller::MascotaServiceType;
+use crate::controllers::historia_clinica_controller::HistoriaClinicaServiceType;
+use crate::controllers::turno_controller::TurnoServiceType;
+use crate::controllers::vacunacion_controller::VacunacionServiceType;
+use crate::controllers::prescripcion_controller::PrescripcionServiceType;
+use chrono::{NaiveDate, Utc};
+use crate::controllers::personal_controller::{self, PersonalServiceType};
+use crate::controllers::validacion::{self, Normalizable};
+use crate::controllers::paginacion::{Paginado, ParametrosLista};
+use crate::controllers::parche;
+use crate::controllers::usuario::Usuario;
+use crate::controllers::concurrencia::{ConEtag, IfMatch, IfNoneMatch};
 use std::sync::Mutex;
 
-// DTO para crear una clínica
-#[derive(Debug, Deserialize)]
+// DTO para crear una clínica. Los campos ausentes se leen vacíos para que la
+// validación los informe como obligatorios.
+#[derive(Debug, Default, Deserialize, Serialize, Validate)]
+#[serde(default)]
 pub struct ClinicaCreateDto {
+    #[validate(length(min = 1, max = 120))]
     pub nombre: String,
+    #[validate(length(min = 1, max = 200))]
     pub direccion: String,
+    #[validate(custom(function = "validacion::validar_telefono"))]
     pub telefono: String,
+    #[validate(email)]
     pub correo: String,
 }
 
-type ClinicaServiceType = Mutex<ClinicaService<InMemoryClinicaRepository>>;
-
-#[get("/clinicas")]
-pub async fn listar_clinicas(service: &State<ClinicaServiceType>) -> Result<Json<Vec<Clinica>>, Status> {
-    let clinicas = service.lock()
-        .map_err(|_| Status::InternalServerError)?
-        .listar_clinicas()
-        .into_iter()
-        .cloned()
-        .collect();
-    Ok(Json(clinicas))
+impl Normalizable for ClinicaCreateDto {
+    fn normalizar(&mut self) {
+        validacion::normalizar_texto(&mut self.nombre);
+        validacion::normalizar_texto(&mut self.direccion);
+        validacion::normalizar_telefono(&mut self.telefono);
+        validacion::normalizar_correo(&mut self.correo);
+    }
+}
+
+impl From<&Clinica> for ClinicaCreateDto {
+    fn from(clinica: &Clinica) -> Self {
+        Self {
+            nombre: clinica.nombre.clone(),
+            direccion: clinica.direccion.clone(),
+            telefono: clinica.telefono.clone(),
+            correo: clinica.correo.clone(),
+        }
+    }
+}
+
+pub type ClinicaServiceType = Mutex<ClinicaService<dyn ClinicaRepository + Send>>;
+
+#[get("/clinicas?<nombre>&<archivadas>&<lista..>")]
+pub async fn listar_clinicas(
+    nombre: Option<String>,
+    archivadas: Option<bool>,
+    lista: ParametrosLista,
+    service: &State<ClinicaServiceType>
+) -> Result<Paginado<Clinica>, DomainError> {
+    let consulta = lista.consulta(FiltroClinicas {
+        nombre,
+        incluir_archivadas: archivadas.unwrap_or(false),
+    })?;
+
+    let clinicas = service.lock()?
+        .listar_clinicas(&consulta)?;
+    Ok(Paginado::new(clinicas, &consulta))
 }
 
 #[get("/clinicas/<id>")]
 pub async fn obtener_clinica(
     id: String,
+    condicion: IfNoneMatch,
     service: &State<ClinicaServiceType>
-) -> Result<Json<Clinica>, Status> {
-    let uuid = Uuid::parse_str(&id).map_err(|_| Status::BadRequest)?;
+) -> Result<ConEtag<Clinica>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
     
-    service.lock()
-        .map_err(|_| Status::InternalServerError)?
-        .obtener_clinica(uuid)
-        .map(|clinica| Json(clinica.clone()))
-        .ok_or(Status::NotFound)
+    service.lock()?
+        .obtener_clinica(uuid)?
+        .map(|clinica| ConEtag::leer(clinica, &condicion))
+        .ok_or_else(|| DomainError::NotFound(format!("La clínica {} no existe", uuid)))
 }
 
 #[post("/clinicas", data = "<clinica_dto>")]
 pub async fn crear_clinica(
     clinica_dto: Json<ClinicaCreateDto>,
+    usuario: Usuario,
     service: &State<ClinicaServiceType>
-) -> Result<Json<Clinica>, Status> {
-    let result = service.lock()
-        .map_err(|_| Status::InternalServerError)?
+) -> Result<ConEtag<Clinica>, DomainError> {
+    let clinica_dto = validacion::validar(clinica_dto)?;
+
+    let clinica = service.lock()?
         .crear_clinica(
-            clinica_dto.nombre.clone(),
-            clinica_dto.direccion.clone(),
-            clinica_dto.telefono.clone(),
-            clinica_dto.correo.clone(),
-        );
-
-    match result {
-        Ok(clinica) => Ok(Json(clinica)),
-        Err(_) => Err(Status::InternalServerError),
-    }
+            clinica_dto.nombre,
+            clinica_dto.direccion,
+            clinica_dto.telefono,
+            clinica_dto.correo,
+            usuario.autor(),
+        )?;
+
+    Ok(ConEtag::new(clinica))
 }
 
-#[get("/clinicas/<id>/clientes")]
+#[get("/clinicas/<id>/clientes?<nombre>&<correo>&<archivados>&<lista..>")]
 pub async fn listar_clientes_clinica(
     id: String,
-    service: &State<ClinicaServiceType>
-) -> Result<Json<Vec<Cliente>>, Status> {
-    let uuid = Uuid::parse_str(&id).map_err(|_| Status::BadRequest)?;
-    
-    let clientes = service.lock()
-        .map_err(|_| Status::InternalServerError)?
-        .obtener_clientes_clinica(uuid)
-        .into_iter()
-        .cloned()
-        .collect();
-    
-    Ok(Json(clientes))
+    nombre: Option<String>,
+    correo: Option<String>,
+    archivados: Option<bool>,
+    lista: ParametrosLista,
+    service: &State<ClinicaServiceType>,
+    clientes: &State<ClienteServiceType>
+) -> Result<Paginado<Cliente>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+    let consulta = lista.consulta(FiltroClientes {
+        id_clinica: Some(uuid),
+        nombre,
+        correo,
+        incluir_archivados: archivados.unwrap_or(false),
+    })?;
+
+    let clinicas = service.lock()?;
+    if clinicas.obtener_clinica(uuid)?.is_none() {
+        return Err(DomainError::NotFound(format!("La clínica {} no existe", uuid)));
+    }
+
+    let clientes = clientes.lock()?
+        .listar_clientes(&consulta)?;
+
+    Ok(Paginado::new(clientes, &consulta))
+}
+
+#[get("/clinicas/<id>/personal?<rol>&<especialidad>&<archivados>&<lista..>")]
+pub async fn listar_personal_clinica(
+    id: String,
+    rol: Option<String>,
+    especialidad: Option<String>,
+    archivados: Option<bool>,
+    lista: ParametrosLista,
+    service: &State<ClinicaServiceType>,
+    personal: &State<PersonalServiceType>
+) -> Result<Paginado<Personal>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+    let consulta = lista.consulta(FiltroPersonal {
+        id_clinica: Some(uuid),
+        rol: personal_controller::leer_rol(rol.as_deref())?,
+        nombre: None,
+        especialidad,
+        incluir_archivados: archivados.unwrap_or(false),
+    })?;
+
+    let clinicas = service.lock()?;
+    if clinicas.obtener_clinica(uuid)?.is_none() {
+        return Err(DomainError::NotFound(format!("La clínica {} no existe", uuid)));
+    }
+
+    let personal = personal.lock()?
+        .listar_personal(&consulta)?;
+
+    Ok(Paginado::new(personal, &consulta))
+}
+
+// Agenda del día (UTC, hoy si falta `fecha`); con `cancelados=true` incluye
+// también los turnos cancelados y las ausencias
+#[get("/clinicas/<id>/agenda?<fecha>&<id_veterinario>&<cancelados>")]
+pub async fn obtener_agenda_clinica(
+    id: String,
+    fecha: Option<String>,
+    id_veterinario: Option<String>,
+    cancelados: Option<bool>,
+    service: &State<ClinicaServiceType>,
+    turnos: &State<TurnoServiceType>
+) -> Result<Json<Agenda>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+    let fecha = match fecha.as_deref() {
+        Some(valor) => NaiveDate::parse_from_str(valor, "%Y-%m-%d")
+            .map_err(|_| DomainError::BadRequest("`fecha` debe tener el formato AAAA-MM-DD".to_string()))?,
+        None => Utc::now().date_naive(),
+    };
+    let id_veterinario = id_veterinario.as_deref().map(Uuid::parse_str).transpose()?;
+
+    let clinicas = service.lock()?;
+    if clinicas.obtener_clinica(uuid)?.is_none() {
+        return Err(DomainError::NotFound(format!("La clínica {} no existe", uuid)));
+    }
+
+    let agenda = turnos.lock()?
+        .agenda(uuid, fecha, id_veterinario, cancelados.unwrap_or(false))?;
+    Ok(Json(agenda))
+}
+
+// Vacunas de las mascotas de la clínica que vencen en los próximos `dias`
+// días (30 si falta), incluidas las ya vencidas, ordenadas por vencimiento
+#[get("/clinicas/<id>/vacunas-pendientes?<dias>")]
+pub async fn listar_vacunas_pendientes_clinica(
+    id: String,
+    dias: Option<u32>,
+    service: &State<ClinicaServiceType>,
+    clientes: &State<ClienteServiceType>,
+    mascotas: &State<MascotaServiceType>,
+    vacunaciones: &State<VacunacionServiceType>
+) -> Result<Json<Vec<VacunaPendiente>>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+    let dias = dias.unwrap_or(30);
+    if dias > 365 {
+        return Err(DomainError::BadRequest("`dias` debe estar entre 0 y 365".to_string()));
+    }
+
+    // Orden de bloqueo: clínicas, clientes, mascotas, vacunaciones
+    let clinicas = service.lock()?;
+    if clinicas.obtener_clinica(uuid)?.is_none() {
+        return Err(DomainError::NotFound(format!("La clínica {} no existe", uuid)));
+    }
+    let clientes = clientes.lock()?;
+    let mascotas = mascotas.lock()?;
+    let vacunaciones = vacunaciones.lock()?;
+
+    let pendientes = vencimientos::vacunas_pendientes(
+        &clientes, &mascotas, &vacunaciones, uuid, Utc::now().date_naive(), dias,
+    )?;
+    Ok(Json(pendientes))
 }
 
 #[put("/clinicas/<id>", data = "<clinica_dto>")]
 pub async fn actualizar_clinica(
     id: &str,
     clinica_dto: Json<ClinicaCreateDto>,
+    if_match: IfMatch,
+    usuario: Usuario,
     service: &State<ClinicaServiceType>
-) -> Result<Json<Clinica>, Status> {
-    let uuid = Uuid::parse_str(&id).map_err(|_| Status::BadRequest)?;
-    
-    let result = service.lock()
-        .map_err(|_| Status::InternalServerError)?
+) -> Result<ConEtag<Clinica>, DomainError> {
+    let uuid = Uuid::parse_str(id)?;
+    let esperada = if_match.version_esperada()?;
+    let clinica_dto = validacion::validar(clinica_dto)?;
+
+    let clinica = service.lock()?
         .actualizar_clinica(
             uuid,
-            clinica_dto.nombre.clone(),
-            clinica_dto.direccion.clone(),
-            clinica_dto.telefono.clone(),
-            clinica_dto.correo.clone(),
-        );
-
-    match result {
-        Ok(clinica) => Ok(Json(clinica)),
-        Err(_) => Err(Status::NotFound),
+            clinica_dto.nombre,
+            clinica_dto.direccion,
+            clinica_dto.telefono,
+            clinica_dto.correo,
+            &esperada,
+            usuario.autor(),
+        )?;
+
+    Ok(ConEtag::new(clinica))
+}
+
+// JSON Merge Patch: solo se modifican los campos enviados
+#[patch("/clinicas/<id>", data = "<parche>")]
+pub async fn modificar_clinica(
+    id: &str,
+    parche: Json<Value>,
+    if_match: IfMatch,
+    usuario: Usuario,
+    service: &State<ClinicaServiceType>
+) -> Result<ConEtag<Clinica>, DomainError> {
+    let uuid = Uuid::parse_str(id)?;
+    let esperada = if_match.version_esperada()?;
+
+    let mut clinicas = service.lock()?;
+    let actual = clinicas.obtener_clinica(uuid)?
+        .ok_or_else(|| DomainError::NotFound(format!("La clínica {} no existe", uuid)))?;
+    let (dto, campos) = parche::aplicar(&ClinicaCreateDto::from(&actual), parche.into_inner())?;
+
+    let clinica = clinicas.modificar_clinica(uuid, CambiosClinica {
+        nombre: campos.tomar("nombre", dto.nombre),
+        direccion: campos.tomar("direccion", dto.direccion),
+        telefono: campos.tomar("telefono", dto.telefono),
+        correo: campos.tomar("correo", dto.correo),
+    }, &esperada, usuario.autor())?;
+
+    Ok(ConEtag::new(clinica))
+}
+
+// Por defecto archiva la clínica; con `permanente=true` la elimina, y con
+// `cascada=true` también a sus clientes, turnos, mascotas, historias y
+// entradas. El personal no se elimina: solo deja de figurar en la clínica.
+#[delete("/clinicas/<id>?<permanente>&<cascada>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn eliminar_clinica(
+    id: &str,
+    permanente: Option<bool>,
+    cascada: Option<bool>,
+    if_match: IfMatch,
+    usuario: Usuario,
+    service: &State<ClinicaServiceType>,
+    personal: &State<PersonalServiceType>,
+    clientes: &State<ClienteServiceType>,
+    mascotas: &State<MascotaServiceType>,
+    historias: &State<HistoriaClinicaServiceType>,
+    turnos: &State<TurnoServiceType>,
+    vacunaciones: &State<VacunacionServiceType>,
+    prescripciones: &State<PrescripcionServiceType>
+) -> Result<Status, DomainError> {
+    let uuid = Uuid::parse_str(id)?;
+    let esperada = if_match.version_esperada()?;
+
+    let mut clinicas = service.lock()?;
+
+    if !permanente.unwrap_or(false) {
+        return clinicas.archivar_clinica(uuid, &esperada, usuario.autor())
+            .map(|_| Status::NoContent);
     }
+
+    let mut personal = personal.lock()?;
+    let mut clientes = clientes.lock()?;
+    let mut mascotas = mascotas.lock()?;
+    let mut historias = historias.lock()?;
+    let mut turnos = turnos.lock()?;
+    let mut vacunaciones = vacunaciones.lock()?;
+    let mut prescripciones = prescripciones.lock()?;
+
+    eliminacion::eliminar_clinica(
+        &mut clinicas,
+        &mut personal,
+        &mut clientes,
+        &mut mascotas,
+        &mut historias,
+        &mut turnos,
+        &mut vacunaciones,
+        &mut prescripciones,
+        uuid,
+        cascada.unwrap_or(false),
+        &esperada,
+    )?;
+    Ok(Status::NoContent)
+}
+
+#[post("/clinicas/<id>/restaurar")]
+pub async fn restaurar_clinica(
+    id: &str,
+    usuario: Usuario,
+    service: &State<ClinicaServiceType>
+) -> Result<ConEtag<Clinica>, DomainError> {
+    let uuid = Uuid::parse_str(id)?;
+
+    service.lock()?
+        .restaurar_clinica(uuid, usuario.autor())
+        .map(ConEtag::new)
 }
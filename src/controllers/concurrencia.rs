@@ -0,0 +1,200 @@
+use rocket::http::{Header, Status};
+use rocket::request::{self, FromRequest, Request};
+use rocket::response::{self, Responder, Response};
+use rocket::serde::json::Json;
+use serde::Serialize;
+use crate::error::DomainError;
+use crate::models::Versionado;
+use crate::services::concurrencia::VersionEsperada;
+
+// ETags y precondiciones HTTP sobre la versión de cada entidad. El ETag de una
+// entidad es su versión entre comillas (`"3"`); si la respuesta incluye datos
+// de otra entidad, lleva también la versión de esa (`"3.5"`).
+
+fn etag<T: Versionado>(entidad: &T) -> String {
+    match entidad.version_incluida() {
+        Some(incluida) => format!("\"{}.{}\"", entidad.version(), incluida),
+        None => format!("\"{}\"", entidad.version()),
+    }
+}
+
+// ETags de una lista (`"3", W/"4"`), sin la marca de débil
+fn etiquetas(valor: &str) -> impl Iterator<Item = &str> {
+    valor.split(',').map(|etiqueta| {
+        let etiqueta = etiqueta.trim();
+        etiqueta.strip_prefix("W/").unwrap_or(etiqueta)
+    })
+}
+
+// Versiones de la entidad en una lista de ETags; las que no son nuestras se
+// ignoran. Las precondiciones de escritura solo miran la entidad que se modifica.
+fn versiones(valor: &str) -> Vec<u64> {
+    etiquetas(valor)
+        .filter_map(|etiqueta| {
+            let etiqueta = etiqueta.strip_prefix('"')?.strip_suffix('"')?;
+            etiqueta.split('.').next()?.parse().ok()
+        })
+        .collect()
+}
+
+// Cabecera `If-Match`, obligatoria en PUT, PATCH y DELETE
+pub struct IfMatch(Option<String>);
+
+impl IfMatch {
+    pub fn version_esperada(&self) -> Result<VersionEsperada, DomainError> {
+        match self.0.as_deref().map(str::trim) {
+            None => Err(DomainError::PreconditionRequired(
+                "Se requiere la cabecera If-Match con el ETag de la última lectura".to_string(),
+            )),
+            Some("*") => Ok(VersionEsperada::Cualquiera),
+            Some(valor) => Ok(VersionEsperada::Alguna(versiones(valor))),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IfMatch {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        request::Outcome::Success(IfMatch(req.headers().get_one("If-Match").map(str::to_string)))
+    }
+}
+
+// Cabecera `If-None-Match` de las lecturas condicionales
+pub struct IfNoneMatch(Option<String>);
+
+impl IfNoneMatch {
+    fn coincide(&self, etag: &str) -> bool {
+        match self.0.as_deref().map(str::trim) {
+            None => false,
+            Some("*") => true,
+            Some(valor) => etiquetas(valor).any(|etiqueta| etiqueta == etag),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IfNoneMatch {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        request::Outcome::Success(IfNoneMatch(req.headers().get_one("If-None-Match").map(str::to_string)))
+    }
+}
+
+// Entidad serializada como JSON con su `ETag`; responde `304 Not Modified` sin
+// cuerpo si el cliente ya tiene esa versión.
+pub struct ConEtag<T> {
+    status: Status,
+    etag: String,
+    entidad: Option<T>,
+}
+
+impl<T: Versionado> ConEtag<T> {
+    pub fn new(entidad: T) -> Self {
+        Self::con_status(Status::Ok, entidad)
+    }
+
+    pub fn con_status(status: Status, entidad: T) -> Self {
+        Self { status, etag: etag(&entidad), entidad: Some(entidad) }
+    }
+
+    pub fn leer(entidad: T, condicion: &IfNoneMatch) -> Self {
+        let etag = etag(&entidad);
+        if condicion.coincide(&etag) {
+            Self { status: Status::NotModified, etag, entidad: None }
+        } else {
+            Self::new(entidad)
+        }
+    }
+}
+
+impl<'r, T: Serialize> Responder<'r, 'static> for ConEtag<T> {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let mut respuesta = match self.entidad {
+            Some(entidad) => Json(entidad).respond_to(req)?,
+            None => Response::new(),
+        };
+        respuesta.set_status(self.status);
+        respuesta.set_header(Header::new("ETag", self.etag));
+        Ok(respuesta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::HistoriaClinica;
+    use crate::services::alertas_clinicas::HistoriaClinicaConAlertas;
+    use uuid::Uuid;
+
+    fn con_alertas(historia: &HistoriaClinica, version_mascota: u64) -> HistoriaClinicaConAlertas {
+        HistoriaClinicaConAlertas {
+            historia: historia.clone(),
+            alertas: Vec::new(),
+            version_mascota: Some(version_mascota),
+        }
+    }
+
+    fn si_no_coincide(valor: &str) -> IfNoneMatch {
+        IfNoneMatch(Some(valor.to_string()))
+    }
+
+    fn si_coincide(valor: Option<&str>) -> IfMatch {
+        IfMatch(valor.map(str::to_string))
+    }
+
+    // Un cambio en las alergias de la mascota invalida la historia que el cliente tenía
+    #[test]
+    fn el_etag_de_la_historia_cambia_con_la_mascota() {
+        let historia = HistoriaClinica::new(Uuid::new_v4(), Uuid::new_v4(), None);
+        let leida = ConEtag::new(con_alertas(&historia, 2));
+        assert_eq!(leida.etag, format!("\"{}.2\"", historia.version));
+
+        let sin_cambios = ConEtag::leer(con_alertas(&historia, 2), &si_no_coincide(&leida.etag));
+        assert_eq!(sin_cambios.status, Status::NotModified);
+        assert!(sin_cambios.entidad.is_none());
+
+        let mascota_modificada = ConEtag::leer(con_alertas(&historia, 3), &si_no_coincide(&leida.etag));
+        assert_eq!(mascota_modificada.status, Status::Ok);
+        assert!(mascota_modificada.entidad.is_some());
+    }
+
+    // El ETag combinado sirve como precondición para modificar la historia
+    #[test]
+    fn if_match_toma_la_version_de_la_historia() {
+        let esperada = IfMatch(Some("\"4.2\"".to_string())).version_esperada().unwrap();
+        assert!(esperada.comprobar("La historia clínica", 4).is_ok());
+        assert!(matches!(esperada.comprobar("La historia clínica", 5), Err(DomainError::PreconditionFailed(_))));
+    }
+
+    #[test]
+    fn sin_if_match_responde_428() {
+        let error = si_coincide(None).version_esperada().unwrap_err();
+        assert!(matches!(error, DomainError::PreconditionRequired(_)));
+        assert_eq!(error.status(), Status::PreconditionRequired);
+    }
+
+    #[test]
+    fn un_etag_desactualizado_responde_412() {
+        let esperada = si_coincide(Some("\"3\"")).version_esperada().unwrap();
+        assert!(esperada.comprobar("El cliente", 3).is_ok());
+
+        let error = esperada.comprobar("El cliente", 4).unwrap_err();
+        assert_eq!(error.status(), Status::PreconditionFailed);
+    }
+
+    // Acepta cualquiera de las versiones de la lista, débiles o no, e ignora las
+    // etiquetas que no son nuestras
+    #[test]
+    fn if_match_acepta_una_lista_de_etags() {
+        let esperada = si_coincide(Some(" \"2\", W/\"4\", \"otra\" ")).version_esperada().unwrap();
+        assert!(esperada.comprobar("El cliente", 2).is_ok());
+        assert!(esperada.comprobar("El cliente", 4).is_ok());
+        assert!(esperada.comprobar("El cliente", 3).is_err());
+
+        let cualquiera = si_coincide(Some("*")).version_esperada().unwrap();
+        assert!(cualquiera.comprobar("El cliente", 7).is_ok());
+    }
+}
@@ -0,0 +1,96 @@
+use rocket::http::{ContentType, Status};
+use rocket::request::Request;
+use rocket::response::{self, Responder, Response};
+use serde::Serialize;
+use std::io::Cursor;
+use log::{error, warn};
+use crate::error::{DomainError, FieldError};
+
+// Cuerpo de error según RFC 7807 (`application/problem+json`)
+#[derive(Debug, Serialize)]
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    pub tipo: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    pub instance: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errores: Vec<FieldError>,
+}
+
+impl ProblemDetails {
+    pub fn new(status: Status, detail: String, instance: String) -> Self {
+        Self {
+            tipo: "about:blank".to_string(),
+            title: status.reason().unwrap_or("Error").to_string(),
+            status: status.code,
+            detail,
+            instance,
+            errores: Vec::new(),
+        }
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ProblemDetails {
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
+        let status = Status::from_code(self.status).unwrap_or(Status::InternalServerError);
+        let body = serde_json::to_string(&self).map_err(|_| Status::InternalServerError)?;
+
+        Response::build()
+            .status(status)
+            .header(ContentType::new("application", "problem+json"))
+            .sized_body(body.len(), Cursor::new(body))
+            .ok()
+    }
+}
+
+impl DomainError {
+    pub fn status(&self) -> Status {
+        match self {
+            DomainError::BadRequest(_) => Status::BadRequest,
+            DomainError::NotFound(_) => Status::NotFound,
+            DomainError::Validation(_) | DomainError::InvalidFields(_) => Status::UnprocessableEntity,
+            DomainError::Conflict(_) => Status::Conflict,
+            DomainError::PreconditionFailed(_) => Status::PreconditionFailed,
+            DomainError::PreconditionRequired(_) => Status::PreconditionRequired,
+            DomainError::Storage(_) | DomainError::Internal(_) => Status::InternalServerError,
+        }
+    }
+}
+
+impl<'r> Responder<'r, 'static> for DomainError {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let status = self.status();
+        let detail = match &self {
+            // Los detalles internos no se exponen al cliente
+            DomainError::Storage(_) | DomainError::Internal(_) => {
+                error!("{} {}: {}", req.method(), req.uri(), self);
+                "Error interno del servidor".to_string()
+            }
+            _ => {
+                warn!("{} {} rechazada ({}): {}", req.method(), req.uri(), status.code, self);
+                self.to_string()
+            }
+        };
+
+        let mut problema = ProblemDetails::new(status, detail, req.uri().path().to_string());
+        if let DomainError::InvalidFields(campos) = self {
+            problema.errores = campos;
+        }
+        problema.respond_to(req)
+    }
+}
+
+// Errores que Rocket produce antes de llegar a un handler (ruta inexistente,
+// cuerpo JSON inválido, ...) también se devuelven como problem+json.
+#[catch(default)]
+pub fn problema_por_defecto(status: Status, req: &Request) -> ProblemDetails {
+    let detail = match status.code {
+        404 => "El recurso solicitado no existe",
+        400 => "La solicitud no es válida",
+        422 => "El cuerpo de la solicitud no es válido",
+        _ => status.reason().unwrap_or("Error"),
+    };
+    ProblemDetails::new(status, detail.to_string(), req.uri().path().to_string())
+}
@@ -0,0 +1,208 @@
+use rocket::serde::json::Json;
+use rocket::State;
+use rocket::http::Status;
+use serde::Deserialize;
+use uuid::Uuid;
+use validator::Validate;
+use crate::error::DomainError;
+use crate::models::Especie;
+use crate::models::especie::{Etiquetas, Raza, IDIOMA_PREDETERMINADO};
+use crate::services::EspecieService;
+use crate::services::especie_service::{DatosEspecie, OpcionEspecie};
+use crate::services::catalogo_especies::{self, MigracionEspecies};
+use crate::services::eliminacion;
+use crate::repositories::especie_repository::{EspecieRepository, FiltroEspecies};
+use crate::controllers::mascota_controller::MascotaServiceType;
+use crate::controllers::medicamento_controller::MedicamentoServiceType;
+use crate::controllers::validacion::{self, Normalizable};
+use crate::controllers::paginacion::{Paginado, ParametrosLista};
+use crate::controllers::usuario::Usuario;
+use crate::controllers::concurrencia::{ConEtag, IfMatch, IfNoneMatch};
+use std::sync::Mutex;
+
+#[derive(Debug, Default, Deserialize, Validate)]
+#[serde(default)]
+pub struct EspecieCreateDto {
+    #[validate(length(min = 1, max = 40))]
+    pub codigo: String,
+    // Por idioma; la de "es" es obligatoria
+    pub etiquetas: Etiquetas,
+    #[validate(length(max = 30))]
+    pub alias: Vec<String>,
+    #[validate(length(max = 300))]
+    pub razas: Vec<Raza>,
+}
+
+// Idiomas en minúsculas y textos sin espacios sobrantes
+fn normalizar_etiquetas(etiquetas: &mut Etiquetas) {
+    *etiquetas = std::mem::take(etiquetas)
+        .into_iter()
+        .map(|(idioma, etiqueta)| (idioma.trim().to_lowercase(), etiqueta.trim().to_string()))
+        .collect();
+}
+
+impl Normalizable for EspecieCreateDto {
+    fn normalizar(&mut self) {
+        validacion::normalizar_texto(&mut self.codigo);
+        normalizar_etiquetas(&mut self.etiquetas);
+        self.alias.iter_mut().for_each(validacion::normalizar_texto);
+        for raza in &mut self.razas {
+            validacion::normalizar_texto(&mut raza.codigo);
+            normalizar_etiquetas(&mut raza.etiquetas);
+            raza.alias.iter_mut().for_each(validacion::normalizar_texto);
+        }
+    }
+}
+
+impl From<EspecieCreateDto> for DatosEspecie {
+    fn from(dto: EspecieCreateDto) -> Self {
+        Self {
+            codigo: dto.codigo,
+            etiquetas: dto.etiquetas,
+            alias: dto.alias,
+            razas: dto.razas,
+        }
+    }
+}
+
+pub type EspecieServiceType = Mutex<EspecieService<dyn EspecieRepository + Send>>;
+
+#[get("/especies?<archivadas>&<lista..>")]
+pub async fn listar_especies(
+    archivadas: Option<bool>,
+    lista: ParametrosLista,
+    service: &State<EspecieServiceType>
+) -> Result<Paginado<Especie>, DomainError> {
+    let consulta = lista.consulta(FiltroEspecies {
+        incluir_archivadas: archivadas.unwrap_or(false),
+    })?;
+
+    let especies = service.lock()?
+        .listar_especies(&consulta)?;
+    Ok(Paginado::new(especies, &consulta))
+}
+
+// Especies vigentes con sus razas, con las etiquetas en el idioma pedido (o en
+// español si no la tienen), para las listas desplegables
+#[get("/especies/opciones?<idioma>")]
+pub async fn listar_opciones_especies(
+    idioma: Option<String>,
+    service: &State<EspecieServiceType>
+) -> Result<Json<Vec<OpcionEspecie>>, DomainError> {
+    let idioma = idioma
+        .map(|i| i.trim().to_lowercase())
+        .unwrap_or_else(|| IDIOMA_PREDETERMINADO.to_string());
+
+    Ok(Json(service.lock()?.opciones(&idioma)?))
+}
+
+#[get("/especies/<id>")]
+pub async fn obtener_especie(
+    id: String,
+    condicion: IfNoneMatch,
+    service: &State<EspecieServiceType>
+) -> Result<ConEtag<Especie>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+
+    service.lock()?
+        .obtener_especie(uuid)?
+        .map(|especie| ConEtag::leer(especie, &condicion))
+        .ok_or_else(|| DomainError::NotFound(format!("La especie {} no existe", uuid)))
+}
+
+#[post("/especies", data = "<especie_dto>")]
+pub async fn crear_especie(
+    especie_dto: Json<EspecieCreateDto>,
+    usuario: Usuario,
+    service: &State<EspecieServiceType>
+) -> Result<ConEtag<Especie>, DomainError> {
+    let datos = validacion::validar(especie_dto)?.into();
+
+    let especie = service.lock()?
+        .crear_especie(datos, usuario.autor())?;
+
+    Ok(ConEtag::new(especie))
+}
+
+// Reemplaza etiquetas, alias y razas; el código no cambia. Las mascotas ya
+// registradas conservan sus códigos aunque la raza se quite del catálogo.
+#[put("/especies/<id>", data = "<especie_dto>")]
+pub async fn actualizar_especie(
+    id: String,
+    especie_dto: Json<EspecieCreateDto>,
+    if_match: IfMatch,
+    usuario: Usuario,
+    service: &State<EspecieServiceType>
+) -> Result<ConEtag<Especie>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+    let esperada = if_match.version_esperada()?;
+    let datos = validacion::validar(especie_dto)?.into();
+
+    let especie = service.lock()?
+        .actualizar_especie(uuid, datos, &esperada, usuario.autor())?;
+
+    Ok(ConEtag::new(especie))
+}
+
+// Por defecto archiva la especie, que deja de aceptarse en las mascotas
+// nuevas; con `permanente=true` la elimina si ninguna mascota ni ningún rango
+// de dosis la tiene.
+#[delete("/especies/<id>?<permanente>")]
+pub async fn eliminar_especie(
+    id: String,
+    permanente: Option<bool>,
+    if_match: IfMatch,
+    usuario: Usuario,
+    service: &State<EspecieServiceType>,
+    mascotas: &State<MascotaServiceType>,
+    medicamentos: &State<MedicamentoServiceType>
+) -> Result<Status, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+    let esperada = if_match.version_esperada()?;
+
+    let mut especies = service.lock()?;
+
+    if !permanente.unwrap_or(false) {
+        return especies.archivar_especie(uuid, &esperada, usuario.autor())
+            .map(|_| Status::NoContent);
+    }
+
+    let mascotas = mascotas.lock()?;
+    let medicamentos = medicamentos.lock()?;
+    eliminacion::eliminar_especie(&mut especies, &mascotas, &medicamentos, uuid, &esperada)?;
+    Ok(Status::NoContent)
+}
+
+#[post("/especies/<id>/restaurar")]
+pub async fn restaurar_especie(
+    id: String,
+    usuario: Usuario,
+    service: &State<EspecieServiceType>
+) -> Result<ConEtag<Especie>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+
+    service.lock()?
+        .restaurar_especie(uuid, usuario.autor())
+        .map(ConEtag::new)
+}
+
+// Lleva la especie y la raza de texto libre de las mascotas, y la especie de
+// los rangos de dosis de los medicamentos, a los códigos del catálogo. Sin
+// `aplicar=true` solo informa qué cambiaría y qué no se reconoce.
+#[post("/especies/migracion?<aplicar>")]
+pub async fn migrar_especies_mascotas(
+    aplicar: Option<bool>,
+    usuario: Usuario,
+    service: &State<EspecieServiceType>,
+    mascotas: &State<MascotaServiceType>,
+    medicamentos: &State<MedicamentoServiceType>
+) -> Result<Json<MigracionEspecies>, DomainError> {
+    // Orden de bloqueo: especies, mascotas, medicamentos
+    let especies = service.lock()?;
+    let mut mascotas = mascotas.lock()?;
+    let mut medicamentos = medicamentos.lock()?;
+
+    Ok(Json(catalogo_especies::migrar(
+        &especies, &mut mascotas, &mut medicamentos, aplicar.unwrap_or(false), usuario.autor(),
+    )?))
+}
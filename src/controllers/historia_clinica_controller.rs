@@ -2,113 +2,545 @@ use rocket::serde::json::Json;
 use rocket::State;
 use rocket::http::Status;
 use uuid::Uuid;
-use crate::models::{HistoriaClinica, EntradaHistoriaClinica};
+use crate::error::DomainError;
+use crate::models::{EntradaHistoriaClinica, SignosVitales};
 use crate::services::HistoriaClinicaService;
-use crate::repositories::historia_clinica_repository::InMemoryHistoriaClinicaRepository;
+use crate::services::historia_clinica_service::VerificacionFirma;
+use crate::services::alertas_clinicas::{self, HistoriaClinicaConAlertas};
+use crate::services::integridad_referencial;
+use crate::services::eliminacion;
+use crate::repositories::historia_clinica_repository::{FiltroEntradas, HistoriaClinicaRepository};
+use crate::controllers::cliente_controller::ClienteServiceType;
+use crate::controllers::mascota_controller::MascotaServiceType;
+use crate::controllers::personal_controller::PersonalServiceType;
+use crate::controllers::prescripcion_controller::PrescripcionServiceType;
 use serde::Deserialize;
+use validator::Validate;
+use crate::controllers::validacion::{self, Normalizable};
+use crate::controllers::paginacion::{Paginado, ParametrosLista};
+use crate::controllers::usuario::Usuario;
+use crate::controllers::concurrencia::{ConEtag, IfMatch, IfNoneMatch};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
 use std::sync::Mutex;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Validate)]
+#[serde(default)]
 pub struct HistoriaClinicaCreateDto {
+    #[validate(custom(function = "validacion::validar_uuid"))]
     pub id_mascota: String,
+    #[validate(custom(function = "validacion::validar_uuid"))]
     pub id_cliente: String,
 }
 
-#[derive(Debug, Deserialize)]
+impl Normalizable for HistoriaClinicaCreateDto {
+    fn normalizar(&mut self) {
+        validacion::normalizar_texto(&mut self.id_mascota);
+        validacion::normalizar_texto(&mut self.id_cliente);
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Validate)]
+#[serde(default)]
 pub struct EntradaHistoriaClinicaCreateDto {
+    #[validate(length(min = 1, max = 2000))]
     pub descripcion: String,
+    #[validate(length(min = 1, max = 2000))]
     pub diagnostico: String,
+    #[validate(length(min = 1, max = 2000))]
     pub tratamiento: String,
+    #[validate(length(max = 4000))]
     pub notas: Option<String>,
+    // Veterinario que atendió
+    #[validate(custom(function = "validacion::validar_uuid"))]
+    pub id_veterinario: Option<String>,
+    #[validate(range(exclusive_min = 0.0, max = 2000.0))]
+    pub peso_kg: Option<f64>,
+    #[validate(range(min = 25.0, max = 45.0))]
+    pub temperatura_c: Option<f64>,
+    #[validate(range(min = 1, max = 500))]
+    pub frecuencia_cardiaca: Option<u32>,
+    #[validate(range(min = 1, max = 300))]
+    pub frecuencia_respiratoria: Option<u32>,
+}
+
+impl Normalizable for EntradaHistoriaClinicaCreateDto {
+    fn normalizar(&mut self) {
+        validacion::normalizar_texto(&mut self.descripcion);
+        validacion::normalizar_texto(&mut self.diagnostico);
+        validacion::normalizar_texto(&mut self.tratamiento);
+        validacion::normalizar_opcional(&mut self.notas);
+        validacion::normalizar_opcional(&mut self.id_veterinario);
+    }
+}
+
+impl EntradaHistoriaClinicaCreateDto {
+    fn signos_vitales(&self) -> SignosVitales {
+        SignosVitales {
+            peso_kg: self.peso_kg,
+            temperatura_c: self.temperatura_c,
+            frecuencia_cardiaca: self.frecuencia_cardiaca,
+            frecuencia_respiratoria: self.frecuencia_respiratoria,
+        }
+    }
+}
+
+// Una enmienda reemplaza el contenido completo de la entrada e indica el motivo
+#[derive(Debug, Default, Deserialize, Validate)]
+#[serde(default)]
+pub struct EnmiendaEntradaDto {
+    #[validate(length(min = 1, max = 2000))]
+    pub descripcion: String,
+    #[validate(length(min = 1, max = 2000))]
+    pub diagnostico: String,
+    #[validate(length(min = 1, max = 2000))]
+    pub tratamiento: String,
+    #[validate(length(max = 4000))]
+    pub notas: Option<String>,
+    // Si falta se conserva el de la entrada enmendada
+    #[validate(custom(function = "validacion::validar_uuid"))]
+    pub id_veterinario: Option<String>,
+    #[validate(range(exclusive_min = 0.0, max = 2000.0))]
+    pub peso_kg: Option<f64>,
+    #[validate(range(min = 25.0, max = 45.0))]
+    pub temperatura_c: Option<f64>,
+    #[validate(range(min = 1, max = 500))]
+    pub frecuencia_cardiaca: Option<u32>,
+    #[validate(range(min = 1, max = 300))]
+    pub frecuencia_respiratoria: Option<u32>,
+    #[validate(length(min = 1, max = 500))]
+    pub motivo: String,
+}
+
+impl Normalizable for EnmiendaEntradaDto {
+    fn normalizar(&mut self) {
+        validacion::normalizar_texto(&mut self.descripcion);
+        validacion::normalizar_texto(&mut self.diagnostico);
+        validacion::normalizar_texto(&mut self.tratamiento);
+        validacion::normalizar_opcional(&mut self.notas);
+        validacion::normalizar_opcional(&mut self.id_veterinario);
+        validacion::normalizar_texto(&mut self.motivo);
+    }
+}
+
+impl EnmiendaEntradaDto {
+    fn signos_vitales(&self) -> SignosVitales {
+        SignosVitales {
+            peso_kg: self.peso_kg,
+            temperatura_c: self.temperatura_c,
+            frecuencia_cardiaca: self.frecuencia_cardiaca,
+            frecuencia_respiratoria: self.frecuencia_respiratoria,
+        }
+    }
+}
+
+// El firmante se identifica con su id en el directorio de personal
+#[derive(Debug, Default, Deserialize, Validate)]
+#[serde(default)]
+pub struct FirmaEntradaDto {
+    #[validate(custom(function = "validacion::validar_uuid"))]
+    pub id_veterinario: String,
+}
+
+impl Normalizable for FirmaEntradaDto {
+    fn normalizar(&mut self) {
+        validacion::normalizar_texto(&mut self.id_veterinario);
+    }
 }
 
-type HistoriaClinicaServiceType = Mutex<HistoriaClinicaService<InMemoryHistoriaClinicaRepository>>;
+pub type HistoriaClinicaServiceType = Mutex<HistoriaClinicaService<dyn HistoriaClinicaRepository + Send>>;
 
+// Todas las respuestas con una historia clínica incluyen las alertas (alergias
+// y condiciones crónicas) de su mascota
 #[get("/mascotas/<id_mascota>/historia-clinica")]
 pub async fn obtener_historia_mascota(
     id_mascota: String,
-    service: &State<HistoriaClinicaServiceType>
-) -> Result<Json<HistoriaClinica>, Status> {
-    let uuid = Uuid::parse_str(&id_mascota).map_err(|_| Status::BadRequest)?;
-    
-    service.lock()
-        .map_err(|_| Status::InternalServerError)?
-        .obtener_historia_mascota(uuid)
-        .map(|historia| Json(historia.clone()))
-        .ok_or(Status::NotFound)
+    condicion: IfNoneMatch,
+    service: &State<HistoriaClinicaServiceType>,
+    mascotas: &State<MascotaServiceType>
+) -> Result<ConEtag<HistoriaClinicaConAlertas>, DomainError> {
+    let uuid = Uuid::parse_str(&id_mascota)?;
+
+    // Orden de bloqueo: mascotas, historias
+    let mascotas = mascotas.lock()?;
+    let historia = service.lock()?
+        .obtener_historia_mascota(uuid)?
+        .ok_or_else(|| DomainError::NotFound(format!("La mascota {} no tiene historia clínica", uuid)))?;
+    Ok(ConEtag::leer(alertas_clinicas::con_alertas(&mascotas, historia)?, &condicion))
 }
 
 #[get("/historias-clinicas/<id>")]
 pub async fn obtener_historia(
     id: String,
-    service: &State<HistoriaClinicaServiceType>
-) -> Result<Json<HistoriaClinica>, Status> {
-    let uuid = Uuid::parse_str(&id).map_err(|_| Status::BadRequest)?;
-    
-    service.lock()
-        .map_err(|_| Status::InternalServerError)?
-        .obtener_historia(uuid)
-        .map(|historia| Json(historia.clone()))
-        .ok_or(Status::NotFound)
+    condicion: IfNoneMatch,
+    service: &State<HistoriaClinicaServiceType>,
+    mascotas: &State<MascotaServiceType>
+) -> Result<ConEtag<HistoriaClinicaConAlertas>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+
+    let mascotas = mascotas.lock()?;
+    let historia = service.lock()?
+        .obtener_historia(uuid)?
+        .ok_or_else(|| DomainError::NotFound(format!("La historia clínica {} no existe", uuid)))?;
+    Ok(ConEtag::leer(alertas_clinicas::con_alertas(&mascotas, historia)?, &condicion))
 }
 
 #[post("/historias-clinicas", data = "<historia_dto>")]
 pub async fn crear_historia(
     historia_dto: Json<HistoriaClinicaCreateDto>,
-    service: &State<HistoriaClinicaServiceType>
-) -> Result<Json<HistoriaClinica>, Status> {
-    let id_mascota = Uuid::parse_str(&historia_dto.id_mascota)
-        .map_err(|_| Status::BadRequest)?;
-    let id_cliente = Uuid::parse_str(&historia_dto.id_cliente)
-        .map_err(|_| Status::BadRequest)?;
-
-    let result = service.lock()
-        .map_err(|_| Status::InternalServerError)?
-        .crear_historia(id_mascota, id_cliente);
-
-    match result {
-        Ok(historia) => Ok(Json(historia)),
-        Err(_) => Err(Status::InternalServerError),
+    usuario: Usuario,
+    service: &State<HistoriaClinicaServiceType>,
+    clientes: &State<ClienteServiceType>,
+    mascotas: &State<MascotaServiceType>
+) -> Result<ConEtag<HistoriaClinicaConAlertas>, DomainError> {
+    let historia_dto = validacion::validar(historia_dto)?;
+    let id_mascota = Uuid::parse_str(&historia_dto.id_mascota)?;
+    let id_cliente = Uuid::parse_str(&historia_dto.id_cliente)?;
+
+    // Orden de bloqueo: clientes, mascotas, historias
+    let clientes = clientes.lock()?;
+    let mascotas = mascotas.lock()?;
+    integridad_referencial::validar_historia(&clientes, &mascotas, id_mascota, id_cliente)?;
+
+    let historia = service.lock()?
+        .crear_historia(id_mascota, id_cliente, usuario.autor())?;
+
+    Ok(ConEtag::new(alertas_clinicas::con_alertas(&mascotas, historia)?))
+}
+
+// Idempotente: devuelve la historia de la mascota (200) o la crea para su
+// dueño actual (201).
+#[put("/mascotas/<id_mascota>/historia-clinica")]
+pub async fn obtener_o_crear_historia_mascota(
+    id_mascota: String,
+    usuario: Usuario,
+    service: &State<HistoriaClinicaServiceType>,
+    clientes: &State<ClienteServiceType>,
+    mascotas: &State<MascotaServiceType>
+) -> Result<ConEtag<HistoriaClinicaConAlertas>, DomainError> {
+    let id_mascota = Uuid::parse_str(&id_mascota)?;
+
+    let clientes = clientes.lock()?;
+    let mascotas = mascotas.lock()?;
+    let mascota = mascotas.obtener_mascota(id_mascota)?
+        .ok_or_else(|| DomainError::NotFound(format!("La mascota {} no existe", id_mascota)))?;
+    integridad_referencial::validar_historia(&clientes, &mascotas, id_mascota, mascota.id_cliente)?;
+
+    let (historia, creada) = service.lock()?
+        .obtener_o_crear_historia(id_mascota, mascota.id_cliente, usuario.autor())?;
+
+    let status = if creada { Status::Created } else { Status::Ok };
+    Ok(ConEtag::con_status(status, alertas_clinicas::con_alertas(&mascotas, historia)?))
+}
+
+#[post("/mascotas/<id_mascota>/historia-clinica/fusionar")]
+pub async fn fusionar_historias_mascota(
+    id_mascota: String,
+    usuario: Usuario,
+    service: &State<HistoriaClinicaServiceType>,
+    mascotas: &State<MascotaServiceType>
+) -> Result<ConEtag<HistoriaClinicaConAlertas>, DomainError> {
+    let id_mascota = Uuid::parse_str(&id_mascota)?;
+
+    let mascotas = mascotas.lock()?;
+    let historia = service.lock()?
+        .fusionar_historias_mascota(id_mascota, usuario.autor())?;
+
+    Ok(ConEtag::new(alertas_clinicas::con_alertas(&mascotas, historia)?))
+}
+
+// Acepta un instante RFC 3339 o una fecha sola; una fecha como `hasta` incluye
+// todo ese día.
+pub fn leer_limite(parametro: &str, valor: &str, fin_del_dia: bool) -> Result<DateTime<Utc>, DomainError> {
+    if let Ok(instante) = DateTime::parse_from_rfc3339(valor) {
+        return Ok(instante.with_timezone(&Utc));
     }
+    let fecha = NaiveDate::parse_from_str(valor, "%Y-%m-%d").map_err(|_| {
+        DomainError::BadRequest(format!(
+            "`{}` debe ser una fecha (AAAA-MM-DD) o un instante RFC 3339", parametro
+        ))
+    })?;
+    let inicio = fecha.and_time(NaiveTime::MIN);
+    let limite = if fin_del_dia {
+        fecha.and_hms_milli_opt(23, 59, 59, 999).unwrap_or(inicio)
+    } else {
+        inicio
+    };
+    Ok(limite.and_utc())
 }
 
-#[get("/historias-clinicas/<id>/entradas")]
+#[get("/historias-clinicas/<id>/entradas?<desde>&<hasta>&<enmendadas>&<lista..>")]
 pub async fn listar_entradas(
     id: String,
+    desde: Option<String>,
+    hasta: Option<String>,
+    enmendadas: Option<bool>,
+    lista: ParametrosLista,
     service: &State<HistoriaClinicaServiceType>
-) -> Result<Json<Vec<EntradaHistoriaClinica>>, Status> {
-    let uuid = Uuid::parse_str(&id).map_err(|_| Status::BadRequest)?;
-    
-    let entradas = service.lock()
-        .map_err(|_| Status::InternalServerError)?
-        .obtener_entradas(uuid)
-        .into_iter()
-        .cloned()
-        .collect();
-    
-    Ok(Json(entradas))
+) -> Result<Paginado<EntradaHistoriaClinica>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+    let consulta = lista.consulta(FiltroEntradas {
+        desde: desde.as_deref().map(|d| leer_limite("desde", d, false)).transpose()?,
+        hasta: hasta.as_deref().map(|h| leer_limite("hasta", h, true)).transpose()?,
+        incluir_enmendadas: enmendadas.unwrap_or(false),
+    })?;
+
+    let entradas = service.lock()?
+        .listar_entradas(uuid, &consulta)?;
+
+    Ok(Paginado::new(entradas, &consulta))
 }
 
 #[post("/historias-clinicas/<id>/entradas", data = "<entrada_dto>")]
+#[allow(clippy::too_many_arguments)]
 pub async fn crear_entrada(
     id: String,
     entrada_dto: Json<EntradaHistoriaClinicaCreateDto>,
-    service: &State<HistoriaClinicaServiceType>
-) -> Result<Json<EntradaHistoriaClinica>, Status> {
-    let id_historia = Uuid::parse_str(&id).map_err(|_| Status::BadRequest)?;
+    usuario: Usuario,
+    service: &State<HistoriaClinicaServiceType>,
+    personal: &State<PersonalServiceType>,
+    clientes: &State<ClienteServiceType>,
+    mascotas: &State<MascotaServiceType>
+) -> Result<ConEtag<EntradaHistoriaClinica>, DomainError> {
+    let id_historia = Uuid::parse_str(&id)?;
+    let entrada_dto = validacion::validar(entrada_dto)?;
+    let id_veterinario = entrada_dto.id_veterinario.as_deref().map(Uuid::parse_str).transpose()?;
 
-    let result = service.lock()
-        .map_err(|_| Status::InternalServerError)?
+    // Orden de bloqueo: personal, clientes, mascotas, historias
+    let personal = personal.lock()?;
+    let clientes = clientes.lock()?;
+    let mascotas = mascotas.lock()?;
+    let mut historias = service.lock()?;
+    if let Some(id_veterinario) = id_veterinario {
+        integridad_referencial::validar_veterinario(
+            &personal, &clientes, &mascotas, &historias, id_historia, id_veterinario,
+        )?;
+    }
+
+    let signos_vitales = entrada_dto.signos_vitales();
+    let entrada = historias
         .agregar_entrada(
             id_historia,
-            entrada_dto.descripcion.clone(),
-            entrada_dto.diagnostico.clone(),
-            entrada_dto.tratamiento.clone(),
-            entrada_dto.notas.clone(),
-        );
-
-    match result {
-        Ok(entrada) => Ok(Json(entrada)),
-        Err(_) => Err(Status::InternalServerError),
+            entrada_dto.descripcion,
+            entrada_dto.diagnostico,
+            entrada_dto.tratamiento,
+            entrada_dto.notas,
+            id_veterinario,
+            signos_vitales,
+            usuario.autor(),
+        )?;
+
+    Ok(ConEtag::new(entrada))
+}
+
+#[get("/historias-clinicas/<id>/entradas/<id_entrada>")]
+pub async fn obtener_entrada(
+    id: String,
+    id_entrada: String,
+    condicion: IfNoneMatch,
+    service: &State<HistoriaClinicaServiceType>
+) -> Result<ConEtag<EntradaHistoriaClinica>, DomainError> {
+    let id_historia = Uuid::parse_str(&id)?;
+    let id_entrada = Uuid::parse_str(&id_entrada)?;
+
+    service.lock()?
+        .obtener_entrada(id_historia, id_entrada)
+        .map(|entrada| ConEtag::leer(entrada, &condicion))
+}
+
+// Solo los borradores se editan
+#[put("/historias-clinicas/<id>/entradas/<id_entrada>", data = "<entrada_dto>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn modificar_borrador(
+    id: String,
+    id_entrada: String,
+    entrada_dto: Json<EntradaHistoriaClinicaCreateDto>,
+    if_match: IfMatch,
+    usuario: Usuario,
+    service: &State<HistoriaClinicaServiceType>,
+    personal: &State<PersonalServiceType>,
+    clientes: &State<ClienteServiceType>,
+    mascotas: &State<MascotaServiceType>
+) -> Result<ConEtag<EntradaHistoriaClinica>, DomainError> {
+    let id_historia = Uuid::parse_str(&id)?;
+    let id_entrada = Uuid::parse_str(&id_entrada)?;
+    let esperada = if_match.version_esperada()?;
+    let entrada_dto = validacion::validar(entrada_dto)?;
+    let id_veterinario = entrada_dto.id_veterinario.as_deref().map(Uuid::parse_str).transpose()?;
+
+    let personal = personal.lock()?;
+    let clientes = clientes.lock()?;
+    let mascotas = mascotas.lock()?;
+    let mut historias = service.lock()?;
+    if let Some(id_veterinario) = id_veterinario {
+        integridad_referencial::validar_veterinario(
+            &personal, &clientes, &mascotas, &historias, id_historia, id_veterinario,
+        )?;
+    }
+
+    let signos_vitales = entrada_dto.signos_vitales();
+    let entrada = historias
+        .modificar_borrador(
+            id_historia,
+            id_entrada,
+            entrada_dto.descripcion,
+            entrada_dto.diagnostico,
+            entrada_dto.tratamiento,
+            entrada_dto.notas,
+            id_veterinario,
+            signos_vitales,
+            &esperada,
+            usuario.autor(),
+        )?;
+
+    Ok(ConEtag::new(entrada))
+}
+
+// Firma el borrador el veterinario que lo atendió, que debe seguir activo en la
+// clínica del dueño; queda de solo lectura
+#[post("/historias-clinicas/<id>/entradas/<id_entrada>/firmar", data = "<firma_dto>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn firmar_entrada(
+    id: String,
+    id_entrada: String,
+    firma_dto: Json<FirmaEntradaDto>,
+    if_match: IfMatch,
+    usuario: Usuario,
+    service: &State<HistoriaClinicaServiceType>,
+    personal: &State<PersonalServiceType>,
+    clientes: &State<ClienteServiceType>,
+    mascotas: &State<MascotaServiceType>
+) -> Result<ConEtag<EntradaHistoriaClinica>, DomainError> {
+    let id_historia = Uuid::parse_str(&id)?;
+    let id_entrada = Uuid::parse_str(&id_entrada)?;
+    let esperada = if_match.version_esperada()?;
+    let firma_dto = validacion::validar(firma_dto)?;
+    let id_veterinario = Uuid::parse_str(&firma_dto.id_veterinario)?;
+
+    // Orden de bloqueo: personal, clientes, mascotas, historias
+    let personal = personal.lock()?;
+    let clientes = clientes.lock()?;
+    let mascotas = mascotas.lock()?;
+    let mut historias = service.lock()?;
+    let firmante = integridad_referencial::validar_veterinario(
+        &personal, &clientes, &mascotas, &historias, id_historia, id_veterinario,
+    )?;
+
+    historias
+        .firmar_entrada(id_historia, id_entrada, &esperada, &firmante, usuario.autor())
+        .map(ConEtag::new)
+}
+
+#[get("/historias-clinicas/<id>/entradas/<id_entrada>/firma")]
+pub async fn verificar_firma_entrada(
+    id: String,
+    id_entrada: String,
+    service: &State<HistoriaClinicaServiceType>
+) -> Result<Json<VerificacionFirma>, DomainError> {
+    let id_historia = Uuid::parse_str(&id)?;
+    let id_entrada = Uuid::parse_str(&id_entrada)?;
+
+    service.lock()?
+        .verificar_firma(id_historia, id_entrada)
+        .map(Json)
+}
+
+// Las entradas firmadas no se editan: la corrección se agrega como una revisión nueva
+#[post("/historias-clinicas/<id>/entradas/<id_entrada>/enmiendas", data = "<enmienda_dto>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn enmendar_entrada(
+    id: String,
+    id_entrada: String,
+    enmienda_dto: Json<EnmiendaEntradaDto>,
+    usuario: Usuario,
+    service: &State<HistoriaClinicaServiceType>,
+    personal: &State<PersonalServiceType>,
+    clientes: &State<ClienteServiceType>,
+    mascotas: &State<MascotaServiceType>
+) -> Result<ConEtag<EntradaHistoriaClinica>, DomainError> {
+    let id_historia = Uuid::parse_str(&id)?;
+    let id_entrada = Uuid::parse_str(&id_entrada)?;
+    let enmienda_dto = validacion::validar(enmienda_dto)?;
+    let id_veterinario = enmienda_dto.id_veterinario.as_deref().map(Uuid::parse_str).transpose()?;
+
+    let personal = personal.lock()?;
+    let clientes = clientes.lock()?;
+    let mascotas = mascotas.lock()?;
+    let mut historias = service.lock()?;
+    if let Some(id_veterinario) = id_veterinario {
+        integridad_referencial::validar_veterinario(
+            &personal, &clientes, &mascotas, &historias, id_historia, id_veterinario,
+        )?;
+    }
+
+    let signos_vitales = enmienda_dto.signos_vitales();
+    let revision = historias
+        .enmendar_entrada(
+            id_historia,
+            id_entrada,
+            enmienda_dto.descripcion,
+            enmienda_dto.diagnostico,
+            enmienda_dto.tratamiento,
+            enmienda_dto.notas,
+            id_veterinario,
+            signos_vitales,
+            enmienda_dto.motivo,
+            usuario.autor(),
+        )?;
+
+    Ok(ConEtag::con_status(Status::Created, revision))
+}
+
+#[get("/historias-clinicas/<id>/entradas/<id_entrada>/revisiones")]
+pub async fn listar_revisiones_entrada(
+    id: String,
+    id_entrada: String,
+    service: &State<HistoriaClinicaServiceType>
+) -> Result<Json<Vec<EntradaHistoriaClinica>>, DomainError> {
+    let id_historia = Uuid::parse_str(&id)?;
+    let id_entrada = Uuid::parse_str(&id_entrada)?;
+
+    service.lock()?
+        .listar_revisiones(id_historia, id_entrada)
+        .map(Json)
+}
+
+// Por defecto archiva la historia; con `permanente=true` la elimina, y con
+// `cascada=true` también a sus entradas y sus prescripciones.
+#[delete("/historias-clinicas/<id>?<permanente>&<cascada>")]
+pub async fn eliminar_historia(
+    id: String,
+    permanente: Option<bool>,
+    cascada: Option<bool>,
+    if_match: IfMatch,
+    usuario: Usuario,
+    service: &State<HistoriaClinicaServiceType>,
+    prescripciones: &State<PrescripcionServiceType>
+) -> Result<Status, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+    let esperada = if_match.version_esperada()?;
+
+    let mut historias = service.lock()?;
+
+    if !permanente.unwrap_or(false) {
+        return historias.archivar_historia(uuid, &esperada, usuario.autor())
+            .map(|_| Status::NoContent);
     }
+
+    let mut prescripciones = prescripciones.lock()?;
+    eliminacion::eliminar_historia(&mut historias, &mut prescripciones, uuid, cascada.unwrap_or(false), &esperada)?;
+    Ok(Status::NoContent)
+}
+
+#[post("/historias-clinicas/<id>/restaurar")]
+pub async fn restaurar_historia(
+    id: String,
+    usuario: Usuario,
+    service: &State<HistoriaClinicaServiceType>,
+    mascotas: &State<MascotaServiceType>
+) -> Result<ConEtag<HistoriaClinicaConAlertas>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+
+    let mascotas = mascotas.lock()?;
+    let historia = service.lock()?.restaurar_historia(uuid, usuario.autor())?;
+    Ok(ConEtag::new(alertas_clinicas::con_alertas(&mascotas, historia)?))
 }
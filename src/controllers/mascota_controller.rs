@@ -1,117 +1,327 @@
 use rocket::serde::json::Json;
 use rocket::State;
 use rocket::http::Status;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use uuid::Uuid;
+use validator::Validate;
 use chrono::NaiveDate;
+use crate::error::DomainError;
 use crate::models::Mascota;
+use crate::models::mascota::{Alergia, CondicionCronica};
 use crate::services::MascotaService;
-use crate::repositories::mascota_repository::InMemoryMascotaRepository;
+use crate::services::mascota_service::CambiosMascota;
+use crate::services::integridad_referencial;
+use crate::services::eliminacion;
+use crate::services::signos_vitales::{self, SerieSignosVitales};
+use crate::repositories::mascota_repository::{FiltroMascotas, MascotaRepository};
+use crate::controllers::cliente_controller::ClienteServiceType;
+use crate::controllers::especie_controller::EspecieServiceType;
+use crate::controllers::historia_clinica_controller::{leer_limite, HistoriaClinicaServiceType};
+use crate::controllers::turno_controller::TurnoServiceType;
+use crate::controllers::vacunacion_controller::VacunacionServiceType;
+use crate::controllers::prescripcion_controller::PrescripcionServiceType;
+use crate::controllers::validacion::{self, Normalizable};
+use crate::controllers::paginacion::{Paginado, ParametrosLista};
+use crate::controllers::parche;
+use crate::controllers::usuario::Usuario;
+use crate::controllers::concurrencia::{ConEtag, IfMatch, IfNoneMatch};
 use std::sync::Mutex;
 
-#[derive(Debug, Deserialize)]
+// La especie y la raza se aceptan por código, etiqueta en cualquier idioma o
+// alias del catálogo de especies, y se guardan como códigos
+#[derive(Debug, Default, Deserialize, Serialize, Validate)]
+#[serde(default)]
 pub struct MascotaCreateDto {
+    #[validate(length(min = 1, max = 80))]
     pub nombre: String,
+    #[validate(length(min = 1, max = 60))]
     pub especie: String,
+    #[validate(length(min = 1, max = 80))]
     pub raza: String,
+    #[validate(custom(function = "validacion::validar_fecha_pasada"))]
     pub fecha_nacimiento: Option<NaiveDate>,
+    #[validate(custom(function = "validacion::validar_uuid"))]
     pub id_cliente: String,
+    #[validate(length(max = 50))]
+    pub alergias: Vec<Alergia>,
+    #[validate(length(max = 50))]
+    pub condiciones_cronicas: Vec<CondicionCronica>,
 }
 
-type MascotaServiceType = Mutex<MascotaService<InMemoryMascotaRepository>>;
+impl Normalizable for MascotaCreateDto {
+    fn normalizar(&mut self) {
+        validacion::normalizar_texto(&mut self.nombre);
+        validacion::normalizar_texto(&mut self.especie);
+        validacion::normalizar_texto(&mut self.raza);
+        validacion::normalizar_texto(&mut self.id_cliente);
+        for alergia in &mut self.alergias {
+            validacion::normalizar_texto(&mut alergia.sustancia);
+            validacion::normalizar_opcional(&mut alergia.reaccion);
+        }
+        for condicion in &mut self.condiciones_cronicas {
+            validacion::normalizar_texto(&mut condicion.condicion);
+            validacion::normalizar_opcional(&mut condicion.notas);
+        }
+    }
+}
 
-#[get("/mascotas")] 
-pub async fn listar_mascotas(
-    service: &State<MascotaServiceType>
-) -> Result<Json<Vec<Mascota>>, Status> {
-    let mascotas = service.lock()
-        .map_err(|_| Status::InternalServerError)?
-        .listar_mascotas()
-        .into_iter()
-        .cloned()
-        .collect();
-
-    Ok(Json(mascotas))
+impl From<&Mascota> for MascotaCreateDto {
+    fn from(mascota: &Mascota) -> Self {
+        Self {
+            nombre: mascota.nombre.clone(),
+            especie: mascota.especie.clone(),
+            raza: mascota.raza.clone(),
+            fecha_nacimiento: mascota.fecha_nacimiento,
+            id_cliente: mascota.id_cliente.to_string(),
+            alergias: mascota.alergias.clone(),
+            condiciones_cronicas: mascota.condiciones_cronicas.clone(),
+        }
+    }
 }
 
-#[get("/mascotas?<id_cliente>")]
-pub async fn listar_mascotas_cliente(
-    id_cliente: String,
+pub type MascotaServiceType = Mutex<MascotaService<dyn MascotaRepository + Send>>;
+
+#[get("/mascotas?<id_cliente>&<nombre>&<especie>&<archivadas>&<lista..>")]
+pub async fn listar_mascotas(
+    id_cliente: Option<String>,
+    nombre: Option<String>,
+    especie: Option<String>,
+    archivadas: Option<bool>,
+    lista: ParametrosLista,
     service: &State<MascotaServiceType>
-) -> Result<Json<Vec<Mascota>>, Status> {
-    let uuid = Uuid::parse_str(&id_cliente).map_err(|_| Status::BadRequest)?;
-    
-    let mascotas = service.lock()
-        .map_err(|_| Status::InternalServerError)?
-        .listar_mascotas_cliente(uuid)
-        .into_iter()
-        .cloned()
-        .collect();
-    
-    Ok(Json(mascotas))
+) -> Result<Paginado<Mascota>, DomainError> {
+    let consulta = lista.consulta(FiltroMascotas {
+        id_cliente: id_cliente.as_deref().map(Uuid::parse_str).transpose()?,
+        nombre,
+        especie,
+        incluir_archivadas: archivadas.unwrap_or(false),
+    })?;
+
+    let mascotas = service.lock()?
+        .listar_mascotas(&consulta)?;
+
+    Ok(Paginado::new(mascotas, &consulta))
 }
 
 #[get("/mascotas/<id>")]
 pub async fn obtener_mascota(
     id: String,
+    condicion: IfNoneMatch,
     service: &State<MascotaServiceType>
-) -> Result<Json<Mascota>, Status> {
-    let uuid = Uuid::parse_str(&id).map_err(|_| Status::BadRequest)?;
+) -> Result<ConEtag<Mascota>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
     
-    service.lock()
-        .map_err(|_| Status::InternalServerError)?
-        .obtener_mascota(uuid)
-        .map(|mascota| Json(mascota.clone()))
-        .ok_or(Status::NotFound)
+    service.lock()?
+        .obtener_mascota(uuid)?
+        .map(|mascota| ConEtag::leer(mascota, &condicion))
+        .ok_or_else(|| DomainError::NotFound(format!("La mascota {} no existe", uuid)))
+}
+
+// Serie de signos vitales de las consultas, con estadísticas por signo y los
+// valores fuera del rango de referencia de la especie
+#[get("/mascotas/<id>/signos-vitales?<desde>&<hasta>")]
+pub async fn obtener_signos_vitales_mascota(
+    id: String,
+    desde: Option<String>,
+    hasta: Option<String>,
+    service: &State<MascotaServiceType>,
+    historias: &State<HistoriaClinicaServiceType>
+) -> Result<Json<SerieSignosVitales>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+    let desde = desde.as_deref().map(|d| leer_limite("desde", d, false)).transpose()?;
+    let hasta = hasta.as_deref().map(|h| leer_limite("hasta", h, true)).transpose()?;
+
+    let mascotas = service.lock()?;
+    let historias = historias.lock()?;
+    signos_vitales::serie(&mascotas, &historias, uuid, desde, hasta).map(Json)
 }
 
 #[post("/mascotas", data = "<mascota_dto>")]
 pub async fn crear_mascota(
     mascota_dto: Json<MascotaCreateDto>,
-    service: &State<MascotaServiceType>
-) -> Result<Json<Mascota>, Status> {
-    let id_cliente = Uuid::parse_str(&mascota_dto.id_cliente)
-        .map_err(|_| Status::BadRequest)?;
+    usuario: Usuario,
+    service: &State<MascotaServiceType>,
+    clientes: &State<ClienteServiceType>,
+    especies: &State<EspecieServiceType>
+) -> Result<ConEtag<Mascota>, DomainError> {
+    let mascota_dto = validacion::validar(mascota_dto)?;
+    let id_cliente = Uuid::parse_str(&mascota_dto.id_cliente)?;
+    let (especie, raza) = especies.lock()?
+        .resolver(&mascota_dto.especie, &mascota_dto.raza)?;
 
-    let result = service.lock()
-        .map_err(|_| Status::InternalServerError)?
+    // El cliente queda bloqueado hasta guardar la mascota
+    let clientes = clientes.lock()?;
+    integridad_referencial::validar_mascota(&clientes, id_cliente)?;
+
+    let mascota = service.lock()?
         .crear_mascota(
-            mascota_dto.nombre.clone(),
-            mascota_dto.especie.clone(),
-            mascota_dto.raza.clone(),
+            mascota_dto.nombre,
+            especie,
+            raza,
             mascota_dto.fecha_nacimiento,
             id_cliente,
-        );
+            mascota_dto.alergias,
+            mascota_dto.condiciones_cronicas,
+            usuario.autor(),
+        )?;
 
-    match result {
-        Ok(mascota) => Ok(Json(mascota)),
-        Err(_) => Err(Status::InternalServerError),
-    }
+    Ok(ConEtag::new(mascota))
 }
 
+// Si cambia el dueño, la historia clínica de la mascota pasa al dueño nuevo
 #[put("/mascotas/<id>", data = "<mascota_dto>")]
+#[allow(clippy::too_many_arguments)]
 pub async fn actualizar_mascota(
     id: String,
     mascota_dto: Json<MascotaCreateDto>,
-    service: &State<MascotaServiceType>
-) -> Result<Json<Mascota>, Status> {
-    let uuid = Uuid::parse_str(&id).map_err(|_| Status::BadRequest)?;
-    let id_cliente = Uuid::parse_str(&mascota_dto.id_cliente)
-        .map_err(|_| Status::BadRequest)?;
+    if_match: IfMatch,
+    usuario: Usuario,
+    service: &State<MascotaServiceType>,
+    clientes: &State<ClienteServiceType>,
+    especies: &State<EspecieServiceType>,
+    historias: &State<HistoriaClinicaServiceType>
+) -> Result<ConEtag<Mascota>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+    let esperada = if_match.version_esperada()?;
+    let mascota_dto = validacion::validar(mascota_dto)?;
+    let id_cliente = Uuid::parse_str(&mascota_dto.id_cliente)?;
+    let (especie, raza) = especies.lock()?
+        .resolver(&mascota_dto.especie, &mascota_dto.raza)?;
 
-    let result = service.lock()
-        .map_err(|_| Status::InternalServerError)?
+    // Orden de bloqueo: clientes, mascotas, historias
+    let clientes = clientes.lock()?;
+    integridad_referencial::validar_mascota(&clientes, id_cliente)?;
+
+    let mut mascotas = service.lock()?;
+    let mascota = mascotas
         .actualizar_mascota(
             uuid,
-            mascota_dto.nombre.clone(),
-            mascota_dto.especie.clone(),
-            mascota_dto.raza.clone(),
+            mascota_dto.nombre,
+            especie,
+            raza,
             mascota_dto.fecha_nacimiento,
             id_cliente,
-        );
+            mascota_dto.alergias,
+            mascota_dto.condiciones_cronicas,
+            &esperada,
+            usuario.autor(),
+        )?;
+    historias.lock()?
+        .reasignar_cliente(mascota.id, mascota.id_cliente, usuario.autor())?;
+
+    Ok(ConEtag::new(mascota))
+}
+
+// JSON Merge Patch: solo se modifican los campos enviados; `"fecha_nacimiento": null`
+// la borra. Las listas de alergias y condiciones crónicas se reemplazan enteras.
+// Si cambia la especie o la raza, se validan las dos contra el catálogo; si
+// cambia el dueño, la historia clínica pasa al dueño nuevo.
+#[patch("/mascotas/<id>", data = "<parche>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn modificar_mascota(
+    id: String,
+    parche: Json<Value>,
+    if_match: IfMatch,
+    usuario: Usuario,
+    service: &State<MascotaServiceType>,
+    clientes: &State<ClienteServiceType>,
+    especies: &State<EspecieServiceType>,
+    historias: &State<HistoriaClinicaServiceType>
+) -> Result<ConEtag<Mascota>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+    let esperada = if_match.version_esperada()?;
 
-    match result {
-        Ok(mascota) => Ok(Json(mascota)),
-        Err(_) => Err(Status::NotFound),
+    // Orden de bloqueo: especies, clientes, mascotas, historias
+    let especies = especies.lock()?;
+    let clientes = clientes.lock()?;
+    let mut mascotas = service.lock()?;
+    let actual = mascotas.obtener_mascota(uuid)?
+        .ok_or_else(|| DomainError::NotFound(format!("La mascota {} no existe", uuid)))?;
+    let (dto, campos) = parche::aplicar(&MascotaCreateDto::from(&actual), parche.into_inner())?;
+
+    let id_cliente = campos.tomar("id_cliente", Uuid::parse_str(&dto.id_cliente)?);
+    if let Some(id_cliente) = id_cliente {
+        integridad_referencial::validar_mascota(&clientes, id_cliente)?;
     }
+
+    let (especie, raza) = if campos.contiene("especie") || campos.contiene("raza") {
+        let (especie, raza) = especies.resolver(&dto.especie, &dto.raza)?;
+        (Some(especie), Some(raza))
+    } else {
+        (None, None)
+    };
+
+    let mascota = mascotas.modificar_mascota(uuid, CambiosMascota {
+        nombre: campos.tomar("nombre", dto.nombre),
+        especie,
+        raza,
+        fecha_nacimiento: campos.tomar("fecha_nacimiento", dto.fecha_nacimiento),
+        id_cliente,
+        alergias: campos.tomar("alergias", dto.alergias),
+        condiciones_cronicas: campos.tomar("condiciones_cronicas", dto.condiciones_cronicas),
+    }, &esperada, usuario.autor())?;
+    if id_cliente.is_some() {
+        historias.lock()?
+            .reasignar_cliente(mascota.id, mascota.id_cliente, usuario.autor())?;
+    }
+
+    Ok(ConEtag::new(mascota))
+}
+
+// Por defecto archiva la mascota; con `permanente=true` la elimina, y con
+// `cascada=true` también a sus turnos, vacunaciones, historias y entradas.
+#[delete("/mascotas/<id>?<permanente>&<cascada>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn eliminar_mascota(
+    id: String,
+    permanente: Option<bool>,
+    cascada: Option<bool>,
+    if_match: IfMatch,
+    usuario: Usuario,
+    service: &State<MascotaServiceType>,
+    historias: &State<HistoriaClinicaServiceType>,
+    turnos: &State<TurnoServiceType>,
+    vacunaciones: &State<VacunacionServiceType>,
+    prescripciones: &State<PrescripcionServiceType>
+) -> Result<Status, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+    let esperada = if_match.version_esperada()?;
+
+    let mut mascotas = service.lock()?;
+
+    if !permanente.unwrap_or(false) {
+        return mascotas.archivar_mascota(uuid, &esperada, usuario.autor())
+            .map(|_| Status::NoContent);
+    }
+
+    let mut historias = historias.lock()?;
+    let mut turnos = turnos.lock()?;
+    let mut vacunaciones = vacunaciones.lock()?;
+    let mut prescripciones = prescripciones.lock()?;
+
+    eliminacion::eliminar_mascota(
+        &mut mascotas,
+        &mut historias,
+        &mut turnos,
+        &mut vacunaciones,
+        &mut prescripciones,
+        uuid,
+        cascada.unwrap_or(false),
+        &esperada,
+    )?;
+    Ok(Status::NoContent)
+}
+
+#[post("/mascotas/<id>/restaurar")]
+pub async fn restaurar_mascota(
+    id: String,
+    usuario: Usuario,
+    service: &State<MascotaServiceType>
+) -> Result<ConEtag<Mascota>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+
+    service.lock()?
+        .restaurar_mascota(uuid, usuario.autor())
+        .map(ConEtag::new)
 }
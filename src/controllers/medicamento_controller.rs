@@ -0,0 +1,190 @@
+use rocket::serde::json::Json;
+use rocket::State;
+use rocket::http::Status;
+use serde::Deserialize;
+use uuid::Uuid;
+use validator::Validate;
+use crate::error::DomainError;
+use crate::models::Medicamento;
+use crate::models::medicamento::RangoDosis;
+use crate::services::MedicamentoService;
+use crate::services::medicamento_service::DatosMedicamento;
+use crate::services::{catalogo_especies, eliminacion};
+use crate::repositories::medicamento_repository::{FiltroMedicamentos, MedicamentoRepository};
+use crate::controllers::prescripcion_controller::PrescripcionServiceType;
+use crate::controllers::especie_controller::EspecieServiceType;
+use crate::controllers::validacion::{self, Normalizable};
+use crate::controllers::paginacion::{Paginado, ParametrosLista};
+use crate::controllers::usuario::Usuario;
+use crate::controllers::concurrencia::{ConEtag, IfMatch, IfNoneMatch};
+use std::sync::Mutex;
+
+#[derive(Debug, Default, Deserialize, Validate)]
+#[serde(default)]
+pub struct MedicamentoCreateDto {
+    #[validate(length(min = 1, max = 120))]
+    pub principio_activo: String,
+    #[validate(length(min = 1, max = 120))]
+    pub nombre_comercial: Option<String>,
+    // "comprimido", "suspensión oral", "inyectable", ...
+    #[validate(length(min = 1, max = 120))]
+    pub presentacion: String,
+    // mg de principio activo por unidad de la presentación
+    #[validate(required, range(exclusive_min = 0.0))]
+    pub concentracion_mg: Option<f64>,
+    #[validate(length(min = 1, max = 30))]
+    pub unidad: String,
+    #[validate(length(max = 50))]
+    pub dosis: Vec<RangoDosis>,
+}
+
+impl Normalizable for MedicamentoCreateDto {
+    fn normalizar(&mut self) {
+        validacion::normalizar_texto(&mut self.principio_activo);
+        validacion::normalizar_opcional(&mut self.nombre_comercial);
+        validacion::normalizar_texto(&mut self.presentacion);
+        validacion::normalizar_texto(&mut self.unidad);
+        for rango in &mut self.dosis {
+            validacion::normalizar_texto(&mut rango.especie);
+        }
+    }
+}
+
+impl From<MedicamentoCreateDto> for DatosMedicamento {
+    fn from(dto: MedicamentoCreateDto) -> Self {
+        Self {
+            principio_activo: dto.principio_activo,
+            nombre_comercial: dto.nombre_comercial,
+            presentacion: dto.presentacion,
+            concentracion_mg: dto.concentracion_mg.unwrap_or_default(),
+            unidad: dto.unidad,
+            dosis: dto.dosis,
+        }
+    }
+}
+
+pub type MedicamentoServiceType = Mutex<MedicamentoService<dyn MedicamentoRepository + Send>>;
+
+// Valida el cuerpo y lleva la especie de cada rango de dosis a su código del
+// catálogo, como en las mascotas
+fn datos_medicamento(
+    medicamento_dto: Json<MedicamentoCreateDto>,
+    especies: &State<EspecieServiceType>,
+) -> Result<DatosMedicamento, DomainError> {
+    let mut datos: DatosMedicamento = validacion::validar(medicamento_dto)?.into();
+    let especies = especies.lock()?;
+    datos.dosis = catalogo_especies::resolver_dosis(&especies, datos.dosis)?;
+    Ok(datos)
+}
+
+// La especie puede indicarse como en las mascotas: código, etiqueta o alias
+#[get("/medicamentos?<nombre>&<especie>&<archivados>&<lista..>")]
+pub async fn listar_medicamentos(
+    nombre: Option<String>,
+    especie: Option<String>,
+    archivados: Option<bool>,
+    lista: ParametrosLista,
+    service: &State<MedicamentoServiceType>,
+    especies: &State<EspecieServiceType>
+) -> Result<Paginado<Medicamento>, DomainError> {
+    let especie = match especie {
+        Some(especie) => Some(especies.lock()?.codigo_especie(&especie)?.unwrap_or(especie)),
+        None => None,
+    };
+    let consulta = lista.consulta(FiltroMedicamentos {
+        nombre,
+        especie,
+        incluir_archivados: archivados.unwrap_or(false),
+    })?;
+
+    let medicamentos = service.lock()?
+        .listar_medicamentos(&consulta)?;
+    Ok(Paginado::new(medicamentos, &consulta))
+}
+
+#[get("/medicamentos/<id>")]
+pub async fn obtener_medicamento(
+    id: String,
+    condicion: IfNoneMatch,
+    service: &State<MedicamentoServiceType>
+) -> Result<ConEtag<Medicamento>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+
+    service.lock()?
+        .obtener_medicamento(uuid)?
+        .map(|medicamento| ConEtag::leer(medicamento, &condicion))
+        .ok_or_else(|| DomainError::NotFound(format!("El medicamento {} no existe", uuid)))
+}
+
+#[post("/medicamentos", data = "<medicamento_dto>")]
+pub async fn crear_medicamento(
+    medicamento_dto: Json<MedicamentoCreateDto>,
+    usuario: Usuario,
+    service: &State<MedicamentoServiceType>,
+    especies: &State<EspecieServiceType>
+) -> Result<ConEtag<Medicamento>, DomainError> {
+    let datos = datos_medicamento(medicamento_dto, especies)?;
+
+    let medicamento = service.lock()?
+        .crear_medicamento(datos, usuario.autor())?;
+
+    Ok(ConEtag::new(medicamento))
+}
+
+#[put("/medicamentos/<id>", data = "<medicamento_dto>")]
+pub async fn actualizar_medicamento(
+    id: String,
+    medicamento_dto: Json<MedicamentoCreateDto>,
+    if_match: IfMatch,
+    usuario: Usuario,
+    service: &State<MedicamentoServiceType>,
+    especies: &State<EspecieServiceType>
+) -> Result<ConEtag<Medicamento>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+    let esperada = if_match.version_esperada()?;
+    let datos = datos_medicamento(medicamento_dto, especies)?;
+
+    let medicamento = service.lock()?
+        .actualizar_medicamento(uuid, datos, &esperada, usuario.autor())?;
+
+    Ok(ConEtag::new(medicamento))
+}
+
+// Por defecto archiva el medicamento, que deja de poder prescribirse; con
+// `permanente=true` lo elimina si nunca se prescribió.
+#[delete("/medicamentos/<id>?<permanente>")]
+pub async fn eliminar_medicamento(
+    id: String,
+    permanente: Option<bool>,
+    if_match: IfMatch,
+    usuario: Usuario,
+    service: &State<MedicamentoServiceType>,
+    prescripciones: &State<PrescripcionServiceType>
+) -> Result<Status, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+    let esperada = if_match.version_esperada()?;
+
+    let mut medicamentos = service.lock()?;
+
+    if !permanente.unwrap_or(false) {
+        return medicamentos.archivar_medicamento(uuid, &esperada, usuario.autor())
+            .map(|_| Status::NoContent);
+    }
+
+    let prescripciones = prescripciones.lock()?;
+    eliminacion::eliminar_medicamento(&mut medicamentos, &prescripciones, uuid, &esperada)?;
+    Ok(Status::NoContent)
+}
+
+#[post("/medicamentos/<id>/restaurar")]
+pub async fn restaurar_medicamento(
+    id: String,
+    usuario: Usuario,
+    service: &State<MedicamentoServiceType>
+) -> Result<ConEtag<Medicamento>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+
+    service.lock()?
+        .restaurar_medicamento(uuid, usuario.autor())
+        .map(ConEtag::new)
+}
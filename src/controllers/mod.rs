@@ -2,8 +2,28 @@ pub mod clinica_controller;
 pub mod cliente_controller;
 pub mod mascota_controller;
 pub mod historia_clinica_controller;
+pub mod personal_controller;
+pub mod turno_controller;
+pub mod vacunacion_controller;
+pub mod medicamento_controller;
+pub mod prescripcion_controller;
+pub mod especie_controller;
+pub mod busqueda_controller;
+pub mod error;
+pub mod validacion;
+pub mod paginacion;
+pub mod parche;
+pub mod concurrencia;
+pub mod usuario;
 
 pub use clinica_controller::*;
 pub use cliente_controller::*;
 pub use mascota_controller::*;
 pub use historia_clinica_controller::*;
+pub use personal_controller::*;
+pub use turno_controller::*;
+pub use vacunacion_controller::*;
+pub use medicamento_controller::*;
+pub use prescripcion_controller::*;
+pub use especie_controller::*;
+pub use busqueda_controller::*;
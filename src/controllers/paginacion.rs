@@ -0,0 +1,108 @@
+use rocket::http::Header;
+use rocket::request::Request;
+use rocket::response::{self, Responder};
+use rocket::serde::json::Json;
+use serde::Serialize;
+use crate::error::DomainError;
+use crate::repositories::consulta::{CampoOrden, Consulta, Direccion, Pagina};
+
+pub const POR_PAGINA_PREDETERMINADO: u32 = 50;
+pub const POR_PAGINA_MAXIMO: u32 = 200;
+
+// Parámetros comunes a todos los listados: `?page=2&per_page=20&sort=-nombre`.
+// Un `-` delante del campo invierte el orden.
+#[derive(Debug, Default, FromForm)]
+pub struct ParametrosLista {
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+    pub sort: Option<String>,
+}
+
+impl ParametrosLista {
+    pub fn consulta<F, C: CampoOrden + Default>(&self, filtro: F) -> Result<Consulta<F, C>, DomainError> {
+        let pagina = self.page.unwrap_or(1);
+        if pagina == 0 {
+            return Err(DomainError::BadRequest("`page` empieza en 1".to_string()));
+        }
+
+        let por_pagina = self.per_page.unwrap_or(POR_PAGINA_PREDETERMINADO);
+        if !(1..=POR_PAGINA_MAXIMO).contains(&por_pagina) {
+            return Err(DomainError::BadRequest(format!(
+                "`per_page` debe estar entre 1 y {}", POR_PAGINA_MAXIMO
+            )));
+        }
+
+        let (orden, direccion) = match self.sort.as_deref().map(str::trim) {
+            None | Some("") => (C::default(), Direccion::Ascendente),
+            Some(campo) => {
+                let (nombre, direccion) = match campo.strip_prefix('-') {
+                    Some(nombre) => (nombre, Direccion::Descendente),
+                    None => (campo.strip_prefix('+').unwrap_or(campo), Direccion::Ascendente),
+                };
+                let orden = C::desde_nombre(nombre).ok_or_else(|| {
+                    DomainError::BadRequest(format!(
+                        "No se puede ordenar por `{}`; campos válidos: {}",
+                        nombre, C::NOMBRES.join(", ")
+                    ))
+                })?;
+                (orden, direccion)
+            }
+        };
+
+        Ok(Consulta { filtro, orden, direccion, pagina, por_pagina })
+    }
+}
+
+// Respuesta de un listado: el cuerpo es el arreglo de la página pedida y los
+// metadatos van en cabeceras (`X-Total-Count`, `X-Page`, `X-Per-Page`, `Link`).
+pub struct Paginado<T> {
+    pagina: Pagina<T>,
+    numero: u32,
+    por_pagina: u32,
+}
+
+impl<T> Paginado<T> {
+    pub fn new<F, C>(pagina: Pagina<T>, consulta: &Consulta<F, C>) -> Self {
+        Self { pagina, numero: consulta.pagina, por_pagina: consulta.por_pagina }
+    }
+
+    fn enlaces(&self, req: &Request<'_>) -> Vec<String> {
+        let total_paginas = self.pagina.total.div_ceil(self.por_pagina as usize).max(1) as u32;
+
+        // Se conservan los demás parámetros tal como llegaron
+        let ruta = req.uri().path().to_string();
+        let otros: Vec<&str> = req.uri().query()
+            .map(|q| q.as_str().split('&')
+                .filter(|p| !p.is_empty() && !p.starts_with("page="))
+                .collect())
+            .unwrap_or_default();
+        let enlace = |numero: u32, rel: &str| {
+            let mut parametros = otros.clone();
+            let page = format!("page={}", numero);
+            parametros.push(&page);
+            format!("<{}?{}>; rel=\"{}\"", ruta, parametros.join("&"), rel)
+        };
+
+        let mut enlaces = vec![enlace(1, "first")];
+        if self.numero > 1 {
+            enlaces.push(enlace((self.numero - 1).min(total_paginas), "prev"));
+        }
+        if self.numero < total_paginas {
+            enlaces.push(enlace(self.numero + 1, "next"));
+        }
+        enlaces.push(enlace(total_paginas, "last"));
+        enlaces
+    }
+}
+
+impl<'r, T: Serialize> Responder<'r, 'static> for Paginado<T> {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let enlaces = self.enlaces(req).join(", ");
+        let mut respuesta = Json(self.pagina.elementos).respond_to(req)?;
+        respuesta.set_header(Header::new("X-Total-Count", self.pagina.total.to_string()));
+        respuesta.set_header(Header::new("X-Page", self.numero.to_string()));
+        respuesta.set_header(Header::new("X-Per-Page", self.por_pagina.to_string()));
+        respuesta.set_header(Header::new("Link", enlaces));
+        Ok(respuesta)
+    }
+}
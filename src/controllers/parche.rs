@@ -0,0 +1,144 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use validator::Validate;
+use crate::error::{DomainError, FieldError};
+use crate::controllers::validacion::Normalizable;
+
+// Actualizaciones parciales con JSON Merge Patch (RFC 7396). El parche se
+// aplica sobre el DTO de la entidad actual y se valida el resultado completo;
+// el servicio recibe luego solo los campos que el parche mencionaba.
+
+// Campos presentes en el parche (incluidos los que se envían como `null`)
+pub struct CamposParche(HashSet<String>);
+
+impl CamposParche {
+    pub fn tomar<V>(&self, campo: &str, valor: V) -> Option<V> {
+        self.0.contains(campo).then_some(valor)
+    }
+
+    pub fn contiene(&self, campo: &str) -> bool {
+        self.0.contains(campo)
+    }
+}
+
+pub fn aplicar_merge_patch(objetivo: &mut Value, parche: &Value) {
+    let Value::Object(cambios) = parche else {
+        *objetivo = parche.clone();
+        return;
+    };
+    if !objetivo.is_object() {
+        *objetivo = Value::Object(Map::new());
+    }
+    if let Value::Object(destino) = objetivo {
+        for (clave, valor) in cambios {
+            if valor.is_null() {
+                destino.remove(clave);
+            } else {
+                aplicar_merge_patch(destino.entry(clave.clone()).or_insert(Value::Null), valor);
+            }
+        }
+    }
+}
+
+pub fn aplicar<T>(actual: &T, parche: Value) -> Result<(T, CamposParche), DomainError>
+where
+    T: Serialize + DeserializeOwned + Normalizable + Validate,
+{
+    let Value::Object(cambios) = &parche else {
+        return Err(DomainError::Validation("El parche debe ser un objeto JSON".to_string()));
+    };
+
+    let mut documento = serde_json::to_value(actual)
+        .map_err(|e| DomainError::Internal(format!("No se pudo serializar el DTO: {}", e)))?;
+
+    let desconocidos: Vec<FieldError> = cambios.keys()
+        .filter(|clave| documento.get(clave.as_str()).is_none())
+        .map(|clave| FieldError {
+            campo: clave.clone(),
+            regla: "unknown".to_string(),
+            mensaje: "no es un campo modificable".to_string(),
+        })
+        .collect();
+    if !desconocidos.is_empty() {
+        return Err(DomainError::InvalidFields(desconocidos));
+    }
+
+    aplicar_merge_patch(&mut documento, &parche);
+
+    // Un campo obligatorio quitado con `null` queda vacío y la validación lo
+    // informa como tal.
+    let mut dto: T = serde_json::from_value(documento)
+        .map_err(|e| DomainError::Validation(format!("El parche no es válido: {}", e)))?;
+    dto.normalizar();
+    dto.validate()?;
+
+    Ok((dto, CamposParche(cambios.keys().cloned().collect())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controllers::mascota_controller::MascotaCreateDto;
+    use chrono::NaiveDate;
+    use serde_json::json;
+
+    fn mascota() -> MascotaCreateDto {
+        MascotaCreateDto {
+            nombre: "Firulais".to_string(),
+            especie: "perro".to_string(),
+            raza: "mestizo".to_string(),
+            fecha_nacimiento: NaiveDate::from_ymd_opt(2020, 3, 1),
+            id_cliente: "6f9619ff-8b86-4d01-b42d-00cf4fc964ff".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn los_campos_ausentes_conservan_su_valor() {
+        let (dto, campos) = aplicar(&mascota(), json!({ "raza": "  caniche " })).unwrap();
+
+        assert_eq!(dto.raza, "caniche");
+        assert_eq!(dto.nombre, "Firulais");
+        assert_eq!(dto.fecha_nacimiento, NaiveDate::from_ymd_opt(2020, 3, 1));
+        assert!(campos.contiene("raza"));
+        assert_eq!(campos.tomar("nombre", dto.nombre), None);
+    }
+
+    #[test]
+    fn null_quita_un_campo_opcional() {
+        let (dto, campos) = aplicar(&mascota(), json!({ "fecha_nacimiento": null })).unwrap();
+
+        assert_eq!(dto.fecha_nacimiento, None);
+        // El campo figura en el parche aunque se haya enviado como `null`
+        assert_eq!(campos.tomar("fecha_nacimiento", dto.fecha_nacimiento), Some(None));
+    }
+
+    #[test]
+    fn null_en_un_campo_obligatorio_lo_informa_como_obligatorio() {
+        let Err(DomainError::InvalidFields(errores)) = aplicar(&mascota(), json!({ "nombre": null })) else {
+            panic!("se esperaba un error por campo");
+        };
+        assert_eq!(errores.len(), 1);
+        assert_eq!((errores[0].campo.as_str(), errores[0].regla.as_str()), ("nombre", "required"));
+    }
+
+    #[test]
+    fn rechaza_campos_desconocidos_y_parches_que_no_son_objetos() {
+        let Err(DomainError::InvalidFields(errores)) = aplicar(&mascota(), json!({ "color": "negro" })) else {
+            panic!("se esperaba un error por campo");
+        };
+        assert_eq!((errores[0].campo.as_str(), errores[0].regla.as_str()), ("color", "unknown"));
+
+        assert!(matches!(aplicar(&mascota(), json!(["nombre"])), Err(DomainError::Validation(_))));
+    }
+
+    #[test]
+    fn null_anidado_quita_solo_esa_clave() {
+        let mut objetivo = json!({ "a": { "b": 1, "c": 2 }, "d": 3 });
+        aplicar_merge_patch(&mut objetivo, &json!({ "a": { "b": null } }));
+
+        assert_eq!(objetivo, json!({ "a": { "c": 2 }, "d": 3 }));
+    }
+}
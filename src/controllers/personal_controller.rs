@@ -0,0 +1,297 @@
+use rocket::serde::json::Json;
+use rocket::State;
+use rocket::http::Status;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+use crate::error::DomainError;
+use crate::models::Personal;
+use crate::models::personal::RolPersonal;
+use crate::services::PersonalService;
+use crate::services::personal_service::{CambiosPersonal, DatosPersonal};
+use crate::services::integridad_referencial;
+use crate::services::eliminacion;
+use crate::repositories::personal_repository::{FiltroPersonal, PersonalRepository};
+use crate::controllers::clinica_controller::ClinicaServiceType;
+use crate::controllers::historia_clinica_controller::HistoriaClinicaServiceType;
+use crate::controllers::turno_controller::TurnoServiceType;
+use crate::controllers::vacunacion_controller::VacunacionServiceType;
+use crate::controllers::validacion::{self, Normalizable};
+use crate::controllers::paginacion::{Paginado, ParametrosLista};
+use crate::controllers::parche;
+use crate::controllers::usuario::Usuario;
+use crate::controllers::concurrencia::{ConEtag, IfMatch, IfNoneMatch};
+use std::sync::Mutex;
+
+#[derive(Debug, Default, Deserialize, Serialize, Validate)]
+#[serde(default)]
+pub struct PersonalCreateDto {
+    #[validate(length(min = 1, max = 80))]
+    pub nombre: String,
+    #[validate(length(min = 1, max = 80))]
+    pub apellido: String,
+    #[validate(required)]
+    pub rol: Option<RolPersonal>,
+    #[validate(length(min = 1, max = 40))]
+    pub matricula: Option<String>,
+    #[validate(custom(function = "validar_especialidades"))]
+    pub especialidades: Vec<String>,
+    // Al menos una clínica
+    #[validate(length(min = 1), custom(function = "validacion::validar_uuids"))]
+    pub clinicas: Vec<String>,
+    #[validate(email)]
+    pub correo: String,
+    #[validate(custom(function = "validacion::validar_telefono"))]
+    pub telefono: String,
+}
+
+fn validar_especialidades(especialidades: &[String]) -> Result<(), ValidationError> {
+    if especialidades.len() > 20 || especialidades.iter().any(|e| e.chars().count() > 80) {
+        return Err(ValidationError::new("length")
+            .with_message("admite hasta 20 especialidades de hasta 80 caracteres".into()));
+    }
+    Ok(())
+}
+
+// Quita los vacíos y los repetidos (sin distinguir mayúsculas), conservando el orden
+fn normalizar_lista(valores: &mut Vec<String>) {
+    let mut vistos: Vec<String> = Vec::new();
+    valores.retain_mut(|valor| {
+        validacion::normalizar_texto(valor);
+        let clave = valor.to_lowercase();
+        if valor.is_empty() || vistos.contains(&clave) {
+            return false;
+        }
+        vistos.push(clave);
+        true
+    });
+}
+
+impl Normalizable for PersonalCreateDto {
+    fn normalizar(&mut self) {
+        validacion::normalizar_texto(&mut self.nombre);
+        validacion::normalizar_texto(&mut self.apellido);
+        validacion::normalizar_opcional(&mut self.matricula);
+        normalizar_lista(&mut self.especialidades);
+        normalizar_lista(&mut self.clinicas);
+        validacion::normalizar_correo(&mut self.correo);
+        validacion::normalizar_telefono(&mut self.telefono);
+    }
+}
+
+impl From<&Personal> for PersonalCreateDto {
+    fn from(personal: &Personal) -> Self {
+        Self {
+            nombre: personal.nombre.clone(),
+            apellido: personal.apellido.clone(),
+            rol: Some(personal.rol),
+            matricula: personal.matricula.clone(),
+            especialidades: personal.especialidades.clone(),
+            clinicas: personal.clinicas.iter().map(Uuid::to_string).collect(),
+            correo: personal.correo.clone(),
+            telefono: personal.telefono.clone(),
+        }
+    }
+}
+
+impl PersonalCreateDto {
+    fn ids_clinicas(&self) -> Result<Vec<Uuid>, DomainError> {
+        Ok(self.clinicas.iter().map(|id| Uuid::parse_str(id)).collect::<Result<_, _>>()?)
+    }
+
+    fn datos(self) -> Result<DatosPersonal, DomainError> {
+        let clinicas = self.ids_clinicas()?;
+        Ok(DatosPersonal {
+            nombre: self.nombre,
+            apellido: self.apellido,
+            rol: self.rol.unwrap_or(RolPersonal::Veterinario),
+            matricula: self.matricula,
+            especialidades: self.especialidades,
+            clinicas,
+            correo: self.correo,
+            telefono: self.telefono,
+        })
+    }
+}
+
+pub type PersonalServiceType = Mutex<PersonalService<dyn PersonalRepository + Send>>;
+
+pub fn leer_rol(rol: Option<&str>) -> Result<Option<RolPersonal>, DomainError> {
+    rol.map(|codigo| {
+        RolPersonal::desde_codigo(codigo).ok_or_else(|| DomainError::BadRequest(format!(
+            "Rol desconocido `{}`; roles válidos: veterinario, auxiliar, recepcion, administracion",
+            codigo
+        )))
+    })
+    .transpose()
+}
+
+// Clínicas de la lista que la persona todavía no tenía
+fn clinicas_nuevas(clinicas: &[Uuid], actual: Option<&Personal>) -> Vec<Uuid> {
+    clinicas.iter()
+        .copied()
+        .filter(|id| actual.is_none_or(|p| !p.trabaja_en(*id)))
+        .collect()
+}
+
+#[get("/personal?<nombre>&<rol>&<especialidad>&<id_clinica>&<archivados>&<lista..>")]
+pub async fn listar_personal(
+    nombre: Option<String>,
+    rol: Option<String>,
+    especialidad: Option<String>,
+    id_clinica: Option<String>,
+    archivados: Option<bool>,
+    lista: ParametrosLista,
+    service: &State<PersonalServiceType>
+) -> Result<Paginado<Personal>, DomainError> {
+    let consulta = lista.consulta(FiltroPersonal {
+        id_clinica: id_clinica.as_deref().map(Uuid::parse_str).transpose()?,
+        rol: leer_rol(rol.as_deref())?,
+        nombre,
+        especialidad,
+        incluir_archivados: archivados.unwrap_or(false),
+    })?;
+
+    let personal = service.lock()?
+        .listar_personal(&consulta)?;
+    Ok(Paginado::new(personal, &consulta))
+}
+
+#[get("/personal/<id>")]
+pub async fn obtener_personal(
+    id: String,
+    condicion: IfNoneMatch,
+    service: &State<PersonalServiceType>
+) -> Result<ConEtag<Personal>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+
+    service.lock()?
+        .obtener_personal(uuid)?
+        .map(|personal| ConEtag::leer(personal, &condicion))
+        .ok_or_else(|| DomainError::NotFound(format!("El integrante del personal {} no existe", uuid)))
+}
+
+#[post("/personal", data = "<personal_dto>")]
+pub async fn crear_personal(
+    personal_dto: Json<PersonalCreateDto>,
+    usuario: Usuario,
+    service: &State<PersonalServiceType>,
+    clinicas: &State<ClinicaServiceType>
+) -> Result<ConEtag<Personal>, DomainError> {
+    let datos = validacion::validar(personal_dto)?.datos()?;
+
+    // Orden de bloqueo: clínicas, personal
+    let clinicas = clinicas.lock()?;
+    integridad_referencial::validar_personal(&clinicas, &datos.clinicas)?;
+
+    let personal = service.lock()?
+        .crear_personal(datos, usuario.autor())?;
+
+    Ok(ConEtag::new(personal))
+}
+
+#[put("/personal/<id>", data = "<personal_dto>")]
+pub async fn actualizar_personal(
+    id: String,
+    personal_dto: Json<PersonalCreateDto>,
+    if_match: IfMatch,
+    usuario: Usuario,
+    service: &State<PersonalServiceType>,
+    clinicas: &State<ClinicaServiceType>
+) -> Result<ConEtag<Personal>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+    let esperada = if_match.version_esperada()?;
+    let datos = validacion::validar(personal_dto)?.datos()?;
+
+    let clinicas = clinicas.lock()?;
+    let mut personal = service.lock()?;
+    let actual = personal.obtener_personal(uuid)?;
+    integridad_referencial::validar_personal(&clinicas, &clinicas_nuevas(&datos.clinicas, actual.as_ref()))?;
+
+    let personal = personal.actualizar_personal(uuid, datos, &esperada, usuario.autor())?;
+
+    Ok(ConEtag::new(personal))
+}
+
+// JSON Merge Patch: solo se modifican los campos enviados
+#[patch("/personal/<id>", data = "<parche>")]
+pub async fn modificar_personal(
+    id: String,
+    parche: Json<Value>,
+    if_match: IfMatch,
+    usuario: Usuario,
+    service: &State<PersonalServiceType>,
+    clinicas: &State<ClinicaServiceType>
+) -> Result<ConEtag<Personal>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+    let esperada = if_match.version_esperada()?;
+
+    let clinicas = clinicas.lock()?;
+    let mut personal = service.lock()?;
+    let actual = personal.obtener_personal(uuid)?
+        .ok_or_else(|| DomainError::NotFound(format!("El integrante del personal {} no existe", uuid)))?;
+    let (dto, campos) = parche::aplicar(&PersonalCreateDto::from(&actual), parche.into_inner())?;
+
+    let ids_clinicas = campos.tomar("clinicas", dto.ids_clinicas()?);
+    if let Some(ids) = &ids_clinicas {
+        integridad_referencial::validar_personal(&clinicas, &clinicas_nuevas(ids, Some(&actual)))?;
+    }
+
+    let personal = personal.modificar_personal(uuid, CambiosPersonal {
+        nombre: campos.tomar("nombre", dto.nombre),
+        apellido: campos.tomar("apellido", dto.apellido),
+        rol: campos.tomar("rol", dto.rol).flatten(),
+        matricula: campos.tomar("matricula", dto.matricula),
+        especialidades: campos.tomar("especialidades", dto.especialidades),
+        clinicas: ids_clinicas,
+        correo: campos.tomar("correo", dto.correo),
+        telefono: campos.tomar("telefono", dto.telefono),
+    }, &esperada, usuario.autor())?;
+
+    Ok(ConEtag::new(personal))
+}
+
+// Por defecto archiva; con `permanente=true` elimina al integrante si no
+// figura en ninguna entrada de historia clínica ni tiene turnos ni vacunas aplicadas.
+#[delete("/personal/<id>?<permanente>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn eliminar_personal(
+    id: String,
+    permanente: Option<bool>,
+    if_match: IfMatch,
+    usuario: Usuario,
+    service: &State<PersonalServiceType>,
+    historias: &State<HistoriaClinicaServiceType>,
+    turnos: &State<TurnoServiceType>,
+    vacunaciones: &State<VacunacionServiceType>
+) -> Result<Status, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+    let esperada = if_match.version_esperada()?;
+
+    let mut personal = service.lock()?;
+
+    if !permanente.unwrap_or(false) {
+        return personal.archivar_personal(uuid, &esperada, usuario.autor())
+            .map(|_| Status::NoContent);
+    }
+
+    let historias = historias.lock()?;
+    let turnos = turnos.lock()?;
+    let vacunaciones = vacunaciones.lock()?;
+    eliminacion::eliminar_personal(&mut personal, &historias, &turnos, &vacunaciones, uuid, &esperada)?;
+    Ok(Status::NoContent)
+}
+
+#[post("/personal/<id>/restaurar")]
+pub async fn restaurar_personal(
+    id: String,
+    usuario: Usuario,
+    service: &State<PersonalServiceType>
+) -> Result<ConEtag<Personal>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+
+    service.lock()?
+        .restaurar_personal(uuid, usuario.autor())
+        .map(ConEtag::new)
+}
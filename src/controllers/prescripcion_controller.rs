@@ -0,0 +1,327 @@
+use rocket::serde::json::Json;
+use rocket::State;
+use rocket::http::Status;
+use rocket::response::content::RawHtml;
+use serde::Deserialize;
+use uuid::Uuid;
+use validator::Validate;
+use crate::error::DomainError;
+use crate::models::Prescripcion;
+use crate::services::PrescripcionService;
+use crate::services::prescripcion_service::DatosPrescripcion;
+use crate::services::recetas::{self, Receta};
+use crate::repositories::prescripcion_repository::{FiltroPrescripciones, PrescripcionRepository};
+use crate::controllers::clinica_controller::ClinicaServiceType;
+use crate::controllers::cliente_controller::ClienteServiceType;
+use crate::controllers::mascota_controller::MascotaServiceType;
+use crate::controllers::historia_clinica_controller::{leer_limite, HistoriaClinicaServiceType};
+use crate::controllers::personal_controller::PersonalServiceType;
+use crate::controllers::medicamento_controller::MedicamentoServiceType;
+use crate::controllers::validacion::{self, Normalizable};
+use crate::controllers::paginacion::{Paginado, ParametrosLista};
+use crate::controllers::usuario::Usuario;
+use crate::controllers::concurrencia::{ConEtag, IfMatch, IfNoneMatch};
+use std::fmt::Write;
+use std::sync::Mutex;
+
+#[derive(Debug, Default, Deserialize, Validate)]
+#[serde(default)]
+pub struct PrescripcionCreateDto {
+    #[validate(custom(function = "validacion::validar_uuid"))]
+    pub id_medicamento: String,
+    #[validate(required, range(exclusive_min = 0.0, max = 1000.0))]
+    pub dosis_mg_kg: Option<f64>,
+    #[validate(required, range(min = 1, max = 168))]
+    pub frecuencia_horas: Option<u32>,
+    #[validate(required, range(min = 1, max = 365))]
+    pub duracion_dias: Option<u32>,
+    #[validate(length(min = 1, max = 1000))]
+    pub indicaciones: Option<String>,
+    // Por qué se prescribe un medicamento al que la mascota es alérgica
+    #[validate(length(min = 1, max = 500))]
+    pub motivo_excepcion: Option<String>,
+}
+
+impl Normalizable for PrescripcionCreateDto {
+    fn normalizar(&mut self) {
+        validacion::normalizar_texto(&mut self.id_medicamento);
+        validacion::normalizar_opcional(&mut self.indicaciones);
+        validacion::normalizar_opcional(&mut self.motivo_excepcion);
+    }
+}
+
+impl PrescripcionCreateDto {
+    fn datos(self) -> Result<DatosPrescripcion, DomainError> {
+        Ok(DatosPrescripcion {
+            id_medicamento: Uuid::parse_str(&self.id_medicamento)?,
+            dosis_mg_kg: self.dosis_mg_kg.unwrap_or_default(),
+            frecuencia_horas: self.frecuencia_horas.unwrap_or_default(),
+            duracion_dias: self.duracion_dias.unwrap_or_default(),
+            indicaciones: self.indicaciones,
+            motivo_excepcion: self.motivo_excepcion,
+        })
+    }
+}
+
+pub type PrescripcionServiceType = Mutex<PrescripcionService<dyn PrescripcionRepository + Send>>;
+
+#[get("/historias-clinicas/<id>/entradas/<id_entrada>/prescripciones")]
+pub async fn listar_prescripciones_entrada(
+    id: String,
+    id_entrada: String,
+    historias: &State<HistoriaClinicaServiceType>,
+    service: &State<PrescripcionServiceType>
+) -> Result<Json<Vec<Prescripcion>>, DomainError> {
+    let id_historia = Uuid::parse_str(&id)?;
+    let id_entrada = Uuid::parse_str(&id_entrada)?;
+
+    let historias = historias.lock()?;
+    let prescripciones = service.lock()?;
+    recetas::prescripciones_entrada(&historias, &prescripciones, id_historia, id_entrada)
+        .map(Json)
+}
+
+#[get("/historias-clinicas/<id>/entradas/<id_entrada>/prescripciones/<id_prescripcion>")]
+pub async fn obtener_prescripcion(
+    id: String,
+    id_entrada: String,
+    id_prescripcion: String,
+    condicion: IfNoneMatch,
+    historias: &State<HistoriaClinicaServiceType>,
+    service: &State<PrescripcionServiceType>
+) -> Result<ConEtag<Prescripcion>, DomainError> {
+    let id_historia = Uuid::parse_str(&id)?;
+    let id_entrada = Uuid::parse_str(&id_entrada)?;
+    let id_prescripcion = Uuid::parse_str(&id_prescripcion)?;
+
+    historias.lock()?
+        .obtener_entrada(id_historia, id_entrada)?;
+    service.lock()?
+        .obtener_prescripcion(id_entrada, id_prescripcion)
+        .map(|prescripcion| ConEtag::leer(prescripcion, &condicion))
+}
+
+// La dosis se calcula con el último peso registrado de la mascota; si queda
+// fuera del rango del catálogo para su especie la prescripción se guarda con
+// una advertencia. Si la mascota es alérgica al medicamento responde 409 salvo
+// que se indique `motivo_excepcion`.
+#[post("/historias-clinicas/<id>/entradas/<id_entrada>/prescripciones", data = "<prescripcion_dto>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn crear_prescripcion(
+    id: String,
+    id_entrada: String,
+    prescripcion_dto: Json<PrescripcionCreateDto>,
+    usuario: Usuario,
+    mascotas: &State<MascotaServiceType>,
+    historias: &State<HistoriaClinicaServiceType>,
+    medicamentos: &State<MedicamentoServiceType>,
+    service: &State<PrescripcionServiceType>
+) -> Result<ConEtag<Prescripcion>, DomainError> {
+    let id_historia = Uuid::parse_str(&id)?;
+    let id_entrada = Uuid::parse_str(&id_entrada)?;
+    let datos = validacion::validar(prescripcion_dto)?.datos()?;
+
+    // Orden de bloqueo: mascotas, historias, medicamentos, prescripciones
+    let mascotas = mascotas.lock()?;
+    let historias = historias.lock()?;
+    let medicamentos = medicamentos.lock()?;
+    let mut prescripciones = service.lock()?;
+
+    let prescripcion = recetas::prescribir(
+        &mascotas,
+        &historias,
+        &medicamentos,
+        &mut prescripciones,
+        id_historia,
+        id_entrada,
+        datos,
+        usuario.autor(),
+    )?;
+
+    Ok(ConEtag::new(prescripcion))
+}
+
+// Solo mientras la entrada está en borrador
+#[delete("/historias-clinicas/<id>/entradas/<id_entrada>/prescripciones/<id_prescripcion>")]
+pub async fn eliminar_prescripcion(
+    id: String,
+    id_entrada: String,
+    id_prescripcion: String,
+    if_match: IfMatch,
+    historias: &State<HistoriaClinicaServiceType>,
+    service: &State<PrescripcionServiceType>
+) -> Result<Status, DomainError> {
+    let id_historia = Uuid::parse_str(&id)?;
+    let id_entrada = Uuid::parse_str(&id_entrada)?;
+    let id_prescripcion = Uuid::parse_str(&id_prescripcion)?;
+    let esperada = if_match.version_esperada()?;
+
+    let historias = historias.lock()?;
+    let mut prescripciones = service.lock()?;
+    recetas::eliminar_prescripcion(&historias, &mut prescripciones, id_historia, id_entrada, id_prescripcion, &esperada)?;
+    Ok(Status::NoContent)
+}
+
+#[get("/mascotas/<id>/prescripciones?<id_medicamento>&<desde>&<hasta>&<lista..>")]
+pub async fn listar_prescripciones_mascota(
+    id: String,
+    id_medicamento: Option<String>,
+    desde: Option<String>,
+    hasta: Option<String>,
+    lista: ParametrosLista,
+    mascotas: &State<MascotaServiceType>,
+    service: &State<PrescripcionServiceType>
+) -> Result<Paginado<Prescripcion>, DomainError> {
+    let id_mascota = Uuid::parse_str(&id)?;
+    let consulta = lista.consulta(FiltroPrescripciones {
+        id_mascota: Some(id_mascota),
+        id_entrada: None,
+        id_medicamento: id_medicamento.as_deref().map(Uuid::parse_str).transpose()?,
+        desde: desde.as_deref().map(|d| leer_limite("desde", d, false)).transpose()?,
+        hasta: hasta.as_deref().map(|h| leer_limite("hasta", h, true)).transpose()?,
+    })?;
+
+    let mascotas = mascotas.lock()?;
+    if mascotas.obtener_mascota(id_mascota)?.is_none() {
+        return Err(DomainError::NotFound(format!("La mascota {} no existe", id_mascota)));
+    }
+
+    let prescripciones = service.lock()?
+        .listar_prescripciones(&consulta)?;
+    Ok(Paginado::new(prescripciones, &consulta))
+}
+
+// Receta para imprimir con las prescripciones de la mascota, de una consulta
+// (`id_entrada`) o de un período
+#[get("/mascotas/<id>/receta?<id_entrada>&<desde>&<hasta>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn obtener_receta_mascota(
+    id: String,
+    id_entrada: Option<String>,
+    desde: Option<String>,
+    hasta: Option<String>,
+    clinicas: &State<ClinicaServiceType>,
+    personal: &State<PersonalServiceType>,
+    clientes: &State<ClienteServiceType>,
+    mascotas: &State<MascotaServiceType>,
+    historias: &State<HistoriaClinicaServiceType>,
+    service: &State<PrescripcionServiceType>
+) -> Result<RawHtml<String>, DomainError> {
+    let id_mascota = Uuid::parse_str(&id)?;
+    let filtro = FiltroPrescripciones {
+        id_entrada: id_entrada.as_deref().map(Uuid::parse_str).transpose()?,
+        desde: desde.as_deref().map(|d| leer_limite("desde", d, false)).transpose()?,
+        hasta: hasta.as_deref().map(|h| leer_limite("hasta", h, true)).transpose()?,
+        ..Default::default()
+    };
+
+    let clinicas = clinicas.lock()?;
+    let personal = personal.lock()?;
+    let clientes = clientes.lock()?;
+    let mascotas = mascotas.lock()?;
+    let historias = historias.lock()?;
+    let prescripciones = service.lock()?;
+
+    let receta = recetas::receta(
+        &clinicas,
+        &personal,
+        &clientes,
+        &mascotas,
+        &historias,
+        &prescripciones,
+        id_mascota,
+        filtro,
+    )?;
+    Ok(RawHtml(renderizar_receta(&receta)))
+}
+
+fn escapar(texto: &str) -> String {
+    texto
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn renderizar_receta(receta: &Receta) -> String {
+    let mut html = String::new();
+    let clinica = &receta.clinica;
+    let cliente = &receta.cliente;
+    let mascota = &receta.mascota;
+
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"es\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Receta - {mascota}</title>\n\
+         <style>body{{font-family:sans-serif;margin:2em}}table{{border-collapse:collapse;width:100%}}\
+         th,td{{border:1px solid #999;padding:.4em;text-align:left}}.advertencia{{color:#a00}}</style>\n\
+         </head>\n<body>\n<h1>{clinica}</h1>\n<p>{direccion} · {telefono} · {correo}</p>\n\
+         <h2>Receta</h2>\n<p>Paciente: <strong>{mascota}</strong> ({especie}, {raza})<br>\n\
+         Propietario: {cliente}<br>\nEmitida: {emision}</p>\n",
+        mascota = escapar(&mascota.nombre),
+        clinica = escapar(&clinica.nombre),
+        direccion = escapar(&clinica.direccion),
+        telefono = escapar(&clinica.telefono),
+        correo = escapar(&clinica.correo),
+        especie = escapar(&mascota.especie),
+        raza = escapar(&mascota.raza),
+        cliente = escapar(&format!("{} {}", cliente.nombre, cliente.apellido)),
+        emision = receta.fecha_emision.format("%d/%m/%Y"),
+    );
+
+    if !mascota.alergias.is_empty() {
+        let alergias: Vec<String> = mascota.alergias.iter()
+            .map(|a| format!("{} ({})", escapar(&a.sustancia), a.severidad.codigo()))
+            .collect();
+        let _ = writeln!(html, "<p class=\"advertencia\">Alergias: {}</p>", alergias.join(", "));
+    }
+
+    if receta.items.is_empty() {
+        html.push_str("<p>Sin prescripciones.</p>\n");
+    } else {
+        html.push_str(
+            "<table>\n<tr><th>Fecha</th><th>Medicamento</th><th>Dosis</th><th>Frecuencia</th>\
+             <th>Duración</th><th>Indicaciones</th><th>Veterinario</th></tr>\n",
+        );
+        for item in &receta.items {
+            let p = &item.prescripcion;
+            let veterinario = item.veterinario.as_ref()
+                .map(|v| match &v.matricula {
+                    Some(matricula) => format!("{} {} (MP {})", v.nombre, v.apellido, matricula),
+                    None => format!("{} {}", v.nombre, v.apellido),
+                })
+                .unwrap_or_default();
+            let advertencia = p.advertencia.as_ref()
+                .map(|a| format!("<br><span class=\"advertencia\">{}</span>", escapar(a)))
+                .unwrap_or_default();
+            let excepcion = p.motivo_excepcion.as_ref()
+                .map(|m| format!("<br><span class=\"advertencia\">Prescripto pese a alergia: {}</span>", escapar(m)))
+                .unwrap_or_default();
+            let _ = writeln!(
+                html,
+                "<tr><td>{fecha}</td><td>{principio} {concentracion} mg/{unidad} ({presentacion}){excepcion}</td>\
+                 <td>{cantidad} {unidad} ({dosis_mg} mg; {dosis_mg_kg} mg/kg para {peso} kg){advertencia}</td>\
+                 <td>cada {frecuencia} h</td><td>{duracion} días</td><td>{indicaciones}</td><td>{veterinario}</td></tr>",
+                fecha = item.fecha_consulta.format("%d/%m/%Y"),
+                principio = escapar(&p.principio_activo),
+                concentracion = p.concentracion_mg,
+                unidad = escapar(&p.unidad),
+                presentacion = escapar(&p.presentacion),
+                excepcion = excepcion,
+                cantidad = p.cantidad,
+                dosis_mg = p.dosis_mg,
+                dosis_mg_kg = p.dosis_mg_kg,
+                peso = p.peso_kg,
+                advertencia = advertencia,
+                frecuencia = p.frecuencia_horas,
+                duracion = p.duracion_dias,
+                indicaciones = escapar(p.indicaciones.as_deref().unwrap_or_default()),
+                veterinario = escapar(&veterinario),
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
@@ -0,0 +1,386 @@
+use rocket::serde::json::Json;
+use rocket::State;
+use serde::Deserialize;
+use uuid::Uuid;
+use validator::Validate;
+use log::error;
+use crate::error::DomainError;
+use crate::models::{SignosVitales, Turno};
+use crate::models::turno::EstadoTurno;
+use crate::services::TurnoService;
+use crate::services::integridad_referencial;
+use crate::repositories::turno_repository::{FiltroTurnos, TurnoRepository};
+use crate::controllers::clinica_controller::ClinicaServiceType;
+use crate::controllers::cliente_controller::ClienteServiceType;
+use crate::controllers::mascota_controller::MascotaServiceType;
+use crate::controllers::historia_clinica_controller::{self, HistoriaClinicaServiceType};
+use crate::controllers::personal_controller::PersonalServiceType;
+use crate::controllers::validacion::{self, Normalizable};
+use crate::controllers::paginacion::{Paginado, ParametrosLista};
+use crate::controllers::usuario::Usuario;
+use crate::controllers::concurrencia::{ConEtag, IfMatch, IfNoneMatch};
+use chrono::{DateTime, Utc};
+use std::sync::Mutex;
+
+// La clínica y el cliente se toman de la mascota y su dueño actual
+#[derive(Debug, Default, Deserialize, Validate)]
+#[serde(default)]
+pub struct TurnoCreateDto {
+    #[validate(custom(function = "validacion::validar_uuid"))]
+    pub id_mascota: String,
+    #[validate(custom(function = "validacion::validar_uuid"))]
+    pub id_veterinario: String,
+    #[validate(required)]
+    pub inicio: Option<DateTime<Utc>>,
+    #[validate(required)]
+    pub fin: Option<DateTime<Utc>>,
+    #[validate(length(min = 1, max = 500))]
+    pub motivo: String,
+}
+
+impl Normalizable for TurnoCreateDto {
+    fn normalizar(&mut self) {
+        validacion::normalizar_texto(&mut self.id_mascota);
+        validacion::normalizar_texto(&mut self.id_veterinario);
+        validacion::normalizar_texto(&mut self.motivo);
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Validate)]
+#[serde(default)]
+pub struct ReprogramarTurnoDto {
+    #[validate(required)]
+    pub inicio: Option<DateTime<Utc>>,
+    #[validate(required)]
+    pub fin: Option<DateTime<Utc>>,
+    // Si falta se conserva el veterinario del turno
+    #[validate(custom(function = "validacion::validar_uuid"))]
+    pub id_veterinario: Option<String>,
+}
+
+impl Normalizable for ReprogramarTurnoDto {
+    fn normalizar(&mut self) {
+        validacion::normalizar_opcional(&mut self.id_veterinario);
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Validate)]
+#[serde(default)]
+pub struct CancelarTurnoDto {
+    #[validate(length(min = 1, max = 500))]
+    pub motivo: Option<String>,
+}
+
+impl Normalizable for CancelarTurnoDto {
+    fn normalizar(&mut self) {
+        validacion::normalizar_opcional(&mut self.motivo);
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Validate)]
+#[serde(default)]
+pub struct EstadoTurnoDto {
+    #[validate(required)]
+    pub estado: Option<EstadoTurno>,
+}
+
+impl Normalizable for EstadoTurnoDto {
+    fn normalizar(&mut self) {}
+}
+
+// Con `abrir_entrada` se agrega un borrador a la historia clínica de la
+// mascota, a nombre del veterinario del turno; la descripción por defecto es
+// el motivo del turno.
+#[derive(Debug, Default, Deserialize, Validate)]
+#[serde(default)]
+pub struct CompletarTurnoDto {
+    pub abrir_entrada: bool,
+    #[validate(length(min = 1, max = 2000))]
+    pub descripcion: Option<String>,
+    #[validate(length(max = 2000))]
+    pub diagnostico: Option<String>,
+    #[validate(length(max = 2000))]
+    pub tratamiento: Option<String>,
+    #[validate(length(max = 4000))]
+    pub notas: Option<String>,
+    #[validate(range(exclusive_min = 0.0, max = 2000.0))]
+    pub peso_kg: Option<f64>,
+    #[validate(range(min = 25.0, max = 45.0))]
+    pub temperatura_c: Option<f64>,
+    #[validate(range(min = 1, max = 500))]
+    pub frecuencia_cardiaca: Option<u32>,
+    #[validate(range(min = 1, max = 300))]
+    pub frecuencia_respiratoria: Option<u32>,
+}
+
+impl Normalizable for CompletarTurnoDto {
+    fn normalizar(&mut self) {
+        validacion::normalizar_opcional(&mut self.descripcion);
+        validacion::normalizar_opcional(&mut self.diagnostico);
+        validacion::normalizar_opcional(&mut self.tratamiento);
+        validacion::normalizar_opcional(&mut self.notas);
+    }
+}
+
+impl CompletarTurnoDto {
+    fn signos_vitales(&self) -> SignosVitales {
+        SignosVitales {
+            peso_kg: self.peso_kg,
+            temperatura_c: self.temperatura_c,
+            frecuencia_cardiaca: self.frecuencia_cardiaca,
+            frecuencia_respiratoria: self.frecuencia_respiratoria,
+        }
+    }
+}
+
+pub type TurnoServiceType = Mutex<TurnoService<dyn TurnoRepository + Send>>;
+
+pub fn leer_estado(estado: Option<&str>) -> Result<Option<EstadoTurno>, DomainError> {
+    estado.map(|codigo| {
+        EstadoTurno::desde_codigo(codigo).ok_or_else(|| DomainError::BadRequest(format!(
+            "Estado desconocido `{}`; estados válidos: {}", codigo, EstadoTurno::CODIGOS.join(", ")
+        )))
+    })
+    .transpose()
+}
+
+fn leer_uuid(valor: Option<&str>) -> Result<Option<Uuid>, DomainError> {
+    Ok(valor.map(Uuid::parse_str).transpose()?)
+}
+
+#[get("/turnos?<id_clinica>&<id_veterinario>&<id_mascota>&<id_cliente>&<estado>&<desde>&<hasta>&<activos>&<lista..>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn listar_turnos(
+    id_clinica: Option<String>,
+    id_veterinario: Option<String>,
+    id_mascota: Option<String>,
+    id_cliente: Option<String>,
+    estado: Option<String>,
+    desde: Option<String>,
+    hasta: Option<String>,
+    activos: Option<bool>,
+    lista: ParametrosLista,
+    service: &State<TurnoServiceType>
+) -> Result<Paginado<Turno>, DomainError> {
+    let consulta = lista.consulta(FiltroTurnos {
+        id_clinica: leer_uuid(id_clinica.as_deref())?,
+        id_veterinario: leer_uuid(id_veterinario.as_deref())?,
+        id_mascota: leer_uuid(id_mascota.as_deref())?,
+        id_cliente: leer_uuid(id_cliente.as_deref())?,
+        estado: leer_estado(estado.as_deref())?,
+        desde: desde.as_deref()
+            .map(|d| historia_clinica_controller::leer_limite("desde", d, false))
+            .transpose()?,
+        hasta: hasta.as_deref()
+            .map(|h| historia_clinica_controller::leer_limite("hasta", h, true))
+            .transpose()?,
+        solo_activos: activos.unwrap_or(false),
+    })?;
+
+    let turnos = service.lock()?
+        .listar_turnos(&consulta)?;
+    Ok(Paginado::new(turnos, &consulta))
+}
+
+#[get("/turnos/<id>")]
+pub async fn obtener_turno(
+    id: String,
+    condicion: IfNoneMatch,
+    service: &State<TurnoServiceType>
+) -> Result<ConEtag<Turno>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+
+    service.lock()?
+        .obtener_turno(uuid)?
+        .map(|turno| ConEtag::leer(turno, &condicion))
+        .ok_or_else(|| DomainError::NotFound(format!("El turno {} no existe", uuid)))
+}
+
+#[post("/turnos", data = "<turno_dto>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn crear_turno(
+    turno_dto: Json<TurnoCreateDto>,
+    usuario: Usuario,
+    service: &State<TurnoServiceType>,
+    clinicas: &State<ClinicaServiceType>,
+    personal: &State<PersonalServiceType>,
+    clientes: &State<ClienteServiceType>,
+    mascotas: &State<MascotaServiceType>
+) -> Result<ConEtag<Turno>, DomainError> {
+    let turno_dto = validacion::validar(turno_dto)?;
+    let id_mascota = Uuid::parse_str(&turno_dto.id_mascota)?;
+    let id_veterinario = Uuid::parse_str(&turno_dto.id_veterinario)?;
+
+    // Orden de bloqueo: clínicas, personal, clientes, mascotas, turnos
+    let clinicas = clinicas.lock()?;
+    let personal = personal.lock()?;
+    let clientes = clientes.lock()?;
+    let mascotas = mascotas.lock()?;
+    let mascota = mascotas.obtener_mascota(id_mascota)?
+        .ok_or_else(|| DomainError::Validation(format!("La mascota {} no existe", id_mascota)))?;
+    let cliente = clientes.obtener_cliente(mascota.id_cliente)?
+        .ok_or_else(|| DomainError::Validation(format!("El cliente {} no existe", mascota.id_cliente)))?;
+    integridad_referencial::validar_turno(
+        &clinicas,
+        &personal,
+        &clientes,
+        &mascotas,
+        cliente.id_clinica,
+        cliente.id,
+        id_mascota,
+        id_veterinario,
+    )?;
+
+    let turno = service.lock()?
+        .crear_turno(
+            cliente.id_clinica,
+            id_mascota,
+            cliente.id,
+            id_veterinario,
+            turno_dto.inicio.unwrap_or_default(),
+            turno_dto.fin.unwrap_or_default(),
+            turno_dto.motivo,
+            usuario.autor(),
+        )?;
+
+    Ok(ConEtag::new(turno))
+}
+
+#[post("/turnos/<id>/reprogramar", data = "<reprogramar_dto>")]
+pub async fn reprogramar_turno(
+    id: String,
+    reprogramar_dto: Json<ReprogramarTurnoDto>,
+    if_match: IfMatch,
+    usuario: Usuario,
+    service: &State<TurnoServiceType>,
+    personal: &State<PersonalServiceType>
+) -> Result<ConEtag<Turno>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+    let esperada = if_match.version_esperada()?;
+    let dto = validacion::validar(reprogramar_dto)?;
+    let id_veterinario = leer_uuid(dto.id_veterinario.as_deref())?;
+
+    let personal = personal.lock()?;
+    let mut turnos = service.lock()?;
+    if let Some(id_veterinario) = id_veterinario {
+        let turno = turnos.obtener_turno(uuid)?
+            .ok_or_else(|| DomainError::NotFound(format!("El turno {} no existe", uuid)))?;
+        integridad_referencial::validar_veterinario_clinica(&personal, id_veterinario, turno.id_clinica)?;
+    }
+
+    let turno = turnos.reprogramar_turno(
+        uuid,
+        dto.inicio.unwrap_or_default(),
+        dto.fin.unwrap_or_default(),
+        id_veterinario,
+        &esperada,
+        usuario.autor(),
+    )?;
+
+    Ok(ConEtag::new(turno))
+}
+
+#[post("/turnos/<id>/cancelar", data = "<cancelar_dto>")]
+pub async fn cancelar_turno(
+    id: String,
+    cancelar_dto: Json<CancelarTurnoDto>,
+    if_match: IfMatch,
+    usuario: Usuario,
+    service: &State<TurnoServiceType>
+) -> Result<ConEtag<Turno>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+    let esperada = if_match.version_esperada()?;
+    let dto = validacion::validar(cancelar_dto)?;
+
+    service.lock()?
+        .cancelar_turno(uuid, dto.motivo, &esperada, usuario.autor())
+        .map(ConEtag::new)
+}
+
+// Confirmación, llegada y ausencia; cancelar y completar tienen su propia ruta
+#[post("/turnos/<id>/estado", data = "<estado_dto>")]
+pub async fn cambiar_estado_turno(
+    id: String,
+    estado_dto: Json<EstadoTurnoDto>,
+    if_match: IfMatch,
+    usuario: Usuario,
+    service: &State<TurnoServiceType>
+) -> Result<ConEtag<Turno>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+    let esperada = if_match.version_esperada()?;
+    let estado = validacion::validar(estado_dto)?.estado.unwrap_or(EstadoTurno::Confirmado);
+
+    match estado {
+        EstadoTurno::Cancelado => Err(DomainError::BadRequest(format!(
+            "Para cancelar el turno use POST /api/turnos/{}/cancelar", uuid
+        ))),
+        EstadoTurno::Completado => Err(DomainError::BadRequest(format!(
+            "Para completar el turno use POST /api/turnos/{}/completar", uuid
+        ))),
+        _ => service.lock()?
+            .cambiar_estado(uuid, estado, &esperada, usuario.autor())
+            .map(ConEtag::new),
+    }
+}
+
+#[post("/turnos/<id>/completar", data = "<completar_dto>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn completar_turno(
+    id: String,
+    completar_dto: Json<CompletarTurnoDto>,
+    if_match: IfMatch,
+    usuario: Usuario,
+    service: &State<TurnoServiceType>,
+    clientes: &State<ClienteServiceType>,
+    mascotas: &State<MascotaServiceType>,
+    historias: &State<HistoriaClinicaServiceType>
+) -> Result<ConEtag<Turno>, DomainError> {
+    let uuid = Uuid::parse_str(&id)?;
+    let esperada = if_match.version_esperada()?;
+    let dto = validacion::validar(completar_dto)?;
+
+    // Orden de bloqueo: clientes, mascotas, historias, turnos
+    let clientes = clientes.lock()?;
+    let mascotas = mascotas.lock()?;
+    let mut historias = historias.lock()?;
+    let mut turnos = service.lock()?;
+    let turno = turnos.comprobar_transicion(uuid, EstadoTurno::Completado, &esperada)?;
+
+    // La transición ya está comprobada; si igual falla al guardar el turno, la
+    // entrada se descarta para que un reintento no la duplique
+    let entrada = if dto.abrir_entrada {
+        // La historia es la de la mascota con su dueño actual
+        let mascota = mascotas.obtener_mascota(turno.id_mascota)?
+            .ok_or_else(|| DomainError::Validation(format!("La mascota {} no existe", turno.id_mascota)))?;
+        integridad_referencial::validar_historia(&clientes, &mascotas, mascota.id, mascota.id_cliente)?;
+        let (historia, _) = historias.obtener_o_crear_historia(mascota.id, mascota.id_cliente, usuario.autor())?;
+
+        let signos_vitales = dto.signos_vitales();
+        let entrada = historias.agregar_entrada(
+            historia.id,
+            dto.descripcion.unwrap_or_else(|| turno.motivo.clone()),
+            dto.diagnostico.unwrap_or_default(),
+            dto.tratamiento.unwrap_or_default(),
+            dto.notas,
+            Some(turno.id_veterinario),
+            signos_vitales,
+            usuario.autor(),
+        )?;
+        Some((historia.id, entrada.id))
+    } else {
+        None
+    };
+
+    let id_entrada = entrada.map(|(_, id_entrada)| id_entrada);
+    match turnos.completar_turno(uuid, id_entrada, &esperada, usuario.autor()) {
+        Ok(turno) => Ok(ConEtag::new(turno)),
+        Err(e) => {
+            if let Some((id_historia, id_entrada)) = entrada {
+                if let Err(e) = historias.descartar_borrador(id_historia, id_entrada) {
+                    error!("No se pudo descartar la entrada {} del turno {}: {}", id_entrada, uuid, e);
+                }
+            }
+            Err(e)
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use rocket::request::{self, FromRequest, Request};
+
+// Usuario que realiza la operación, según la cabecera `X-Usuario`. La API no
+// autentica: el valor solo se anota como autor en la auditoría de los registros.
+pub struct Usuario(Option<String>);
+
+impl Usuario {
+    pub fn autor(&self) -> Option<&str> {
+        self.0.as_deref()
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Usuario {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let usuario = req.headers().get_one("X-Usuario")
+            .map(str::trim)
+            .filter(|u| !u.is_empty())
+            .map(str::to_string);
+        request::Outcome::Success(Usuario(usuario))
+    }
+}
@@ -0,0 +1,177 @@
+use rocket::serde::json::Json;
+use rocket::State;
+use rocket::http::Status;
+use serde::Deserialize;
+use uuid::Uuid;
+use validator::Validate;
+use chrono::NaiveDate;
+use crate::error::DomainError;
+use crate::models::Vacunacion;
+use crate::services::VacunacionService;
+use crate::services::vacunacion_service::DatosVacunacion;
+use crate::services::integridad_referencial;
+use crate::repositories::vacunacion_repository::{FiltroVacunaciones, VacunacionRepository};
+use crate::controllers::cliente_controller::ClienteServiceType;
+use crate::controllers::mascota_controller::MascotaServiceType;
+use crate::controllers::personal_controller::PersonalServiceType;
+use crate::controllers::validacion::{self, Normalizable};
+use crate::controllers::paginacion::{Paginado, ParametrosLista};
+use crate::controllers::usuario::Usuario;
+use crate::controllers::concurrencia::{ConEtag, IfMatch, IfNoneMatch};
+use std::sync::Mutex;
+
+#[derive(Debug, Default, Deserialize, Validate)]
+#[serde(default)]
+pub struct VacunacionCreateDto {
+    #[validate(length(min = 1, max = 120))]
+    pub vacuna: String,
+    #[validate(length(min = 1, max = 60))]
+    pub lote: String,
+    #[validate(length(min = 1, max = 120))]
+    pub fabricante: String,
+    // Cantidad o número de dosis, como figura en el carnet ("1 ml", "2ª dosis")
+    #[validate(length(min = 1, max = 60))]
+    pub dosis: String,
+    #[validate(required, custom(function = "validacion::validar_fecha_pasada"))]
+    pub fecha_aplicacion: Option<NaiveDate>,
+    pub proxima_dosis: Option<NaiveDate>,
+    // Veterinario que la aplicó; puede faltar si se aplicó en otra clínica
+    #[validate(custom(function = "validacion::validar_uuid"))]
+    pub id_veterinario: Option<String>,
+}
+
+impl Normalizable for VacunacionCreateDto {
+    fn normalizar(&mut self) {
+        validacion::normalizar_texto(&mut self.vacuna);
+        validacion::normalizar_texto(&mut self.lote);
+        validacion::normalizar_texto(&mut self.fabricante);
+        validacion::normalizar_texto(&mut self.dosis);
+        validacion::normalizar_opcional(&mut self.id_veterinario);
+    }
+}
+
+impl VacunacionCreateDto {
+    fn datos(self) -> Result<DatosVacunacion, DomainError> {
+        Ok(DatosVacunacion {
+            id_veterinario: self.id_veterinario.as_deref().map(Uuid::parse_str).transpose()?,
+            vacuna: self.vacuna,
+            lote: self.lote,
+            fabricante: self.fabricante,
+            dosis: self.dosis,
+            fecha_aplicacion: self.fecha_aplicacion.unwrap_or_default(),
+            proxima_dosis: self.proxima_dosis,
+        })
+    }
+}
+
+pub type VacunacionServiceType = Mutex<VacunacionService<dyn VacunacionRepository + Send>>;
+
+#[get("/mascotas/<id>/vacunas?<vacuna>&<lista..>")]
+pub async fn listar_vacunas_mascota(
+    id: String,
+    vacuna: Option<String>,
+    lista: ParametrosLista,
+    service: &State<VacunacionServiceType>,
+    mascotas: &State<MascotaServiceType>
+) -> Result<Paginado<Vacunacion>, DomainError> {
+    let id_mascota = Uuid::parse_str(&id)?;
+    let consulta = lista.consulta(FiltroVacunaciones {
+        id_mascota: Some(id_mascota),
+        vacuna,
+    })?;
+
+    let mascotas = mascotas.lock()?;
+    if mascotas.obtener_mascota(id_mascota)?.is_none() {
+        return Err(DomainError::NotFound(format!("La mascota {} no existe", id_mascota)));
+    }
+
+    let vacunaciones = service.lock()?
+        .listar_vacunaciones(&consulta)?;
+    Ok(Paginado::new(vacunaciones, &consulta))
+}
+
+#[get("/mascotas/<id>/vacunas/<id_vacuna>")]
+pub async fn obtener_vacuna_mascota(
+    id: String,
+    id_vacuna: String,
+    condicion: IfNoneMatch,
+    service: &State<VacunacionServiceType>
+) -> Result<ConEtag<Vacunacion>, DomainError> {
+    let id_mascota = Uuid::parse_str(&id)?;
+    let id_vacuna = Uuid::parse_str(&id_vacuna)?;
+
+    service.lock()?
+        .obtener_vacunacion(id_mascota, id_vacuna)
+        .map(|vacunacion| ConEtag::leer(vacunacion, &condicion))
+}
+
+#[post("/mascotas/<id>/vacunas", data = "<vacunacion_dto>")]
+pub async fn registrar_vacuna_mascota(
+    id: String,
+    vacunacion_dto: Json<VacunacionCreateDto>,
+    usuario: Usuario,
+    service: &State<VacunacionServiceType>,
+    personal: &State<PersonalServiceType>,
+    clientes: &State<ClienteServiceType>,
+    mascotas: &State<MascotaServiceType>
+) -> Result<ConEtag<Vacunacion>, DomainError> {
+    let id_mascota = Uuid::parse_str(&id)?;
+    let datos = validacion::validar(vacunacion_dto)?.datos()?;
+
+    // Orden de bloqueo: personal, clientes, mascotas, vacunaciones
+    let personal = personal.lock()?;
+    let clientes = clientes.lock()?;
+    let mascotas = mascotas.lock()?;
+    integridad_referencial::validar_vacunacion(&personal, &clientes, &mascotas, id_mascota, datos.id_veterinario)?;
+
+    let vacunacion = service.lock()?
+        .registrar_vacunacion(id_mascota, datos, usuario.autor())?;
+
+    Ok(ConEtag::new(vacunacion))
+}
+
+#[put("/mascotas/<id>/vacunas/<id_vacuna>", data = "<vacunacion_dto>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn actualizar_vacuna_mascota(
+    id: String,
+    id_vacuna: String,
+    vacunacion_dto: Json<VacunacionCreateDto>,
+    if_match: IfMatch,
+    usuario: Usuario,
+    service: &State<VacunacionServiceType>,
+    personal: &State<PersonalServiceType>,
+    clientes: &State<ClienteServiceType>,
+    mascotas: &State<MascotaServiceType>
+) -> Result<ConEtag<Vacunacion>, DomainError> {
+    let id_mascota = Uuid::parse_str(&id)?;
+    let id_vacuna = Uuid::parse_str(&id_vacuna)?;
+    let esperada = if_match.version_esperada()?;
+    let datos = validacion::validar(vacunacion_dto)?.datos()?;
+
+    let personal = personal.lock()?;
+    let clientes = clientes.lock()?;
+    let mascotas = mascotas.lock()?;
+    integridad_referencial::validar_vacunacion(&personal, &clientes, &mascotas, id_mascota, datos.id_veterinario)?;
+
+    let vacunacion = service.lock()?
+        .actualizar_vacunacion(id_mascota, id_vacuna, datos, &esperada, usuario.autor())?;
+
+    Ok(ConEtag::new(vacunacion))
+}
+
+// Las vacunaciones no se archivan: se eliminan para corregir un registro erróneo
+#[delete("/mascotas/<id>/vacunas/<id_vacuna>")]
+pub async fn eliminar_vacuna_mascota(
+    id: String,
+    id_vacuna: String,
+    if_match: IfMatch,
+    service: &State<VacunacionServiceType>
+) -> Result<Status, DomainError> {
+    let id_mascota = Uuid::parse_str(&id)?;
+    let id_vacuna = Uuid::parse_str(&id_vacuna)?;
+    let esperada = if_match.version_esperada()?;
+
+    service.lock()?
+        .eliminar_vacunacion(id_mascota, id_vacuna, &esperada)?;
+    Ok(Status::NoContent)
+}
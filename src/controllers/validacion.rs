@@ -0,0 +1,80 @@
+use chrono::{NaiveDate, Utc};
+use rocket::serde::json::Json;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+use crate::error::DomainError;
+
+// Reglas y normalizaciones compartidas por los DTOs. Los DTOs se normalizan
+// antes de validarse, de modo que los servicios reciben siempre datos limpios.
+
+pub trait Normalizable {
+    fn normalizar(&mut self);
+}
+
+// Normaliza y valida el cuerpo de una solicitud; los errores se informan por campo
+pub fn validar<T: Normalizable + Validate>(dto: Json<T>) -> Result<T, DomainError> {
+    let mut dto = dto.into_inner();
+    dto.normalizar();
+    dto.validate()?;
+    Ok(dto)
+}
+
+pub fn normalizar_texto(valor: &mut String) {
+    *valor = valor.trim().to_string();
+}
+
+pub fn normalizar_correo(correo: &mut String) {
+    *correo = correo.trim().to_lowercase();
+}
+
+// Quita separadores habituales ("+54 (11) 4555-1234" -> "+541145551234") y
+// reemplaza el prefijo internacional "00" por "+".
+pub fn normalizar_telefono(telefono: &mut String) {
+    let digitos: String = telefono
+        .chars()
+        .filter(|c| !c.is_whitespace() && !matches!(c, '-' | '.' | '(' | ')' | '/'))
+        .collect();
+
+    *telefono = match digitos.strip_prefix("00") {
+        Some(resto) => format!("+{}", resto),
+        None => digitos,
+    };
+}
+
+pub fn normalizar_opcional(valor: &mut Option<String>) {
+    if let Some(texto) = valor {
+        normalizar_texto(texto);
+        if texto.is_empty() {
+            *valor = None;
+        }
+    }
+}
+
+// Formato E.164: un '+' opcional seguido de 6 a 15 dígitos
+pub fn validar_telefono(telefono: &str) -> Result<(), ValidationError> {
+    let digitos = telefono.strip_prefix('+').unwrap_or(telefono);
+    if (6..=15).contains(&digitos.len()) && digitos.chars().all(|c| c.is_ascii_digit()) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("phone")
+            .with_message("debe contener entre 6 y 15 dígitos, con un '+' inicial opcional".into()))
+    }
+}
+
+pub fn validar_uuid(valor: &str) -> Result<(), ValidationError> {
+    Uuid::parse_str(valor)
+        .map(|_| ())
+        .map_err(|_| ValidationError::new("uuid").with_message("no es un identificador válido".into()))
+}
+
+pub fn validar_uuids(valores: &[String]) -> Result<(), ValidationError> {
+    valores.iter().try_for_each(|valor| validar_uuid(valor))
+}
+
+pub fn validar_fecha_pasada(fecha: &NaiveDate) -> Result<(), ValidationError> {
+    if *fecha > Utc::now().date_naive() {
+        return Err(ValidationError::new("not_future")
+            .with_message("no puede ser una fecha futura".into()));
+    }
+    Ok(())
+}
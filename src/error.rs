@@ -0,0 +1,124 @@
+use serde::Serialize;
+use std::fmt;
+use std::sync::PoisonError;
+use validator::{ValidationErrors, ValidationErrorsKind};
+
+// Error de validación de un campo concreto de un DTO
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldError {
+    pub campo: String,
+    pub regla: String,
+    pub mensaje: String,
+}
+
+// Error común a repositorios, servicios y controladores. Cada variante se
+// corresponde con un código HTTP (ver `controllers::error`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DomainError {
+    // La solicitud está mal formada (p. ej. un identificador que no es un UUID)
+    BadRequest(String),
+    NotFound(String),
+    // Los datos están bien formados pero no son aceptables
+    Validation(String),
+    // Uno o más campos del cuerpo no cumplen sus reglas de validación
+    InvalidFields(Vec<FieldError>),
+    Conflict(String),
+    // La versión indicada en `If-Match` no es la actual
+    PreconditionFailed(String),
+    // Falta `If-Match` en una operación que lo exige
+    PreconditionRequired(String),
+    // Falla del backend de almacenamiento; el detalle solo se registra en el log
+    #[cfg_attr(not(any(feature = "storage-file", feature = "storage-sqlite")), allow(dead_code))]
+    Storage(String),
+    Internal(String),
+}
+
+impl DomainError {
+    pub fn mensaje(&self) -> &str {
+        match self {
+            DomainError::InvalidFields(_) => "Los datos enviados no son válidos",
+            DomainError::BadRequest(m)
+            | DomainError::NotFound(m)
+            | DomainError::Validation(m)
+            | DomainError::Conflict(m)
+            | DomainError::PreconditionFailed(m)
+            | DomainError::PreconditionRequired(m)
+            | DomainError::Storage(m)
+            | DomainError::Internal(m) => m,
+        }
+    }
+}
+
+impl fmt::Display for DomainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.mensaje())?;
+        if let DomainError::InvalidFields(campos) = self {
+            let detalle: Vec<String> = campos.iter()
+                .map(|c| format!("{} ({})", c.campo, c.regla))
+                .collect();
+            write!(f, ": {}", detalle.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for DomainError {}
+
+impl From<uuid::Error> for DomainError {
+    fn from(err: uuid::Error) -> Self {
+        DomainError::BadRequest(format!("Identificador inválido: {}", err))
+    }
+}
+
+// Un Mutex envenenado indica que otro hilo falló a mitad de una operación
+impl<T> From<PoisonError<T>> for DomainError {
+    fn from(_: PoisonError<T>) -> Self {
+        DomainError::Internal("El estado compartido quedó inconsistente".to_string())
+    }
+}
+
+impl From<ValidationErrors> for DomainError {
+    fn from(errores: ValidationErrors) -> Self {
+        let mut campos = Vec::new();
+        for (campo, tipo) in errores.errors() {
+            let ValidationErrorsKind::Field(errores_campo) = tipo else {
+                continue;
+            };
+            for error in errores_campo {
+                // Un campo vacío (o ausente en el JSON) se informa como obligatorio
+                let vacio = error.params.get("value")
+                    .and_then(|v| v.as_str())
+                    .is_some_and(|v| v.is_empty());
+
+                let (regla, mensaje) = match (error.code.as_ref(), &error.message) {
+                    _ if vacio => ("required".to_string(), "es obligatorio".to_string()),
+                    ("length", _) => ("length".to_string(), mensaje_longitud(error)),
+                    ("email", _) => ("email".to_string(), "no es un correo electrónico válido".to_string()),
+                    (codigo, Some(mensaje)) => (codigo.to_string(), mensaje.to_string()),
+                    (codigo, None) => (codigo.to_string(), "no es válido".to_string()),
+                };
+                campos.push(FieldError { campo: campo.to_string(), regla, mensaje });
+            }
+        }
+        campos.sort_by(|a, b| a.campo.cmp(&b.campo));
+        DomainError::InvalidFields(campos)
+    }
+}
+
+fn mensaje_longitud(error: &validator::ValidationError) -> String {
+    let min = error.params.get("min").and_then(|v| v.as_u64());
+    let max = error.params.get("max").and_then(|v| v.as_u64());
+    match (min, max) {
+        (Some(min), Some(max)) => format!("debe tener entre {} y {} caracteres", min, max),
+        (Some(min), None) => format!("debe tener al menos {} caracteres", min),
+        (None, Some(max)) => format!("debe tener como máximo {} caracteres", max),
+        (None, None) => "tiene una longitud inválida".to_string(),
+    }
+}
+
+#[cfg(feature = "storage-sqlite")]
+impl From<rusqlite::Error> for DomainError {
+    fn from(err: rusqlite::Error) -> Self {
+        DomainError::Storage(err.to_string())
+    }
+}
@@ -4,34 +4,29 @@ mod models;
 mod services;
 mod controllers;
 mod repositories;
+mod config;
+mod error;
 
-use controllers::{
-    clinica_controller::*,
-    cliente_controller::*,
-    mascota_controller::*,
-    historia_clinica_controller::*,
-};
+use controllers::*;
 
-use repositories::{
-    clinica_repository::InMemoryClinicaRepository,
-    cliente_repository::InMemoryClienteRepository,
-    mascota_repository::InMemoryMascotaRepository,
-    historia_clinica_repository::InMemoryHistoriaClinicaRepository
-};
-#[cfg(feature = "storage-file")]
-use repositories::clinica_repository::FileClinicaRepository;
+use repositories::Repositorios;
 use services::{
     ClinicaService,
     ClienteService,
     MascotaService,
     HistoriaClinicaService,
+    PersonalService,
+    TurnoService,
+    VacunacionService,
+    MedicamentoService,
+    PrescripcionService,
+    EspecieService,
 };
 
 use std::sync::Mutex;
 use rocket::http::Method;
 use rocket_cors::{AllowedHeaders, AllowedOrigins, Cors, CorsOptions};
 use log::info;
-use env_logger;
 
 fn make_cors() -> Cors {
     CorsOptions {
@@ -40,10 +35,14 @@ fn make_cors() -> Cors {
             Method::Get,
             Method::Post,
             Method::Put,
+            Method::Patch,
             Method::Delete,
         ].into_iter().map(From::from).collect(), // Métodos HTTP permitidos
         allowed_headers: AllowedHeaders::all(), // Permite todos los headers
         allow_credentials: true, // Permite credenciales
+        // Metadatos de paginación y ETag visibles para el navegador
+        expose_headers: ["X-Total-Count", "X-Page", "X-Per-Page", "Link", "ETag"]
+            .iter().map(|h| h.to_string()).collect(),
         ..Default::default()
     }
     .to_cors()
@@ -53,49 +52,135 @@ fn make_cors() -> Cors {
 #[launch]
 fn rocket() -> _ {
     env_logger::init(); // Inicializa el logger
+    let figment = config::figment();
+    let storage = config::storage_config(&figment)
+        .unwrap_or_else(|e| panic!("{}", e));
+    info!("Almacenamiento: {} ({})", storage.backend, storage.data_dir.display());
+
     // Inicializar servicios
-    #[cfg(feature = "storage-memory")]
-    let clinica_repository = InMemoryClinicaRepository::new();
-    #[cfg(feature = "storage-file")]
-    let clinica_repository = FileClinicaRepository::new();
-    let cliente_repository = InMemoryClienteRepository::new();
-    let mascota_repository = InMemoryMascotaRepository::new();
-    let historia_clinica_repository = InMemoryHistoriaClinicaRepository::new();
+    let repositorios = Repositorios::desde_config(&storage)
+        .unwrap_or_else(|e| panic!("Error inicializando el almacenamiento: {}", e));
 
-    let clinica_service = ClinicaService::new(clinica_repository);
-    let cliente_service = ClienteService::new(cliente_repository);
-    let mascota_service = MascotaService::new(mascota_repository);
-    let historia_clinica_service = HistoriaClinicaService::new(historia_clinica_repository);
+    // Los servicios se gestionan sobre trait objects para que los controladores
+    // no dependan del backend de almacenamiento elegido.
+    let clinica_service = ClinicaService::new(repositorios.clinicas);
+    let cliente_service = ClienteService::new(repositorios.clientes);
+    let mascota_service = MascotaService::new(repositorios.mascotas);
+    let historia_clinica_service = HistoriaClinicaService::new(repositorios.historias_clinicas);
+    let personal_service = PersonalService::new(repositorios.personal);
+    let turno_service = TurnoService::new(repositorios.turnos);
+    let vacunacion_service = VacunacionService::new(repositorios.vacunaciones);
+    let medicamento_service = MedicamentoService::new(repositorios.medicamentos);
+    let prescripcion_service = PrescripcionService::new(repositorios.prescripciones);
+    let mut especie_service = EspecieService::new(repositorios.especies);
+    especie_service.cargar_catalogo_inicial()
+        .unwrap_or_else(|e| panic!("Error cargando el catálogo de especies: {}", e));
 
-    rocket::build()
+    rocket::custom(figment)
         .attach(make_cors()) // Agregamos el middleware CORS
         .manage(Mutex::new(clinica_service))
         .manage(Mutex::new(cliente_service))
         .manage(Mutex::new(mascota_service))
         .manage(Mutex::new(historia_clinica_service))
+        .manage(Mutex::new(personal_service))
+        .manage(Mutex::new(turno_service))
+        .manage(Mutex::new(vacunacion_service))
+        .manage(Mutex::new(medicamento_service))
+        .manage(Mutex::new(prescripcion_service))
+        .manage(Mutex::new(especie_service))
+        .register("/api", catchers![controllers::error::problema_por_defecto])
         .mount("/api", routes![
             // Clínicas
             listar_clinicas,
             obtener_clinica,
             crear_clinica,
             actualizar_clinica,
+            modificar_clinica,
             listar_clientes_clinica,
+            listar_personal_clinica,
+            obtener_agenda_clinica,
+            listar_vacunas_pendientes_clinica,
+            eliminar_clinica,
+            restaurar_clinica,
             // Clientes
             listar_clientes,
             obtener_cliente,
             crear_cliente,
+            actualizar_cliente,
+            modificar_cliente,
+            eliminar_cliente,
+            restaurar_cliente,
             // Mascotas
             listar_mascotas,
-            listar_mascotas_cliente,
             obtener_mascota,
             crear_mascota,
+            actualizar_mascota,
+            modificar_mascota,
+            obtener_signos_vitales_mascota,
+            eliminar_mascota,
+            restaurar_mascota,
             // Historias Clínicas
             obtener_historia_mascota,
             obtener_historia,
             crear_historia,
+            obtener_o_crear_historia_mascota,
+            fusionar_historias_mascota,
             listar_entradas,
             crear_entrada,
-            actualizar_cliente,
-            actualizar_mascota,
+            obtener_entrada,
+            modificar_borrador,
+            firmar_entrada,
+            verificar_firma_entrada,
+            enmendar_entrada,
+            listar_revisiones_entrada,
+            eliminar_historia,
+            restaurar_historia,
+            // Personal
+            listar_personal,
+            obtener_personal,
+            crear_personal,
+            actualizar_personal,
+            modificar_personal,
+            eliminar_personal,
+            restaurar_personal,
+            // Turnos
+            listar_turnos,
+            obtener_turno,
+            crear_turno,
+            reprogramar_turno,
+            cancelar_turno,
+            cambiar_estado_turno,
+            completar_turno,
+            // Vacunas
+            listar_vacunas_mascota,
+            obtener_vacuna_mascota,
+            registrar_vacuna_mascota,
+            actualizar_vacuna_mascota,
+            eliminar_vacuna_mascota,
+            // Medicamentos
+            listar_medicamentos,
+            obtener_medicamento,
+            crear_medicamento,
+            actualizar_medicamento,
+            eliminar_medicamento,
+            restaurar_medicamento,
+            // Prescripciones
+            listar_prescripciones_entrada,
+            obtener_prescripcion,
+            crear_prescripcion,
+            eliminar_prescripcion,
+            listar_prescripciones_mascota,
+            obtener_receta_mascota,
+            // Especies y razas
+            listar_especies,
+            listar_opciones_especies,
+            obtener_especie,
+            crear_especie,
+            actualizar_especie,
+            eliminar_especie,
+            restaurar_especie,
+            migrar_especies_mascotas,
+            // Búsqueda
+            buscar,
         ])
 }
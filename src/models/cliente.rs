@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -10,6 +11,23 @@ pub struct Cliente {
     pub telefono: String,
     pub direccion: String,
     pub id_clinica: Uuid,
+    #[serde(default)]
+    pub archivado: bool,
+    #[serde(default)]
+    pub fecha_archivado: Option<DateTime<Utc>>,
+    // Se incrementa en cada modificación; los registros anteriores al versionado quedan en 0
+    #[serde(default)]
+    pub version: u64,
+    // Alta y última modificación; los registros anteriores a la auditoría toman
+    // la fecha en que se cargaron y quedan sin autor
+    #[serde(default = "Utc::now")]
+    pub fecha_creacion: DateTime<Utc>,
+    #[serde(default = "Utc::now")]
+    pub fecha_actualizacion: DateTime<Utc>,
+    #[serde(default)]
+    pub creado_por: Option<String>,
+    #[serde(default)]
+    pub actualizado_por: Option<String>,
 }
 
 impl Cliente {
@@ -20,7 +38,9 @@ impl Cliente {
         telefono: String,
         direccion: String,
         id_clinica: Uuid,
+        autor: Option<&str>,
     ) -> Self {
+        let ahora = Utc::now();
         Self {
             id: Uuid::new_v4(),
             nombre,
@@ -29,6 +49,13 @@ impl Cliente {
             telefono,
             direccion,
             id_clinica,
+            archivado: false,
+            fecha_archivado: None,
+            version: 1,
+            fecha_creacion: ahora,
+            fecha_actualizacion: ahora,
+            creado_por: autor.map(str::to_string),
+            actualizado_por: autor.map(str::to_string),
         }
     }
 }
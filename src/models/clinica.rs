@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -8,16 +9,47 @@ pub struct Clinica {
     pub direccion: String,
     pub telefono: String,
     pub correo: String,
+    #[serde(default)]
+    pub archivado: bool,
+    #[serde(default)]
+    pub fecha_archivado: Option<DateTime<Utc>>,
+    // Se incrementa en cada modificación; los registros anteriores al versionado quedan en 0
+    #[serde(default)]
+    pub version: u64,
+    // Alta y última modificación; los registros anteriores a la auditoría toman
+    // la fecha en que se cargaron y quedan sin autor
+    #[serde(default = "Utc::now")]
+    pub fecha_creacion: DateTime<Utc>,
+    #[serde(default = "Utc::now")]
+    pub fecha_actualizacion: DateTime<Utc>,
+    #[serde(default)]
+    pub creado_por: Option<String>,
+    #[serde(default)]
+    pub actualizado_por: Option<String>,
 }
 
 impl Clinica {
-    pub fn new(nombre: String, direccion: String, telefono: String, correo: String) -> Self {
+    pub fn new(
+        nombre: String,
+        direccion: String,
+        telefono: String,
+        correo: String,
+        autor: Option<&str>,
+    ) -> Self {
+        let ahora = Utc::now();
         Self {
             id: Uuid::new_v4(),
             nombre,
             direccion,
             telefono,
             correo,
+            archivado: false,
+            fecha_archivado: None,
+            version: 1,
+            fecha_creacion: ahora,
+            fecha_actualizacion: ahora,
+            creado_por: autor.map(str::to_string),
+            actualizado_por: autor.map(str::to_string),
         }
     }
 }
@@ -1,7 +1,68 @@
 use chrono::DateTime;
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
+use super::signos_vitales::SignosVitales;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EstadoEntrada {
+    // Editable por el veterinario hasta que la firma
+    Borrador,
+    // De solo lectura; solo puede corregirse con una enmienda
+    Firmada,
+    // Cargada antes de que existiera la firma: es definitiva como una firmada,
+    // pero no tiene firmante ni hash que verificar
+    Legado,
+}
+
+impl EstadoEntrada {
+    fn legado() -> Self {
+        EstadoEntrada::Legado
+    }
+
+    #[cfg(feature = "storage-sqlite")]
+    pub fn codigo(self) -> &'static str {
+        match self {
+            EstadoEntrada::Borrador => "borrador",
+            EstadoEntrada::Firmada => "firmada",
+            EstadoEntrada::Legado => "legado",
+        }
+    }
+
+    #[cfg(feature = "storage-sqlite")]
+    pub fn desde_codigo(codigo: &str) -> Option<Self> {
+        match codigo {
+            "borrador" => Some(EstadoEntrada::Borrador),
+            "firmada" => Some(EstadoEntrada::Firmada),
+            "legado" => Some(EstadoEntrada::Legado),
+            _ => None,
+        }
+    }
+}
+
+// Lo que cubre el hash de una entrada firmada, en un orden fijo
+#[derive(Serialize)]
+struct ContenidoFirmado<'a> {
+    id: Uuid,
+    fecha: DateTime<Utc>,
+    descripcion: &'a str,
+    diagnostico: &'a str,
+    tratamiento: &'a str,
+    notas: Option<&'a str>,
+    id_veterinario: Option<Uuid>,
+    peso_kg: Option<f64>,
+    temperatura_c: Option<f64>,
+    frecuencia_cardiaca: Option<u32>,
+    frecuencia_respiratoria: Option<u32>,
+    id_original: Option<Uuid>,
+    enmienda_a: Option<Uuid>,
+    motivo_enmienda: Option<&'a str>,
+    firmada_por: Option<&'a str>,
+    fecha_firma: Option<DateTime<Utc>>,
+    id_firmante: Option<Uuid>,
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct EntradaHistoriaClinica {
@@ -12,24 +73,228 @@ pub struct EntradaHistoriaClinica {
     pub diagnostico: String,
     pub tratamiento: String,
     pub notas: Option<String>,
+    // Profesional que atendió; las entradas anteriores al directorio de personal no lo tienen
+    #[serde(default)]
+    pub id_veterinario: Option<Uuid>,
+    // Medidos en la consulta; van en el mismo nivel que el resto de los campos
+    #[serde(flatten)]
+    pub signos_vitales: SignosVitales,
+    #[serde(default)]
+    pub version: u64,
+    // Alta y última modificación; los registros anteriores a la auditoría toman
+    // la fecha en que se cargaron y quedan sin autor
+    #[serde(default = "Utc::now")]
+    pub fecha_creacion: DateTime<Utc>,
+    #[serde(default = "Utc::now")]
+    pub fecha_actualizacion: DateTime<Utc>,
+    #[serde(default)]
+    pub creado_por: Option<String>,
+    #[serde(default)]
+    pub actualizado_por: Option<String>,
+    // Las entradas no se modifican: una corrección es una revisión nueva que
+    // reemplaza a `enmienda_a`. `id_original` es la primera entrada de la cadena.
+    #[serde(default)]
+    pub id_original: Option<Uuid>,
+    #[serde(default)]
+    pub enmienda_a: Option<Uuid>,
+    #[serde(default)]
+    pub motivo_enmienda: Option<String>,
+    #[serde(default = "EstadoEntrada::legado")]
+    pub estado: EstadoEntrada,
+    // Quién y cuándo firmó, y el SHA-256 del contenido firmado; las entradas
+    // anteriores a la firma no los tienen. `id_firmante` es el veterinario del
+    // directorio de personal y `firmada_por` su nombre al momento de firmar.
+    #[serde(default)]
+    pub id_firmante: Option<Uuid>,
+    #[serde(default)]
+    pub firmada_por: Option<String>,
+    #[serde(default)]
+    pub fecha_firma: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub hash_contenido: Option<String>,
 }
 
 impl EntradaHistoriaClinica {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         id_historia_clinica: Uuid,
         descripcion: String,
         diagnostico: String,
         tratamiento: String,
         notas: Option<String>,
+        id_veterinario: Option<Uuid>,
+        signos_vitales: SignosVitales,
+        autor: Option<&str>,
     ) -> Self {
+        let ahora = Utc::now();
         Self {
             id: Uuid::new_v4(),
             id_historia_clinica,
-            fecha: Utc::now(),
+            fecha: ahora,
             descripcion,
             diagnostico,
             tratamiento,
             notas,
+            id_veterinario,
+            signos_vitales,
+            version: 1,
+            fecha_creacion: ahora,
+            fecha_actualizacion: ahora,
+            creado_por: autor.map(str::to_string),
+            actualizado_por: autor.map(str::to_string),
+            id_original: None,
+            enmienda_a: None,
+            motivo_enmienda: None,
+            estado: EstadoEntrada::Borrador,
+            id_firmante: None,
+            firmada_por: None,
+            fecha_firma: None,
+            hash_contenido: None,
+        }
+    }
+
+    pub fn en_borrador(&self) -> bool {
+        self.estado == EstadoEntrada::Borrador
+    }
+
+    // Entrada que esta revisión reemplaza; una enmienda en borrador todavía no
+    // reemplaza a nadie
+    pub fn reemplaza_a(&self) -> Option<Uuid> {
+        self.enmienda_a.filter(|_| !self.en_borrador())
+    }
+
+    // Pasa la entrada a firmada; desde ahí el hash permite comprobar que su
+    // contenido no cambió
+    pub fn firmar(&mut self, id_firmante: Uuid, nombre: &str) {
+        self.estado = EstadoEntrada::Firmada;
+        self.id_firmante = Some(id_firmante);
+        self.firmada_por = Some(nombre.to_string());
+        self.fecha_firma = Some(Utc::now());
+        self.hash_contenido = Some(self.calcular_hash());
+    }
+
+    // SHA-256 en hexadecimal del contenido y los datos de la firma
+    pub fn calcular_hash(&self) -> String {
+        let contenido = ContenidoFirmado {
+            id: self.id,
+            fecha: self.fecha,
+            descripcion: &self.descripcion,
+            diagnostico: &self.diagnostico,
+            tratamiento: &self.tratamiento,
+            notas: self.notas.as_deref(),
+            id_veterinario: self.id_veterinario,
+            peso_kg: self.signos_vitales.peso_kg,
+            temperatura_c: self.signos_vitales.temperatura_c,
+            frecuencia_cardiaca: self.signos_vitales.frecuencia_cardiaca,
+            frecuencia_respiratoria: self.signos_vitales.frecuencia_respiratoria,
+            id_original: self.id_original,
+            enmienda_a: self.enmienda_a,
+            motivo_enmienda: self.motivo_enmienda.as_deref(),
+            firmada_por: self.firmada_por.as_deref(),
+            fecha_firma: self.fecha_firma,
+            id_firmante: self.id_firmante,
+        };
+        let bytes = serde_json::to_vec(&contenido).unwrap_or_default();
+        format!("{:x}", Sha256::digest(bytes))
+    }
+
+    // Revisión que reemplaza a esta entrada; conserva la fecha de la atención
+    #[allow(clippy::too_many_arguments)]
+    pub fn enmendar(
+        &self,
+        descripcion: String,
+        diagnostico: String,
+        tratamiento: String,
+        notas: Option<String>,
+        id_veterinario: Option<Uuid>,
+        signos_vitales: SignosVitales,
+        motivo: String,
+        autor: Option<&str>,
+    ) -> Self {
+        Self {
+            fecha: self.fecha,
+            id_original: Some(self.id_original.unwrap_or(self.id)),
+            enmienda_a: Some(self.id),
+            motivo_enmienda: Some(motivo),
+            ..Self::new(
+                self.id_historia_clinica,
+                descripcion,
+                diagnostico,
+                tratamiento,
+                notas,
+                id_veterinario,
+                signos_vitales,
+                autor,
+            )
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entrada() -> EntradaHistoriaClinica {
+        EntradaHistoriaClinica::new(
+            Uuid::new_v4(),
+            "Control anual".to_string(),
+            "Sano".to_string(),
+            "Ninguno".to_string(),
+            None,
+            Some(Uuid::new_v4()),
+            SignosVitales { peso_kg: Some(12.5), ..Default::default() },
+            None,
+        )
+    }
+
+    #[test]
+    fn firmar_registra_el_hash_del_contenido() {
+        let mut entrada = entrada();
+        let id_firmante = Uuid::new_v4();
+        entrada.firmar(id_firmante, "Ana Gómez");
+
+        assert_eq!(entrada.estado, EstadoEntrada::Firmada);
+        assert_eq!(entrada.id_firmante, Some(id_firmante));
+        assert_eq!(entrada.firmada_por.as_deref(), Some("Ana Gómez"));
+        assert_eq!(entrada.hash_contenido, Some(entrada.calcular_hash()));
+    }
+
+    #[test]
+    fn el_hash_sobrevive_a_guardar_y_leer() {
+        let mut entrada = entrada();
+        entrada.firmar(Uuid::new_v4(), "Ana Gómez");
+
+        let leida: EntradaHistoriaClinica =
+            serde_json::from_str(&serde_json::to_string(&entrada).unwrap()).unwrap();
+        assert_eq!(leida.hash_contenido, Some(leida.calcular_hash()));
+    }
+
+    #[test]
+    fn el_hash_cambia_con_el_contenido_o_el_firmante() {
+        let mut entrada = entrada();
+        entrada.firmar(Uuid::new_v4(), "Ana Gómez");
+        let hash = entrada.calcular_hash();
+
+        let mut alterada = entrada.clone();
+        alterada.diagnostico = "Otitis".to_string();
+        assert_ne!(alterada.calcular_hash(), hash);
+
+        let mut alterada = entrada.clone();
+        alterada.signos_vitales.peso_kg = Some(13.0);
+        assert_ne!(alterada.calcular_hash(), hash);
+
+        let mut alterada = entrada.clone();
+        alterada.id_firmante = Some(Uuid::new_v4());
+        assert_ne!(alterada.calcular_hash(), hash);
+    }
+
+    #[test]
+    fn sin_estado_la_entrada_es_de_legado() {
+        let mut valor = serde_json::to_value(entrada()).unwrap();
+        valor.as_object_mut().unwrap().remove("estado");
+
+        let leida: EntradaHistoriaClinica = serde_json::from_value(valor).unwrap();
+        assert_eq!(leida.estado, EstadoEntrada::Legado);
+        assert!(!leida.en_borrador());
+    }
+}
@@ -0,0 +1,90 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+// Idioma de las etiquetas que toda especie y raza debe tener; es también el
+// que se usa cuando falta la del idioma pedido
+pub const IDIOMA_PREDETERMINADO: &str = "es";
+
+// Nombre para mostrar por idioma ("es", "en", "pt", ...)
+pub type Etiquetas = BTreeMap<String, String>;
+
+fn etiqueta_en<'a>(etiquetas: &'a Etiquetas, idioma: &str, codigo: &'a str) -> &'a str {
+    etiquetas.get(idioma)
+        .or_else(|| etiquetas.get(IDIOMA_PREDETERMINADO))
+        .map(String::as_str)
+        .unwrap_or(codigo)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Raza {
+    pub codigo: String,
+    pub etiquetas: Etiquetas,
+    // Otras formas en que se la nombra ("ovejero" para el pastor alemán)
+    #[serde(default)]
+    pub alias: Vec<String>,
+}
+
+impl Raza {
+    pub fn etiqueta(&self, idioma: &str) -> &str {
+        etiqueta_en(&self.etiquetas, idioma, &self.codigo)
+    }
+}
+
+// Especie del catálogo de referencia. Las mascotas guardan su `codigo` y el de
+// la raza; una especie sin razas admite cualquier raza como texto libre.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Especie {
+    pub id: Uuid,
+    pub codigo: String,
+    pub etiquetas: Etiquetas,
+    #[serde(default)]
+    pub alias: Vec<String>,
+    #[serde(default)]
+    pub razas: Vec<Raza>,
+    #[serde(default)]
+    pub archivado: bool,
+    #[serde(default)]
+    pub fecha_archivado: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub version: u64,
+    #[serde(default = "Utc::now")]
+    pub fecha_creacion: DateTime<Utc>,
+    #[serde(default = "Utc::now")]
+    pub fecha_actualizacion: DateTime<Utc>,
+    #[serde(default)]
+    pub creado_por: Option<String>,
+    #[serde(default)]
+    pub actualizado_por: Option<String>,
+}
+
+impl Especie {
+    pub fn new(
+        codigo: String,
+        etiquetas: Etiquetas,
+        alias: Vec<String>,
+        razas: Vec<Raza>,
+        autor: Option<&str>,
+    ) -> Self {
+        let ahora = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            codigo,
+            etiquetas,
+            alias,
+            razas,
+            archivado: false,
+            fecha_archivado: None,
+            version: 1,
+            fecha_creacion: ahora,
+            fecha_actualizacion: ahora,
+            creado_por: autor.map(str::to_string),
+            actualizado_por: autor.map(str::to_string),
+        }
+    }
+
+    pub fn etiqueta(&self, idioma: &str) -> &str {
+        etiqueta_en(&self.etiquetas, idioma, &self.codigo)
+    }
+}
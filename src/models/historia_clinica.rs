@@ -10,10 +10,22 @@ pub struct HistoriaClinica {
     pub id_cliente: Uuid,
     pub fecha_creacion: DateTime<Utc>,
     pub fecha_actualizacion: DateTime<Utc>,
+    #[serde(default)]
+    pub archivado: bool,
+    #[serde(default)]
+    pub fecha_archivado: Option<DateTime<Utc>>,
+    // Se incrementa en cada modificación; los registros anteriores al versionado quedan en 0
+    #[serde(default)]
+    pub version: u64,
+    // Los registros anteriores a la auditoría quedan sin autor
+    #[serde(default)]
+    pub creado_por: Option<String>,
+    #[serde(default)]
+    pub actualizado_por: Option<String>,
 }
 
 impl HistoriaClinica {
-    pub fn new(id_mascota: Uuid, id_cliente: Uuid) -> Self {
+    pub fn new(id_mascota: Uuid, id_cliente: Uuid, autor: Option<&str>) -> Self {
         let ahora = Utc::now();
         Self {
             id: Uuid::new_v4(),
@@ -21,6 +33,11 @@ impl HistoriaClinica {
             id_cliente,
             fecha_creacion: ahora,
             fecha_actualizacion: ahora,
+            archivado: false,
+            fecha_archivado: None,
+            version: 1,
+            creado_por: autor.map(str::to_string),
+            actualizado_por: autor.map(str::to_string),
         }
     }
 }
@@ -1,7 +1,43 @@
-use chrono::NaiveDate;
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severidad {
+    Leve,
+    Moderada,
+    Grave,
+}
+
+impl Severidad {
+    pub fn codigo(self) -> &'static str {
+        match self {
+            Severidad::Leve => "leve",
+            Severidad::Moderada => "moderada",
+            Severidad::Grave => "grave",
+        }
+    }
+}
+
+// Sustancia a la que la mascota reaccionó; se coteja con el principio activo
+// de cada prescripción
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Alergia {
+    pub sustancia: String,
+    pub severidad: Severidad,
+    #[serde(default)]
+    pub reaccion: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CondicionCronica {
+    pub condicion: String,
+    pub severidad: Severidad,
+    #[serde(default)]
+    pub notas: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Mascota {
     pub id: Uuid,
@@ -10,16 +46,42 @@ pub struct Mascota {
     pub raza: String,
     pub fecha_nacimiento: Option<NaiveDate>,
     pub id_cliente: Uuid,
+    #[serde(default)]
+    pub alergias: Vec<Alergia>,
+    #[serde(default)]
+    pub condiciones_cronicas: Vec<CondicionCronica>,
+    #[serde(default)]
+    pub archivado: bool,
+    #[serde(default)]
+    pub fecha_archivado: Option<DateTime<Utc>>,
+    // Se incrementa en cada modificación; los registros anteriores al versionado quedan en 0
+    #[serde(default)]
+    pub version: u64,
+    // Alta y última modificación; los registros anteriores a la auditoría toman
+    // la fecha en que se cargaron y quedan sin autor
+    #[serde(default = "Utc::now")]
+    pub fecha_creacion: DateTime<Utc>,
+    #[serde(default = "Utc::now")]
+    pub fecha_actualizacion: DateTime<Utc>,
+    #[serde(default)]
+    pub creado_por: Option<String>,
+    #[serde(default)]
+    pub actualizado_por: Option<String>,
 }
 
 impl Mascota {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         nombre: String,
         especie: String,
         raza: String,
         fecha_nacimiento: Option<NaiveDate>,
         id_cliente: Uuid,
+        alergias: Vec<Alergia>,
+        condiciones_cronicas: Vec<CondicionCronica>,
+        autor: Option<&str>,
     ) -> Self {
+        let ahora = Utc::now();
         Self {
             id: Uuid::new_v4(),
             nombre,
@@ -27,6 +89,15 @@ impl Mascota {
             raza,
             fecha_nacimiento,
             id_cliente,
+            alergias,
+            condiciones_cronicas,
+            archivado: false,
+            fecha_archivado: None,
+            version: 1,
+            fecha_creacion: ahora,
+            fecha_actualizacion: ahora,
+            creado_por: autor.map(str::to_string),
+            actualizado_por: autor.map(str::to_string),
         }
     }
 }
@@ -0,0 +1,80 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// Dosis aceptada para una especie, en mg de principio activo por kg de peso
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RangoDosis {
+    // Código de la especie en el catálogo
+    pub especie: String,
+    pub minima_mg_kg: f64,
+    pub maxima_mg_kg: f64,
+}
+
+impl RangoDosis {
+    pub fn contiene(&self, dosis_mg_kg: f64) -> bool {
+        (self.minima_mg_kg..=self.maxima_mg_kg).contains(&dosis_mg_kg)
+    }
+}
+
+// Medicamento del catálogo. La concentración es la cantidad de principio
+// activo por unidad de la presentación (mg por comprimido, mg por ml, ...)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Medicamento {
+    pub id: Uuid,
+    pub principio_activo: String,
+    pub nombre_comercial: Option<String>,
+    pub presentacion: String,
+    pub concentracion_mg: f64,
+    pub unidad: String,
+    pub dosis: Vec<RangoDosis>,
+    #[serde(default)]
+    pub archivado: bool,
+    #[serde(default)]
+    pub fecha_archivado: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub version: u64,
+    #[serde(default = "Utc::now")]
+    pub fecha_creacion: DateTime<Utc>,
+    #[serde(default = "Utc::now")]
+    pub fecha_actualizacion: DateTime<Utc>,
+    #[serde(default)]
+    pub creado_por: Option<String>,
+    #[serde(default)]
+    pub actualizado_por: Option<String>,
+}
+
+impl Medicamento {
+    pub fn new(
+        principio_activo: String,
+        nombre_comercial: Option<String>,
+        presentacion: String,
+        concentracion_mg: f64,
+        unidad: String,
+        dosis: Vec<RangoDosis>,
+        autor: Option<&str>,
+    ) -> Self {
+        let ahora = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            principio_activo,
+            nombre_comercial,
+            presentacion,
+            concentracion_mg,
+            unidad,
+            dosis,
+            archivado: false,
+            fecha_archivado: None,
+            version: 1,
+            fecha_creacion: ahora,
+            fecha_actualizacion: ahora,
+            creado_por: autor.map(str::to_string),
+            actualizado_por: autor.map(str::to_string),
+        }
+    }
+
+    // Rango de dosis para la especie, por su código del catálogo
+    pub fn rango_para(&self, especie: &str) -> Option<&RangoDosis> {
+        self.dosis.iter().find(|rango| rango.especie == especie)
+    }
+}
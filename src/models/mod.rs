@@ -3,9 +3,138 @@ pub mod cliente;
 pub mod mascota;
 pub mod historia_clinica;
 pub mod entrada_historia_clinica;
+pub mod personal;
+pub mod turno;
+pub mod vacunacion;
+pub mod medicamento;
+pub mod prescripcion;
+pub mod signos_vitales;
+pub mod especie;
 
 pub use clinica::Clinica;
 pub use cliente::Cliente;
 pub use mascota::Mascota;
 pub use historia_clinica::HistoriaClinica;
 pub use entrada_historia_clinica::EntradaHistoriaClinica;
+pub use personal::Personal;
+pub use turno::Turno;
+pub use vacunacion::Vacunacion;
+pub use medicamento::Medicamento;
+pub use prescripcion::Prescripcion;
+pub use signos_vitales::SignosVitales;
+pub use especie::Especie;
+
+use chrono::Utc;
+
+// Entidades con control de concurrencia optimista: la versión se publica como `ETag`
+pub trait Versionado {
+    fn version(&self) -> u64;
+
+    // Versión de otra entidad cuyos datos van en la misma respuesta; entra en el
+    // ETag para que un cambio en ella también invalide las lecturas condicionales
+    fn version_incluida(&self) -> Option<u64> {
+        None
+    }
+}
+
+macro_rules! impl_versionado {
+    ($($tipo:ty),*) => {
+        $(impl Versionado for $tipo {
+            fn version(&self) -> u64 {
+                self.version
+            }
+        })*
+    };
+}
+
+impl_versionado!(
+    Clinica, Cliente, Mascota, HistoriaClinica, EntradaHistoriaClinica, Personal, Turno, Vacunacion, Medicamento,
+    Prescripcion, Especie
+);
+
+// Entidades con fecha y autor de alta y de última modificación
+pub trait Auditado {
+    // Anota una modificación: avanza la versión y registra cuándo y quién la hizo
+    fn registrar_cambio(&mut self, autor: Option<&str>);
+}
+
+macro_rules! impl_auditado {
+    ($($tipo:ty),*) => {
+        $(impl Auditado for $tipo {
+            fn registrar_cambio(&mut self, autor: Option<&str>) {
+                self.version += 1;
+                self.fecha_actualizacion = Utc::now();
+                self.actualizado_por = autor.map(str::to_string);
+            }
+        })*
+    };
+}
+
+impl_auditado!(
+    Clinica, Cliente, Mascota, HistoriaClinica, EntradaHistoriaClinica, Personal, Turno, Vacunacion, Medicamento,
+    Prescripcion, Especie
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    fn cliente(autor: Option<&str>) -> Cliente {
+        Cliente::new(
+            "Ana".to_string(),
+            "Gómez".to_string(),
+            "ana@example.com".to_string(),
+            "555-0000".to_string(),
+            "Calle 1".to_string(),
+            Uuid::new_v4(),
+            autor,
+        )
+    }
+
+    #[test]
+    fn el_alta_registra_al_autor_en_ambos_campos() {
+        let cliente = cliente(Some("recepcion"));
+
+        assert_eq!(cliente.version, 1);
+        assert_eq!(cliente.creado_por.as_deref(), Some("recepcion"));
+        assert_eq!(cliente.actualizado_por.as_deref(), Some("recepcion"));
+        assert_eq!(cliente.fecha_creacion, cliente.fecha_actualizacion);
+    }
+
+    #[test]
+    fn registrar_cambio_conserva_los_datos_del_alta() {
+        let mut cliente = cliente(Some("recepcion"));
+        let alta = cliente.fecha_creacion;
+
+        cliente.registrar_cambio(Some("dra.lopez"));
+        assert_eq!(cliente.version, 2);
+        assert_eq!(cliente.fecha_creacion, alta);
+        assert!(cliente.fecha_actualizacion >= alta);
+        assert_eq!(cliente.creado_por.as_deref(), Some("recepcion"));
+        assert_eq!(cliente.actualizado_por.as_deref(), Some("dra.lopez"));
+
+        // Un cambio sin usuario identificado no arrastra el autor anterior
+        cliente.registrar_cambio(None);
+        assert_eq!(cliente.version, 3);
+        assert_eq!(cliente.actualizado_por, None);
+        assert_eq!(cliente.creado_por.as_deref(), Some("recepcion"));
+    }
+
+    #[test]
+    fn los_registros_anteriores_a_la_auditoria_quedan_sin_autor() {
+        let cliente: Cliente = serde_json::from_value(json!({
+            "id": Uuid::new_v4(),
+            "nombre": "Ana",
+            "apellido": "Gómez",
+            "correo": "ana@example.com",
+            "telefono": "555-0000",
+            "direccion": "Calle 1",
+            "id_clinica": Uuid::new_v4(),
+        })).unwrap();
+
+        assert_eq!(cliente.version, 0);
+        assert_eq!((cliente.creado_por, cliente.actualizado_por), (None, None));
+    }
+}
@@ -0,0 +1,105 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RolPersonal {
+    Veterinario,
+    Auxiliar,
+    Recepcion,
+    Administracion,
+}
+
+impl RolPersonal {
+    #[cfg(feature = "storage-sqlite")]
+    pub fn codigo(self) -> &'static str {
+        match self {
+            RolPersonal::Veterinario => "veterinario",
+            RolPersonal::Auxiliar => "auxiliar",
+            RolPersonal::Recepcion => "recepcion",
+            RolPersonal::Administracion => "administracion",
+        }
+    }
+
+    pub fn desde_codigo(codigo: &str) -> Option<Self> {
+        match codigo {
+            "veterinario" => Some(RolPersonal::Veterinario),
+            "auxiliar" => Some(RolPersonal::Auxiliar),
+            "recepcion" => Some(RolPersonal::Recepcion),
+            "administracion" => Some(RolPersonal::Administracion),
+            _ => None,
+        }
+    }
+}
+
+// Integrante del personal de una o más clínicas
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Personal {
+    pub id: Uuid,
+    pub nombre: String,
+    pub apellido: String,
+    pub rol: RolPersonal,
+    // Matrícula profesional; obligatoria para los veterinarios
+    pub matricula: Option<String>,
+    #[serde(default)]
+    pub especialidades: Vec<String>,
+    // Clínicas en las que trabaja
+    #[serde(default)]
+    pub clinicas: Vec<Uuid>,
+    pub correo: String,
+    pub telefono: String,
+    #[serde(default)]
+    pub archivado: bool,
+    #[serde(default)]
+    pub fecha_archivado: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub version: u64,
+    #[serde(default = "Utc::now")]
+    pub fecha_creacion: DateTime<Utc>,
+    #[serde(default = "Utc::now")]
+    pub fecha_actualizacion: DateTime<Utc>,
+    #[serde(default)]
+    pub creado_por: Option<String>,
+    #[serde(default)]
+    pub actualizado_por: Option<String>,
+}
+
+impl Personal {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        nombre: String,
+        apellido: String,
+        rol: RolPersonal,
+        matricula: Option<String>,
+        especialidades: Vec<String>,
+        clinicas: Vec<Uuid>,
+        correo: String,
+        telefono: String,
+        autor: Option<&str>,
+    ) -> Self {
+        let ahora = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            nombre,
+            apellido,
+            rol,
+            matricula,
+            especialidades,
+            clinicas,
+            correo,
+            telefono,
+            archivado: false,
+            fecha_archivado: None,
+            version: 1,
+            fecha_creacion: ahora,
+            fecha_actualizacion: ahora,
+            creado_por: autor.map(str::to_string),
+            actualizado_por: autor.map(str::to_string),
+        }
+    }
+
+    pub fn trabaja_en(&self, id_clinica: Uuid) -> bool {
+        self.clinicas.contains(&id_clinica)
+    }
+}
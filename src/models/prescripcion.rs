@@ -0,0 +1,187 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use super::mascota::Alergia;
+use super::medicamento::{Medicamento, RangoDosis};
+
+// Indicación de un medicamento en una consulta. Copia los datos del catálogo y
+// el peso con que se calculó la dosis: la receta no cambia si después se edita
+// el medicamento o se vuelve a pesar a la mascota.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Prescripcion {
+    pub id: Uuid,
+    pub id_mascota: Uuid,
+    pub id_entrada: Uuid,
+    pub id_medicamento: Uuid,
+    pub principio_activo: String,
+    pub presentacion: String,
+    pub concentracion_mg: f64,
+    pub unidad: String,
+    pub peso_kg: f64,
+    pub dosis_mg_kg: f64,
+    // Dosis por toma, en mg y en unidades de la presentación
+    pub dosis_mg: f64,
+    pub cantidad: f64,
+    pub frecuencia_horas: u32,
+    pub duracion_dias: u32,
+    pub indicaciones: Option<String>,
+    // Rango del catálogo para la especie de la mascota, si lo tiene
+    pub rango: Option<RangoDosis>,
+    // Por qué conviene revisar la dosis: fuera de rango o especie sin rango
+    pub advertencia: Option<String>,
+    // Alergias de la mascota que coinciden con el medicamento y motivo por el
+    // que se prescribió de todos modos
+    #[serde(default)]
+    pub alergias: Vec<Alergia>,
+    #[serde(default)]
+    pub motivo_excepcion: Option<String>,
+    #[serde(default)]
+    pub version: u64,
+    #[serde(default = "Utc::now")]
+    pub fecha_creacion: DateTime<Utc>,
+    #[serde(default = "Utc::now")]
+    pub fecha_actualizacion: DateTime<Utc>,
+    #[serde(default)]
+    pub creado_por: Option<String>,
+    #[serde(default)]
+    pub actualizado_por: Option<String>,
+}
+
+fn redondear(valor: f64) -> f64 {
+    (valor * 100.0).round() / 100.0
+}
+
+impl Prescripcion {
+    // Calcula la dosis por toma a partir del peso y la compara con el rango
+    // del catálogo para la especie
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        id_mascota: Uuid,
+        id_entrada: Uuid,
+        medicamento: &Medicamento,
+        especie: &str,
+        peso_kg: f64,
+        dosis_mg_kg: f64,
+        frecuencia_horas: u32,
+        duracion_dias: u32,
+        indicaciones: Option<String>,
+        autor: Option<&str>,
+    ) -> Self {
+        let rango = medicamento.rango_para(especie).cloned();
+        let advertencia = match &rango {
+            None => Some(format!(
+                "El catálogo no indica dosis de {} para la especie {}",
+                medicamento.principio_activo, especie
+            )),
+            Some(rango) if !rango.contiene(dosis_mg_kg) => Some(format!(
+                "La dosis de {} mg/kg está fuera del rango para {} ({}–{} mg/kg)",
+                dosis_mg_kg, especie, rango.minima_mg_kg, rango.maxima_mg_kg
+            )),
+            Some(_) => None,
+        };
+        let dosis_mg = dosis_mg_kg * peso_kg;
+
+        let ahora = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            id_mascota,
+            id_entrada,
+            id_medicamento: medicamento.id,
+            principio_activo: medicamento.principio_activo.clone(),
+            presentacion: medicamento.presentacion.clone(),
+            concentracion_mg: medicamento.concentracion_mg,
+            unidad: medicamento.unidad.clone(),
+            peso_kg,
+            dosis_mg_kg,
+            dosis_mg: redondear(dosis_mg),
+            cantidad: redondear(dosis_mg / medicamento.concentracion_mg),
+            frecuencia_horas,
+            duracion_dias,
+            indicaciones,
+            rango,
+            advertencia,
+            alergias: Vec::new(),
+            motivo_excepcion: None,
+            version: 1,
+            fecha_creacion: ahora,
+            fecha_actualizacion: ahora,
+            creado_por: autor.map(str::to_string),
+            actualizado_por: autor.map(str::to_string),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Amoxicilina en suspensión de 50 mg/ml con rango solo para perros
+    fn amoxicilina() -> Medicamento {
+        Medicamento::new(
+            "Amoxicilina".to_string(),
+            None,
+            "Suspensión oral".to_string(),
+            50.0,
+            "ml".to_string(),
+            vec![RangoDosis { especie: "perro".to_string(), minima_mg_kg: 10.0, maxima_mg_kg: 20.0 }],
+            None,
+        )
+    }
+
+    fn prescribir(especie: &str, peso_kg: f64, dosis_mg_kg: f64) -> Prescripcion {
+        Prescripcion::new(
+            Uuid::new_v4(), Uuid::new_v4(), &amoxicilina(), especie, peso_kg, dosis_mg_kg, 12, 7, None, None,
+        )
+    }
+
+    #[test]
+    fn calcula_la_dosis_por_toma_con_el_peso() {
+        let prescripcion = prescribir("perro", 12.5, 15.0);
+        assert_eq!(prescripcion.dosis_mg, 187.5);
+        assert_eq!(prescripcion.cantidad, 3.75);
+        assert_eq!(prescripcion.concentracion_mg, 50.0);
+        assert_eq!(prescripcion.unidad, "ml");
+    }
+
+    #[test]
+    fn redondea_a_dos_decimales() {
+        let prescripcion = prescribir("perro", 3.333, 12.0);
+        assert_eq!(prescripcion.dosis_mg, 40.0);
+        assert_eq!(prescripcion.cantidad, 0.8);
+
+        let prescripcion = prescribir("perro", 7.1, 11.3);
+        assert_eq!(prescripcion.dosis_mg, 80.23);
+        assert_eq!(prescripcion.cantidad, 1.6);
+    }
+
+    #[test]
+    fn sin_advertencia_dentro_del_rango() {
+        let prescripcion = prescribir("perro", 10.0, 15.0);
+        assert!(prescripcion.advertencia.is_none());
+        let rango = prescripcion.rango.unwrap();
+        assert_eq!((rango.minima_mg_kg, rango.maxima_mg_kg), (10.0, 20.0));
+    }
+
+    #[test]
+    fn los_extremos_del_rango_son_validos() {
+        assert!(prescribir("perro", 10.0, 10.0).advertencia.is_none());
+        assert!(prescribir("perro", 10.0, 20.0).advertencia.is_none());
+    }
+
+    #[test]
+    fn advierte_una_dosis_fuera_del_rango() {
+        for dosis in [9.99, 20.01] {
+            let prescripcion = prescribir("perro", 10.0, dosis);
+            assert!(prescripcion.rango.is_some());
+            assert!(prescripcion.advertencia.unwrap().contains("fuera del rango"));
+        }
+    }
+
+    #[test]
+    fn advierte_una_especie_sin_rango() {
+        let prescripcion = prescribir("gato", 4.0, 15.0);
+        assert!(prescripcion.rango.is_none());
+        assert!(prescripcion.advertencia.unwrap().contains("no indica dosis"));
+        assert_eq!(prescripcion.dosis_mg, 60.0);
+    }
+}
@@ -0,0 +1,129 @@
+use serde::{Deserialize, Serialize};
+
+// Signos vitales medidos en una consulta; cualquiera puede faltar
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub struct SignosVitales {
+    // El más reciente es el que se usa para calcular dosis
+    #[serde(default)]
+    pub peso_kg: Option<f64>,
+    #[serde(default)]
+    pub temperatura_c: Option<f64>,
+    // Latidos por minuto
+    #[serde(default)]
+    pub frecuencia_cardiaca: Option<u32>,
+    // Respiraciones por minuto
+    #[serde(default)]
+    pub frecuencia_respiratoria: Option<u32>,
+}
+
+impl SignosVitales {
+    pub fn vacios(&self) -> bool {
+        *self == SignosVitales::default()
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Copy)]
+pub struct RangoReferencia {
+    pub minimo: f64,
+    pub maximo: f64,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NivelAlerta {
+    Bajo,
+    Alto,
+}
+
+// Signo fuera del rango de referencia de la especie
+#[derive(Debug, Serialize, Clone)]
+pub struct AlertaSignoVital {
+    pub signo: &'static str,
+    pub valor: f64,
+    pub nivel: NivelAlerta,
+    pub rango: RangoReferencia,
+}
+
+// Valores normales de un adulto en reposo. El peso no tiene referencia: depende
+// de la raza y la edad.
+#[derive(Debug, Serialize, Clone, Copy)]
+pub struct RangosReferencia {
+    pub temperatura_c: RangoReferencia,
+    pub frecuencia_cardiaca: RangoReferencia,
+    pub frecuencia_respiratoria: RangoReferencia,
+}
+
+const fn rango(minimo: f64, maximo: f64) -> RangoReferencia {
+    RangoReferencia { minimo, maximo }
+}
+
+const PERRO: RangosReferencia = RangosReferencia {
+    temperatura_c: rango(37.5, 39.2),
+    frecuencia_cardiaca: rango(60.0, 140.0),
+    frecuencia_respiratoria: rango(10.0, 30.0),
+};
+
+const GATO: RangosReferencia = RangosReferencia {
+    temperatura_c: rango(37.8, 39.2),
+    frecuencia_cardiaca: rango(140.0, 220.0),
+    frecuencia_respiratoria: rango(20.0, 30.0),
+};
+
+const CONEJO: RangosReferencia = RangosReferencia {
+    temperatura_c: rango(38.5, 40.0),
+    frecuencia_cardiaca: rango(180.0, 250.0),
+    frecuencia_respiratoria: rango(30.0, 60.0),
+};
+
+// Por código de especie del catálogo (ver `/especies`)
+const REFERENCIAS: [(&str, RangosReferencia); 3] = [
+    ("perro", PERRO),
+    ("gato", GATO),
+    ("conejo", CONEJO),
+];
+
+impl RangosReferencia {
+    // None si la especie no tiene referencia o la mascota todavía guarda la
+    // especie como texto libre
+    pub fn para_especie(codigo: &str) -> Option<Self> {
+        REFERENCIAS.iter()
+            .find(|(especie, _)| *especie == codigo)
+            .map(|(_, rangos)| *rangos)
+    }
+
+    pub fn evaluar(&self, signos: &SignosVitales) -> Vec<AlertaSignoVital> {
+        [
+            ("temperatura_c", signos.temperatura_c, self.temperatura_c),
+            ("frecuencia_cardiaca", signos.frecuencia_cardiaca.map(f64::from), self.frecuencia_cardiaca),
+            ("frecuencia_respiratoria", signos.frecuencia_respiratoria.map(f64::from), self.frecuencia_respiratoria),
+        ]
+        .into_iter()
+        .filter_map(|(signo, valor, rango)| {
+            let valor = valor?;
+            let nivel = if valor < rango.minimo {
+                NivelAlerta::Bajo
+            } else if valor > rango.maximo {
+                NivelAlerta::Alto
+            } else {
+                return None;
+            };
+            Some(AlertaSignoVital { signo, valor, nivel, rango })
+        })
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn busca_los_rangos_por_codigo_de_especie() {
+        for codigo in ["perro", "gato", "conejo"] {
+            assert!(RangosReferencia::para_especie(codigo).is_some(), "{}", codigo);
+        }
+        for texto in ["Perro", "canino", "felino", "huron"] {
+            assert!(RangosReferencia::para_especie(texto).is_none(), "{}", texto);
+        }
+    }
+}
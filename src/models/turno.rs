@@ -0,0 +1,165 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EstadoTurno {
+    Reservado,
+    Confirmado,
+    // La mascota llegó y espera ser atendida
+    EnEspera,
+    Completado,
+    Ausente,
+    Cancelado,
+}
+
+impl EstadoTurno {
+    pub const CODIGOS: &'static [&'static str] =
+        &["reservado", "confirmado", "en_espera", "completado", "ausente", "cancelado"];
+
+    pub fn codigo(self) -> &'static str {
+        match self {
+            EstadoTurno::Reservado => "reservado",
+            EstadoTurno::Confirmado => "confirmado",
+            EstadoTurno::EnEspera => "en_espera",
+            EstadoTurno::Completado => "completado",
+            EstadoTurno::Ausente => "ausente",
+            EstadoTurno::Cancelado => "cancelado",
+        }
+    }
+
+    pub fn desde_codigo(codigo: &str) -> Option<Self> {
+        match codigo {
+            "reservado" => Some(EstadoTurno::Reservado),
+            "confirmado" => Some(EstadoTurno::Confirmado),
+            "en_espera" => Some(EstadoTurno::EnEspera),
+            "completado" => Some(EstadoTurno::Completado),
+            "ausente" => Some(EstadoTurno::Ausente),
+            "cancelado" => Some(EstadoTurno::Cancelado),
+            _ => None,
+        }
+    }
+
+    // Los turnos cancelados o con ausencia liberan el horario del veterinario
+    pub fn ocupa_agenda(self) -> bool {
+        !matches!(self, EstadoTurno::Cancelado | EstadoTurno::Ausente)
+    }
+
+    // Transiciones permitidas; completado, ausente y cancelado son finales
+    pub fn admite(self, nuevo: EstadoTurno) -> bool {
+        use EstadoTurno::*;
+        matches!(
+            (self, nuevo),
+            (Reservado, Confirmado)
+                | (Reservado | Confirmado, EnEspera)
+                | (Reservado | Confirmado | EnEspera, Completado)
+                | (Reservado | Confirmado, Ausente)
+                | (Reservado | Confirmado, Cancelado)
+        )
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Turno {
+    pub id: Uuid,
+    pub id_clinica: Uuid,
+    pub id_mascota: Uuid,
+    pub id_cliente: Uuid,
+    pub id_veterinario: Uuid,
+    pub inicio: DateTime<Utc>,
+    pub fin: DateTime<Utc>,
+    pub motivo: String,
+    pub estado: EstadoTurno,
+    #[serde(default)]
+    pub motivo_cancelacion: Option<String>,
+    // Entrada de historia clínica abierta al completar el turno
+    #[serde(default)]
+    pub id_entrada: Option<Uuid>,
+    #[serde(default)]
+    pub version: u64,
+    #[serde(default = "Utc::now")]
+    pub fecha_creacion: DateTime<Utc>,
+    #[serde(default = "Utc::now")]
+    pub fecha_actualizacion: DateTime<Utc>,
+    #[serde(default)]
+    pub creado_por: Option<String>,
+    #[serde(default)]
+    pub actualizado_por: Option<String>,
+}
+
+impl Turno {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        id_clinica: Uuid,
+        id_mascota: Uuid,
+        id_cliente: Uuid,
+        id_veterinario: Uuid,
+        inicio: DateTime<Utc>,
+        fin: DateTime<Utc>,
+        motivo: String,
+        autor: Option<&str>,
+    ) -> Self {
+        let ahora = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            id_clinica,
+            id_mascota,
+            id_cliente,
+            id_veterinario,
+            inicio,
+            fin,
+            motivo,
+            estado: EstadoTurno::Reservado,
+            motivo_cancelacion: None,
+            id_entrada: None,
+            version: 1,
+            fecha_creacion: ahora,
+            fecha_actualizacion: ahora,
+            creado_por: autor.map(str::to_string),
+            actualizado_por: autor.map(str::to_string),
+        }
+    }
+
+    // Intervalo semiabierto: un turno puede empezar cuando termina el anterior
+    pub fn se_superpone(&self, inicio: DateTime<Utc>, fin: DateTime<Utc>) -> bool {
+        self.inicio < fin && inicio < self.fin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn hora(h: u32, m: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 5, 12, h, m, 0).unwrap()
+    }
+
+    fn turno(inicio: DateTime<Utc>, fin: DateTime<Utc>) -> Turno {
+        Turno::new(
+            Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4(),
+            inicio, fin, "Control".to_string(), None,
+        )
+    }
+
+    #[test]
+    fn se_superpone_si_los_intervalos_se_cruzan() {
+        let turno = turno(hora(10, 0), hora(10, 30));
+
+        assert!(turno.se_superpone(hora(10, 15), hora(10, 45)));
+        assert!(turno.se_superpone(hora(9, 45), hora(10, 15)));
+        assert!(turno.se_superpone(hora(10, 5), hora(10, 20)));
+        assert!(turno.se_superpone(hora(9, 0), hora(11, 0)));
+        assert!(turno.se_superpone(hora(10, 0), hora(10, 30)));
+    }
+
+    #[test]
+    fn los_turnos_contiguos_no_se_superponen() {
+        let turno = turno(hora(10, 0), hora(10, 30));
+
+        assert!(!turno.se_superpone(hora(10, 30), hora(11, 0)));
+        assert!(!turno.se_superpone(hora(9, 30), hora(10, 0)));
+        assert!(!turno.se_superpone(hora(11, 0), hora(11, 30)));
+    }
+}
@@ -0,0 +1,61 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// Aplicación de una vacuna a una mascota
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Vacunacion {
+    pub id: Uuid,
+    pub id_mascota: Uuid,
+    pub vacuna: String,
+    pub lote: String,
+    pub fabricante: String,
+    pub dosis: String,
+    pub fecha_aplicacion: NaiveDate,
+    // Fecha en que corresponde la siguiente dosis o el refuerzo, si lo hay
+    pub proxima_dosis: Option<NaiveDate>,
+    pub id_veterinario: Option<Uuid>,
+    #[serde(default)]
+    pub version: u64,
+    #[serde(default = "Utc::now")]
+    pub fecha_creacion: DateTime<Utc>,
+    #[serde(default = "Utc::now")]
+    pub fecha_actualizacion: DateTime<Utc>,
+    #[serde(default)]
+    pub creado_por: Option<String>,
+    #[serde(default)]
+    pub actualizado_por: Option<String>,
+}
+
+impl Vacunacion {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        id_mascota: Uuid,
+        vacuna: String,
+        lote: String,
+        fabricante: String,
+        dosis: String,
+        fecha_aplicacion: NaiveDate,
+        proxima_dosis: Option<NaiveDate>,
+        id_veterinario: Option<Uuid>,
+        autor: Option<&str>,
+    ) -> Self {
+        let ahora = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            id_mascota,
+            vacuna,
+            lote,
+            fabricante,
+            dosis,
+            fecha_aplicacion,
+            proxima_dosis,
+            id_veterinario,
+            version: 1,
+            fecha_creacion: ahora,
+            fecha_actualizacion: ahora,
+            creado_por: autor.map(str::to_string),
+            actualizado_por: autor.map(str::to_string),
+        }
+    }
+}
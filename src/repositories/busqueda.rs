@@ -0,0 +1,337 @@
+use crate::models::{Cliente, Mascota};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use uuid::Uuid;
+
+// Búsqueda por prefijo sobre clientes y mascotas. Los textos se parten en
+// términos sin acentos y en minúsculas; cada término buscado debe ser prefijo
+// de algún término indexado del registro. El puntaje suma, por cada término
+// buscado, el peso del mejor campo en el que aparece (el doble si coincide
+// completo).
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CampoBusqueda {
+    Apellido,
+    Nombre,
+    Telefono,
+    Correo,
+    Raza,
+}
+
+impl CampoBusqueda {
+    fn peso(self) -> u32 {
+        match self {
+            CampoBusqueda::Apellido => 5,
+            CampoBusqueda::Nombre => 4,
+            CampoBusqueda::Telefono => 3,
+            CampoBusqueda::Correo => 2,
+            CampoBusqueda::Raza => 1,
+        }
+    }
+
+    #[cfg(feature = "storage-sqlite")]
+    pub fn codigo(self) -> &'static str {
+        match self {
+            CampoBusqueda::Apellido => "apellido",
+            CampoBusqueda::Nombre => "nombre",
+            CampoBusqueda::Telefono => "telefono",
+            CampoBusqueda::Correo => "correo",
+            CampoBusqueda::Raza => "raza",
+        }
+    }
+
+    #[cfg(feature = "storage-sqlite")]
+    pub fn desde_codigo(codigo: &str) -> Option<Self> {
+        match codigo {
+            "apellido" => Some(CampoBusqueda::Apellido),
+            "nombre" => Some(CampoBusqueda::Nombre),
+            "telefono" => Some(CampoBusqueda::Telefono),
+            "correo" => Some(CampoBusqueda::Correo),
+            "raza" => Some(CampoBusqueda::Raza),
+            _ => None,
+        }
+    }
+}
+
+// Entidades que aparecen en la búsqueda
+pub trait Indexable {
+    fn id(&self) -> Uuid;
+    // Los registros archivados no se indexan
+    fn archivado(&self) -> bool;
+    fn campos(&self) -> Vec<(CampoBusqueda, &str)>;
+    // Texto con el que se desempatan resultados de igual puntaje
+    fn etiqueta(&self) -> String;
+}
+
+impl Indexable for Cliente {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn archivado(&self) -> bool {
+        self.archivado
+    }
+
+    fn campos(&self) -> Vec<(CampoBusqueda, &str)> {
+        vec![
+            (CampoBusqueda::Apellido, &self.apellido),
+            (CampoBusqueda::Nombre, &self.nombre),
+            (CampoBusqueda::Telefono, &self.telefono),
+            (CampoBusqueda::Correo, &self.correo),
+        ]
+    }
+
+    fn etiqueta(&self) -> String {
+        format!("{} {}", self.apellido, self.nombre)
+    }
+}
+
+impl Indexable for Mascota {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn archivado(&self) -> bool {
+        self.archivado
+    }
+
+    fn campos(&self) -> Vec<(CampoBusqueda, &str)> {
+        vec![
+            (CampoBusqueda::Nombre, &self.nombre),
+            (CampoBusqueda::Raza, &self.raza),
+        ]
+    }
+
+    fn etiqueta(&self) -> String {
+        self.nombre.clone()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Coincidencia<T> {
+    pub elemento: T,
+    pub puntaje: u32,
+}
+
+fn sin_acento(c: char) -> char {
+    match c {
+        'á' | 'à' | 'ä' | 'â' | 'ã' | 'å' => 'a',
+        'é' | 'è' | 'ë' | 'ê' => 'e',
+        'í' | 'ì' | 'ï' | 'î' => 'i',
+        'ó' | 'ò' | 'ö' | 'ô' | 'õ' => 'o',
+        'ú' | 'ù' | 'ü' | 'û' => 'u',
+        'ñ' => 'n',
+        'ç' => 'c',
+        'ý' | 'ÿ' => 'y',
+        _ => c,
+    }
+}
+
+pub fn normalizar(texto: &str) -> String {
+    texto.to_lowercase().chars().map(sin_acento).collect()
+}
+
+// Términos de un texto libre, tal como se comparan contra el índice
+pub fn terminos(texto: &str) -> Vec<String> {
+    normalizar(texto)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// Un teléfono se indexa por todos sus finales de 4 o más dígitos, para que
+// "4555" encuentre "+541145551234" aunque no se escriba el prefijo del país.
+fn terminos_telefono(telefono: &str) -> Vec<String> {
+    let digitos: String = telefono.chars().filter(|c| c.is_ascii_digit()).collect();
+    (0..digitos.len().saturating_sub(3))
+        .map(|inicio| digitos[inicio..].to_string())
+        .collect()
+}
+
+pub fn terminos_indexados<T: Indexable>(registro: &T) -> Vec<(CampoBusqueda, String)> {
+    let mut indexados: Vec<(CampoBusqueda, String)> = Vec::new();
+    for (campo, texto) in registro.campos() {
+        let terminos_campo = match campo {
+            CampoBusqueda::Telefono => terminos_telefono(texto),
+            _ => terminos(texto),
+        };
+        for termino in terminos_campo {
+            if !indexados.iter().any(|(c, t)| *c == campo && *t == termino) {
+                indexados.push((campo, termino));
+            }
+        }
+    }
+    indexados
+}
+
+// `None` si algún término buscado no aparece en el registro
+pub fn puntuar(consulta: &[String], indexados: &[(CampoBusqueda, String)]) -> Option<u32> {
+    consulta.iter()
+        .map(|buscado| {
+            indexados.iter()
+                .filter(|(_, termino)| termino.starts_with(buscado.as_str()))
+                .map(|(campo, termino)| campo.peso() * if termino == buscado { 2 } else { 1 })
+                .max()
+        })
+        .sum()
+}
+
+// Término más largo de la consulta: es el más selectivo para buscar candidatos
+pub fn termino_principal(consulta: &[String]) -> Option<&String> {
+    consulta.iter().max_by_key(|t| t.len())
+}
+
+// Ordena de mayor a menor puntaje y se queda con los primeros `limite`
+pub fn mejores<T: Indexable>(mut coincidencias: Vec<Coincidencia<T>>, limite: usize) -> Vec<Coincidencia<T>> {
+    coincidencias.sort_by_cached_key(|c| {
+        (Reverse(c.puntaje), normalizar(&c.elemento.etiqueta()), c.elemento.id())
+    });
+    coincidencias.truncate(limite);
+    coincidencias
+}
+
+// Índice invertido para los backends en memoria. Se mantiene en cada `guardar`
+// y `eliminar` del repositorio.
+#[derive(Debug, Default)]
+pub struct IndiceBusqueda {
+    terminos: BTreeMap<String, HashSet<Uuid>>,
+    por_registro: HashMap<Uuid, Vec<(CampoBusqueda, String)>>,
+}
+
+impl IndiceBusqueda {
+    #[cfg(feature = "storage-file")]
+    pub fn construir<'a, T: Indexable + 'a>(registros: impl IntoIterator<Item = &'a T>) -> Self {
+        let mut indice = Self::default();
+        for registro in registros {
+            indice.actualizar(registro);
+        }
+        indice
+    }
+
+    pub fn actualizar<T: Indexable>(&mut self, registro: &T) {
+        let id = registro.id();
+        self.quitar(id);
+        if registro.archivado() {
+            return;
+        }
+
+        let indexados = terminos_indexados(registro);
+        for (_, termino) in &indexados {
+            self.terminos.entry(termino.clone()).or_default().insert(id);
+        }
+        self.por_registro.insert(id, indexados);
+    }
+
+    pub fn quitar(&mut self, id: Uuid) {
+        let Some(indexados) = self.por_registro.remove(&id) else {
+            return;
+        };
+        for (_, termino) in indexados {
+            if let Some(ids) = self.terminos.get_mut(&termino) {
+                ids.remove(&id);
+                if ids.is_empty() {
+                    self.terminos.remove(&termino);
+                }
+            }
+        }
+    }
+
+    pub fn buscar(&self, consulta: &[String]) -> Vec<(Uuid, u32)> {
+        let Some(principal) = termino_principal(consulta) else {
+            return Vec::new();
+        };
+
+        let candidatos: HashSet<Uuid> = self.terminos.range(principal.clone()..)
+            .take_while(|(termino, _)| termino.starts_with(principal.as_str()))
+            .flat_map(|(_, ids)| ids.iter().copied())
+            .collect();
+
+        candidatos.into_iter()
+            .filter_map(|id| {
+                let indexados = self.por_registro.get(&id)?;
+                puntuar(consulta, indexados).map(|puntaje| (id, puntaje))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cliente(nombre: &str, apellido: &str, telefono: &str) -> Cliente {
+        Cliente::new(
+            nombre.to_string(),
+            apellido.to_string(),
+            format!("{}@example.com", nombre.to_lowercase()),
+            telefono.to_string(),
+            "Calle 1".to_string(),
+            Uuid::new_v4(),
+            None,
+        )
+    }
+
+    fn ids(indice: &IndiceBusqueda, consulta: &str) -> Vec<Uuid> {
+        indice.buscar(&terminos(consulta)).into_iter().map(|(id, _)| id).collect()
+    }
+
+    #[test]
+    fn encuentra_por_prefijo_sin_distinguir_acentos() {
+        let mut indice = IndiceBusqueda::default();
+        let ana = cliente("Ana", "Gómez", "+54 11 4555-1234");
+        indice.actualizar(&ana);
+
+        assert_eq!(ids(&indice, "gom"), [ana.id]);
+        assert_eq!(ids(&indice, "GÓMEZ an"), [ana.id]);
+        assert_eq!(ids(&indice, "4555"), [ana.id]);
+        // Cada término debe ser prefijo de alguno indexado
+        assert!(ids(&indice, "omez").is_empty());
+        assert!(ids(&indice, "gomez luis").is_empty());
+    }
+
+    #[test]
+    fn la_coincidencia_completa_puntua_mas_que_el_prefijo() {
+        let mut indice = IndiceBusqueda::default();
+        let gomez = cliente("Ana", "Gomez", "555-0000");
+        let gomeziano = cliente("Ana", "Gomeziano", "555-0000");
+        indice.actualizar(&gomez);
+        indice.actualizar(&gomeziano);
+
+        let puntajes: HashMap<Uuid, u32> = indice.buscar(&terminos("gomez")).into_iter().collect();
+        assert!(puntajes[&gomez.id] > puntajes[&gomeziano.id]);
+    }
+
+    #[test]
+    fn reindexa_al_actualizar() {
+        let mut indice = IndiceBusqueda::default();
+        let mut ana = cliente("Ana", "Gómez", "555-0000");
+        indice.actualizar(&ana);
+
+        ana.apellido = "Pérez".to_string();
+        indice.actualizar(&ana);
+        assert!(ids(&indice, "gomez").is_empty());
+        assert_eq!(ids(&indice, "perez"), [ana.id]);
+        assert!(!indice.terminos.contains_key("gomez"));
+
+        ana.archivado = true;
+        indice.actualizar(&ana);
+        assert!(ids(&indice, "perez").is_empty());
+    }
+
+    #[test]
+    fn quita_los_terminos_al_eliminar() {
+        let mut indice = IndiceBusqueda::default();
+        let ana = cliente("Ana", "Gómez", "555-0000");
+        let luis = cliente("Luis", "Gómez", "555-0000");
+        indice.actualizar(&ana);
+        indice.actualizar(&luis);
+
+        indice.quitar(ana.id);
+        assert_eq!(ids(&indice, "gomez"), [luis.id]);
+        assert!(!indice.terminos.contains_key("ana"));
+
+        indice.quitar(luis.id);
+        assert!(indice.terminos.is_empty() && indice.por_registro.is_empty());
+    }
+}
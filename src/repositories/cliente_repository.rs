@@ -1,42 +1,439 @@
 use crate::models::Cliente;
 use std::collections::HashMap;
 use uuid::Uuid;
+use crate::error::DomainError;
+use super::busqueda::{mejores, Coincidencia, IndiceBusqueda};
+use super::consulta::{comparar_texto, contiene, paginar, CampoOrden, Consulta, Pagina};
+use std::cmp::Ordering;
+
+#[cfg(any(feature = "storage-file", feature = "storage-sqlite"))]
+use std::path::Path;
+#[cfg(feature = "storage-file")]
+use super::file_repository::FileRepository;
+#[cfg(feature = "storage-sqlite")]
+use super::sqlite_repository::{
+    abrir_conexion, buscar_en_indice, desindexar, indexar, indexar_faltantes, leer_uuid, listar_pagina,
+    Filtros,
+};
+#[cfg(feature = "storage-sqlite")]
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+#[derive(Debug, Clone, Default)]
+pub struct FiltroClientes {
+    pub id_clinica: Option<Uuid>,
+    // Coincidencia parcial en "nombre apellido", sin distinguir mayúsculas
+    pub nombre: Option<String>,
+    pub correo: Option<String>,
+    pub incluir_archivados: bool,
+}
+
+impl FiltroClientes {
+    fn acepta(&self, cliente: &Cliente) -> bool {
+        (self.incluir_archivados || !cliente.archivado)
+            && self.id_clinica.is_none_or(|id| cliente.id_clinica == id)
+            && self.nombre.as_ref().is_none_or(|n| {
+                contiene(&format!("{} {}", cliente.nombre, cliente.apellido), n)
+            })
+            && self.correo.as_ref().is_none_or(|c| contiene(&cliente.correo, c))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum OrdenClientes {
+    #[default]
+    Apellido,
+    Nombre,
+    Correo,
+}
+
+impl CampoOrden for OrdenClientes {
+    const NOMBRES: &'static [&'static str] = &["apellido", "nombre", "correo"];
+
+    fn desde_nombre(nombre: &str) -> Option<Self> {
+        match nombre {
+            "apellido" => Some(OrdenClientes::Apellido),
+            "nombre" => Some(OrdenClientes::Nombre),
+            "correo" => Some(OrdenClientes::Correo),
+            _ => None,
+        }
+    }
+}
+
+impl OrdenClientes {
+    fn comparar(self, a: &Cliente, b: &Cliente) -> Ordering {
+        match self {
+            OrdenClientes::Apellido => comparar_texto(&a.apellido, &b.apellido)
+                .then_with(|| comparar_texto(&a.nombre, &b.nombre)),
+            OrdenClientes::Nombre => comparar_texto(&a.nombre, &b.nombre),
+            OrdenClientes::Correo => comparar_texto(&a.correo, &b.correo),
+        }
+        .then(a.id.cmp(&b.id))
+    }
+
+    #[cfg(feature = "storage-sqlite")]
+    fn columnas(self) -> &'static [&'static str] {
+        match self {
+            OrdenClientes::Apellido => &["apellido COLLATE NOCASE", "nombre COLLATE NOCASE"],
+            OrdenClientes::Nombre => &["nombre COLLATE NOCASE"],
+            OrdenClientes::Correo => &["correo COLLATE NOCASE"],
+        }
+    }
+}
+
+pub type ConsultaClientes = Consulta<FiltroClientes, OrdenClientes>;
 
 pub trait ClienteRepository {
-    fn obtener(&self, id: Uuid) -> Option<&Cliente>;
-    fn listar(&self) -> Vec<&Cliente>;
-    fn guardar(&mut self, cliente: Cliente) -> Result<(), String>;
-    fn eliminar(&mut self, id: Uuid) -> Result<(), String>;
+    fn obtener(&self, id: Uuid) -> Result<Option<Cliente>, DomainError>;
+    fn listar(&self, consulta: &ConsultaClientes) -> Result<Pagina<Cliente>, DomainError>;
+    fn listar_por_clinica(&self, id_clinica: Uuid) -> Result<Vec<Cliente>, DomainError>;
+    fn guardar(&mut self, cliente: Cliente) -> Result<(), DomainError>;
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError>;
+    // Búsqueda por prefijo (ver `repositories::busqueda`); excluye los archivados
+    fn buscar(&self, terminos: &[String], limite: usize) -> Result<Vec<Coincidencia<Cliente>>, DomainError>;
 }
 
 pub struct InMemoryClienteRepository {
     clientes: HashMap<Uuid, Cliente>,
+    indice: IndiceBusqueda,
 }
 
 impl InMemoryClienteRepository {
     pub fn new() -> Self {
         Self {
             clientes: HashMap::new(),
+            indice: IndiceBusqueda::default(),
         }
     }
 }
 
 impl ClienteRepository for InMemoryClienteRepository {
-    fn obtener(&self, id: Uuid) -> Option<&Cliente> {
-        self.clientes.get(&id)
+    fn obtener(&self, id: Uuid) -> Result<Option<Cliente>, DomainError> {
+        Ok(self.clientes.get(&id).cloned())
+    }
+
+    fn listar(&self, consulta: &ConsultaClientes) -> Result<Pagina<Cliente>, DomainError> {
+        Ok(paginar(
+            self.clientes.values(),
+            consulta,
+            |c| consulta.filtro.acepta(c),
+            |a, b| consulta.orden.comparar(a, b),
+        ))
     }
 
-    fn listar(&self) -> Vec<&Cliente> {
-        self.clientes.values().collect()
+    fn listar_por_clinica(&self, id_clinica: Uuid) -> Result<Vec<Cliente>, DomainError> {
+        Ok(self.clientes.values()
+            .filter(|c| c.id_clinica == id_clinica)
+            .cloned()
+            .collect())
     }
 
-    fn guardar(&mut self, cliente: Cliente) -> Result<(), String> {
+    fn guardar(&mut self, cliente: Cliente) -> Result<(), DomainError> {
+        self.indice.actualizar(&cliente);
         self.clientes.insert(cliente.id, cliente);
         Ok(())
     }
 
-    fn eliminar(&mut self, id: Uuid) -> Result<(), String> {
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.indice.quitar(id);
         self.clientes.remove(&id);
         Ok(())
     }
+
+    fn buscar(&self, terminos: &[String], limite: usize) -> Result<Vec<Coincidencia<Cliente>>, DomainError> {
+        let coincidencias = self.indice.buscar(terminos).into_iter()
+            .filter_map(|(id, puntaje)| {
+                self.clientes.get(&id).map(|elemento| Coincidencia { elemento: elemento.clone(), puntaje })
+            })
+            .collect();
+        Ok(mejores(coincidencias, limite))
+    }
+}
+
+#[cfg(feature = "storage-file")]
+pub struct FileClienteRepository {
+    storage: FileRepository<Cliente>,
+    indice: IndiceBusqueda,
+}
+
+#[cfg(feature = "storage-file")]
+impl FileClienteRepository {
+    pub fn new(data_dir: &Path) -> Result<Self, DomainError> {
+        let storage: FileRepository<Cliente> = FileRepository::open(data_dir.join("clientes.json"))?;
+        let indice = IndiceBusqueda::construir(storage.records());
+        Ok(Self { storage, indice })
+    }
+}
+
+#[cfg(feature = "storage-file")]
+impl ClienteRepository for FileClienteRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<Cliente>, DomainError> {
+        Ok(self.storage.get(id).cloned())
+    }
+
+    fn listar(&self, consulta: &ConsultaClientes) -> Result<Pagina<Cliente>, DomainError> {
+        Ok(paginar(
+            self.storage.records().iter(),
+            consulta,
+            |c| consulta.filtro.acepta(c),
+            |a, b| consulta.orden.comparar(a, b),
+        ))
+    }
+
+    fn listar_por_clinica(&self, id_clinica: Uuid) -> Result<Vec<Cliente>, DomainError> {
+        Ok(self.storage.records().iter()
+            .filter(|c| c.id_clinica == id_clinica)
+            .cloned()
+            .collect())
+    }
+
+    fn guardar(&mut self, cliente: Cliente) -> Result<(), DomainError> {
+        self.storage.upsert(cliente.clone())?;
+        self.indice.actualizar(&cliente);
+        Ok(())
+    }
+
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.storage.delete(id)?;
+        self.indice.quitar(id);
+        Ok(())
+    }
+
+    fn buscar(&self, terminos: &[String], limite: usize) -> Result<Vec<Coincidencia<Cliente>>, DomainError> {
+        let coincidencias = self.indice.buscar(terminos).into_iter()
+            .filter_map(|(id, puntaje)| {
+                self.storage.get(id).map(|elemento| Coincidencia { elemento: elemento.clone(), puntaje })
+            })
+            .collect();
+        Ok(mejores(coincidencias, limite))
+    }
+}
+
+#[cfg(feature = "storage-sqlite")]
+pub struct SqliteClienteRepository {
+    conexion: Connection,
+}
+
+#[cfg(feature = "storage-sqlite")]
+impl SqliteClienteRepository {
+    pub fn new(ruta: &Path) -> Result<Self, DomainError> {
+        let mut conexion = abrir_conexion(ruta)?;
+        indexar_faltantes(&mut conexion, "cliente", "clientes", Self::desde_fila)?;
+        Ok(Self { conexion })
+    }
+
+    fn desde_fila(row: &Row) -> rusqlite::Result<Cliente> {
+        Ok(Cliente {
+            id: leer_uuid(row, "id")?,
+            nombre: row.get("nombre")?,
+            apellido: row.get("apellido")?,
+            correo: row.get("correo")?,
+            telefono: row.get("telefono")?,
+            direccion: row.get("direccion")?,
+            id_clinica: leer_uuid(row, "id_clinica")?,
+            archivado: row.get("archivado")?,
+            fecha_archivado: row.get("fecha_archivado")?,
+            version: row.get("version")?,
+            fecha_creacion: row.get("fecha_creacion")?,
+            fecha_actualizacion: row.get("fecha_actualizacion")?,
+            creado_por: row.get("creado_por")?,
+            actualizado_por: row.get("actualizado_por")?,
+        })
+    }
+}
+
+#[cfg(feature = "storage-sqlite")]
+impl ClienteRepository for SqliteClienteRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<Cliente>, DomainError> {
+        self.conexion
+            .query_row(
+                "SELECT * FROM clientes WHERE id = ?1",
+                params![id.to_string()],
+                Self::desde_fila,
+            )
+            .optional()
+            .map_err(DomainError::from)
+    }
+
+    fn listar(&self, consulta: &ConsultaClientes) -> Result<Pagina<Cliente>, DomainError> {
+        let filtro = &consulta.filtro;
+        let mut filtros = Filtros::default();
+        if !filtro.incluir_archivados {
+            filtros.agregar_fija("archivado = 0");
+        }
+        if let Some(id_clinica) = filtro.id_clinica {
+            filtros.agregar("id_clinica = ?", id_clinica.to_string());
+        }
+        if let Some(nombre) = &filtro.nombre {
+            filtros.agregar(
+                "instr(lower(nombre || ' ' || apellido), lower(?)) > 0",
+                nombre.clone(),
+            );
+        }
+        if let Some(correo) = &filtro.correo {
+            filtros.agregar("instr(lower(correo), lower(?)) > 0", correo.clone());
+        }
+
+        listar_pagina(
+            &self.conexion,
+            "clientes",
+            &filtros,
+            consulta.orden.columnas(),
+            consulta,
+            Self::desde_fila,
+        )
+        .map_err(DomainError::from)
+    }
+
+    fn listar_por_clinica(&self, id_clinica: Uuid) -> Result<Vec<Cliente>, DomainError> {
+        self.conexion
+            .prepare_cached("SELECT * FROM clientes WHERE id_clinica = ?1")
+            .and_then(|mut stmt| {
+                stmt.query_map(params![id_clinica.to_string()], Self::desde_fila)?
+                    .collect()
+            })
+            .map_err(DomainError::from)
+    }
+
+    fn guardar(&mut self, cliente: Cliente) -> Result<(), DomainError> {
+        let tx = self.conexion.transaction()?;
+        tx.execute(
+            "INSERT INTO clientes
+                (id, nombre, apellido, correo, telefono, direccion, id_clinica,
+                 archivado, fecha_archivado, version,
+                 fecha_creacion, fecha_actualizacion, creado_por, actualizado_por)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
+             ON CONFLICT(id) DO UPDATE SET
+                nombre = excluded.nombre,
+                apellido = excluded.apellido,
+                correo = excluded.correo,
+                telefono = excluded.telefono,
+                direccion = excluded.direccion,
+                id_clinica = excluded.id_clinica,
+                archivado = excluded.archivado,
+                fecha_archivado = excluded.fecha_archivado,
+                version = excluded.version,
+                fecha_actualizacion = excluded.fecha_actualizacion,
+                actualizado_por = excluded.actualizado_por",
+            params![
+                cliente.id.to_string(),
+                cliente.nombre,
+                cliente.apellido,
+                cliente.correo,
+                cliente.telefono,
+                cliente.direccion,
+                cliente.id_clinica.to_string(),
+                cliente.archivado,
+                cliente.fecha_archivado,
+                cliente.version,
+                cliente.fecha_creacion,
+                cliente.fecha_actualizacion,
+                cliente.creado_por,
+                cliente.actualizado_por,
+            ],
+        )?;
+        indexar(&tx, "cliente", &cliente)?;
+        tx.commit().map_err(DomainError::from)
+    }
+
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError> {
+        let tx = self.conexion.transaction()?;
+        tx.execute("DELETE FROM clientes WHERE id = ?1", params![id.to_string()])?;
+        desindexar(&tx, "cliente", id)?;
+        tx.commit().map_err(DomainError::from)
+    }
+
+    fn buscar(&self, terminos: &[String], limite: usize) -> Result<Vec<Coincidencia<Cliente>>, DomainError> {
+        let mut coincidencias = Vec::new();
+        for (id, puntaje) in buscar_en_indice(&self.conexion, "cliente", terminos)? {
+            if let Some(elemento) = self.obtener(id)? {
+                coincidencias.push(Coincidencia { elemento, puntaje });
+            }
+        }
+        Ok(mejores(coincidencias, limite))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::consulta::Direccion;
+
+    fn clientes(id_clinica: Uuid) -> Vec<Cliente> {
+        [("Ana", "Gomez"), ("Zoe", "Alvarez"), ("Luis", "Gomez"), ("beto", "gomez")]
+            .into_iter()
+            .map(|(nombre, apellido)| Cliente::new(
+                nombre.to_string(),
+                apellido.to_string(),
+                format!("{}@example.com", nombre),
+                "555-0000".to_string(),
+                "Calle 1".to_string(),
+                id_clinica,
+                None,
+            ))
+            .collect()
+    }
+
+    fn por_apellido(direccion: Direccion) -> ConsultaClientes {
+        Consulta {
+            filtro: FiltroClientes::default(),
+            orden: OrdenClientes::Apellido,
+            direccion,
+            pagina: 1,
+            por_pagina: 10,
+        }
+    }
+
+    fn nombres(repository: &dyn ClienteRepository, consulta: &ConsultaClientes) -> Vec<String> {
+        repository.listar(consulta).unwrap()
+            .elementos
+            .into_iter()
+            .map(|c| format!("{} {}", c.apellido, c.nombre))
+            .collect()
+    }
+
+    #[test]
+    fn el_orden_descendente_invierte_todas_las_columnas() {
+        let mut repository = InMemoryClienteRepository::new();
+        for cliente in clientes(Uuid::new_v4()) {
+            repository.guardar(cliente).unwrap();
+        }
+
+        assert_eq!(
+            nombres(&repository, &por_apellido(Direccion::Descendente)),
+            ["Gomez Luis", "gomez beto", "Gomez Ana", "Alvarez Zoe"],
+        );
+    }
+
+    // `sort=-apellido` ordena igual en SQLite que en memoria
+    #[cfg(feature = "storage-sqlite")]
+    #[test]
+    fn sqlite_ordena_como_en_memoria() {
+        use crate::models::Clinica;
+        use crate::repositories::clinica_repository::{ClinicaRepository, SqliteClinicaRepository};
+
+        let directorio = std::env::temp_dir().join(format!("centralvet-prueba-{}", Uuid::new_v4()));
+        let ruta = directorio.join("centralvet.db");
+        let clinica = Clinica::new(
+            "Central".to_string(),
+            "Calle 1".to_string(),
+            "555-0000".to_string(),
+            "central@example.com".to_string(),
+            None,
+        );
+        SqliteClinicaRepository::new(&ruta).unwrap().guardar(clinica.clone()).unwrap();
+
+        let mut en_memoria = InMemoryClienteRepository::new();
+        let mut sqlite = SqliteClienteRepository::new(&ruta).unwrap();
+        for cliente in clientes(clinica.id) {
+            en_memoria.guardar(cliente.clone()).unwrap();
+            sqlite.guardar(cliente).unwrap();
+        }
+
+        for direccion in [Direccion::Ascendente, Direccion::Descendente] {
+            let consulta = por_apellido(direccion);
+            assert_eq!(nombres(&sqlite, &consulta), nombres(&en_memoria, &consulta));
+        }
+        drop(sqlite);
+        std::fs::remove_dir_all(directorio).unwrap();
+    }
 }
@@ -1,141 +1,262 @@
-use crate::models::{Clinica, Cliente};
+use crate::models::Clinica;
 use std::collections::HashMap;
 use uuid::Uuid;
+use crate::error::DomainError;
+use super::consulta::{comparar_texto, contiene, paginar, CampoOrden, Consulta, Pagina};
+use std::cmp::Ordering;
 
+#[cfg(any(feature = "storage-file", feature = "storage-sqlite"))]
+use std::path::Path;
 #[cfg(feature = "storage-file")]
 use super::file_repository::FileRepository;
+#[cfg(feature = "storage-sqlite")]
+use super::sqlite_repository::{abrir_conexion, leer_uuid, listar_pagina, Filtros};
+#[cfg(feature = "storage-sqlite")]
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+#[derive(Debug, Clone, Default)]
+pub struct FiltroClinicas {
+    // Coincidencia parcial, sin distinguir mayúsculas
+    pub nombre: Option<String>,
+    pub incluir_archivadas: bool,
+}
+
+impl FiltroClinicas {
+    fn acepta(&self, clinica: &Clinica) -> bool {
+        (self.incluir_archivadas || !clinica.archivado)
+            && self.nombre.as_ref().is_none_or(|n| contiene(&clinica.nombre, n))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum OrdenClinicas {
+    #[default]
+    Nombre,
+    Direccion,
+    Correo,
+}
+
+impl CampoOrden for OrdenClinicas {
+    const NOMBRES: &'static [&'static str] = &["nombre", "direccion", "correo"];
+
+    fn desde_nombre(nombre: &str) -> Option<Self> {
+        match nombre {
+            "nombre" => Some(OrdenClinicas::Nombre),
+            "direccion" => Some(OrdenClinicas::Direccion),
+            "correo" => Some(OrdenClinicas::Correo),
+            _ => None,
+        }
+    }
+}
+
+impl OrdenClinicas {
+    fn comparar(self, a: &Clinica, b: &Clinica) -> Ordering {
+        match self {
+            OrdenClinicas::Nombre => comparar_texto(&a.nombre, &b.nombre),
+            OrdenClinicas::Direccion => comparar_texto(&a.direccion, &b.direccion),
+            OrdenClinicas::Correo => comparar_texto(&a.correo, &b.correo),
+        }
+        .then(a.id.cmp(&b.id))
+    }
+
+    #[cfg(feature = "storage-sqlite")]
+    fn columnas(self) -> &'static [&'static str] {
+        match self {
+            OrdenClinicas::Nombre => &["nombre COLLATE NOCASE"],
+            OrdenClinicas::Direccion => &["direccion COLLATE NOCASE"],
+            OrdenClinicas::Correo => &["correo COLLATE NOCASE"],
+        }
+    }
+}
+
+pub type ConsultaClinicas = Consulta<FiltroClinicas, OrdenClinicas>;
 
 pub trait ClinicaRepository {
-    fn obtener(&self, id: Uuid) -> Option<&Clinica>;
-    fn listar(&self) -> Vec<&Clinica>;
-    fn guardar(&mut self, clinica: Clinica) -> Result<(), String>;
-    fn eliminar(&mut self, id: Uuid) -> Result<(), String>;
-    
-    // Operaciones relacionadas con clientes
-    fn obtener_clientes(&self, id_clinica: Uuid) -> Vec<&Cliente>;
-    fn agregar_cliente(&mut self, id_clinica: Uuid, cliente: Cliente) -> Result<(), String>;
-    fn eliminar_cliente(&mut self, id_clinica: Uuid, id_cliente: Uuid) -> Result<(), String>;
+    fn obtener(&self, id: Uuid) -> Result<Option<Clinica>, DomainError>;
+    fn listar(&self, consulta: &ConsultaClinicas) -> Result<Pagina<Clinica>, DomainError>;
+    fn guardar(&mut self, clinica: Clinica) -> Result<(), DomainError>;
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError>;
 }
 
 pub struct InMemoryClinicaRepository {
     clinicas: HashMap<Uuid, Clinica>,
-    clientes_por_clinica: HashMap<Uuid, Vec<Cliente>>,
 }
 
 impl InMemoryClinicaRepository {
     pub fn new() -> Self {
         Self {
             clinicas: HashMap::new(),
-            clientes_por_clinica: HashMap::new(),
         }
     }
 }
 
 impl ClinicaRepository for InMemoryClinicaRepository {
-    fn obtener(&self, id: Uuid) -> Option<&Clinica> {
-        self.clinicas.get(&id)
+    fn obtener(&self, id: Uuid) -> Result<Option<Clinica>, DomainError> {
+        Ok(self.clinicas.get(&id).cloned())
     }
 
-    fn listar(&self) -> Vec<&Clinica> {
-        self.clinicas.values().collect()
+    fn listar(&self, consulta: &ConsultaClinicas) -> Result<Pagina<Clinica>, DomainError> {
+        Ok(paginar(
+            self.clinicas.values(),
+            consulta,
+            |c| consulta.filtro.acepta(c),
+            |a, b| consulta.orden.comparar(a, b),
+        ))
     }
 
-    fn guardar(&mut self, clinica: Clinica) -> Result<(), String> {
+    fn guardar(&mut self, clinica: Clinica) -> Result<(), DomainError> {
         self.clinicas.insert(clinica.id, clinica);
         Ok(())
     }
 
-    fn eliminar(&mut self, id: Uuid) -> Result<(), String> {
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError> {
         self.clinicas.remove(&id);
         Ok(())
     }
-
-    fn obtener_clientes(&self, id_clinica: Uuid) -> Vec<&Cliente> {
-        self.clientes_por_clinica.get(&id_clinica)
-            .map(|clientes| clientes.iter().collect())
-            .unwrap_or_else(|| Vec::new())
-    }
-
-    fn agregar_cliente(&mut self, id_clinica: Uuid, cliente: Cliente) -> Result<(), String> {
-        self.clientes_por_clinica.entry(id_clinica).or_default().push(cliente);
-        Ok(())
-    }
-
-    fn eliminar_cliente(&mut self, id_clinica: Uuid, id_cliente: Uuid) -> Result<(), String> {
-        self.clientes_por_clinica.entry(id_clinica).or_default().retain(|c| c.id != id_cliente);
-        Ok(())
-    }
 }
 
 #[cfg(feature = "storage-file")]
 pub struct FileClinicaRepository {
     storage: FileRepository<Clinica>,
-    cached_clinicas: Vec<Clinica>,
-    clientes_por_clinica: HashMap<Uuid, Vec<Cliente>>,
 }
 
 #[cfg(feature = "storage-file")]
 impl FileClinicaRepository {
-    pub fn new() -> Self {
-        let storage = FileRepository::new("data/clinicas.json");
-        let cached_clinicas = storage.load().unwrap_or_default();
-        Self {
-            storage,
-            cached_clinicas,
-            clientes_por_clinica: HashMap::new(),
-        }
+    pub fn new(data_dir: &Path) -> Result<Self, DomainError> {
+        Ok(Self {
+            storage: FileRepository::open(data_dir.join("clinicas.json"))?,
+        })
     }
 }
 
 #[cfg(feature = "storage-file")]
 impl ClinicaRepository for FileClinicaRepository {
-    fn listar(&self) -> Vec<&Clinica> {
-        self.cached_clinicas.iter().collect()
+    fn listar(&self, consulta: &ConsultaClinicas) -> Result<Pagina<Clinica>, DomainError> {
+        Ok(paginar(
+            self.storage.records().iter(),
+            consulta,
+            |c| consulta.filtro.acepta(c),
+            |a, b| consulta.orden.comparar(a, b),
+        ))
     }
 
-    fn guardar(&mut self, clinica: Clinica) -> Result<(), String> {
-        if let Some(idx) = self.cached_clinicas.iter().position(|c| c.id == clinica.id) {
-            self.cached_clinicas[idx] = clinica;
-        } else {
-            self.cached_clinicas.push(clinica);
-        }
-        self.storage.save(self.cached_clinicas.clone())
+    fn guardar(&mut self, clinica: Clinica) -> Result<(), DomainError> {
+        self.storage.upsert(clinica)
     }
 
-    fn obtener(&self, id: Uuid) -> Option<&Clinica> {
-        self.cached_clinicas.iter().find(|c| c.id == id)
+    fn obtener(&self, id: Uuid) -> Result<Option<Clinica>, DomainError> {
+        Ok(self.storage.get(id).cloned())
     }
 
-    fn eliminar(&mut self, id: Uuid) -> Result<(), String> {
-        self.cached_clinicas.retain(|c| c.id != id);
-        self.storage.save(self.cached_clinicas.clone())
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.storage.delete(id)
     }
+}
 
-    fn obtener_clientes(&self, id_clinica: Uuid) -> Vec<&Cliente> {
-        self.clientes_por_clinica.get(&id_clinica)
-            .map(|clientes| clientes.iter().collect())
-            .unwrap_or_default()
+#[cfg(feature = "storage-sqlite")]
+pub struct SqliteClinicaRepository {
+    conexion: Connection,
+}
+
+#[cfg(feature = "storage-sqlite")]
+impl SqliteClinicaRepository {
+    pub fn new(ruta: &Path) -> Result<Self, DomainError> {
+        Ok(Self {
+            conexion: abrir_conexion(ruta)?,
+        })
     }
 
-    fn agregar_cliente(&mut self, id_clinica: Uuid, cliente: Cliente) -> Result<(), String> {
-        if self.cached_clinicas.iter().find(|c| c.id == id_clinica).is_none() {
-            return Err("Clínica no encontrada".to_string());
-        }
-        
-        self.clientes_por_clinica.entry(id_clinica).or_default().push(cliente);
-        Ok(())
+    fn desde_fila(row: &Row) -> rusqlite::Result<Clinica> {
+        Ok(Clinica {
+            id: leer_uuid(row, "id")?,
+            nombre: row.get("nombre")?,
+            direccion: row.get("direccion")?,
+            telefono: row.get("telefono")?,
+            correo: row.get("correo")?,
+            archivado: row.get("archivado")?,
+            fecha_archivado: row.get("fecha_archivado")?,
+            version: row.get("version")?,
+            fecha_creacion: row.get("fecha_creacion")?,
+            fecha_actualizacion: row.get("fecha_actualizacion")?,
+            creado_por: row.get("creado_por")?,
+            actualizado_por: row.get("actualizado_por")?,
+        })
+    }
+}
+
+#[cfg(feature = "storage-sqlite")]
+impl ClinicaRepository for SqliteClinicaRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<Clinica>, DomainError> {
+        self.conexion
+            .query_row(
+                "SELECT * FROM clinicas WHERE id = ?1",
+                params![id.to_string()],
+                Self::desde_fila,
+            )
+            .optional()
+            .map_err(DomainError::from)
     }
 
-    fn eliminar_cliente(&mut self, id_clinica: Uuid, id_cliente: Uuid) -> Result<(), String> {
-        if self.cached_clinicas.iter().find(|c| c.id == id_clinica).is_none() {
-            return Err("Clínica no encontrada".to_string());
+    fn listar(&self, consulta: &ConsultaClinicas) -> Result<Pagina<Clinica>, DomainError> {
+        let mut filtros = Filtros::default();
+        if !consulta.filtro.incluir_archivadas {
+            filtros.agregar_fija("archivado = 0");
+        }
+        if let Some(nombre) = &consulta.filtro.nombre {
+            filtros.agregar("instr(lower(nombre), lower(?)) > 0", nombre.clone());
         }
 
-        self.clientes_por_clinica
-            .entry(id_clinica)
-            .or_default()
-            .retain(|c| c.id != id_cliente);
-        
-        Ok(())
+        listar_pagina(
+            &self.conexion,
+            "clinicas",
+            &filtros,
+            consulta.orden.columnas(),
+            consulta,
+            Self::desde_fila,
+        )
+        .map_err(DomainError::from)
     }
-} 
\ No newline at end of file
+
+    fn guardar(&mut self, clinica: Clinica) -> Result<(), DomainError> {
+        self.conexion
+            .execute(
+                "INSERT INTO clinicas
+                    (id, nombre, direccion, telefono, correo, archivado, fecha_archivado, version,
+                     fecha_creacion, fecha_actualizacion, creado_por, actualizado_por)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+                 ON CONFLICT(id) DO UPDATE SET
+                    nombre = excluded.nombre,
+                    direccion = excluded.direccion,
+                    telefono = excluded.telefono,
+                    correo = excluded.correo,
+                    archivado = excluded.archivado,
+                    fecha_archivado = excluded.fecha_archivado,
+                    version = excluded.version,
+                    fecha_actualizacion = excluded.fecha_actualizacion,
+                    actualizado_por = excluded.actualizado_por",
+                params![
+                    clinica.id.to_string(),
+                    clinica.nombre,
+                    clinica.direccion,
+                    clinica.telefono,
+                    clinica.correo,
+                    clinica.archivado,
+                    clinica.fecha_archivado,
+                    clinica.version,
+                    clinica.fecha_creacion,
+                    clinica.fecha_actualizacion,
+                    clinica.creado_por,
+                    clinica.actualizado_por,
+                ],
+            )
+            .map(|_| ())
+            .map_err(DomainError::from)
+    }
+
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.conexion
+            .execute("DELETE FROM clinicas WHERE id = ?1", params![id.to_string()])
+            .map(|_| ())
+            .map_err(DomainError::from)
+    }
+}
@@ -0,0 +1,98 @@
+use std::cmp::Ordering;
+
+// Parámetros de un listado: filtro y campo de orden propios de cada entidad,
+// más la dirección y la página pedida. Los repositorios los resuelven cada uno
+// a su manera (en memoria o con SQL) y siempre desempatan por `id` para que el
+// orden sea estable entre llamadas.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Direccion {
+    #[default]
+    Ascendente,
+    Descendente,
+}
+
+impl Direccion {
+    pub fn aplicar(self, orden: Ordering) -> Ordering {
+        match self {
+            Direccion::Ascendente => orden,
+            Direccion::Descendente => orden.reverse(),
+        }
+    }
+
+    #[cfg(feature = "storage-sqlite")]
+    pub fn sql(self) -> &'static str {
+        match self {
+            Direccion::Ascendente => "ASC",
+            Direccion::Descendente => "DESC",
+        }
+    }
+}
+
+// Campo por el que puede ordenarse un listado, tal como se nombra en `sort=`
+pub trait CampoOrden: Sized + Copy {
+    const NOMBRES: &'static [&'static str];
+
+    fn desde_nombre(nombre: &str) -> Option<Self>;
+}
+
+#[derive(Debug, Clone)]
+pub struct Consulta<F, C> {
+    pub filtro: F,
+    pub orden: C,
+    pub direccion: Direccion,
+    // Numeradas desde 1
+    pub pagina: u32,
+    pub por_pagina: u32,
+}
+
+impl<F, C> Consulta<F, C> {
+    pub fn desplazamiento(&self) -> usize {
+        (self.pagina.saturating_sub(1) as usize) * self.por_pagina as usize
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Pagina<T> {
+    pub elementos: Vec<T>,
+    // Cantidad total de elementos que cumplen el filtro, sin paginar
+    pub total: usize,
+}
+
+impl<T> Default for Pagina<T> {
+    fn default() -> Self {
+        Self { elementos: Vec::new(), total: 0 }
+    }
+}
+
+// Implementación para los backends que tienen todos los registros en memoria
+pub fn paginar<'a, T, F, C>(
+    registros: impl Iterator<Item = &'a T>,
+    consulta: &Consulta<F, C>,
+    acepta: impl Fn(&T) -> bool,
+    comparar: impl Fn(&T, &T) -> Ordering,
+) -> Pagina<T>
+where
+    T: Clone + 'a,
+{
+    let mut filtrados: Vec<&T> = registros.filter(|r| acepta(r)).collect();
+    filtrados.sort_by(|a, b| consulta.direccion.aplicar(comparar(a, b)));
+
+    let total = filtrados.len();
+    let elementos = filtrados.into_iter()
+        .skip(consulta.desplazamiento())
+        .take(consulta.por_pagina as usize)
+        .cloned()
+        .collect();
+
+    Pagina { elementos, total }
+}
+
+// Comparación sin distinguir mayúsculas, usada en los órdenes por texto
+pub fn comparar_texto(a: &str, b: &str) -> Ordering {
+    a.to_lowercase().cmp(&b.to_lowercase())
+}
+
+pub fn contiene(texto: &str, buscado: &str) -> bool {
+    texto.to_lowercase().contains(&buscado.to_lowercase())
+}
@@ -0,0 +1,274 @@
+use crate::models::Especie;
+use std::collections::HashMap;
+use uuid::Uuid;
+use crate::error::DomainError;
+use super::consulta::{comparar_texto, paginar, CampoOrden, Consulta, Pagina};
+use std::cmp::Ordering;
+
+#[cfg(any(feature = "storage-file", feature = "storage-sqlite"))]
+use std::path::Path;
+#[cfg(feature = "storage-file")]
+use super::file_repository::FileRepository;
+#[cfg(feature = "storage-sqlite")]
+use super::sqlite_repository::{abrir_conexion, leer_json, leer_uuid, listar_pagina, Filtros};
+#[cfg(feature = "storage-sqlite")]
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+#[derive(Debug, Clone, Default)]
+pub struct FiltroEspecies {
+    pub incluir_archivadas: bool,
+}
+
+impl FiltroEspecies {
+    fn acepta(&self, especie: &Especie) -> bool {
+        self.incluir_archivadas || !especie.archivado
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum OrdenEspecies {
+    #[default]
+    Codigo,
+}
+
+impl CampoOrden for OrdenEspecies {
+    const NOMBRES: &'static [&'static str] = &["codigo"];
+
+    fn desde_nombre(nombre: &str) -> Option<Self> {
+        match nombre {
+            "codigo" => Some(OrdenEspecies::Codigo),
+            _ => None,
+        }
+    }
+}
+
+impl OrdenEspecies {
+    fn comparar(self, a: &Especie, b: &Especie) -> Ordering {
+        match self {
+            OrdenEspecies::Codigo => comparar_texto(&a.codigo, &b.codigo),
+        }
+        .then(a.id.cmp(&b.id))
+    }
+
+    #[cfg(feature = "storage-sqlite")]
+    fn columnas(self) -> &'static [&'static str] {
+        match self {
+            OrdenEspecies::Codigo => &["codigo COLLATE NOCASE"],
+        }
+    }
+}
+
+pub type ConsultaEspecies = Consulta<FiltroEspecies, OrdenEspecies>;
+
+pub trait EspecieRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<Especie>, DomainError>;
+    fn listar(&self, consulta: &ConsultaEspecies) -> Result<Pagina<Especie>, DomainError>;
+    // Incluye las archivadas; el catálogo es chico y se recorre entero para
+    // resolver nombres y alias
+    fn listar_todas(&self) -> Result<Vec<Especie>, DomainError>;
+    fn guardar(&mut self, especie: Especie) -> Result<(), DomainError>;
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError>;
+}
+
+pub struct InMemoryEspecieRepository {
+    especies: HashMap<Uuid, Especie>,
+}
+
+impl InMemoryEspecieRepository {
+    pub fn new() -> Self {
+        Self {
+            especies: HashMap::new(),
+        }
+    }
+}
+
+impl EspecieRepository for InMemoryEspecieRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<Especie>, DomainError> {
+        Ok(self.especies.get(&id).cloned())
+    }
+
+    fn listar(&self, consulta: &ConsultaEspecies) -> Result<Pagina<Especie>, DomainError> {
+        Ok(paginar(
+            self.especies.values(),
+            consulta,
+            |e| consulta.filtro.acepta(e),
+            |a, b| consulta.orden.comparar(a, b),
+        ))
+    }
+
+    fn listar_todas(&self) -> Result<Vec<Especie>, DomainError> {
+        Ok(self.especies.values().cloned().collect())
+    }
+
+    fn guardar(&mut self, especie: Especie) -> Result<(), DomainError> {
+        self.especies.insert(especie.id, especie);
+        Ok(())
+    }
+
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.especies.remove(&id);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "storage-file")]
+pub struct FileEspecieRepository {
+    storage: FileRepository<Especie>,
+}
+
+#[cfg(feature = "storage-file")]
+impl FileEspecieRepository {
+    pub fn new(data_dir: &Path) -> Result<Self, DomainError> {
+        Ok(Self {
+            storage: FileRepository::open(data_dir.join("especies.json"))?,
+        })
+    }
+}
+
+#[cfg(feature = "storage-file")]
+impl EspecieRepository for FileEspecieRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<Especie>, DomainError> {
+        Ok(self.storage.get(id).cloned())
+    }
+
+    fn listar(&self, consulta: &ConsultaEspecies) -> Result<Pagina<Especie>, DomainError> {
+        Ok(paginar(
+            self.storage.records().iter(),
+            consulta,
+            |e| consulta.filtro.acepta(e),
+            |a, b| consulta.orden.comparar(a, b),
+        ))
+    }
+
+    fn listar_todas(&self) -> Result<Vec<Especie>, DomainError> {
+        Ok(self.storage.records().to_vec())
+    }
+
+    fn guardar(&mut self, especie: Especie) -> Result<(), DomainError> {
+        self.storage.upsert(especie)
+    }
+
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.storage.delete(id)
+    }
+}
+
+#[cfg(feature = "storage-sqlite")]
+pub struct SqliteEspecieRepository {
+    conexion: Connection,
+}
+
+#[cfg(feature = "storage-sqlite")]
+impl SqliteEspecieRepository {
+    pub fn new(ruta: &Path) -> Result<Self, DomainError> {
+        Ok(Self {
+            conexion: abrir_conexion(ruta)?,
+        })
+    }
+
+    fn desde_fila(row: &Row) -> rusqlite::Result<Especie> {
+        Ok(Especie {
+            id: leer_uuid(row, "id")?,
+            codigo: row.get("codigo")?,
+            etiquetas: leer_json(row, "etiquetas")?,
+            alias: leer_json(row, "alias")?,
+            razas: leer_json(row, "razas")?,
+            archivado: row.get("archivado")?,
+            fecha_archivado: row.get("fecha_archivado")?,
+            version: row.get("version")?,
+            fecha_creacion: row.get("fecha_creacion")?,
+            fecha_actualizacion: row.get("fecha_actualizacion")?,
+            creado_por: row.get("creado_por")?,
+            actualizado_por: row.get("actualizado_por")?,
+        })
+    }
+}
+
+#[cfg(feature = "storage-sqlite")]
+impl EspecieRepository for SqliteEspecieRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<Especie>, DomainError> {
+        self.conexion
+            .query_row(
+                "SELECT * FROM especies WHERE id = ?1",
+                params![id.to_string()],
+                Self::desde_fila,
+            )
+            .optional()
+            .map_err(DomainError::from)
+    }
+
+    fn listar(&self, consulta: &ConsultaEspecies) -> Result<Pagina<Especie>, DomainError> {
+        let mut filtros = Filtros::default();
+        if !consulta.filtro.incluir_archivadas {
+            filtros.agregar_fija("archivado = 0");
+        }
+
+        listar_pagina(
+            &self.conexion,
+            "especies",
+            &filtros,
+            consulta.orden.columnas(),
+            consulta,
+            Self::desde_fila,
+        )
+        .map_err(DomainError::from)
+    }
+
+    fn listar_todas(&self) -> Result<Vec<Especie>, DomainError> {
+        self.conexion
+            .prepare_cached("SELECT * FROM especies")
+            .and_then(|mut stmt| stmt.query_map([], Self::desde_fila)?.collect())
+            .map_err(DomainError::from)
+    }
+
+    // Las etiquetas, los alias y las razas se guardan como JSON
+    fn guardar(&mut self, especie: Especie) -> Result<(), DomainError> {
+        let etiquetas = serde_json::to_string(&especie.etiquetas)
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+        let alias = serde_json::to_string(&especie.alias)
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+        let razas = serde_json::to_string(&especie.razas)
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+
+        self.conexion
+            .execute(
+                "INSERT INTO especies
+                    (id, codigo, etiquetas, alias, razas, archivado, fecha_archivado, version,
+                     fecha_creacion, fecha_actualizacion, creado_por, actualizado_por)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+                 ON CONFLICT(id) DO UPDATE SET
+                    codigo = excluded.codigo,
+                    etiquetas = excluded.etiquetas,
+                    alias = excluded.alias,
+                    razas = excluded.razas,
+                    archivado = excluded.archivado,
+                    fecha_archivado = excluded.fecha_archivado,
+                    version = excluded.version,
+                    fecha_actualizacion = excluded.fecha_actualizacion,
+                    actualizado_por = excluded.actualizado_por",
+                params![
+                    especie.id.to_string(),
+                    especie.codigo,
+                    etiquetas,
+                    alias,
+                    razas,
+                    especie.archivado,
+                    especie.fecha_archivado,
+                    especie.version,
+                    especie.fecha_creacion,
+                    especie.fecha_actualizacion,
+                    especie.creado_por,
+                    especie.actualizado_por,
+                ],
+            )
+            .map(|_| ())
+            .map_err(DomainError::from)
+    }
+
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.conexion
+            .execute("DELETE FROM especies WHERE id = ?1", params![id.to_string()])
+            .map(|_| ())
+            .map_err(DomainError::from)
+    }
+}
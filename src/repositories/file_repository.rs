@@ -1,39 +1,443 @@
-use std::fs::{File, OpenOptions};
-use std::io::{BufReader, BufWriter, Write, Read};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use log::{error, info, warn};
 use serde::{Serialize, Deserialize};
+use uuid::Uuid;
+use crate::error::DomainError;
+use crate::models::{
+    Clinica, Cliente, Mascota, HistoriaClinica, EntradaHistoriaClinica, Personal, Turno, Vacunacion, Medicamento,
+    Prescripcion, Especie,
+};
 
+// Cantidad de operaciones en el journal a partir de la cual se reescribe el snapshot.
+const COMPACT_EVERY: usize = 100;
+
+pub trait Identificable {
+    fn id(&self) -> Uuid;
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum JournalEntry<T> {
+    Upsert { record: T },
+    Delete { id: Uuid },
+}
+
+// Almacenamiento en un snapshot JSON (`<archivo>.json`) más un journal append-only
+// (`<archivo>.json.journal`) con las operaciones posteriores al último snapshot.
+// El snapshot se reemplaza con write-to-temp-and-rename, de modo que un corte
+// deja siempre una copia completa; el journal se reaplica al abrir.
 pub struct FileRepository<T> {
-    file_path: String,
-    phantom: std::marker::PhantomData<T>,
+    file_path: PathBuf,
+    journal_path: PathBuf,
+    journal: File,
+    journal_entries: usize,
+    records: Vec<T>,
 }
 
-impl<T: Serialize + for<'a> Deserialize<'a>> FileRepository<T> {
-    pub fn new(file_path: &str) -> Self {
-        Self {
-            file_path: file_path.to_string(),
-            phantom: std::marker::PhantomData,
+impl<T: Identificable + Serialize + for<'a> Deserialize<'a>> FileRepository<T> {
+    pub fn open(file_path: PathBuf) -> Result<Self, DomainError> {
+        if let Some(dir) = file_path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| DomainError::Storage(format!("{}: {}", dir.display(), e)))?;
+        }
+
+        let journal_path = with_suffix(&file_path, "journal");
+        let tmp_path = with_suffix(&file_path, "tmp");
+        if tmp_path.exists() {
+            // Snapshot a medio escribir: el original y el journal siguen intactos.
+            warn!("Descartando snapshot incompleto {}", tmp_path.display());
+            fs::remove_file(&tmp_path)
+                .map_err(|e| DomainError::Storage(format!("{}: {}", tmp_path.display(), e)))?;
         }
-    }
 
-    pub fn save(&self, records: Vec<T>) -> Result<(), String> {
-        let file = OpenOptions::new()
-            .write(true)
+        let mut records = Self::load_snapshot(&file_path)?;
+        let (journal_entries, torn) = Self::replay_journal(&journal_path, &mut records)?;
+
+        let journal = OpenOptions::new()
             .create(true)
-            .truncate(true)
-            .open(&self.file_path)
-            .map_err(|e| e.to_string())?;
+            .append(true)
+            .open(&journal_path)
+            .map_err(|e| DomainError::Storage(format!("{}: {}", journal_path.display(), e)))?;
+
+        let mut repository = Self {
+            file_path,
+            journal_path,
+            journal,
+            journal_entries,
+            records,
+        };
+
+        // Compactar también descarta del disco una operación incompleta, que de
+        // otro modo quedaría pegada delante de la próxima escritura.
+        if repository.journal_entries > 0 || torn {
+            info!(
+                "Recuperadas {} operaciones del journal {}",
+                repository.journal_entries,
+                repository.journal_path.display()
+            );
+            repository.compact()?;
+        }
+        Ok(repository)
+    }
+
+    pub fn records(&self) -> &[T] {
+        &self.records
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<&T> {
+        self.records.iter().find(|r| r.id() == id)
+    }
+
+    pub fn upsert(&mut self, record: T) -> Result<(), DomainError> {
+        self.append(&JournalEntry::Upsert { record: &record })?;
+
+        if let Some(idx) = self.records.iter().position(|r| r.id() == record.id()) {
+            self.records[idx] = record;
+        } else {
+            self.records.push(record);
+        }
+        self.compact_if_needed()
+    }
+
+    pub fn delete(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.append(&JournalEntry::<&T>::Delete { id })?;
+
+        self.records.retain(|r| r.id() != id);
+        self.compact_if_needed()
+    }
 
-        let writer = BufWriter::new(file);
-        serde_json::to_writer_pretty(writer, &records)
-            .map_err(|e| e.to_string())
+    // Si la escritura falla a medias, el journal se recorta al largo anterior:
+    // de lo contrario la próxima operación quedaría pegada a la línea incompleta
+    // y el journal no podría reaplicarse al arrancar.
+    fn append(&mut self, entry: &JournalEntry<&T>) -> Result<(), DomainError> {
+        let mut line = serde_json::to_vec(entry).map_err(|e| DomainError::Storage(e.to_string()))?;
+        line.push(b'\n');
+
+        let largo = self.journal.metadata()
+            .map_err(|e| DomainError::Storage(format!("{}: {}", self.journal_path.display(), e)))?
+            .len();
+        if let Err(e) = self.journal.write_all(&line).and_then(|_| self.journal.sync_data()) {
+            if let Err(recorte) = self.journal.set_len(largo) {
+                error!("No se pudo recortar {}: {}", self.journal_path.display(), recorte);
+            }
+            return Err(DomainError::Storage(format!("{}: {}", self.journal_path.display(), e)));
+        }
+        self.journal_entries += 1;
+        Ok(())
+    }
+
+    fn compact_if_needed(&mut self) -> Result<(), DomainError> {
+        if self.journal_entries >= COMPACT_EVERY {
+            self.compact()?;
+        }
+        Ok(())
     }
 
-    pub fn load(&self) -> Result<Vec<T>, String> {
-        let file = File::open(&self.file_path)
-            .map_err(|e| e.to_string())?;
+    // Escribe un snapshot nuevo y recién entonces vacía el journal. Si el proceso
+    // se corta entre ambos pasos, reaplicar el journal sobre el snapshot nuevo es
+    // inocuo porque las operaciones son idempotentes.
+    fn compact(&mut self) -> Result<(), DomainError> {
+        let tmp_path = with_suffix(&self.file_path, "tmp");
+        let file = File::create(&tmp_path)
+            .map_err(|e| DomainError::Storage(format!("{}: {}", tmp_path.display(), e)))?;
+
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut writer, &self.records)
+            .map_err(|e| DomainError::Storage(e.to_string()))?;
+        writer.into_inner()
+            .map_err(|e| DomainError::Storage(e.to_string()))?
+            .sync_all()
+            .map_err(|e| DomainError::Storage(format!("{}: {}", tmp_path.display(), e)))?;
+
+        fs::rename(&tmp_path, &self.file_path)
+            .map_err(|e| DomainError::Storage(format!("{}: {}", self.file_path.display(), e)))?;
+        sync_parent_dir(&self.file_path)?;
+
+        self.journal.set_len(0)
+            .and_then(|_| self.journal.sync_all())
+            .map_err(|e| DomainError::Storage(format!("{}: {}", self.journal_path.display(), e)))?;
+        self.journal_entries = 0;
+        Ok(())
+    }
+
+    fn load_snapshot(file_path: &Path) -> Result<Vec<T>, DomainError> {
+        let file = match File::open(file_path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(DomainError::Storage(format!("{}: {}", file_path.display(), e))),
+        };
 
         let reader = BufReader::new(file);
         serde_json::from_reader(reader)
-            .map_err(|e| e.to_string())
+            .map_err(|e| DomainError::Storage(format!("{} está dañado: {}", file_path.display(), e)))
+    }
+
+    fn replay_journal(journal_path: &Path, records: &mut Vec<T>) -> Result<(usize, bool), DomainError> {
+        let content = match fs::read_to_string(journal_path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((0, false)),
+            Err(e) => return Err(DomainError::Storage(format!("{}: {}", journal_path.display(), e))),
+        };
+
+        // Cada operación termina en '\n'; un resto sin salto de línea es una
+        // escritura que no llegó a completarse y no se había confirmado.
+        let (complete, torn) = match content.rfind('\n') {
+            Some(idx) => content.split_at(idx + 1),
+            None => ("", content.as_str()),
+        };
+        if !torn.is_empty() {
+            warn!("Descartando operación incompleta al final de {}", journal_path.display());
+        }
+
+        let mut applied = 0;
+        for (line_number, line) in complete.lines().enumerate() {
+            let entry: JournalEntry<T> = serde_json::from_str(line).map_err(|e| {
+                DomainError::Storage(format!(
+                    "{} está dañado en la línea {}: {}",
+                    journal_path.display(), line_number + 1, e
+                ))
+            })?;
+
+            match entry {
+                JournalEntry::Upsert { record } => {
+                    if let Some(idx) = records.iter().position(|r| r.id() == record.id()) {
+                        records[idx] = record;
+                    } else {
+                        records.push(record);
+                    }
+                }
+                JournalEntry::Delete { id } => records.retain(|r| r.id() != id),
+            }
+            applied += 1;
+        }
+        Ok((applied, !torn.is_empty()))
+    }
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+// El rename solo es durable una vez sincronizado el directorio que lo contiene.
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> Result<(), DomainError> {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => File::open(dir)
+            .and_then(|d| d.sync_all())
+            .map_err(|e| DomainError::Storage(format!("{}: {}", dir.display(), e))),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) -> Result<(), DomainError> {
+    Ok(())
+}
+
+impl Identificable for Clinica {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+}
+
+impl Identificable for Cliente {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+}
+
+impl Identificable for Mascota {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+}
+
+impl Identificable for HistoriaClinica {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+}
+
+impl Identificable for EntradaHistoriaClinica {
+    fn id(&self) -> Uuid {
+        self.id
     }
-} 
\ No newline at end of file
+}
+
+impl Identificable for Personal {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+}
+
+impl Identificable for Turno {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+}
+
+impl Identificable for Vacunacion {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+}
+
+impl Identificable for Medicamento {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+}
+
+impl Identificable for Prescripcion {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+}
+
+impl Identificable for Especie {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Registro {
+        id: Uuid,
+        valor: u32,
+    }
+
+    impl Identificable for Registro {
+        fn id(&self) -> Uuid {
+            self.id
+        }
+    }
+
+    fn registro(valor: u32) -> Registro {
+        Registro { id: Uuid::new_v4(), valor }
+    }
+
+    // Directorio temporal que se borra al terminar la prueba
+    struct Directorio(PathBuf);
+
+    impl Directorio {
+        fn nuevo() -> Self {
+            Self(std::env::temp_dir().join(format!("centralvet-prueba-{}", Uuid::new_v4())))
+        }
+
+        fn snapshot(&self) -> PathBuf {
+            self.0.join("registros.json")
+        }
+
+        fn journal(&self) -> PathBuf {
+            with_suffix(&self.snapshot(), "journal")
+        }
+
+        fn abrir(&self) -> FileRepository<Registro> {
+            FileRepository::open(self.snapshot()).unwrap()
+        }
+    }
+
+    impl Drop for Directorio {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn agregar_al_journal(ruta: &Path, contenido: &str) {
+        OpenOptions::new().append(true).open(ruta).unwrap()
+            .write_all(contenido.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn reaplica_el_journal_al_abrir() {
+        let dir = Directorio::nuevo();
+        let a = registro(1);
+        let b = registro(2);
+        {
+            let mut repositorio = dir.abrir();
+            repositorio.upsert(a.clone()).unwrap();
+            repositorio.upsert(b.clone()).unwrap();
+            repositorio.upsert(Registro { valor: 10, ..a.clone() }).unwrap();
+            repositorio.delete(b.id).unwrap();
+        }
+        assert!(!dir.snapshot().exists());
+
+        let repositorio = dir.abrir();
+        assert_eq!(repositorio.records(), &[Registro { valor: 10, ..a }]);
+        // Al abrir se compacta: el snapshot tiene todo y el journal queda vacío
+        assert!(dir.snapshot().exists());
+        assert_eq!(fs::metadata(dir.journal()).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn descarta_la_operacion_incompleta_del_final() {
+        let dir = Directorio::nuevo();
+        let a = registro(1);
+        dir.abrir().upsert(a.clone()).unwrap();
+        agregar_al_journal(&dir.journal(), r#"{"op":"upsert","record":{"id":"#);
+
+        let mut repositorio = dir.abrir();
+        assert_eq!(repositorio.records(), std::slice::from_ref(&a));
+        assert_eq!(fs::metadata(dir.journal()).unwrap().len(), 0);
+
+        // La operación siguiente no queda pegada al resto descartado
+        let b = registro(2);
+        repositorio.upsert(b.clone()).unwrap();
+        drop(repositorio);
+        assert_eq!(dir.abrir().records(), &[a, b]);
+    }
+
+    #[test]
+    fn rechaza_un_journal_danado_antes_del_final() {
+        let dir = Directorio::nuevo();
+        dir.abrir().upsert(registro(1)).unwrap();
+        let siguiente = serde_json::to_string(&JournalEntry::Upsert { record: registro(2) }).unwrap();
+        agregar_al_journal(&dir.journal(), &format!("no es json\n{}\n", siguiente));
+
+        // Una línea completa que no se puede leer no es un corte: no se descarta
+        let resultado = FileRepository::<Registro>::open(dir.snapshot());
+        assert!(matches!(resultado, Err(DomainError::Storage(_))));
+    }
+
+    #[test]
+    fn compacta_cada_cierta_cantidad_de_operaciones() {
+        let dir = Directorio::nuevo();
+        let mut repositorio = dir.abrir();
+        for valor in 0..COMPACT_EVERY as u32 - 1 {
+            repositorio.upsert(registro(valor)).unwrap();
+        }
+        assert!(!dir.snapshot().exists());
+        assert_eq!(repositorio.journal_entries, COMPACT_EVERY - 1);
+
+        repositorio.upsert(registro(COMPACT_EVERY as u32)).unwrap();
+        assert_eq!(repositorio.journal_entries, 0);
+        assert_eq!(fs::metadata(dir.journal()).unwrap().len(), 0);
+        let snapshot = FileRepository::<Registro>::load_snapshot(&dir.snapshot()).unwrap();
+        assert_eq!(snapshot, repositorio.records());
+
+        let registros = repositorio.records().to_vec();
+        drop(repositorio);
+        assert_eq!(dir.abrir().records(), registros.as_slice());
+    }
+
+    #[test]
+    fn descarta_un_snapshot_temporal_incompleto() {
+        let dir = Directorio::nuevo();
+        let a = registro(1);
+        dir.abrir().upsert(a.clone()).unwrap();
+        let temporal = with_suffix(&dir.snapshot(), "tmp");
+        fs::write(&temporal, "[{\"id\":").unwrap();
+
+        assert_eq!(dir.abrir().records(), &[a]);
+        assert!(!temporal.exists());
+    }
+}
@@ -1,16 +1,139 @@
 use crate::models::{HistoriaClinica, EntradaHistoriaClinica};
-use std::collections::HashMap;
+#[cfg(feature = "storage-sqlite")]
+use crate::models::entrada_historia_clinica::EstadoEntrada;
+#[cfg(feature = "storage-sqlite")]
+use crate::models::SignosVitales;
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
+use crate::error::DomainError;
+use super::consulta::{paginar, CampoOrden, Consulta, Pagina};
+use chrono::{DateTime, Utc};
+use std::cmp::Ordering;
+
+#[cfg(any(feature = "storage-file", feature = "storage-sqlite"))]
+use std::path::Path;
+#[cfg(feature = "storage-file")]
+use super::file_repository::FileRepository;
+#[cfg(feature = "storage-sqlite")]
+use super::sqlite_repository::{
+    abrir_conexion, leer_uuid, leer_uuid_opcional, listar_pagina, Filtros,
+};
+#[cfg(feature = "storage-sqlite")]
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+// Rango de fechas de las entradas, ambos extremos incluidos
+#[derive(Debug, Clone, Default)]
+pub struct FiltroEntradas {
+    pub desde: Option<DateTime<Utc>>,
+    pub hasta: Option<DateTime<Utc>>,
+    // Por defecto solo se listan las revisiones vigentes
+    pub incluir_enmendadas: bool,
+}
+
+impl FiltroEntradas {
+    fn acepta(&self, entrada: &EntradaHistoriaClinica, enmendadas: &HashSet<Uuid>) -> bool {
+        (self.incluir_enmendadas || !enmendadas.contains(&entrada.id))
+            && self.desde.is_none_or(|desde| entrada.fecha >= desde)
+            && self.hasta.is_none_or(|hasta| entrada.fecha <= hasta)
+    }
+}
+
+// Entradas que ya fueron reemplazadas por una revisión firmada
+fn enmendadas<'a>(entradas: impl IntoIterator<Item = &'a EntradaHistoriaClinica>) -> HashSet<Uuid> {
+    entradas.into_iter().filter_map(|e| e.reemplaza_a()).collect()
+}
+
+fn ordenar_revisiones(revisiones: &mut [EntradaHistoriaClinica]) {
+    revisiones.sort_by_key(|e| (e.enmienda_a.is_some(), e.fecha_creacion, e.id));
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum OrdenEntradas {
+    #[default]
+    Fecha,
+}
+
+impl CampoOrden for OrdenEntradas {
+    const NOMBRES: &'static [&'static str] = &["fecha"];
+
+    fn desde_nombre(nombre: &str) -> Option<Self> {
+        match nombre {
+            "fecha" => Some(OrdenEntradas::Fecha),
+            _ => None,
+        }
+    }
+}
+
+impl OrdenEntradas {
+    fn comparar(self, a: &EntradaHistoriaClinica, b: &EntradaHistoriaClinica) -> Ordering {
+        match self {
+            OrdenEntradas::Fecha => a.fecha.cmp(&b.fecha),
+        }
+        .then(a.id.cmp(&b.id))
+    }
+
+    #[cfg(feature = "storage-sqlite")]
+    fn columnas(self) -> &'static [&'static str] {
+        match self {
+            OrdenEntradas::Fecha => &["fecha"],
+        }
+    }
+}
+
+pub type ConsultaEntradas = Consulta<FiltroEntradas, OrdenEntradas>;
+
+fn entrada_existente(id: Uuid) -> DomainError {
+    DomainError::Conflict(format!("La entrada {} ya existe; las entradas no se modifican", id))
+}
+
+fn entrada_inexistente(id: Uuid) -> DomainError {
+    DomainError::NotFound(format!("La entrada {} no existe", id))
+}
+
+pub fn entrada_firmada(id: Uuid) -> DomainError {
+    DomainError::Conflict(format!(
+        "La entrada {} ya no es un borrador y no puede modificarse; agregue una enmienda", id
+    ))
+}
 
 pub trait HistoriaClinicaRepository {
-    fn obtener(&self, id: Uuid) -> Option<&HistoriaClinica>;
-    fn obtener_por_mascota(&self, id_mascota: Uuid) -> Option<&HistoriaClinica>;
-    fn guardar(&mut self, historia: HistoriaClinica) -> Result<(), String>;
-    fn eliminar(&mut self, id: Uuid) -> Result<(), String>;
+    fn obtener(&self, id: Uuid) -> Result<Option<HistoriaClinica>, DomainError>;
+    fn obtener_por_mascota(&self, id_mascota: Uuid) -> Result<Option<HistoriaClinica>, DomainError>;
+    fn listar_por_mascota(&self, id_mascota: Uuid) -> Result<Vec<HistoriaClinica>, DomainError>;
+    fn listar_por_cliente(&self, id_cliente: Uuid) -> Result<Vec<HistoriaClinica>, DomainError>;
+    fn guardar(&mut self, historia: HistoriaClinica) -> Result<(), DomainError>;
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError>;
     
-    // Métodos para las entradas
-    fn agregar_entrada(&mut self, entrada: EntradaHistoriaClinica) -> Result<(), String>;
-    fn obtener_entradas(&self, id_historia: Uuid) -> Vec<&EntradaHistoriaClinica>;
+    // Métodos para las entradas. Son registros médicos: solo los borradores se
+    // modifican, y una entrada firmada se corrige con una revisión nueva que la enmienda.
+    fn agregar_entrada(&mut self, entrada: EntradaHistoriaClinica) -> Result<(), DomainError>;
+    // Reemplaza un borrador (también al firmarlo); falla si la entrada guardada ya está firmada
+    fn actualizar_borrador(&mut self, entrada: EntradaHistoriaClinica) -> Result<(), DomainError>;
+    fn obtener_entrada(&self, id: Uuid) -> Result<Option<EntradaHistoriaClinica>, DomainError>;
+    // Revisión que reemplazó a la entrada, si fue enmendada
+    fn obtener_enmienda(&self, id_entrada: Uuid) -> Result<Option<EntradaHistoriaClinica>, DomainError>;
+    // La entrada original y todas sus revisiones, de la más antigua a la más nueva
+    fn listar_revisiones(&self, id_original: Uuid) -> Result<Vec<EntradaHistoriaClinica>, DomainError>;
+    fn obtener_entradas(&self, id_historia: Uuid) -> Result<Vec<EntradaHistoriaClinica>, DomainError>;
+    // Entradas que atendió o firmó
+    fn contar_entradas_veterinario(&self, id_veterinario: Uuid) -> Result<usize, DomainError>;
+    fn listar_entradas(&self, id_historia: Uuid, consulta: &ConsultaEntradas) -> Result<Pagina<EntradaHistoriaClinica>, DomainError>;
+    fn eliminar_entradas(&mut self, id_historia: Uuid) -> Result<(), DomainError>;
+    // Descarta un borrador; falla si la entrada ya no lo es
+    fn eliminar_borrador(&mut self, id: Uuid) -> Result<(), DomainError>;
+    fn reasignar_entradas(&mut self, id_origen: Uuid, id_destino: Uuid) -> Result<(), DomainError>;
+}
+
+// Las entradas firmadas (y las de legado) son parte del registro clínico: la
+// historia que las tiene solo puede archivarse
+fn rechazar_firmadas(id_historia: Uuid, firmadas: usize) -> Result<(), DomainError> {
+    if firmadas > 0 {
+        return Err(DomainError::Conflict(format!(
+            "La historia clínica {} tiene {} entradas firmadas; solo puede archivarse",
+            id_historia, firmadas
+        )));
+    }
+    Ok(())
 }
 
 pub struct InMemoryHistoriaClinicaRepository {
@@ -25,37 +148,674 @@ impl InMemoryHistoriaClinicaRepository {
             entradas: HashMap::new(),
         }
     }
-} 
+}
 
 impl HistoriaClinicaRepository for InMemoryHistoriaClinicaRepository {
-    fn obtener(&self, id: Uuid) -> Option<&HistoriaClinica> {
-        self.historias.get(&id)
+    fn obtener(&self, id: Uuid) -> Result<Option<HistoriaClinica>, DomainError> {
+        Ok(self.historias.get(&id).cloned())
+    }
+
+    // Si hubiera duplicadas se devuelve siempre la más antigua
+    fn obtener_por_mascota(&self, id_mascota: Uuid) -> Result<Option<HistoriaClinica>, DomainError> {
+        Ok(self.historias.values()
+            .filter(|h| h.id_mascota == id_mascota)
+            .min_by_key(|h| (h.fecha_creacion, h.id))
+            .cloned())
+    }
+
+    fn listar_por_mascota(&self, id_mascota: Uuid) -> Result<Vec<HistoriaClinica>, DomainError> {
+        let mut historias: Vec<HistoriaClinica> = self.historias.values()
+            .filter(|h| h.id_mascota == id_mascota)
+            .cloned()
+            .collect();
+        historias.sort_by_key(|h| (h.fecha_creacion, h.id));
+        Ok(historias)
     }
 
-    fn obtener_por_mascota(&self, id_mascota: Uuid) -> Option<&HistoriaClinica> {
-        self.historias.values()
-            .find(|h| h.id_mascota == id_mascota)
+    fn listar_por_cliente(&self, id_cliente: Uuid) -> Result<Vec<HistoriaClinica>, DomainError> {
+        Ok(self.historias.values()
+            .filter(|h| h.id_cliente == id_cliente)
+            .cloned()
+            .collect())
     }
 
-    fn guardar(&mut self, historia: HistoriaClinica) -> Result<(), String> {
+    fn guardar(&mut self, historia: HistoriaClinica) -> Result<(), DomainError> {
         self.historias.insert(historia.id, historia);
         Ok(())
     }
 
-    fn eliminar(&mut self, id: Uuid) -> Result<(), String> {
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError> {
         self.historias.remove(&id);
         Ok(())
     }
 
-    fn agregar_entrada(&mut self, entrada: EntradaHistoriaClinica) -> Result<(), String> {
+    fn agregar_entrada(&mut self, entrada: EntradaHistoriaClinica) -> Result<(), DomainError> {
+        if self.obtener_entrada(entrada.id)?.is_some() {
+            return Err(entrada_existente(entrada.id));
+        }
         self.entradas.entry(entrada.id_historia_clinica).or_insert(vec![]).push(entrada);
         Ok(())
     }
 
-    fn obtener_entradas(&self, id_historia: Uuid) -> Vec<&EntradaHistoriaClinica> {
-        self.entradas.get(&id_historia)
-            .map(|entries| entries.iter().collect())
-            .unwrap_or_default()
+    fn actualizar_borrador(&mut self, entrada: EntradaHistoriaClinica) -> Result<(), DomainError> {
+        let guardada = self.entradas.get_mut(&entrada.id_historia_clinica)
+            .and_then(|entradas| entradas.iter_mut().find(|e| e.id == entrada.id))
+            .ok_or_else(|| entrada_inexistente(entrada.id))?;
+        if !guardada.en_borrador() {
+            return Err(entrada_firmada(entrada.id));
+        }
+        *guardada = entrada;
+        Ok(())
+    }
+
+    fn obtener_entrada(&self, id: Uuid) -> Result<Option<EntradaHistoriaClinica>, DomainError> {
+        Ok(self.entradas.values().flatten().find(|e| e.id == id).cloned())
+    }
+
+    fn obtener_enmienda(&self, id_entrada: Uuid) -> Result<Option<EntradaHistoriaClinica>, DomainError> {
+        Ok(self.entradas.values().flatten().find(|e| e.enmienda_a == Some(id_entrada)).cloned())
+    }
+
+    fn listar_revisiones(&self, id_original: Uuid) -> Result<Vec<EntradaHistoriaClinica>, DomainError> {
+        let mut revisiones: Vec<EntradaHistoriaClinica> = self.entradas.values().flatten()
+            .filter(|e| e.id == id_original || e.id_original == Some(id_original))
+            .cloned()
+            .collect();
+        ordenar_revisiones(&mut revisiones);
+        Ok(revisiones)
+    }
+
+    fn obtener_entradas(&self, id_historia: Uuid) -> Result<Vec<EntradaHistoriaClinica>, DomainError> {
+        Ok(self.entradas.get(&id_historia)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    fn contar_entradas_veterinario(&self, id_veterinario: Uuid) -> Result<usize, DomainError> {
+        Ok(self.entradas.values().flatten()
+            .filter(|e| e.id_veterinario == Some(id_veterinario) || e.id_firmante == Some(id_veterinario))
+            .count())
+    }
+
+    fn listar_entradas(&self, id_historia: Uuid, consulta: &ConsultaEntradas) -> Result<Pagina<EntradaHistoriaClinica>, DomainError> {
+        let enmendadas = enmendadas(self.entradas.get(&id_historia).into_iter().flatten());
+        Ok(paginar(
+            self.entradas.get(&id_historia).into_iter().flatten(),
+            consulta,
+            |e| consulta.filtro.acepta(e, &enmendadas),
+            |a, b| consulta.orden.comparar(a, b),
+        ))
+    }
+
+    fn eliminar_entradas(&mut self, id_historia: Uuid) -> Result<(), DomainError> {
+        let entradas = self.entradas.get(&id_historia).map(Vec::as_slice).unwrap_or_default();
+        rechazar_firmadas(id_historia, entradas.iter().filter(|e| !e.en_borrador()).count())?;
+        self.entradas.remove(&id_historia);
+        Ok(())
+    }
+
+    fn eliminar_borrador(&mut self, id: Uuid) -> Result<(), DomainError> {
+        let entradas = self.entradas.values_mut()
+            .find(|entradas| entradas.iter().any(|e| e.id == id))
+            .ok_or_else(|| entrada_inexistente(id))?;
+        if entradas.iter().any(|e| e.id == id && !e.en_borrador()) {
+            return Err(entrada_firmada(id));
+        }
+        entradas.retain(|e| e.id != id);
+        Ok(())
+    }
+
+    fn reasignar_entradas(&mut self, id_origen: Uuid, id_destino: Uuid) -> Result<(), DomainError> {
+        let movidas = self.entradas.remove(&id_origen).unwrap_or_default();
+        let destino = self.entradas.entry(id_destino).or_default();
+        destino.extend(movidas.into_iter().map(|entrada| EntradaHistoriaClinica {
+            id_historia_clinica: id_destino,
+            ..entrada
+        }));
+        destino.sort_by_key(|e| e.fecha);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "storage-file")]
+pub struct FileHistoriaClinicaRepository {
+    storage_historias: FileRepository<HistoriaClinica>,
+    storage_entradas: FileRepository<EntradaHistoriaClinica>,
+}
+
+#[cfg(feature = "storage-file")]
+impl FileHistoriaClinicaRepository {
+    pub fn new(data_dir: &Path) -> Result<Self, DomainError> {
+        Ok(Self {
+            storage_historias: FileRepository::open(data_dir.join("historias_clinicas.json"))?,
+            storage_entradas: FileRepository::open(data_dir.join("entradas_historia_clinica.json"))?,
+        })
+    }
+}
+
+#[cfg(feature = "storage-file")]
+impl HistoriaClinicaRepository for FileHistoriaClinicaRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<HistoriaClinica>, DomainError> {
+        Ok(self.storage_historias.get(id).cloned())
+    }
+
+    fn obtener_por_mascota(&self, id_mascota: Uuid) -> Result<Option<HistoriaClinica>, DomainError> {
+        Ok(self.storage_historias.records().iter()
+            .filter(|h| h.id_mascota == id_mascota)
+            .min_by_key(|h| (h.fecha_creacion, h.id))
+            .cloned())
+    }
+
+    fn listar_por_mascota(&self, id_mascota: Uuid) -> Result<Vec<HistoriaClinica>, DomainError> {
+        let mut historias: Vec<HistoriaClinica> = self.storage_historias.records().iter()
+            .filter(|h| h.id_mascota == id_mascota)
+            .cloned()
+            .collect();
+        historias.sort_by_key(|h| (h.fecha_creacion, h.id));
+        Ok(historias)
+    }
+
+    fn listar_por_cliente(&self, id_cliente: Uuid) -> Result<Vec<HistoriaClinica>, DomainError> {
+        Ok(self.storage_historias.records().iter()
+            .filter(|h| h.id_cliente == id_cliente)
+            .cloned()
+            .collect())
+    }
+
+    fn guardar(&mut self, historia: HistoriaClinica) -> Result<(), DomainError> {
+        self.storage_historias.upsert(historia)
+    }
+
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.storage_historias.delete(id)
+    }
+
+    fn agregar_entrada(&mut self, entrada: EntradaHistoriaClinica) -> Result<(), DomainError> {
+        if self.storage_historias.get(entrada.id_historia_clinica).is_none() {
+            return Err(DomainError::NotFound(format!(
+                "La historia clínica {} no existe", entrada.id_historia_clinica
+            )));
+        }
+        if self.storage_entradas.get(entrada.id).is_some() {
+            return Err(entrada_existente(entrada.id));
+        }
+
+        self.storage_entradas.upsert(entrada)
+    }
+
+    fn actualizar_borrador(&mut self, entrada: EntradaHistoriaClinica) -> Result<(), DomainError> {
+        let guardada = self.storage_entradas.get(entrada.id)
+            .ok_or_else(|| entrada_inexistente(entrada.id))?;
+        if !guardada.en_borrador() {
+            return Err(entrada_firmada(entrada.id));
+        }
+        self.storage_entradas.upsert(entrada)
+    }
+
+    fn obtener_entrada(&self, id: Uuid) -> Result<Option<EntradaHistoriaClinica>, DomainError> {
+        Ok(self.storage_entradas.get(id).cloned())
+    }
+
+    fn obtener_enmienda(&self, id_entrada: Uuid) -> Result<Option<EntradaHistoriaClinica>, DomainError> {
+        Ok(self.storage_entradas.records().iter()
+            .find(|e| e.enmienda_a == Some(id_entrada))
+            .cloned())
+    }
+
+    fn listar_revisiones(&self, id_original: Uuid) -> Result<Vec<EntradaHistoriaClinica>, DomainError> {
+        let mut revisiones: Vec<EntradaHistoriaClinica> = self.storage_entradas.records().iter()
+            .filter(|e| e.id == id_original || e.id_original == Some(id_original))
+            .cloned()
+            .collect();
+        ordenar_revisiones(&mut revisiones);
+        Ok(revisiones)
+    }
+
+    fn obtener_entradas(&self, id_historia: Uuid) -> Result<Vec<EntradaHistoriaClinica>, DomainError> {
+        let mut entradas: Vec<EntradaHistoriaClinica> = self.storage_entradas.records().iter()
+            .filter(|e| e.id_historia_clinica == id_historia)
+            .cloned()
+            .collect();
+        entradas.sort_by_key(|e| e.fecha);
+        Ok(entradas)
+    }
+
+    fn contar_entradas_veterinario(&self, id_veterinario: Uuid) -> Result<usize, DomainError> {
+        Ok(self.storage_entradas.records().iter()
+            .filter(|e| e.id_veterinario == Some(id_veterinario) || e.id_firmante == Some(id_veterinario))
+            .count())
+    }
+
+    fn listar_entradas(&self, id_historia: Uuid, consulta: &ConsultaEntradas) -> Result<Pagina<EntradaHistoriaClinica>, DomainError> {
+        let enmendadas = enmendadas(
+            self.storage_entradas.records().iter().filter(|e| e.id_historia_clinica == id_historia),
+        );
+        Ok(paginar(
+            self.storage_entradas.records().iter(),
+            consulta,
+            |e| e.id_historia_clinica == id_historia && consulta.filtro.acepta(e, &enmendadas),
+            |a, b| consulta.orden.comparar(a, b),
+        ))
+    }
+
+    fn eliminar_entradas(&mut self, id_historia: Uuid) -> Result<(), DomainError> {
+        let entradas: Vec<&EntradaHistoriaClinica> = self.storage_entradas.records().iter()
+            .filter(|e| e.id_historia_clinica == id_historia)
+            .collect();
+        rechazar_firmadas(id_historia, entradas.iter().filter(|e| !e.en_borrador()).count())?;
+        let ids: Vec<Uuid> = entradas.iter().map(|e| e.id).collect();
+        for id in ids {
+            self.storage_entradas.delete(id)?;
+        }
+        Ok(())
+    }
+
+    fn eliminar_borrador(&mut self, id: Uuid) -> Result<(), DomainError> {
+        let guardada = self.storage_entradas.get(id)
+            .ok_or_else(|| entrada_inexistente(id))?;
+        if !guardada.en_borrador() {
+            return Err(entrada_firmada(id));
+        }
+        self.storage_entradas.delete(id)
+    }
+
+    fn reasignar_entradas(&mut self, id_origen: Uuid, id_destino: Uuid) -> Result<(), DomainError> {
+        let movidas: Vec<EntradaHistoriaClinica> = self.storage_entradas.records().iter()
+            .filter(|e| e.id_historia_clinica == id_origen)
+            .cloned()
+            .collect();
+        for entrada in movidas {
+            self.storage_entradas.upsert(EntradaHistoriaClinica {
+                id_historia_clinica: id_destino,
+                ..entrada
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "storage-sqlite")]
+fn leer_estado(row: &Row) -> rusqlite::Result<EstadoEntrada> {
+    let codigo: String = row.get("estado")?;
+    EstadoEntrada::desde_codigo(&codigo).ok_or_else(|| {
+        rusqlite::Error::FromSqlConversionFailure(
+            0,
+            rusqlite::types::Type::Text,
+            format!("estado de entrada desconocido: {}", codigo).into(),
+        )
+    })
+}
+
+#[cfg(feature = "storage-sqlite")]
+pub struct SqliteHistoriaClinicaRepository {
+    conexion: Connection,
+}
+
+#[cfg(feature = "storage-sqlite")]
+impl SqliteHistoriaClinicaRepository {
+    pub fn new(ruta: &Path) -> Result<Self, DomainError> {
+        Ok(Self {
+            conexion: abrir_conexion(ruta)?,
+        })
+    }
+
+    fn historia_desde_fila(row: &Row) -> rusqlite::Result<HistoriaClinica> {
+        Ok(HistoriaClinica {
+            id: leer_uuid(row, "id")?,
+            id_mascota: leer_uuid(row, "id_mascota")?,
+            id_cliente: leer_uuid(row, "id_cliente")?,
+            fecha_creacion: row.get("fecha_creacion")?,
+            fecha_actualizacion: row.get("fecha_actualizacion")?,
+            archivado: row.get("archivado")?,
+            fecha_archivado: row.get("fecha_archivado")?,
+            version: row.get("version")?,
+            creado_por: row.get("creado_por")?,
+            actualizado_por: row.get("actualizado_por")?,
+        })
+    }
+
+    fn entrada_desde_fila(row: &Row) -> rusqlite::Result<EntradaHistoriaClinica> {
+        Ok(EntradaHistoriaClinica {
+            id: leer_uuid(row, "id")?,
+            id_historia_clinica: leer_uuid(row, "id_historia_clinica")?,
+            fecha: row.get("fecha")?,
+            descripcion: row.get("descripcion")?,
+            diagnostico: row.get("diagnostico")?,
+            tratamiento: row.get("tratamiento")?,
+            notas: row.get("notas")?,
+            id_veterinario: leer_uuid_opcional(row, "id_veterinario")?,
+            signos_vitales: SignosVitales {
+                peso_kg: row.get("peso_kg")?,
+                temperatura_c: row.get("temperatura_c")?,
+                frecuencia_cardiaca: row.get("frecuencia_cardiaca")?,
+                frecuencia_respiratoria: row.get("frecuencia_respiratoria")?,
+            },
+            version: row.get("version")?,
+            fecha_creacion: row.get("fecha_creacion")?,
+            fecha_actualizacion: row.get("fecha_actualizacion")?,
+            creado_por: row.get("creado_por")?,
+            actualizado_por: row.get("actualizado_por")?,
+            id_original: leer_uuid_opcional(row, "id_original")?,
+            enmienda_a: leer_uuid_opcional(row, "enmienda_a")?,
+            motivo_enmienda: row.get("motivo_enmienda")?,
+            estado: leer_estado(row)?,
+            id_firmante: leer_uuid_opcional(row, "id_firmante")?,
+            firmada_por: row.get("firmada_por")?,
+            fecha_firma: row.get("fecha_firma")?,
+            hash_contenido: row.get("hash_contenido")?,
+        })
+    }
+}
+
+#[cfg(feature = "storage-sqlite")]
+impl HistoriaClinicaRepository for SqliteHistoriaClinicaRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<HistoriaClinica>, DomainError> {
+        self.conexion
+            .query_row(
+                "SELECT * FROM historias_clinicas WHERE id = ?1",
+                params![id.to_string()],
+                Self::historia_desde_fila,
+            )
+            .optional()
+            .map_err(DomainError::from)
+    }
+
+    fn obtener_por_mascota(&self, id_mascota: Uuid) -> Result<Option<HistoriaClinica>, DomainError> {
+        self.conexion
+            .query_row(
+                "SELECT * FROM historias_clinicas WHERE id_mascota = ?1
+                 ORDER BY fecha_creacion, id LIMIT 1",
+                params![id_mascota.to_string()],
+                Self::historia_desde_fila,
+            )
+            .optional()
+            .map_err(DomainError::from)
+    }
+
+    fn listar_por_mascota(&self, id_mascota: Uuid) -> Result<Vec<HistoriaClinica>, DomainError> {
+        self.conexion
+            .prepare_cached(
+                "SELECT * FROM historias_clinicas WHERE id_mascota = ?1 ORDER BY fecha_creacion, id",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_map(params![id_mascota.to_string()], Self::historia_desde_fila)?
+                    .collect()
+            })
+            .map_err(DomainError::from)
+    }
+
+    fn listar_por_cliente(&self, id_cliente: Uuid) -> Result<Vec<HistoriaClinica>, DomainError> {
+        self.conexion
+            .prepare_cached(
+                "SELECT * FROM historias_clinicas WHERE id_cliente = ?1 ORDER BY fecha_creacion",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_map(params![id_cliente.to_string()], Self::historia_desde_fila)?
+                    .collect()
+            })
+            .map_err(DomainError::from)
+    }
+
+    fn guardar(&mut self, historia: HistoriaClinica) -> Result<(), DomainError> {
+        self.conexion
+            .execute(
+                "INSERT INTO historias_clinicas
+                    (id, id_mascota, id_cliente, fecha_creacion, fecha_actualizacion,
+                     archivado, fecha_archivado, version, creado_por, actualizado_por)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+                 ON CONFLICT(id) DO UPDATE SET
+                    id_mascota = excluded.id_mascota,
+                    id_cliente = excluded.id_cliente,
+                    fecha_actualizacion = excluded.fecha_actualizacion,
+                    archivado = excluded.archivado,
+                    fecha_archivado = excluded.fecha_archivado,
+                    version = excluded.version,
+                    actualizado_por = excluded.actualizado_por",
+                params![
+                    historia.id.to_string(),
+                    historia.id_mascota.to_string(),
+                    historia.id_cliente.to_string(),
+                    historia.fecha_creacion,
+                    historia.fecha_actualizacion,
+                    historia.archivado,
+                    historia.fecha_archivado,
+                    historia.version,
+                    historia.creado_por,
+                    historia.actualizado_por,
+                ],
+            )
+            .map(|_| ())
+            .map_err(DomainError::from)
+    }
+
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.conexion
+            .execute("DELETE FROM historias_clinicas WHERE id = ?1", params![id.to_string()])
+            .map(|_| ())
+            .map_err(DomainError::from)
+    }
+
+    fn agregar_entrada(&mut self, entrada: EntradaHistoriaClinica) -> Result<(), DomainError> {
+        self.conexion
+            .execute(
+                "INSERT INTO entradas_historia_clinica
+                    (id, id_historia_clinica, fecha, descripcion, diagnostico, tratamiento, notas, version,
+                     fecha_creacion, fecha_actualizacion, creado_por, actualizado_por,
+                     id_original, enmienda_a, motivo_enmienda, estado, firmada_por, fecha_firma,
+                     hash_contenido, id_veterinario, peso_kg, temperatura_c, frecuencia_cardiaca,
+                     frecuencia_respiratoria, id_firmante)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17,
+                         ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25)",
+                params![
+                    entrada.id.to_string(),
+                    entrada.id_historia_clinica.to_string(),
+                    entrada.fecha,
+                    entrada.descripcion,
+                    entrada.diagnostico,
+                    entrada.tratamiento,
+                    entrada.notas,
+                    entrada.version,
+                    entrada.fecha_creacion,
+                    entrada.fecha_actualizacion,
+                    entrada.creado_por,
+                    entrada.actualizado_por,
+                    entrada.id_original.map(|id| id.to_string()),
+                    entrada.enmienda_a.map(|id| id.to_string()),
+                    entrada.motivo_enmienda,
+                    entrada.estado.codigo(),
+                    entrada.firmada_por,
+                    entrada.fecha_firma,
+                    entrada.hash_contenido,
+                    entrada.id_veterinario.map(|id| id.to_string()),
+                    entrada.signos_vitales.peso_kg,
+                    entrada.signos_vitales.temperatura_c,
+                    entrada.signos_vitales.frecuencia_cardiaca,
+                    entrada.signos_vitales.frecuencia_respiratoria,
+                    entrada.id_firmante.map(|id| id.to_string()),
+                ],
+            )
+            .map(|_| ())
+            .map_err(DomainError::from)
+    }
+
+    fn actualizar_borrador(&mut self, entrada: EntradaHistoriaClinica) -> Result<(), DomainError> {
+        let actualizadas = self.conexion.execute(
+            "UPDATE entradas_historia_clinica SET
+                descripcion = ?2,
+                diagnostico = ?3,
+                tratamiento = ?4,
+                notas = ?5,
+                version = ?6,
+                fecha_actualizacion = ?7,
+                actualizado_por = ?8,
+                estado = ?9,
+                firmada_por = ?10,
+                fecha_firma = ?11,
+                hash_contenido = ?12,
+                id_veterinario = ?13,
+                peso_kg = ?14,
+                temperatura_c = ?15,
+                frecuencia_cardiaca = ?16,
+                frecuencia_respiratoria = ?17,
+                id_firmante = ?18
+             WHERE id = ?1 AND estado = 'borrador'",
+            params![
+                entrada.id.to_string(),
+                entrada.descripcion,
+                entrada.diagnostico,
+                entrada.tratamiento,
+                entrada.notas,
+                entrada.version,
+                entrada.fecha_actualizacion,
+                entrada.actualizado_por,
+                entrada.estado.codigo(),
+                entrada.firmada_por,
+                entrada.fecha_firma,
+                entrada.hash_contenido,
+                entrada.id_veterinario.map(|id| id.to_string()),
+                entrada.signos_vitales.peso_kg,
+                entrada.signos_vitales.temperatura_c,
+                entrada.signos_vitales.frecuencia_cardiaca,
+                entrada.signos_vitales.frecuencia_respiratoria,
+                entrada.id_firmante.map(|id| id.to_string()),
+            ],
+        )?;
+        if actualizadas == 0 {
+            return Err(match self.obtener_entrada(entrada.id)? {
+                Some(_) => entrada_firmada(entrada.id),
+                None => entrada_inexistente(entrada.id),
+            });
+        }
+        Ok(())
+    }
+
+    fn obtener_entrada(&self, id: Uuid) -> Result<Option<EntradaHistoriaClinica>, DomainError> {
+        self.conexion
+            .query_row(
+                "SELECT * FROM entradas_historia_clinica WHERE id = ?1",
+                params![id.to_string()],
+                Self::entrada_desde_fila,
+            )
+            .optional()
+            .map_err(DomainError::from)
+    }
+
+    fn obtener_enmienda(&self, id_entrada: Uuid) -> Result<Option<EntradaHistoriaClinica>, DomainError> {
+        self.conexion
+            .query_row(
+                "SELECT * FROM entradas_historia_clinica WHERE enmienda_a = ?1",
+                params![id_entrada.to_string()],
+                Self::entrada_desde_fila,
+            )
+            .optional()
+            .map_err(DomainError::from)
+    }
+
+    fn listar_revisiones(&self, id_original: Uuid) -> Result<Vec<EntradaHistoriaClinica>, DomainError> {
+        self.conexion
+            .prepare_cached(
+                "SELECT * FROM entradas_historia_clinica WHERE id = ?1 OR id_original = ?1
+                 ORDER BY enmienda_a IS NOT NULL, julianday(fecha_creacion), id",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_map(params![id_original.to_string()], Self::entrada_desde_fila)?
+                    .collect()
+            })
+            .map_err(DomainError::from)
+    }
+
+    fn obtener_entradas(&self, id_historia: Uuid) -> Result<Vec<EntradaHistoriaClinica>, DomainError> {
+        self.conexion
+            .prepare_cached(
+                "SELECT * FROM entradas_historia_clinica
+                 WHERE id_historia_clinica = ?1 ORDER BY fecha",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_map(params![id_historia.to_string()], Self::entrada_desde_fila)?
+                    .collect()
+            })
+            .map_err(DomainError::from)
+    }
+
+    fn contar_entradas_veterinario(&self, id_veterinario: Uuid) -> Result<usize, DomainError> {
+        self.conexion.query_row(
+            "SELECT COUNT(*) FROM entradas_historia_clinica WHERE id_veterinario = ?1 OR id_firmante = ?1",
+            params![id_veterinario.to_string()],
+            |row| row.get(0),
+        )
+        .map_err(DomainError::from)
+    }
+
+    fn listar_entradas(&self, id_historia: Uuid, consulta: &ConsultaEntradas) -> Result<Pagina<EntradaHistoriaClinica>, DomainError> {
+        let filtro = &consulta.filtro;
+        let mut filtros = Filtros::default();
+        filtros.agregar("id_historia_clinica = ?", id_historia.to_string());
+        // Se comparan como instantes y no como texto: las fechas guardadas
+        // pueden tener distinta cantidad de decimales
+        if let Some(desde) = filtro.desde {
+            filtros.agregar("julianday(fecha) >= julianday(?)", desde.to_rfc3339());
+        }
+        if let Some(hasta) = filtro.hasta {
+            filtros.agregar("julianday(fecha) <= julianday(?)", hasta.to_rfc3339());
+        }
+        if !filtro.incluir_enmendadas {
+            filtros.agregar_fija(
+                "NOT EXISTS (SELECT 1 FROM entradas_historia_clinica AS revision
+                 WHERE revision.enmienda_a = entradas_historia_clinica.id AND revision.estado <> 'borrador')",
+            );
+        }
+
+        listar_pagina(
+            &self.conexion,
+            "entradas_historia_clinica",
+            &filtros,
+            consulta.orden.columnas(),
+            consulta,
+            Self::entrada_desde_fila,
+        )
+        .map_err(DomainError::from)
+    }
+
+    fn eliminar_entradas(&mut self, id_historia: Uuid) -> Result<(), DomainError> {
+        let firmadas: i64 = self.conexion.query_row(
+            "SELECT COUNT(*) FROM entradas_historia_clinica
+             WHERE id_historia_clinica = ?1 AND estado <> 'borrador'",
+            params![id_historia.to_string()],
+            |fila| fila.get(0),
+        )?;
+        rechazar_firmadas(id_historia, firmadas as usize)?;
+
+        self.conexion
+            .execute(
+                "DELETE FROM entradas_historia_clinica WHERE id_historia_clinica = ?1",
+                params![id_historia.to_string()],
+            )
+            .map(|_| ())
+            .map_err(DomainError::from)
+    }
+
+    fn eliminar_borrador(&mut self, id: Uuid) -> Result<(), DomainError> {
+        let eliminadas = self.conexion.execute(
+            "DELETE FROM entradas_historia_clinica WHERE id = ?1 AND estado = 'borrador'",
+            params![id.to_string()],
+        )?;
+        if eliminadas == 0 {
+            return Err(match self.obtener_entrada(id)? {
+                Some(_) => entrada_firmada(id),
+                None => entrada_inexistente(id),
+            });
+        }
+        Ok(())
+    }
+
+    fn reasignar_entradas(&mut self, id_origen: Uuid, id_destino: Uuid) -> Result<(), DomainError> {
+        self.conexion
+            .execute(
+                "UPDATE entradas_historia_clinica SET id_historia_clinica = ?2
+                 WHERE id_historia_clinica = ?1",
+                params![id_origen.to_string(), id_destino.to_string()],
+            )
+            .map(|_| ())
+            .map_err(DomainError::from)
     }
-    
 }
@@ -1,49 +1,380 @@
 use crate::models::Mascota;
 use std::collections::HashMap;
 use uuid::Uuid;
+use crate::error::DomainError;
+use super::busqueda::{mejores, Coincidencia, IndiceBusqueda};
+use super::consulta::{comparar_texto, contiene, paginar, CampoOrden, Consulta, Pagina};
+use std::cmp::Ordering;
+
+#[cfg(any(feature = "storage-file", feature = "storage-sqlite"))]
+use std::path::Path;
+#[cfg(feature = "storage-file")]
+use super::file_repository::FileRepository;
+#[cfg(feature = "storage-sqlite")]
+use super::sqlite_repository::{
+    abrir_conexion, buscar_en_indice, desindexar, indexar, indexar_faltantes, leer_json, leer_uuid,
+    listar_pagina, Filtros,
+};
+#[cfg(feature = "storage-sqlite")]
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+#[derive(Debug, Clone, Default)]
+pub struct FiltroMascotas {
+    pub id_cliente: Option<Uuid>,
+    // Coincidencias parciales, sin distinguir mayúsculas
+    pub nombre: Option<String>,
+    pub especie: Option<String>,
+    pub incluir_archivadas: bool,
+}
+
+impl FiltroMascotas {
+    fn acepta(&self, mascota: &Mascota) -> bool {
+        (self.incluir_archivadas || !mascota.archivado)
+            && self.id_cliente.is_none_or(|id| mascota.id_cliente == id)
+            && self.nombre.as_ref().is_none_or(|n| contiene(&mascota.nombre, n))
+            && self.especie.as_ref().is_none_or(|e| contiene(&mascota.especie, e))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum OrdenMascotas {
+    #[default]
+    Nombre,
+    Especie,
+    Raza,
+    FechaNacimiento,
+}
+
+impl CampoOrden for OrdenMascotas {
+    const NOMBRES: &'static [&'static str] = &["nombre", "especie", "raza", "fecha_nacimiento"];
+
+    fn desde_nombre(nombre: &str) -> Option<Self> {
+        match nombre {
+            "nombre" => Some(OrdenMascotas::Nombre),
+            "especie" => Some(OrdenMascotas::Especie),
+            "raza" => Some(OrdenMascotas::Raza),
+            "fecha_nacimiento" => Some(OrdenMascotas::FechaNacimiento),
+            _ => None,
+        }
+    }
+}
+
+impl OrdenMascotas {
+    fn comparar(self, a: &Mascota, b: &Mascota) -> Ordering {
+        match self {
+            OrdenMascotas::Nombre => comparar_texto(&a.nombre, &b.nombre),
+            OrdenMascotas::Especie => comparar_texto(&a.especie, &b.especie),
+            OrdenMascotas::Raza => comparar_texto(&a.raza, &b.raza),
+            // Sin fecha primero, igual que los NULL en SQLite
+            OrdenMascotas::FechaNacimiento => a.fecha_nacimiento.cmp(&b.fecha_nacimiento),
+        }
+        .then(a.id.cmp(&b.id))
+    }
+
+    #[cfg(feature = "storage-sqlite")]
+    fn columnas(self) -> &'static [&'static str] {
+        match self {
+            OrdenMascotas::Nombre => &["nombre COLLATE NOCASE"],
+            OrdenMascotas::Especie => &["especie COLLATE NOCASE"],
+            OrdenMascotas::Raza => &["raza COLLATE NOCASE"],
+            OrdenMascotas::FechaNacimiento => &["fecha_nacimiento"],
+        }
+    }
+}
+
+pub type ConsultaMascotas = Consulta<FiltroMascotas, OrdenMascotas>;
 
 pub trait MascotaRepository {
-    fn obtener(&self, id: Uuid) -> Option<&Mascota>;
-    fn listar_por_cliente(&self, id_cliente: Uuid) -> Vec<&Mascota>;
-    fn guardar(&mut self, mascota: Mascota) -> Result<(), String>;
-    fn eliminar(&mut self, id: Uuid) -> Result<(), String>;
-    fn listar(&self) -> Vec<&Mascota>;
+    fn obtener(&self, id: Uuid) -> Result<Option<Mascota>, DomainError>;
+    fn listar_por_cliente(&self, id_cliente: Uuid) -> Result<Vec<Mascota>, DomainError>;
+    // Incluye las archivadas
+    fn listar_todas(&self) -> Result<Vec<Mascota>, DomainError>;
+    fn guardar(&mut self, mascota: Mascota) -> Result<(), DomainError>;
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError>;
+    fn listar(&self, consulta: &ConsultaMascotas) -> Result<Pagina<Mascota>, DomainError>;
+    // Búsqueda por prefijo (ver `repositories::busqueda`); excluye las archivadas
+    fn buscar(&self, terminos: &[String], limite: usize) -> Result<Vec<Coincidencia<Mascota>>, DomainError>;
 }
 
 pub struct InMemoryMascotaRepository {
     mascotas: HashMap<Uuid, Mascota>,
+    indice: IndiceBusqueda,
 }
 
 impl InMemoryMascotaRepository {
     pub fn new() -> Self {
         Self {
             mascotas: HashMap::new(),
+            indice: IndiceBusqueda::default(),
         }
     }
 }
 
 impl MascotaRepository for InMemoryMascotaRepository {
-    fn obtener(&self, id: Uuid) -> Option<&Mascota> {
-        self.mascotas.get(&id)
+    fn obtener(&self, id: Uuid) -> Result<Option<Mascota>, DomainError> {
+        Ok(self.mascotas.get(&id).cloned())
     }
 
-    fn listar_por_cliente(&self, id_cliente: Uuid) -> Vec<&Mascota> {
-        self.mascotas.values()
+    fn listar_por_cliente(&self, id_cliente: Uuid) -> Result<Vec<Mascota>, DomainError> {
+        Ok(self.mascotas.values()
             .filter(|m| m.id_cliente == id_cliente)
-            .collect()
+            .cloned()
+            .collect())
+    }
+
+    fn listar_todas(&self) -> Result<Vec<Mascota>, DomainError> {
+        Ok(self.mascotas.values().cloned().collect())
     }
 
-    fn guardar(&mut self, mascota: Mascota) -> Result<(), String> {
+    fn guardar(&mut self, mascota: Mascota) -> Result<(), DomainError> {
+        self.indice.actualizar(&mascota);
         self.mascotas.insert(mascota.id, mascota);
         Ok(())
     }
 
-    fn eliminar(&mut self, id: Uuid) -> Result<(), String> {
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.indice.quitar(id);
         self.mascotas.remove(&id);
         Ok(())
     }
 
-    fn listar(&self) -> Vec<&Mascota> {
-        self.mascotas.values().collect()
+    fn listar(&self, consulta: &ConsultaMascotas) -> Result<Pagina<Mascota>, DomainError> {
+        Ok(paginar(
+            self.mascotas.values(),
+            consulta,
+            |m| consulta.filtro.acepta(m),
+            |a, b| consulta.orden.comparar(a, b),
+        ))
+    }
+
+    fn buscar(&self, terminos: &[String], limite: usize) -> Result<Vec<Coincidencia<Mascota>>, DomainError> {
+        let coincidencias = self.indice.buscar(terminos).into_iter()
+            .filter_map(|(id, puntaje)| {
+                self.mascotas.get(&id).map(|elemento| Coincidencia { elemento: elemento.clone(), puntaje })
+            })
+            .collect();
+        Ok(mejores(coincidencias, limite))
+    }
+}
+
+#[cfg(feature = "storage-file")]
+pub struct FileMascotaRepository {
+    storage: FileRepository<Mascota>,
+    indice: IndiceBusqueda,
+}
+
+#[cfg(feature = "storage-file")]
+impl FileMascotaRepository {
+    pub fn new(data_dir: &Path) -> Result<Self, DomainError> {
+        let storage: FileRepository<Mascota> = FileRepository::open(data_dir.join("mascotas.json"))?;
+        let indice = IndiceBusqueda::construir(storage.records());
+        Ok(Self { storage, indice })
+    }
+}
+
+#[cfg(feature = "storage-file")]
+impl MascotaRepository for FileMascotaRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<Mascota>, DomainError> {
+        Ok(self.storage.get(id).cloned())
+    }
+
+    fn listar_por_cliente(&self, id_cliente: Uuid) -> Result<Vec<Mascota>, DomainError> {
+        Ok(self.storage.records().iter()
+            .filter(|m| m.id_cliente == id_cliente)
+            .cloned()
+            .collect())
+    }
+
+    fn listar_todas(&self) -> Result<Vec<Mascota>, DomainError> {
+        Ok(self.storage.records().to_vec())
+    }
+
+    fn guardar(&mut self, mascota: Mascota) -> Result<(), DomainError> {
+        self.storage.upsert(mascota.clone())?;
+        self.indice.actualizar(&mascota);
+        Ok(())
+    }
+
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.storage.delete(id)?;
+        self.indice.quitar(id);
+        Ok(())
+    }
+
+    fn listar(&self, consulta: &ConsultaMascotas) -> Result<Pagina<Mascota>, DomainError> {
+        Ok(paginar(
+            self.storage.records().iter(),
+            consulta,
+            |m| consulta.filtro.acepta(m),
+            |a, b| consulta.orden.comparar(a, b),
+        ))
+    }
+
+    fn buscar(&self, terminos: &[String], limite: usize) -> Result<Vec<Coincidencia<Mascota>>, DomainError> {
+        let coincidencias = self.indice.buscar(terminos).into_iter()
+            .filter_map(|(id, puntaje)| {
+                self.storage.get(id).map(|elemento| Coincidencia { elemento: elemento.clone(), puntaje })
+            })
+            .collect();
+        Ok(mejores(coincidencias, limite))
+    }
+}
+
+#[cfg(feature = "storage-sqlite")]
+pub struct SqliteMascotaRepository {
+    conexion: Connection,
+}
+
+#[cfg(feature = "storage-sqlite")]
+impl SqliteMascotaRepository {
+    pub fn new(ruta: &Path) -> Result<Self, DomainError> {
+        let mut conexion = abrir_conexion(ruta)?;
+        indexar_faltantes(&mut conexion, "mascota", "mascotas", Self::desde_fila)?;
+        Ok(Self { conexion })
+    }
+
+    fn desde_fila(row: &Row) -> rusqlite::Result<Mascota> {
+        Ok(Mascota {
+            id: leer_uuid(row, "id")?,
+            nombre: row.get("nombre")?,
+            especie: row.get("especie")?,
+            raza: row.get("raza")?,
+            fecha_nacimiento: row.get("fecha_nacimiento")?,
+            id_cliente: leer_uuid(row, "id_cliente")?,
+            alergias: leer_json(row, "alergias")?,
+            condiciones_cronicas: leer_json(row, "condiciones_cronicas")?,
+            archivado: row.get("archivado")?,
+            fecha_archivado: row.get("fecha_archivado")?,
+            version: row.get("version")?,
+            fecha_creacion: row.get("fecha_creacion")?,
+            fecha_actualizacion: row.get("fecha_actualizacion")?,
+            creado_por: row.get("creado_por")?,
+            actualizado_por: row.get("actualizado_por")?,
+        })
     }
-} 
+}
+
+#[cfg(feature = "storage-sqlite")]
+impl MascotaRepository for SqliteMascotaRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<Mascota>, DomainError> {
+        self.conexion
+            .query_row(
+                "SELECT * FROM mascotas WHERE id = ?1",
+                params![id.to_string()],
+                Self::desde_fila,
+            )
+            .optional()
+            .map_err(DomainError::from)
+    }
+
+    fn listar_por_cliente(&self, id_cliente: Uuid) -> Result<Vec<Mascota>, DomainError> {
+        self.conexion
+            .prepare_cached("SELECT * FROM mascotas WHERE id_cliente = ?1")
+            .and_then(|mut stmt| {
+                stmt.query_map(params![id_cliente.to_string()], Self::desde_fila)?
+                    .collect()
+            })
+            .map_err(DomainError::from)
+    }
+
+    fn listar_todas(&self) -> Result<Vec<Mascota>, DomainError> {
+        self.conexion
+            .prepare_cached("SELECT * FROM mascotas")
+            .and_then(|mut stmt| stmt.query_map([], Self::desde_fila)?.collect())
+            .map_err(DomainError::from)
+    }
+
+    // Las alergias y las condiciones crónicas se guardan como arreglos JSON
+    fn guardar(&mut self, mascota: Mascota) -> Result<(), DomainError> {
+        let alergias = serde_json::to_string(&mascota.alergias)
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+        let condiciones_cronicas = serde_json::to_string(&mascota.condiciones_cronicas)
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+
+        let tx = self.conexion.transaction()?;
+        tx.execute(
+            "INSERT INTO mascotas
+                (id, nombre, especie, raza, fecha_nacimiento, id_cliente,
+                 archivado, fecha_archivado, version,
+                 fecha_creacion, fecha_actualizacion, creado_por, actualizado_por,
+                 alergias, condiciones_cronicas)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
+             ON CONFLICT(id) DO UPDATE SET
+                nombre = excluded.nombre,
+                especie = excluded.especie,
+                raza = excluded.raza,
+                fecha_nacimiento = excluded.fecha_nacimiento,
+                id_cliente = excluded.id_cliente,
+                alergias = excluded.alergias,
+                condiciones_cronicas = excluded.condiciones_cronicas,
+                archivado = excluded.archivado,
+                fecha_archivado = excluded.fecha_archivado,
+                version = excluded.version,
+                fecha_actualizacion = excluded.fecha_actualizacion,
+                actualizado_por = excluded.actualizado_por",
+            params![
+                mascota.id.to_string(),
+                mascota.nombre,
+                mascota.especie,
+                mascota.raza,
+                mascota.fecha_nacimiento,
+                mascota.id_cliente.to_string(),
+                mascota.archivado,
+                mascota.fecha_archivado,
+                mascota.version,
+                mascota.fecha_creacion,
+                mascota.fecha_actualizacion,
+                mascota.creado_por,
+                mascota.actualizado_por,
+                alergias,
+                condiciones_cronicas,
+            ],
+        )?;
+        indexar(&tx, "mascota", &mascota)?;
+        tx.commit().map_err(DomainError::from)
+    }
+
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError> {
+        let tx = self.conexion.transaction()?;
+        tx.execute("DELETE FROM mascotas WHERE id = ?1", params![id.to_string()])?;
+        desindexar(&tx, "mascota", id)?;
+        tx.commit().map_err(DomainError::from)
+    }
+
+    fn buscar(&self, terminos: &[String], limite: usize) -> Result<Vec<Coincidencia<Mascota>>, DomainError> {
+        let mut coincidencias = Vec::new();
+        for (id, puntaje) in buscar_en_indice(&self.conexion, "mascota", terminos)? {
+            if let Some(elemento) = self.obtener(id)? {
+                coincidencias.push(Coincidencia { elemento, puntaje });
+            }
+        }
+        Ok(mejores(coincidencias, limite))
+    }
+
+    fn listar(&self, consulta: &ConsultaMascotas) -> Result<Pagina<Mascota>, DomainError> {
+        let filtro = &consulta.filtro;
+        let mut filtros = Filtros::default();
+        if !filtro.incluir_archivadas {
+            filtros.agregar_fija("archivado = 0");
+        }
+        if let Some(id_cliente) = filtro.id_cliente {
+            filtros.agregar("id_cliente = ?", id_cliente.to_string());
+        }
+        if let Some(nombre) = &filtro.nombre {
+            filtros.agregar("instr(lower(nombre), lower(?)) > 0", nombre.clone());
+        }
+        if let Some(especie) = &filtro.especie {
+            filtros.agregar("instr(lower(especie), lower(?)) > 0", especie.clone());
+        }
+
+        listar_pagina(
+            &self.conexion,
+            "mascotas",
+            &filtros,
+            consulta.orden.columnas(),
+            consulta,
+            Self::desde_fila,
+        )
+        .map_err(DomainError::from)
+    }
+}
@@ -0,0 +1,309 @@
+use crate::models::Medicamento;
+use std::collections::HashMap;
+use uuid::Uuid;
+use crate::error::DomainError;
+use super::consulta::{comparar_texto, contiene, paginar, CampoOrden, Consulta, Pagina};
+use std::cmp::Ordering;
+
+#[cfg(any(feature = "storage-file", feature = "storage-sqlite"))]
+use std::path::Path;
+#[cfg(feature = "storage-file")]
+use super::file_repository::FileRepository;
+#[cfg(feature = "storage-sqlite")]
+use super::sqlite_repository::{abrir_conexion, leer_json, leer_uuid, listar_pagina, Filtros};
+#[cfg(feature = "storage-sqlite")]
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+#[derive(Debug, Clone, Default)]
+pub struct FiltroMedicamentos {
+    // Coincidencia parcial en el principio activo o el nombre comercial
+    pub nombre: Option<String>,
+    // Medicamentos con dosis indicada para la especie (código del catálogo)
+    pub especie: Option<String>,
+    pub incluir_archivados: bool,
+}
+
+impl FiltroMedicamentos {
+    fn acepta(&self, medicamento: &Medicamento) -> bool {
+        (self.incluir_archivados || !medicamento.archivado)
+            && self.nombre.as_ref().is_none_or(|n| {
+                contiene(&medicamento.principio_activo, n)
+                    || medicamento.nombre_comercial.as_ref().is_some_and(|c| contiene(c, n))
+            })
+            && self.especie.as_ref().is_none_or(|e| medicamento.rango_para(e).is_some())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum OrdenMedicamentos {
+    #[default]
+    PrincipioActivo,
+    NombreComercial,
+    Presentacion,
+}
+
+impl CampoOrden for OrdenMedicamentos {
+    const NOMBRES: &'static [&'static str] = &["principio_activo", "nombre_comercial", "presentacion"];
+
+    fn desde_nombre(nombre: &str) -> Option<Self> {
+        match nombre {
+            "principio_activo" => Some(OrdenMedicamentos::PrincipioActivo),
+            "nombre_comercial" => Some(OrdenMedicamentos::NombreComercial),
+            "presentacion" => Some(OrdenMedicamentos::Presentacion),
+            _ => None,
+        }
+    }
+}
+
+impl OrdenMedicamentos {
+    fn comparar(self, a: &Medicamento, b: &Medicamento) -> Ordering {
+        match self {
+            OrdenMedicamentos::PrincipioActivo => comparar_texto(&a.principio_activo, &b.principio_activo),
+            OrdenMedicamentos::NombreComercial => comparar_texto(
+                a.nombre_comercial.as_deref().unwrap_or_default(),
+                b.nombre_comercial.as_deref().unwrap_or_default(),
+            ),
+            OrdenMedicamentos::Presentacion => comparar_texto(&a.presentacion, &b.presentacion),
+        }
+        .then(a.id.cmp(&b.id))
+    }
+
+    #[cfg(feature = "storage-sqlite")]
+    fn columnas(self) -> &'static [&'static str] {
+        match self {
+            OrdenMedicamentos::PrincipioActivo => &["principio_activo COLLATE NOCASE"],
+            OrdenMedicamentos::NombreComercial => &["coalesce(nombre_comercial, '') COLLATE NOCASE"],
+            OrdenMedicamentos::Presentacion => &["presentacion COLLATE NOCASE"],
+        }
+    }
+}
+
+pub type ConsultaMedicamentos = Consulta<FiltroMedicamentos, OrdenMedicamentos>;
+
+pub trait MedicamentoRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<Medicamento>, DomainError>;
+    fn listar(&self, consulta: &ConsultaMedicamentos) -> Result<Pagina<Medicamento>, DomainError>;
+    fn listar_todos(&self) -> Result<Vec<Medicamento>, DomainError>;
+    fn guardar(&mut self, medicamento: Medicamento) -> Result<(), DomainError>;
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError>;
+}
+
+pub struct InMemoryMedicamentoRepository {
+    medicamentos: HashMap<Uuid, Medicamento>,
+}
+
+impl InMemoryMedicamentoRepository {
+    pub fn new() -> Self {
+        Self {
+            medicamentos: HashMap::new(),
+        }
+    }
+}
+
+impl MedicamentoRepository for InMemoryMedicamentoRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<Medicamento>, DomainError> {
+        Ok(self.medicamentos.get(&id).cloned())
+    }
+
+    fn listar(&self, consulta: &ConsultaMedicamentos) -> Result<Pagina<Medicamento>, DomainError> {
+        Ok(paginar(
+            self.medicamentos.values(),
+            consulta,
+            |m| consulta.filtro.acepta(m),
+            |a, b| consulta.orden.comparar(a, b),
+        ))
+    }
+
+    fn listar_todos(&self) -> Result<Vec<Medicamento>, DomainError> {
+        Ok(self.medicamentos.values().cloned().collect())
+    }
+
+    fn guardar(&mut self, medicamento: Medicamento) -> Result<(), DomainError> {
+        self.medicamentos.insert(medicamento.id, medicamento);
+        Ok(())
+    }
+
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.medicamentos.remove(&id);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "storage-file")]
+pub struct FileMedicamentoRepository {
+    storage: FileRepository<Medicamento>,
+}
+
+#[cfg(feature = "storage-file")]
+impl FileMedicamentoRepository {
+    pub fn new(data_dir: &Path) -> Result<Self, DomainError> {
+        Ok(Self {
+            storage: FileRepository::open(data_dir.join("medicamentos.json"))?,
+        })
+    }
+}
+
+#[cfg(feature = "storage-file")]
+impl MedicamentoRepository for FileMedicamentoRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<Medicamento>, DomainError> {
+        Ok(self.storage.get(id).cloned())
+    }
+
+    fn listar(&self, consulta: &ConsultaMedicamentos) -> Result<Pagina<Medicamento>, DomainError> {
+        Ok(paginar(
+            self.storage.records().iter(),
+            consulta,
+            |m| consulta.filtro.acepta(m),
+            |a, b| consulta.orden.comparar(a, b),
+        ))
+    }
+
+    fn listar_todos(&self) -> Result<Vec<Medicamento>, DomainError> {
+        Ok(self.storage.records().to_vec())
+    }
+
+    fn guardar(&mut self, medicamento: Medicamento) -> Result<(), DomainError> {
+        self.storage.upsert(medicamento)
+    }
+
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.storage.delete(id)
+    }
+}
+
+#[cfg(feature = "storage-sqlite")]
+pub struct SqliteMedicamentoRepository {
+    conexion: Connection,
+}
+
+#[cfg(feature = "storage-sqlite")]
+impl SqliteMedicamentoRepository {
+    pub fn new(ruta: &Path) -> Result<Self, DomainError> {
+        Ok(Self {
+            conexion: abrir_conexion(ruta)?,
+        })
+    }
+
+    fn desde_fila(row: &Row) -> rusqlite::Result<Medicamento> {
+        Ok(Medicamento {
+            id: leer_uuid(row, "id")?,
+            principio_activo: row.get("principio_activo")?,
+            nombre_comercial: row.get("nombre_comercial")?,
+            presentacion: row.get("presentacion")?,
+            concentracion_mg: row.get("concentracion_mg")?,
+            unidad: row.get("unidad")?,
+            dosis: leer_json(row, "dosis")?,
+            archivado: row.get("archivado")?,
+            fecha_archivado: row.get("fecha_archivado")?,
+            version: row.get("version")?,
+            fecha_creacion: row.get("fecha_creacion")?,
+            fecha_actualizacion: row.get("fecha_actualizacion")?,
+            creado_por: row.get("creado_por")?,
+            actualizado_por: row.get("actualizado_por")?,
+        })
+    }
+}
+
+#[cfg(feature = "storage-sqlite")]
+impl MedicamentoRepository for SqliteMedicamentoRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<Medicamento>, DomainError> {
+        self.conexion
+            .query_row(
+                "SELECT * FROM medicamentos WHERE id = ?1",
+                params![id.to_string()],
+                Self::desde_fila,
+            )
+            .optional()
+            .map_err(DomainError::from)
+    }
+
+    fn listar(&self, consulta: &ConsultaMedicamentos) -> Result<Pagina<Medicamento>, DomainError> {
+        let filtro = &consulta.filtro;
+        let mut filtros = Filtros::default();
+        if !filtro.incluir_archivados {
+            filtros.agregar_fija("archivado = 0");
+        }
+        if let Some(nombre) = &filtro.nombre {
+            filtros.agregar(
+                "instr(lower(principio_activo || ' ' || coalesce(nombre_comercial, '')), lower(?)) > 0",
+                nombre.clone(),
+            );
+        }
+        if let Some(especie) = &filtro.especie {
+            filtros.agregar(
+                "EXISTS (SELECT 1 FROM json_each(medicamentos.dosis)
+                         WHERE json_extract(value, '$.especie') = ?)",
+                especie.clone(),
+            );
+        }
+
+        listar_pagina(
+            &self.conexion,
+            "medicamentos",
+            &filtros,
+            consulta.orden.columnas(),
+            consulta,
+            Self::desde_fila,
+        )
+        .map_err(DomainError::from)
+    }
+
+    fn listar_todos(&self) -> Result<Vec<Medicamento>, DomainError> {
+        self.conexion
+            .prepare_cached("SELECT * FROM medicamentos")
+            .and_then(|mut stmt| stmt.query_map([], Self::desde_fila)?.collect())
+            .map_err(DomainError::from)
+    }
+
+    // Los rangos de dosis se guardan como un arreglo JSON
+    fn guardar(&mut self, medicamento: Medicamento) -> Result<(), DomainError> {
+        let dosis = serde_json::to_string(&medicamento.dosis)
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+
+        self.conexion
+            .execute(
+                "INSERT INTO medicamentos
+                    (id, principio_activo, nombre_comercial, presentacion, concentracion_mg, unidad, dosis,
+                     archivado, fecha_archivado, version, fecha_creacion, fecha_actualizacion,
+                     creado_por, actualizado_por)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
+                 ON CONFLICT(id) DO UPDATE SET
+                    principio_activo = excluded.principio_activo,
+                    nombre_comercial = excluded.nombre_comercial,
+                    presentacion = excluded.presentacion,
+                    concentracion_mg = excluded.concentracion_mg,
+                    unidad = excluded.unidad,
+                    dosis = excluded.dosis,
+                    archivado = excluded.archivado,
+                    fecha_archivado = excluded.fecha_archivado,
+                    version = excluded.version,
+                    fecha_actualizacion = excluded.fecha_actualizacion,
+                    actualizado_por = excluded.actualizado_por",
+                params![
+                    medicamento.id.to_string(),
+                    medicamento.principio_activo,
+                    medicamento.nombre_comercial,
+                    medicamento.presentacion,
+                    medicamento.concentracion_mg,
+                    medicamento.unidad,
+                    dosis,
+                    medicamento.archivado,
+                    medicamento.fecha_archivado,
+                    medicamento.version,
+                    medicamento.fecha_creacion,
+                    medicamento.fecha_actualizacion,
+                    medicamento.creado_por,
+                    medicamento.actualizado_por,
+                ],
+            )
+            .map(|_| ())
+            .map_err(DomainError::from)
+    }
+
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.conexion
+            .execute("DELETE FROM medicamentos WHERE id = ?1", params![id.to_string()])
+            .map(|_| ())
+            .map_err(DomainError::from)
+    }
+}
@@ -2,4 +2,119 @@ pub mod clinica_repository;
 pub mod cliente_repository;
 pub mod mascota_repository;
 pub mod historia_clinica_repository;
-pub mod file_repository;
\ No newline at end of file
+pub mod personal_repository;
+pub mod turno_repository;
+pub mod vacunacion_repository;
+pub mod medicamento_repository;
+pub mod prescripcion_repository;
+pub mod especie_repository;
+pub mod consulta;
+pub mod busqueda;
+#[cfg(feature = "storage-file")]
+pub mod file_repository;
+#[cfg(feature = "storage-sqlite")]
+pub mod sqlite_repository;
+
+use crate::config::{StorageBackend, StorageConfig};
+use crate::error::DomainError;
+use clinica_repository::{ClinicaRepository, InMemoryClinicaRepository};
+use cliente_repository::{ClienteRepository, InMemoryClienteRepository};
+use mascota_repository::{MascotaRepository, InMemoryMascotaRepository};
+use historia_clinica_repository::{HistoriaClinicaRepository, InMemoryHistoriaClinicaRepository};
+use personal_repository::{PersonalRepository, InMemoryPersonalRepository};
+use turno_repository::{TurnoRepository, InMemoryTurnoRepository};
+use vacunacion_repository::{VacunacionRepository, InMemoryVacunacionRepository};
+use medicamento_repository::{MedicamentoRepository, InMemoryMedicamentoRepository};
+use prescripcion_repository::{PrescripcionRepository, InMemoryPrescripcionRepository};
+use especie_repository::{EspecieRepository, InMemoryEspecieRepository};
+
+pub struct Repositorios {
+    pub clinicas: Box<dyn ClinicaRepository + Send>,
+    pub clientes: Box<dyn ClienteRepository + Send>,
+    pub mascotas: Box<dyn MascotaRepository + Send>,
+    pub historias_clinicas: Box<dyn HistoriaClinicaRepository + Send>,
+    pub personal: Box<dyn PersonalRepository + Send>,
+    pub turnos: Box<dyn TurnoRepository + Send>,
+    pub vacunaciones: Box<dyn VacunacionRepository + Send>,
+    pub medicamentos: Box<dyn MedicamentoRepository + Send>,
+    pub prescripciones: Box<dyn PrescripcionRepository + Send>,
+    pub especies: Box<dyn EspecieRepository + Send>,
+}
+
+impl Repositorios {
+    pub fn desde_config(config: &StorageConfig) -> Result<Self, DomainError> {
+        match config.backend {
+            StorageBackend::Memory => Ok(Self {
+                clinicas: Box::new(InMemoryClinicaRepository::new()),
+                clientes: Box::new(InMemoryClienteRepository::new()),
+                mascotas: Box::new(InMemoryMascotaRepository::new()),
+                historias_clinicas: Box::new(InMemoryHistoriaClinicaRepository::new()),
+                personal: Box::new(InMemoryPersonalRepository::new()),
+                turnos: Box::new(InMemoryTurnoRepository::new()),
+                vacunaciones: Box::new(InMemoryVacunacionRepository::new()),
+                medicamentos: Box::new(InMemoryMedicamentoRepository::new()),
+                prescripciones: Box::new(InMemoryPrescripcionRepository::new()),
+                especies: Box::new(InMemoryEspecieRepository::new()),
+            }),
+            #[cfg(feature = "storage-file")]
+            StorageBackend::File => {
+                use clinica_repository::FileClinicaRepository;
+                use cliente_repository::FileClienteRepository;
+                use mascota_repository::FileMascotaRepository;
+                use historia_clinica_repository::FileHistoriaClinicaRepository;
+                use personal_repository::FilePersonalRepository;
+                use turno_repository::FileTurnoRepository;
+                use vacunacion_repository::FileVacunacionRepository;
+                use medicamento_repository::FileMedicamentoRepository;
+                use prescripcion_repository::FilePrescripcionRepository;
+                use especie_repository::FileEspecieRepository;
+
+                let dir = &config.data_dir;
+                Ok(Self {
+                    clinicas: Box::new(FileClinicaRepository::new(dir)?),
+                    clientes: Box::new(FileClienteRepository::new(dir)?),
+                    mascotas: Box::new(FileMascotaRepository::new(dir)?),
+                    historias_clinicas: Box::new(FileHistoriaClinicaRepository::new(dir)?),
+                    personal: Box::new(FilePersonalRepository::new(dir)?),
+                    turnos: Box::new(FileTurnoRepository::new(dir)?),
+                    vacunaciones: Box::new(FileVacunacionRepository::new(dir)?),
+                    medicamentos: Box::new(FileMedicamentoRepository::new(dir)?),
+                    prescripciones: Box::new(FilePrescripcionRepository::new(dir)?),
+                    especies: Box::new(FileEspecieRepository::new(dir)?),
+                })
+            }
+            #[cfg(feature = "storage-sqlite")]
+            StorageBackend::Sqlite => {
+                use clinica_repository::SqliteClinicaRepository;
+                use cliente_repository::SqliteClienteRepository;
+                use mascota_repository::SqliteMascotaRepository;
+                use historia_clinica_repository::SqliteHistoriaClinicaRepository;
+                use personal_repository::SqlitePersonalRepository;
+                use turno_repository::SqliteTurnoRepository;
+                use vacunacion_repository::SqliteVacunacionRepository;
+                use medicamento_repository::SqliteMedicamentoRepository;
+                use prescripcion_repository::SqlitePrescripcionRepository;
+                use especie_repository::SqliteEspecieRepository;
+
+                let ruta = config.data_dir.join("centralvet.db");
+                Ok(Self {
+                    clinicas: Box::new(SqliteClinicaRepository::new(&ruta)?),
+                    clientes: Box::new(SqliteClienteRepository::new(&ruta)?),
+                    mascotas: Box::new(SqliteMascotaRepository::new(&ruta)?),
+                    historias_clinicas: Box::new(SqliteHistoriaClinicaRepository::new(&ruta)?),
+                    personal: Box::new(SqlitePersonalRepository::new(&ruta)?),
+                    turnos: Box::new(SqliteTurnoRepository::new(&ruta)?),
+                    vacunaciones: Box::new(SqliteVacunacionRepository::new(&ruta)?),
+                    medicamentos: Box::new(SqliteMedicamentoRepository::new(&ruta)?),
+                    prescripciones: Box::new(SqlitePrescripcionRepository::new(&ruta)?),
+                    especies: Box::new(SqliteEspecieRepository::new(&ruta)?),
+                })
+            }
+            #[allow(unreachable_patterns)]
+            backend => Err(DomainError::Internal(format!(
+                "El backend '{}' no está disponible: compilar con la feature storage-{}",
+                backend, backend
+            ))),
+        }
+    }
+}
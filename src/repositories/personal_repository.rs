@@ -0,0 +1,377 @@
+use crate::models::personal::{Personal, RolPersonal};
+use std::collections::HashMap;
+use uuid::Uuid;
+use crate::error::DomainError;
+use super::consulta::{comparar_texto, contiene, paginar, CampoOrden, Consulta, Pagina};
+use std::cmp::Ordering;
+
+#[cfg(any(feature = "storage-file", feature = "storage-sqlite"))]
+use std::path::Path;
+#[cfg(feature = "storage-file")]
+use super::file_repository::FileRepository;
+#[cfg(feature = "storage-sqlite")]
+use super::sqlite_repository::{abrir_conexion, leer_json, leer_uuid, listar_pagina, Filtros};
+#[cfg(feature = "storage-sqlite")]
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+#[derive(Debug, Clone, Default)]
+pub struct FiltroPersonal {
+    pub id_clinica: Option<Uuid>,
+    pub rol: Option<RolPersonal>,
+    // Coincidencia parcial en "nombre apellido", sin distinguir mayúsculas
+    pub nombre: Option<String>,
+    // Coincidencia exacta con alguna especialidad, sin distinguir mayúsculas
+    pub especialidad: Option<String>,
+    pub incluir_archivados: bool,
+}
+
+impl FiltroPersonal {
+    fn acepta(&self, personal: &Personal) -> bool {
+        (self.incluir_archivados || !personal.archivado)
+            && self.id_clinica.is_none_or(|id| personal.trabaja_en(id))
+            && self.rol.is_none_or(|rol| personal.rol == rol)
+            && self.nombre.as_ref().is_none_or(|n| {
+                contiene(&format!("{} {}", personal.nombre, personal.apellido), n)
+            })
+            && self.especialidad.as_ref().is_none_or(|buscada| {
+                personal.especialidades.iter().any(|e| e.to_lowercase() == buscada.to_lowercase())
+            })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum OrdenPersonal {
+    #[default]
+    Apellido,
+    Nombre,
+    Matricula,
+}
+
+impl CampoOrden for OrdenPersonal {
+    const NOMBRES: &'static [&'static str] = &["apellido", "nombre", "matricula"];
+
+    fn desde_nombre(nombre: &str) -> Option<Self> {
+        match nombre {
+            "apellido" => Some(OrdenPersonal::Apellido),
+            "nombre" => Some(OrdenPersonal::Nombre),
+            "matricula" => Some(OrdenPersonal::Matricula),
+            _ => None,
+        }
+    }
+}
+
+impl OrdenPersonal {
+    fn comparar(self, a: &Personal, b: &Personal) -> Ordering {
+        match self {
+            OrdenPersonal::Apellido => comparar_texto(&a.apellido, &b.apellido),
+            OrdenPersonal::Nombre => comparar_texto(&a.nombre, &b.nombre),
+            OrdenPersonal::Matricula => comparar_texto(
+                a.matricula.as_deref().unwrap_or_default(),
+                b.matricula.as_deref().unwrap_or_default(),
+            ),
+        }
+        .then(a.id.cmp(&b.id))
+    }
+
+    #[cfg(feature = "storage-sqlite")]
+    fn columnas(self) -> &'static [&'static str] {
+        match self {
+            OrdenPersonal::Apellido => &["apellido COLLATE NOCASE"],
+            OrdenPersonal::Nombre => &["nombre COLLATE NOCASE"],
+            OrdenPersonal::Matricula => &["coalesce(matricula, '') COLLATE NOCASE"],
+        }
+    }
+}
+
+pub type ConsultaPersonal = Consulta<FiltroPersonal, OrdenPersonal>;
+
+fn misma_matricula(personal: &Personal, matricula: &str) -> bool {
+    personal.matricula.as_deref().is_some_and(|m| m.eq_ignore_ascii_case(matricula))
+}
+
+pub trait PersonalRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<Personal>, DomainError>;
+    // Sin distinguir mayúsculas
+    fn obtener_por_matricula(&self, matricula: &str) -> Result<Option<Personal>, DomainError>;
+    fn listar(&self, consulta: &ConsultaPersonal) -> Result<Pagina<Personal>, DomainError>;
+    // Incluye al personal archivado
+    fn listar_por_clinica(&self, id_clinica: Uuid) -> Result<Vec<Personal>, DomainError>;
+    fn guardar(&mut self, personal: Personal) -> Result<(), DomainError>;
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError>;
+}
+
+pub struct InMemoryPersonalRepository {
+    personal: HashMap<Uuid, Personal>,
+}
+
+impl InMemoryPersonalRepository {
+    pub fn new() -> Self {
+        Self {
+            personal: HashMap::new(),
+        }
+    }
+}
+
+impl PersonalRepository for InMemoryPersonalRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<Personal>, DomainError> {
+        Ok(self.personal.get(&id).cloned())
+    }
+
+    fn obtener_por_matricula(&self, matricula: &str) -> Result<Option<Personal>, DomainError> {
+        Ok(self.personal.values().find(|p| misma_matricula(p, matricula)).cloned())
+    }
+
+    fn listar(&self, consulta: &ConsultaPersonal) -> Result<Pagina<Personal>, DomainError> {
+        Ok(paginar(
+            self.personal.values(),
+            consulta,
+            |p| consulta.filtro.acepta(p),
+            |a, b| consulta.orden.comparar(a, b),
+        ))
+    }
+
+    fn listar_por_clinica(&self, id_clinica: Uuid) -> Result<Vec<Personal>, DomainError> {
+        Ok(self.personal.values()
+            .filter(|p| p.trabaja_en(id_clinica))
+            .cloned()
+            .collect())
+    }
+
+    fn guardar(&mut self, personal: Personal) -> Result<(), DomainError> {
+        self.personal.insert(personal.id, personal);
+        Ok(())
+    }
+
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.personal.remove(&id);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "storage-file")]
+pub struct FilePersonalRepository {
+    storage: FileRepository<Personal>,
+}
+
+#[cfg(feature = "storage-file")]
+impl FilePersonalRepository {
+    pub fn new(data_dir: &Path) -> Result<Self, DomainError> {
+        Ok(Self {
+            storage: FileRepository::open(data_dir.join("personal.json"))?,
+        })
+    }
+}
+
+#[cfg(feature = "storage-file")]
+impl PersonalRepository for FilePersonalRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<Personal>, DomainError> {
+        Ok(self.storage.get(id).cloned())
+    }
+
+    fn obtener_por_matricula(&self, matricula: &str) -> Result<Option<Personal>, DomainError> {
+        Ok(self.storage.records().iter().find(|p| misma_matricula(p, matricula)).cloned())
+    }
+
+    fn listar(&self, consulta: &ConsultaPersonal) -> Result<Pagina<Personal>, DomainError> {
+        Ok(paginar(
+            self.storage.records().iter(),
+            consulta,
+            |p| consulta.filtro.acepta(p),
+            |a, b| consulta.orden.comparar(a, b),
+        ))
+    }
+
+    fn listar_por_clinica(&self, id_clinica: Uuid) -> Result<Vec<Personal>, DomainError> {
+        Ok(self.storage.records().iter()
+            .filter(|p| p.trabaja_en(id_clinica))
+            .cloned()
+            .collect())
+    }
+
+    fn guardar(&mut self, personal: Personal) -> Result<(), DomainError> {
+        self.storage.upsert(personal)
+    }
+
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.storage.delete(id)
+    }
+}
+
+#[cfg(feature = "storage-sqlite")]
+fn leer_rol(row: &Row) -> rusqlite::Result<RolPersonal> {
+    let codigo: String = row.get("rol")?;
+    RolPersonal::desde_codigo(&codigo).ok_or_else(|| {
+        rusqlite::Error::FromSqlConversionFailure(
+            0,
+            rusqlite::types::Type::Text,
+            format!("rol desconocido: {}", codigo).into(),
+        )
+    })
+}
+
+#[cfg(feature = "storage-sqlite")]
+pub struct SqlitePersonalRepository {
+    conexion: Connection,
+}
+
+#[cfg(feature = "storage-sqlite")]
+impl SqlitePersonalRepository {
+    pub fn new(ruta: &Path) -> Result<Self, DomainError> {
+        Ok(Self {
+            conexion: abrir_conexion(ruta)?,
+        })
+    }
+
+    fn desde_fila(row: &Row) -> rusqlite::Result<Personal> {
+        Ok(Personal {
+            id: leer_uuid(row, "id")?,
+            nombre: row.get("nombre")?,
+            apellido: row.get("apellido")?,
+            rol: leer_rol(row)?,
+            matricula: row.get("matricula")?,
+            especialidades: leer_json(row, "especialidades")?,
+            clinicas: leer_json(row, "clinicas")?,
+            correo: row.get("correo")?,
+            telefono: row.get("telefono")?,
+            archivado: row.get("archivado")?,
+            fecha_archivado: row.get("fecha_archivado")?,
+            version: row.get("version")?,
+            fecha_creacion: row.get("fecha_creacion")?,
+            fecha_actualizacion: row.get("fecha_actualizacion")?,
+            creado_por: row.get("creado_por")?,
+            actualizado_por: row.get("actualizado_por")?,
+        })
+    }
+}
+
+#[cfg(feature = "storage-sqlite")]
+impl PersonalRepository for SqlitePersonalRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<Personal>, DomainError> {
+        self.conexion
+            .query_row(
+                "SELECT * FROM personal WHERE id = ?1",
+                params![id.to_string()],
+                Self::desde_fila,
+            )
+            .optional()
+            .map_err(DomainError::from)
+    }
+
+    fn obtener_por_matricula(&self, matricula: &str) -> Result<Option<Personal>, DomainError> {
+        self.conexion
+            .query_row(
+                "SELECT * FROM personal WHERE matricula = ?1 COLLATE NOCASE",
+                params![matricula],
+                Self::desde_fila,
+            )
+            .optional()
+            .map_err(DomainError::from)
+    }
+
+    fn listar(&self, consulta: &ConsultaPersonal) -> Result<Pagina<Personal>, DomainError> {
+        let filtro = &consulta.filtro;
+        let mut filtros = Filtros::default();
+        if !filtro.incluir_archivados {
+            filtros.agregar_fija("archivado = 0");
+        }
+        if let Some(id_clinica) = filtro.id_clinica {
+            filtros.agregar(
+                "EXISTS (SELECT 1 FROM json_each(personal.clinicas) WHERE value = ?)",
+                id_clinica.to_string(),
+            );
+        }
+        if let Some(rol) = filtro.rol {
+            filtros.agregar("rol = ?", rol.codigo().to_string());
+        }
+        if let Some(nombre) = &filtro.nombre {
+            filtros.agregar("instr(lower(nombre || ' ' || apellido), lower(?)) > 0", nombre.clone());
+        }
+        if let Some(especialidad) = &filtro.especialidad {
+            filtros.agregar(
+                "EXISTS (SELECT 1 FROM json_each(personal.especialidades) WHERE lower(value) = lower(?))",
+                especialidad.clone(),
+            );
+        }
+
+        listar_pagina(
+            &self.conexion,
+            "personal",
+            &filtros,
+            consulta.orden.columnas(),
+            consulta,
+            Self::desde_fila,
+        )
+        .map_err(DomainError::from)
+    }
+
+    fn listar_por_clinica(&self, id_clinica: Uuid) -> Result<Vec<Personal>, DomainError> {
+        self.conexion
+            .prepare_cached(
+                "SELECT * FROM personal
+                 WHERE EXISTS (SELECT 1 FROM json_each(personal.clinicas) WHERE value = ?1)",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_map(params![id_clinica.to_string()], Self::desde_fila)?
+                    .collect()
+            })
+            .map_err(DomainError::from)
+    }
+
+    // Las especialidades y las clínicas se guardan como arreglos JSON
+    fn guardar(&mut self, personal: Personal) -> Result<(), DomainError> {
+        let especialidades = serde_json::to_string(&personal.especialidades)
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+        let clinicas = serde_json::to_string(&personal.clinicas)
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+
+        self.conexion
+            .execute(
+                "INSERT INTO personal
+                    (id, nombre, apellido, rol, matricula, especialidades, clinicas, correo, telefono,
+                     archivado, fecha_archivado, version, fecha_creacion, fecha_actualizacion,
+                     creado_por, actualizado_por)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)
+                 ON CONFLICT(id) DO UPDATE SET
+                    nombre = excluded.nombre,
+                    apellido = excluded.apellido,
+                    rol = excluded.rol,
+                    matricula = excluded.matricula,
+                    especialidades = excluded.especialidades,
+                    clinicas = excluded.clinicas,
+                    correo = excluded.correo,
+                    telefono = excluded.telefono,
+                    archivado = excluded.archivado,
+                    fecha_archivado = excluded.fecha_archivado,
+                    version = excluded.version,
+                    fecha_actualizacion = excluded.fecha_actualizacion,
+                    actualizado_por = excluded.actualizado_por",
+                params![
+                    personal.id.to_string(),
+                    personal.nombre,
+                    personal.apellido,
+                    personal.rol.codigo(),
+                    personal.matricula,
+                    especialidades,
+                    clinicas,
+                    personal.correo,
+                    personal.telefono,
+                    personal.archivado,
+                    personal.fecha_archivado,
+                    personal.version,
+                    personal.fecha_creacion,
+                    personal.fecha_actualizacion,
+                    personal.creado_por,
+                    personal.actualizado_por,
+                ],
+            )
+            .map(|_| ())
+            .map_err(DomainError::from)
+    }
+
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.conexion
+            .execute("DELETE FROM personal WHERE id = ?1", params![id.to_string()])
+            .map(|_| ())
+            .map_err(DomainError::from)
+    }
+}
@@ -0,0 +1,357 @@
+use crate::models::Prescripcion;
+use std::collections::HashMap;
+use uuid::Uuid;
+use crate::error::DomainError;
+use super::consulta::{comparar_texto, paginar, CampoOrden, Consulta, Pagina};
+use chrono::{DateTime, Utc};
+use std::cmp::Ordering;
+
+#[cfg(any(feature = "storage-file", feature = "storage-sqlite"))]
+use std::path::Path;
+#[cfg(feature = "storage-file")]
+use super::file_repository::FileRepository;
+#[cfg(feature = "storage-sqlite")]
+use super::sqlite_repository::{abrir_conexion, leer_json, leer_uuid, listar_pagina, Filtros};
+#[cfg(feature = "storage-sqlite")]
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row};
+
+#[derive(Debug, Clone, Default)]
+pub struct FiltroPrescripciones {
+    pub id_mascota: Option<Uuid>,
+    pub id_entrada: Option<Uuid>,
+    pub id_medicamento: Option<Uuid>,
+    // Fecha de la prescripción, ambos extremos incluidos
+    pub desde: Option<DateTime<Utc>>,
+    pub hasta: Option<DateTime<Utc>>,
+}
+
+impl FiltroPrescripciones {
+    fn acepta(&self, prescripcion: &Prescripcion) -> bool {
+        self.id_mascota.is_none_or(|id| prescripcion.id_mascota == id)
+            && self.id_entrada.is_none_or(|id| prescripcion.id_entrada == id)
+            && self.id_medicamento.is_none_or(|id| prescripcion.id_medicamento == id)
+            && self.desde.is_none_or(|desde| prescripcion.fecha_creacion >= desde)
+            && self.hasta.is_none_or(|hasta| prescripcion.fecha_creacion <= hasta)
+    }
+
+    #[cfg(feature = "storage-sqlite")]
+    fn filtros(&self) -> Filtros {
+        let mut filtros = Filtros::default();
+        if let Some(id) = self.id_mascota {
+            filtros.agregar("id_mascota = ?", id.to_string());
+        }
+        if let Some(id) = self.id_entrada {
+            filtros.agregar("id_entrada = ?", id.to_string());
+        }
+        if let Some(id) = self.id_medicamento {
+            filtros.agregar("id_medicamento = ?", id.to_string());
+        }
+        if let Some(desde) = self.desde {
+            filtros.agregar("julianday(fecha_creacion) >= julianday(?)", desde.to_rfc3339());
+        }
+        if let Some(hasta) = self.hasta {
+            filtros.agregar("julianday(fecha_creacion) <= julianday(?)", hasta.to_rfc3339());
+        }
+        filtros
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum OrdenPrescripciones {
+    #[default]
+    Fecha,
+    PrincipioActivo,
+}
+
+impl CampoOrden for OrdenPrescripciones {
+    const NOMBRES: &'static [&'static str] = &["fecha", "principio_activo"];
+
+    fn desde_nombre(nombre: &str) -> Option<Self> {
+        match nombre {
+            "fecha" => Some(OrdenPrescripciones::Fecha),
+            "principio_activo" => Some(OrdenPrescripciones::PrincipioActivo),
+            _ => None,
+        }
+    }
+}
+
+impl OrdenPrescripciones {
+    fn comparar(self, a: &Prescripcion, b: &Prescripcion) -> Ordering {
+        match self {
+            OrdenPrescripciones::Fecha => a.fecha_creacion.cmp(&b.fecha_creacion),
+            OrdenPrescripciones::PrincipioActivo => comparar_texto(&a.principio_activo, &b.principio_activo),
+        }
+        .then(a.id.cmp(&b.id))
+    }
+
+    #[cfg(feature = "storage-sqlite")]
+    fn columnas(self) -> &'static [&'static str] {
+        match self {
+            OrdenPrescripciones::Fecha => &["julianday(fecha_creacion)"],
+            OrdenPrescripciones::PrincipioActivo => &["principio_activo COLLATE NOCASE"],
+        }
+    }
+}
+
+pub type ConsultaPrescripciones = Consulta<FiltroPrescripciones, OrdenPrescripciones>;
+
+fn por_fecha(mut prescripciones: Vec<Prescripcion>) -> Vec<Prescripcion> {
+    prescripciones.sort_by(|a, b| OrdenPrescripciones::Fecha.comparar(a, b));
+    prescripciones
+}
+
+pub trait PrescripcionRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<Prescripcion>, DomainError>;
+    fn listar(&self, consulta: &ConsultaPrescripciones) -> Result<Pagina<Prescripcion>, DomainError>;
+    // Sin paginar, por fecha
+    fn listar_todas(&self, filtro: &FiltroPrescripciones) -> Result<Vec<Prescripcion>, DomainError>;
+    fn contar_por_medicamento(&self, id_medicamento: Uuid) -> Result<usize, DomainError>;
+    fn guardar(&mut self, prescripcion: Prescripcion) -> Result<(), DomainError>;
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError>;
+}
+
+pub struct InMemoryPrescripcionRepository {
+    prescripciones: HashMap<Uuid, Prescripcion>,
+}
+
+impl InMemoryPrescripcionRepository {
+    pub fn new() -> Self {
+        Self {
+            prescripciones: HashMap::new(),
+        }
+    }
+}
+
+impl PrescripcionRepository for InMemoryPrescripcionRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<Prescripcion>, DomainError> {
+        Ok(self.prescripciones.get(&id).cloned())
+    }
+
+    fn listar(&self, consulta: &ConsultaPrescripciones) -> Result<Pagina<Prescripcion>, DomainError> {
+        Ok(paginar(
+            self.prescripciones.values(),
+            consulta,
+            |p| consulta.filtro.acepta(p),
+            |a, b| consulta.orden.comparar(a, b),
+        ))
+    }
+
+    fn listar_todas(&self, filtro: &FiltroPrescripciones) -> Result<Vec<Prescripcion>, DomainError> {
+        Ok(por_fecha(self.prescripciones.values().filter(|p| filtro.acepta(p)).cloned().collect()))
+    }
+
+    fn contar_por_medicamento(&self, id_medicamento: Uuid) -> Result<usize, DomainError> {
+        Ok(self.prescripciones.values()
+            .filter(|p| p.id_medicamento == id_medicamento)
+            .count())
+    }
+
+    fn guardar(&mut self, prescripcion: Prescripcion) -> Result<(), DomainError> {
+        self.prescripciones.insert(prescripcion.id, prescripcion);
+        Ok(())
+    }
+
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.prescripciones.remove(&id);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "storage-file")]
+pub struct FilePrescripcionRepository {
+    storage: FileRepository<Prescripcion>,
+}
+
+#[cfg(feature = "storage-file")]
+impl FilePrescripcionRepository {
+    pub fn new(data_dir: &Path) -> Result<Self, DomainError> {
+        Ok(Self {
+            storage: FileRepository::open(data_dir.join("prescripciones.json"))?,
+        })
+    }
+}
+
+#[cfg(feature = "storage-file")]
+impl PrescripcionRepository for FilePrescripcionRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<Prescripcion>, DomainError> {
+        Ok(self.storage.get(id).cloned())
+    }
+
+    fn listar(&self, consulta: &ConsultaPrescripciones) -> Result<Pagina<Prescripcion>, DomainError> {
+        Ok(paginar(
+            self.storage.records().iter(),
+            consulta,
+            |p| consulta.filtro.acepta(p),
+            |a, b| consulta.orden.comparar(a, b),
+        ))
+    }
+
+    fn listar_todas(&self, filtro: &FiltroPrescripciones) -> Result<Vec<Prescripcion>, DomainError> {
+        Ok(por_fecha(self.storage.records().iter().filter(|p| filtro.acepta(p)).cloned().collect()))
+    }
+
+    fn contar_por_medicamento(&self, id_medicamento: Uuid) -> Result<usize, DomainError> {
+        Ok(self.storage.records().iter()
+            .filter(|p| p.id_medicamento == id_medicamento)
+            .count())
+    }
+
+    fn guardar(&mut self, prescripcion: Prescripcion) -> Result<(), DomainError> {
+        self.storage.upsert(prescripcion)
+    }
+
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.storage.delete(id)
+    }
+}
+
+#[cfg(feature = "storage-sqlite")]
+pub struct SqlitePrescripcionRepository {
+    conexion: Connection,
+}
+
+#[cfg(feature = "storage-sqlite")]
+impl SqlitePrescripcionRepository {
+    pub fn new(ruta: &Path) -> Result<Self, DomainError> {
+        Ok(Self {
+            conexion: abrir_conexion(ruta)?,
+        })
+    }
+
+    fn desde_fila(row: &Row) -> rusqlite::Result<Prescripcion> {
+        Ok(Prescripcion {
+            id: leer_uuid(row, "id")?,
+            id_mascota: leer_uuid(row, "id_mascota")?,
+            id_entrada: leer_uuid(row, "id_entrada")?,
+            id_medicamento: leer_uuid(row, "id_medicamento")?,
+            principio_activo: row.get("principio_activo")?,
+            presentacion: row.get("presentacion")?,
+            concentracion_mg: row.get("concentracion_mg")?,
+            unidad: row.get("unidad")?,
+            peso_kg: row.get("peso_kg")?,
+            dosis_mg_kg: row.get("dosis_mg_kg")?,
+            dosis_mg: row.get("dosis_mg")?,
+            cantidad: row.get("cantidad")?,
+            frecuencia_horas: row.get("frecuencia_horas")?,
+            duracion_dias: row.get("duracion_dias")?,
+            indicaciones: row.get("indicaciones")?,
+            rango: leer_json(row, "rango")?,
+            advertencia: row.get("advertencia")?,
+            alergias: leer_json(row, "alergias")?,
+            motivo_excepcion: row.get("motivo_excepcion")?,
+            version: row.get("version")?,
+            fecha_creacion: row.get("fecha_creacion")?,
+            fecha_actualizacion: row.get("fecha_actualizacion")?,
+            creado_por: row.get("creado_por")?,
+            actualizado_por: row.get("actualizado_por")?,
+        })
+    }
+}
+
+#[cfg(feature = "storage-sqlite")]
+impl PrescripcionRepository for SqlitePrescripcionRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<Prescripcion>, DomainError> {
+        self.conexion
+            .query_row(
+                "SELECT * FROM prescripciones WHERE id = ?1",
+                params![id.to_string()],
+                Self::desde_fila,
+            )
+            .optional()
+            .map_err(DomainError::from)
+    }
+
+    fn listar(&self, consulta: &ConsultaPrescripciones) -> Result<Pagina<Prescripcion>, DomainError> {
+        listar_pagina(
+            &self.conexion,
+            "prescripciones",
+            &consulta.filtro.filtros(),
+            consulta.orden.columnas(),
+            consulta,
+            Self::desde_fila,
+        )
+        .map_err(DomainError::from)
+    }
+
+    fn listar_todas(&self, filtro: &FiltroPrescripciones) -> Result<Vec<Prescripcion>, DomainError> {
+        let filtros = filtro.filtros();
+        self.conexion
+            .prepare(&format!(
+                "SELECT * FROM prescripciones{} ORDER BY julianday(fecha_creacion), id",
+                filtros.sql()
+            ))
+            .and_then(|mut stmt| {
+                stmt.query_map(params_from_iter(filtros.valores()), Self::desde_fila)?
+                    .collect()
+            })
+            .map_err(DomainError::from)
+    }
+
+    fn contar_por_medicamento(&self, id_medicamento: Uuid) -> Result<usize, DomainError> {
+        self.conexion.query_row(
+            "SELECT COUNT(*) FROM prescripciones WHERE id_medicamento = ?1",
+            params![id_medicamento.to_string()],
+            |row| row.get(0),
+        )
+        .map_err(DomainError::from)
+    }
+
+    // El rango y las alergias se guardan como JSON (`null` si la especie no
+    // tenía rango)
+    fn guardar(&mut self, prescripcion: Prescripcion) -> Result<(), DomainError> {
+        let rango = serde_json::to_string(&prescripcion.rango)
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+        let alergias = serde_json::to_string(&prescripcion.alergias)
+            .map_err(|e| DomainError::Internal(e.to_string()))?;
+
+        self.conexion
+            .execute(
+                "INSERT INTO prescripciones
+                    (id, id_mascota, id_entrada, id_medicamento, principio_activo, presentacion,
+                     concentracion_mg, unidad, peso_kg, dosis_mg_kg, dosis_mg, cantidad, frecuencia_horas,
+                     duracion_dias, indicaciones, rango, advertencia, version, fecha_creacion,
+                     fecha_actualizacion, creado_por, actualizado_por, alergias, motivo_excepcion)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18,
+                         ?19, ?20, ?21, ?22, ?23, ?24)
+                 ON CONFLICT(id) DO UPDATE SET
+                    version = excluded.version,
+                    fecha_actualizacion = excluded.fecha_actualizacion,
+                    actualizado_por = excluded.actualizado_por",
+                params![
+                    prescripcion.id.to_string(),
+                    prescripcion.id_mascota.to_string(),
+                    prescripcion.id_entrada.to_string(),
+                    prescripcion.id_medicamento.to_string(),
+                    prescripcion.principio_activo,
+                    prescripcion.presentacion,
+                    prescripcion.concentracion_mg,
+                    prescripcion.unidad,
+                    prescripcion.peso_kg,
+                    prescripcion.dosis_mg_kg,
+                    prescripcion.dosis_mg,
+                    prescripcion.cantidad,
+                    prescripcion.frecuencia_horas,
+                    prescripcion.duracion_dias,
+                    prescripcion.indicaciones,
+                    rango,
+                    prescripcion.advertencia,
+                    prescripcion.version,
+                    prescripcion.fecha_creacion,
+                    prescripcion.fecha_actualizacion,
+                    prescripcion.creado_por,
+                    prescripcion.actualizado_por,
+                    alergias,
+                    prescripcion.motivo_excepcion,
+                ],
+            )
+            .map(|_| ())
+            .map_err(DomainError::from)
+    }
+
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.conexion
+            .execute("DELETE FROM prescripciones WHERE id = ?1", params![id.to_string()])
+            .map(|_| ())
+            .map_err(DomainError::from)
+    }
+}
@@ -0,0 +1,500 @@
+use rusqlite::{params, params_from_iter, Connection, Row};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use uuid::Uuid;
+use crate::error::DomainError;
+use super::consulta::{Consulta, Pagina};
+use super::busqueda::{puntuar, termino_principal, terminos_indexados, CampoBusqueda, Indexable};
+
+// Cada elemento es una versión del esquema; solo se agregan al final.
+const MIGRACIONES: &[&str] = &[
+    "CREATE TABLE clinicas (
+        id TEXT PRIMARY KEY,
+        nombre TEXT NOT NULL,
+        direccion TEXT NOT NULL,
+        telefono TEXT NOT NULL,
+        correo TEXT NOT NULL
+    );
+
+    CREATE TABLE clientes (
+        id TEXT PRIMARY KEY,
+        nombre TEXT NOT NULL,
+        apellido TEXT NOT NULL,
+        correo TEXT NOT NULL,
+        telefono TEXT NOT NULL,
+        direccion TEXT NOT NULL,
+        id_clinica TEXT NOT NULL REFERENCES clinicas(id)
+    );
+    CREATE INDEX idx_clientes_clinica ON clientes(id_clinica);
+
+    CREATE TABLE mascotas (
+        id TEXT PRIMARY KEY,
+        nombre TEXT NOT NULL,
+        especie TEXT NOT NULL,
+        raza TEXT NOT NULL,
+        fecha_nacimiento TEXT,
+        id_cliente TEXT NOT NULL REFERENCES clientes(id)
+    );
+    CREATE INDEX idx_mascotas_cliente ON mascotas(id_cliente);
+
+    CREATE TABLE historias_clinicas (
+        id TEXT PRIMARY KEY,
+        id_mascota TEXT NOT NULL REFERENCES mascotas(id),
+        id_cliente TEXT NOT NULL REFERENCES clientes(id),
+        fecha_creacion TEXT NOT NULL,
+        fecha_actualizacion TEXT NOT NULL
+    );
+    CREATE INDEX idx_historias_mascota ON historias_clinicas(id_mascota);
+
+    CREATE TABLE entradas_historia_clinica (
+        id TEXT PRIMARY KEY,
+        id_historia_clinica TEXT NOT NULL REFERENCES historias_clinicas(id),
+        fecha TEXT NOT NULL,
+        descripcion TEXT NOT NULL,
+        diagnostico TEXT NOT NULL,
+        tratamiento TEXT NOT NULL,
+        notas TEXT
+    );
+    CREATE INDEX idx_entradas_historia ON entradas_historia_clinica(id_historia_clinica);",
+    "ALTER TABLE clinicas ADD COLUMN archivado INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE clinicas ADD COLUMN fecha_archivado TEXT;
+    ALTER TABLE clientes ADD COLUMN archivado INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE clientes ADD COLUMN fecha_archivado TEXT;
+    ALTER TABLE mascotas ADD COLUMN archivado INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE mascotas ADD COLUMN fecha_archivado TEXT;
+    ALTER TABLE historias_clinicas ADD COLUMN archivado INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE historias_clinicas ADD COLUMN fecha_archivado TEXT;",
+    "CREATE INDEX idx_clinicas_nombre ON clinicas(nombre COLLATE NOCASE, id);
+    CREATE INDEX idx_clientes_apellido ON clientes(apellido COLLATE NOCASE, id);
+    CREATE INDEX idx_mascotas_nombre ON mascotas(nombre COLLATE NOCASE, id);
+    CREATE INDEX idx_entradas_historia_fecha ON entradas_historia_clinica(id_historia_clinica, fecha, id);",
+    "CREATE TABLE terminos_busqueda (
+        entidad TEXT NOT NULL,
+        id TEXT NOT NULL,
+        campo TEXT NOT NULL,
+        termino TEXT NOT NULL
+    );
+    CREATE INDEX idx_terminos_busqueda_termino ON terminos_busqueda(entidad, termino);
+    CREATE INDEX idx_terminos_busqueda_id ON terminos_busqueda(entidad, id);",
+    "ALTER TABLE clinicas ADD COLUMN version INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE clientes ADD COLUMN version INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE mascotas ADD COLUMN version INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE historias_clinicas ADD COLUMN version INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE entradas_historia_clinica ADD COLUMN version INTEGER NOT NULL DEFAULT 0;",
+    "ALTER TABLE clinicas ADD COLUMN fecha_creacion TEXT;
+    ALTER TABLE clinicas ADD COLUMN fecha_actualizacion TEXT;
+    ALTER TABLE clinicas ADD COLUMN creado_por TEXT;
+    ALTER TABLE clinicas ADD COLUMN actualizado_por TEXT;
+    UPDATE clinicas SET fecha_creacion = CURRENT_TIMESTAMP, fecha_actualizacion = CURRENT_TIMESTAMP;
+    ALTER TABLE clientes ADD COLUMN fecha_creacion TEXT;
+    ALTER TABLE clientes ADD COLUMN fecha_actualizacion TEXT;
+    ALTER TABLE clientes ADD COLUMN creado_por TEXT;
+    ALTER TABLE clientes ADD COLUMN actualizado_por TEXT;
+    UPDATE clientes SET fecha_creacion = CURRENT_TIMESTAMP, fecha_actualizacion = CURRENT_TIMESTAMP;
+    ALTER TABLE mascotas ADD COLUMN fecha_creacion TEXT;
+    ALTER TABLE mascotas ADD COLUMN fecha_actualizacion TEXT;
+    ALTER TABLE mascotas ADD COLUMN creado_por TEXT;
+    ALTER TABLE mascotas ADD COLUMN actualizado_por TEXT;
+    UPDATE mascotas SET fecha_creacion = CURRENT_TIMESTAMP, fecha_actualizacion = CURRENT_TIMESTAMP;
+    ALTER TABLE historias_clinicas ADD COLUMN creado_por TEXT;
+    ALTER TABLE historias_clinicas ADD COLUMN actualizado_por TEXT;
+    ALTER TABLE entradas_historia_clinica ADD COLUMN fecha_creacion TEXT;
+    ALTER TABLE entradas_historia_clinica ADD COLUMN fecha_actualizacion TEXT;
+    ALTER TABLE entradas_historia_clinica ADD COLUMN creado_por TEXT;
+    ALTER TABLE entradas_historia_clinica ADD COLUMN actualizado_por TEXT;
+    UPDATE entradas_historia_clinica SET fecha_creacion = fecha, fecha_actualizacion = fecha;",
+    "ALTER TABLE entradas_historia_clinica ADD COLUMN id_original TEXT;
+    ALTER TABLE entradas_historia_clinica ADD COLUMN enmienda_a TEXT;
+    ALTER TABLE entradas_historia_clinica ADD COLUMN motivo_enmienda TEXT;
+    CREATE INDEX idx_entradas_original ON entradas_historia_clinica(id_original);
+    CREATE UNIQUE INDEX idx_entradas_enmienda ON entradas_historia_clinica(enmienda_a);
+    CREATE TRIGGER entradas_inmutables
+    BEFORE UPDATE OF fecha, descripcion, diagnostico, tratamiento, notas, id_original, enmienda_a,
+        motivo_enmienda ON entradas_historia_clinica
+    BEGIN
+        SELECT RAISE(ABORT, 'Las entradas de la historia clínica no se modifican; agregue una enmienda');
+    END;",
+    "ALTER TABLE entradas_historia_clinica ADD COLUMN estado TEXT NOT NULL DEFAULT 'legado';
+    ALTER TABLE entradas_historia_clinica ADD COLUMN firmada_por TEXT;
+    ALTER TABLE entradas_historia_clinica ADD COLUMN fecha_firma TEXT;
+    ALTER TABLE entradas_historia_clinica ADD COLUMN hash_contenido TEXT;
+    DROP TRIGGER entradas_inmutables;
+    CREATE TRIGGER entradas_firmadas_inmutables
+    BEFORE UPDATE OF fecha, descripcion, diagnostico, tratamiento, notas, id_original, enmienda_a,
+        motivo_enmienda, estado, firmada_por, fecha_firma, hash_contenido ON entradas_historia_clinica
+    WHEN OLD.estado <> 'borrador'
+    BEGIN
+        SELECT RAISE(ABORT, 'Las entradas firmadas no se modifican; agregue una enmienda');
+    END;",
+    "CREATE TABLE personal (
+        id TEXT PRIMARY KEY,
+        nombre TEXT NOT NULL,
+        apellido TEXT NOT NULL,
+        rol TEXT NOT NULL,
+        matricula TEXT,
+        especialidades TEXT NOT NULL DEFAULT '[]',
+        clinicas TEXT NOT NULL DEFAULT '[]',
+        correo TEXT NOT NULL,
+        telefono TEXT NOT NULL,
+        archivado INTEGER NOT NULL DEFAULT 0,
+        fecha_archivado TEXT,
+        version INTEGER NOT NULL DEFAULT 0,
+        fecha_creacion TEXT,
+        fecha_actualizacion TEXT,
+        creado_por TEXT,
+        actualizado_por TEXT
+    );
+    CREATE UNIQUE INDEX idx_personal_matricula ON personal(matricula COLLATE NOCASE);
+    CREATE INDEX idx_personal_apellido ON personal(apellido COLLATE NOCASE, id);
+    ALTER TABLE entradas_historia_clinica ADD COLUMN id_veterinario TEXT;
+    ALTER TABLE entradas_historia_clinica ADD COLUMN id_firmante TEXT;
+    CREATE INDEX idx_entradas_veterinario ON entradas_historia_clinica(id_veterinario);
+    DROP TRIGGER entradas_firmadas_inmutables;
+    CREATE TRIGGER entradas_firmadas_inmutables
+    BEFORE UPDATE OF fecha, descripcion, diagnostico, tratamiento, notas, id_veterinario, id_original,
+        enmienda_a, motivo_enmienda, estado, id_firmante, firmada_por, fecha_firma, hash_contenido
+        ON entradas_historia_clinica
+    WHEN OLD.estado <> 'borrador'
+    BEGIN
+        SELECT RAISE(ABORT, 'Las entradas firmadas no se modifican; agregue una enmienda');
+    END;",
+    "CREATE TABLE turnos (
+        id TEXT PRIMARY KEY,
+        id_clinica TEXT NOT NULL REFERENCES clinicas(id),
+        id_mascota TEXT NOT NULL REFERENCES mascotas(id),
+        id_cliente TEXT NOT NULL REFERENCES clientes(id),
+        id_veterinario TEXT NOT NULL REFERENCES personal(id),
+        inicio TEXT NOT NULL,
+        fin TEXT NOT NULL,
+        motivo TEXT NOT NULL,
+        estado TEXT NOT NULL,
+        motivo_cancelacion TEXT,
+        id_entrada TEXT,
+        version INTEGER NOT NULL DEFAULT 0,
+        fecha_creacion TEXT NOT NULL,
+        fecha_actualizacion TEXT NOT NULL,
+        creado_por TEXT,
+        actualizado_por TEXT
+    );
+    CREATE INDEX idx_turnos_clinica_inicio ON turnos(id_clinica, inicio);
+    CREATE INDEX idx_turnos_veterinario_inicio ON turnos(id_veterinario, inicio);
+    CREATE INDEX idx_turnos_mascota ON turnos(id_mascota);
+    CREATE INDEX idx_turnos_cliente ON turnos(id_cliente);",
+    "CREATE TABLE vacunaciones (
+        id TEXT PRIMARY KEY,
+        id_mascota TEXT NOT NULL REFERENCES mascotas(id),
+        vacuna TEXT NOT NULL,
+        lote TEXT NOT NULL,
+        fabricante TEXT NOT NULL,
+        dosis TEXT NOT NULL,
+        fecha_aplicacion TEXT NOT NULL,
+        proxima_dosis TEXT,
+        id_veterinario TEXT REFERENCES personal(id),
+        version INTEGER NOT NULL DEFAULT 0,
+        fecha_creacion TEXT NOT NULL,
+        fecha_actualizacion TEXT NOT NULL,
+        creado_por TEXT,
+        actualizado_por TEXT
+    );
+    CREATE INDEX idx_vacunaciones_mascota ON vacunaciones(id_mascota, fecha_aplicacion);
+    CREATE INDEX idx_vacunaciones_proxima ON vacunaciones(proxima_dosis);
+    CREATE INDEX idx_vacunaciones_veterinario ON vacunaciones(id_veterinario);",
+    "ALTER TABLE entradas_historia_clinica ADD COLUMN peso_kg REAL;
+    DROP TRIGGER entradas_firmadas_inmutables;
+    CREATE TRIGGER entradas_firmadas_inmutables
+    BEFORE UPDATE OF fecha, descripcion, diagnostico, tratamiento, notas, id_veterinario, peso_kg,
+        id_original, enmienda_a, motivo_enmienda, estado, id_firmante, firmada_por, fecha_firma,
+        hash_contenido ON entradas_historia_clinica
+    WHEN OLD.estado <> 'borrador'
+    BEGIN
+        SELECT RAISE(ABORT, 'Las entradas firmadas no se modifican; agregue una enmienda');
+    END;
+    CREATE TABLE medicamentos (
+        id TEXT PRIMARY KEY,
+        principio_activo TEXT NOT NULL,
+        nombre_comercial TEXT,
+        presentacion TEXT NOT NULL,
+        concentracion_mg REAL NOT NULL,
+        unidad TEXT NOT NULL,
+        dosis TEXT NOT NULL DEFAULT '[]',
+        archivado INTEGER NOT NULL DEFAULT 0,
+        fecha_archivado TEXT,
+        version INTEGER NOT NULL DEFAULT 0,
+        fecha_creacion TEXT NOT NULL,
+        fecha_actualizacion TEXT NOT NULL,
+        creado_por TEXT,
+        actualizado_por TEXT
+    );
+    CREATE INDEX idx_medicamentos_principio ON medicamentos(principio_activo COLLATE NOCASE, id);
+    CREATE TABLE prescripciones (
+        id TEXT PRIMARY KEY,
+        id_mascota TEXT NOT NULL REFERENCES mascotas(id),
+        id_entrada TEXT NOT NULL REFERENCES entradas_historia_clinica(id),
+        id_medicamento TEXT NOT NULL REFERENCES medicamentos(id),
+        principio_activo TEXT NOT NULL,
+        presentacion TEXT NOT NULL,
+        concentracion_mg REAL NOT NULL,
+        unidad TEXT NOT NULL,
+        peso_kg REAL NOT NULL,
+        dosis_mg_kg REAL NOT NULL,
+        dosis_mg REAL NOT NULL,
+        cantidad REAL NOT NULL,
+        frecuencia_horas INTEGER NOT NULL,
+        duracion_dias INTEGER NOT NULL,
+        indicaciones TEXT,
+        rango TEXT NOT NULL DEFAULT 'null',
+        advertencia TEXT,
+        version INTEGER NOT NULL DEFAULT 0,
+        fecha_creacion TEXT NOT NULL,
+        fecha_actualizacion TEXT NOT NULL,
+        creado_por TEXT,
+        actualizado_por TEXT
+    );
+    CREATE INDEX idx_prescripciones_mascota ON prescripciones(id_mascota, fecha_creacion);
+    CREATE INDEX idx_prescripciones_entrada ON prescripciones(id_entrada);
+    CREATE INDEX idx_prescripciones_medicamento ON prescripciones(id_medicamento);",
+    "ALTER TABLE entradas_historia_clinica ADD COLUMN temperatura_c REAL;
+    ALTER TABLE entradas_historia_clinica ADD COLUMN frecuencia_cardiaca INTEGER;
+    ALTER TABLE entradas_historia_clinica ADD COLUMN frecuencia_respiratoria INTEGER;
+    DROP TRIGGER entradas_firmadas_inmutables;
+    CREATE TRIGGER entradas_firmadas_inmutables
+    BEFORE UPDATE OF fecha, descripcion, diagnostico, tratamiento, notas, id_veterinario, peso_kg,
+        temperatura_c, frecuencia_cardiaca, frecuencia_respiratoria, id_original, enmienda_a,
+        motivo_enmienda, estado, id_firmante, firmada_por, fecha_firma, hash_contenido
+        ON entradas_historia_clinica
+    WHEN OLD.estado <> 'borrador'
+    BEGIN
+        SELECT RAISE(ABORT, 'Las entradas firmadas no se modifican; agregue una enmienda');
+    END;",
+    "ALTER TABLE mascotas ADD COLUMN alergias TEXT NOT NULL DEFAULT '[]';
+    ALTER TABLE mascotas ADD COLUMN condiciones_cronicas TEXT NOT NULL DEFAULT '[]';
+    ALTER TABLE prescripciones ADD COLUMN alergias TEXT NOT NULL DEFAULT '[]';
+    ALTER TABLE prescripciones ADD COLUMN motivo_excepcion TEXT;",
+    "CREATE TABLE especies (
+        id TEXT PRIMARY KEY,
+        codigo TEXT NOT NULL UNIQUE,
+        etiquetas TEXT NOT NULL DEFAULT '{}',
+        alias TEXT NOT NULL DEFAULT '[]',
+        razas TEXT NOT NULL DEFAULT '[]',
+        archivado INTEGER NOT NULL DEFAULT 0,
+        fecha_archivado TEXT,
+        version INTEGER NOT NULL DEFAULT 0,
+        fecha_creacion TEXT NOT NULL,
+        fecha_actualizacion TEXT NOT NULL,
+        creado_por TEXT,
+        actualizado_por TEXT
+    );",
+    "CREATE TRIGGER entradas_firmadas_no_se_eliminan
+    BEFORE DELETE ON entradas_historia_clinica
+    WHEN OLD.estado <> 'borrador'
+    BEGIN
+        SELECT RAISE(ABORT, 'Las entradas firmadas no se eliminan; archive la historia clínica');
+    END;",
+];
+
+pub fn abrir_conexion(ruta: &Path) -> Result<Connection, DomainError> {
+    if let Some(dir) = ruta.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| DomainError::Storage(format!("{}: {}", dir.display(), e)))?;
+    }
+
+    let mut conexion = Connection::open(ruta)?;
+    conexion.busy_timeout(Duration::from_secs(5))?;
+    conexion.pragma_update(None, "foreign_keys", true)?;
+
+    aplicar_migraciones(&mut conexion)?;
+    Ok(conexion)
+}
+
+fn aplicar_migraciones(conexion: &mut Connection) -> Result<(), DomainError> {
+    let version: usize = conexion.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+    for (indice, migracion) in MIGRACIONES.iter().enumerate().skip(version) {
+        let tx = conexion.transaction()?;
+        tx.execute_batch(migracion)?;
+        tx.pragma_update(None, "user_version", indice + 1)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+pub fn leer_uuid(row: &Row, columna: &str) -> rusqlite::Result<Uuid> {
+    let valor: String = row.get(columna)?;
+    Uuid::parse_str(&valor).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
+
+pub fn leer_uuid_opcional(row: &Row, columna: &str) -> rusqlite::Result<Option<Uuid>> {
+    let valor: Option<String> = row.get(columna)?;
+    valor.map(|v| {
+        Uuid::parse_str(&v).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e))
+        })
+    })
+    .transpose()
+}
+
+// Listas y estructuras anidadas que se guardan como JSON en una columna de texto
+pub fn leer_json<T: serde::de::DeserializeOwned>(row: &Row, columna: &str) -> rusqlite::Result<T> {
+    let valor: String = row.get(columna)?;
+    serde_json::from_str(&valor).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
+
+// Condiciones WHERE de un listado junto con sus valores, en el orden de los `?`
+#[derive(Default)]
+pub struct Filtros {
+    clausulas: Vec<&'static str>,
+    valores: Vec<String>,
+}
+
+impl Filtros {
+    pub fn agregar(&mut self, clausula: &'static str, valor: String) {
+        self.clausulas.push(clausula);
+        self.valores.push(valor);
+    }
+
+    pub fn agregar_fija(&mut self, clausula: &'static str) {
+        self.clausulas.push(clausula);
+    }
+
+    pub fn valores(&self) -> &[String] {
+        &self.valores
+    }
+
+    pub fn sql(&self) -> String {
+        if self.clausulas.is_empty() {
+            String::new()
+        } else {
+            format!(" WHERE {}", self.clausulas.join(" AND "))
+        }
+    }
+}
+
+// Cuenta y pagina en la base; `columnas` son las expresiones SQL del campo
+// elegido, de mayor a menor prioridad. El `id` desempata para que el orden sea
+// estable.
+pub fn listar_pagina<T, F, C>(
+    conexion: &Connection,
+    tabla: &str,
+    filtros: &Filtros,
+    columnas: &[&str],
+    consulta: &Consulta<F, C>,
+    desde_fila: fn(&Row) -> rusqlite::Result<T>,
+) -> rusqlite::Result<Pagina<T>> {
+    let condiciones = filtros.sql();
+    let total: i64 = conexion.query_row(
+        &format!("SELECT COUNT(*) FROM {}{}", tabla, condiciones),
+        params_from_iter(&filtros.valores),
+        |row| row.get(0),
+    )?;
+
+    // La dirección se aplica a cada columna, igual que el comparador en memoria se invierte entero
+    let direccion = consulta.direccion.sql();
+    let orden: Vec<String> = columnas.iter()
+        .chain(&["id"])
+        .map(|columna| format!("{} {}", columna, direccion))
+        .collect();
+    let sql = format!(
+        "SELECT * FROM {}{} ORDER BY {} LIMIT {} OFFSET {}",
+        tabla, condiciones, orden.join(", "),
+        consulta.por_pagina, consulta.desplazamiento()
+    );
+    let mut stmt = conexion.prepare_cached(&sql)?;
+    let elementos = stmt.query_map(params_from_iter(&filtros.valores), desde_fila)?
+        .collect::<rusqlite::Result<Vec<T>>>()?;
+
+    Ok(Pagina { elementos, total: total as usize })
+}
+
+// Reemplaza los términos indexados de un registro; se llama dentro de la misma
+// transacción que lo guarda.
+pub fn indexar<T: Indexable>(conexion: &Connection, entidad: &str, registro: &T) -> rusqlite::Result<()> {
+    desindexar(conexion, entidad, registro.id())?;
+    if registro.archivado() {
+        return Ok(());
+    }
+
+    let id = registro.id().to_string();
+    let mut stmt = conexion.prepare_cached(
+        "INSERT INTO terminos_busqueda (entidad, id, campo, termino) VALUES (?1, ?2, ?3, ?4)",
+    )?;
+    for (campo, termino) in terminos_indexados(registro) {
+        stmt.execute(params![entidad, id, campo.codigo(), termino])?;
+    }
+    Ok(())
+}
+
+pub fn desindexar(conexion: &Connection, entidad: &str, id: Uuid) -> rusqlite::Result<()> {
+    conexion
+        .prepare_cached("DELETE FROM terminos_busqueda WHERE entidad = ?1 AND id = ?2")?
+        .execute(params![entidad, id.to_string()])
+        .map(|_| ())
+}
+
+// Indexa los registros activos que todavía no tienen términos (p. ej. los
+// guardados antes de que existiera el índice).
+pub fn indexar_faltantes<T: Indexable>(
+    conexion: &mut Connection,
+    entidad: &str,
+    tabla: &str,
+    desde_fila: fn(&Row) -> rusqlite::Result<T>,
+) -> rusqlite::Result<()> {
+    let sql = format!(
+        "SELECT * FROM {} WHERE archivado = 0
+         AND id NOT IN (SELECT id FROM terminos_busqueda WHERE entidad = ?1)",
+        tabla
+    );
+    let faltantes = conexion.prepare(&sql)?
+        .query_map(params![entidad], desde_fila)?
+        .collect::<rusqlite::Result<Vec<T>>>()?;
+    if faltantes.is_empty() {
+        return Ok(());
+    }
+
+    let tx = conexion.transaction()?;
+    for registro in &faltantes {
+        indexar(&tx, entidad, registro)?;
+    }
+    tx.commit()
+}
+
+pub fn buscar_en_indice(
+    conexion: &Connection,
+    entidad: &str,
+    consulta: &[String],
+) -> rusqlite::Result<Vec<(Uuid, u32)>> {
+    let Some(principal) = termino_principal(consulta) else {
+        return Ok(Vec::new());
+    };
+
+    // Los términos que empiezan con `principal` quedan entre él y él seguido
+    // del mayor carácter posible; se traen todos los términos de esos registros.
+    let mut stmt = conexion.prepare_cached(
+        "SELECT id, campo, termino FROM terminos_busqueda
+         WHERE entidad = ?1 AND id IN (
+            SELECT id FROM terminos_busqueda
+            WHERE entidad = ?1 AND termino >= ?2 AND termino < ?3
+         )",
+    )?;
+    let tope = format!("{}\u{10FFFF}", principal);
+    let filas = stmt.query_map(params![entidad, principal, tope], |row| {
+        Ok((leer_uuid(row, "id")?, row.get::<_, String>("campo")?, row.get::<_, String>("termino")?))
+    })?;
+
+    let mut por_registro: HashMap<Uuid, Vec<(CampoBusqueda, String)>> = HashMap::new();
+    for fila in filas {
+        let (id, campo, termino) = fila?;
+        if let Some(campo) = CampoBusqueda::desde_codigo(&campo) {
+            por_registro.entry(id).or_default().push((campo, termino));
+        }
+    }
+
+    Ok(por_registro.into_iter()
+        .filter_map(|(id, indexados)| puntuar(consulta, &indexados).map(|puntaje| (id, puntaje)))
+        .collect())
+}
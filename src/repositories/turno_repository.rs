@@ -0,0 +1,349 @@
+use crate::models::Turno;
+use crate::models::turno::EstadoTurno;
+use std::collections::HashMap;
+use uuid::Uuid;
+use crate::error::DomainError;
+use super::consulta::{paginar, CampoOrden, Consulta, Pagina};
+use chrono::{DateTime, Utc};
+use std::cmp::Ordering;
+
+#[cfg(any(feature = "storage-file", feature = "storage-sqlite"))]
+use std::path::Path;
+#[cfg(feature = "storage-file")]
+use super::file_repository::FileRepository;
+#[cfg(feature = "storage-sqlite")]
+use super::sqlite_repository::{
+    abrir_conexion, leer_uuid, leer_uuid_opcional, listar_pagina, Filtros,
+};
+#[cfg(feature = "storage-sqlite")]
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row};
+
+#[derive(Debug, Clone, Default)]
+pub struct FiltroTurnos {
+    pub id_clinica: Option<Uuid>,
+    pub id_veterinario: Option<Uuid>,
+    pub id_mascota: Option<Uuid>,
+    pub id_cliente: Option<Uuid>,
+    pub estado: Option<EstadoTurno>,
+    // Turnos que terminan después de `desde` y empiezan hasta `hasta` (inclusive)
+    pub desde: Option<DateTime<Utc>>,
+    pub hasta: Option<DateTime<Utc>>,
+    // Solo los que ocupan la agenda (sin cancelados ni ausentes)
+    pub solo_activos: bool,
+}
+
+impl FiltroTurnos {
+    fn acepta(&self, turno: &Turno) -> bool {
+        self.id_clinica.is_none_or(|id| turno.id_clinica == id)
+            && self.id_veterinario.is_none_or(|id| turno.id_veterinario == id)
+            && self.id_mascota.is_none_or(|id| turno.id_mascota == id)
+            && self.id_cliente.is_none_or(|id| turno.id_cliente == id)
+            && self.estado.is_none_or(|estado| turno.estado == estado)
+            && self.desde.is_none_or(|desde| turno.fin > desde)
+            && self.hasta.is_none_or(|hasta| turno.inicio <= hasta)
+            && (!self.solo_activos || turno.estado.ocupa_agenda())
+    }
+
+    #[cfg(feature = "storage-sqlite")]
+    fn filtros(&self) -> Filtros {
+        let mut filtros = Filtros::default();
+        if let Some(id) = self.id_clinica {
+            filtros.agregar("id_clinica = ?", id.to_string());
+        }
+        if let Some(id) = self.id_veterinario {
+            filtros.agregar("id_veterinario = ?", id.to_string());
+        }
+        if let Some(id) = self.id_mascota {
+            filtros.agregar("id_mascota = ?", id.to_string());
+        }
+        if let Some(id) = self.id_cliente {
+            filtros.agregar("id_cliente = ?", id.to_string());
+        }
+        if let Some(estado) = self.estado {
+            filtros.agregar("estado = ?", estado.codigo().to_string());
+        }
+        // Se comparan como instantes: los decimales guardados pueden variar
+        if let Some(desde) = self.desde {
+            filtros.agregar("julianday(fin) > julianday(?)", desde.to_rfc3339());
+        }
+        if let Some(hasta) = self.hasta {
+            filtros.agregar("julianday(inicio) <= julianday(?)", hasta.to_rfc3339());
+        }
+        if self.solo_activos {
+            filtros.agregar_fija("estado NOT IN ('cancelado', 'ausente')");
+        }
+        filtros
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum OrdenTurnos {
+    #[default]
+    Inicio,
+    Estado,
+}
+
+impl CampoOrden for OrdenTurnos {
+    const NOMBRES: &'static [&'static str] = &["inicio", "estado"];
+
+    fn desde_nombre(nombre: &str) -> Option<Self> {
+        match nombre {
+            "inicio" => Some(OrdenTurnos::Inicio),
+            "estado" => Some(OrdenTurnos::Estado),
+            _ => None,
+        }
+    }
+}
+
+impl OrdenTurnos {
+    fn comparar(self, a: &Turno, b: &Turno) -> Ordering {
+        match self {
+            OrdenTurnos::Inicio => a.inicio.cmp(&b.inicio),
+            OrdenTurnos::Estado => a.estado.codigo().cmp(b.estado.codigo()).then(a.inicio.cmp(&b.inicio)),
+        }
+        .then(a.id.cmp(&b.id))
+    }
+
+    #[cfg(feature = "storage-sqlite")]
+    fn columnas(self) -> &'static [&'static str] {
+        match self {
+            OrdenTurnos::Inicio => &["julianday(inicio)"],
+            OrdenTurnos::Estado => &["estado"],
+        }
+    }
+}
+
+pub type ConsultaTurnos = Consulta<FiltroTurnos, OrdenTurnos>;
+
+fn por_inicio(mut turnos: Vec<Turno>) -> Vec<Turno> {
+    turnos.sort_by(|a, b| OrdenTurnos::Inicio.comparar(a, b));
+    turnos
+}
+
+pub trait TurnoRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<Turno>, DomainError>;
+    fn listar(&self, consulta: &ConsultaTurnos) -> Result<Pagina<Turno>, DomainError>;
+    // Sin paginar, por hora de inicio; para agendas y verificaciones
+    fn listar_todos(&self, filtro: &FiltroTurnos) -> Result<Vec<Turno>, DomainError>;
+    fn guardar(&mut self, turno: Turno) -> Result<(), DomainError>;
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError>;
+}
+
+pub struct InMemoryTurnoRepository {
+    turnos: HashMap<Uuid, Turno>,
+}
+
+impl InMemoryTurnoRepository {
+    pub fn new() -> Self {
+        Self {
+            turnos: HashMap::new(),
+        }
+    }
+}
+
+impl TurnoRepository for InMemoryTurnoRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<Turno>, DomainError> {
+        Ok(self.turnos.get(&id).cloned())
+    }
+
+    fn listar(&self, consulta: &ConsultaTurnos) -> Result<Pagina<Turno>, DomainError> {
+        Ok(paginar(
+            self.turnos.values(),
+            consulta,
+            |t| consulta.filtro.acepta(t),
+            |a, b| consulta.orden.comparar(a, b),
+        ))
+    }
+
+    fn listar_todos(&self, filtro: &FiltroTurnos) -> Result<Vec<Turno>, DomainError> {
+        Ok(por_inicio(self.turnos.values().filter(|t| filtro.acepta(t)).cloned().collect()))
+    }
+
+    fn guardar(&mut self, turno: Turno) -> Result<(), DomainError> {
+        self.turnos.insert(turno.id, turno);
+        Ok(())
+    }
+
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.turnos.remove(&id);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "storage-file")]
+pub struct FileTurnoRepository {
+    storage: FileRepository<Turno>,
+}
+
+#[cfg(feature = "storage-file")]
+impl FileTurnoRepository {
+    pub fn new(data_dir: &Path) -> Result<Self, DomainError> {
+        Ok(Self {
+            storage: FileRepository::open(data_dir.join("turnos.json"))?,
+        })
+    }
+}
+
+#[cfg(feature = "storage-file")]
+impl TurnoRepository for FileTurnoRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<Turno>, DomainError> {
+        Ok(self.storage.get(id).cloned())
+    }
+
+    fn listar(&self, consulta: &ConsultaTurnos) -> Result<Pagina<Turno>, DomainError> {
+        Ok(paginar(
+            self.storage.records().iter(),
+            consulta,
+            |t| consulta.filtro.acepta(t),
+            |a, b| consulta.orden.comparar(a, b),
+        ))
+    }
+
+    fn listar_todos(&self, filtro: &FiltroTurnos) -> Result<Vec<Turno>, DomainError> {
+        Ok(por_inicio(self.storage.records().iter().filter(|t| filtro.acepta(t)).cloned().collect()))
+    }
+
+    fn guardar(&mut self, turno: Turno) -> Result<(), DomainError> {
+        self.storage.upsert(turno)
+    }
+
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.storage.delete(id)
+    }
+}
+
+#[cfg(feature = "storage-sqlite")]
+fn leer_estado(row: &Row) -> rusqlite::Result<EstadoTurno> {
+    let codigo: String = row.get("estado")?;
+    EstadoTurno::desde_codigo(&codigo).ok_or_else(|| {
+        rusqlite::Error::FromSqlConversionFailure(
+            0,
+            rusqlite::types::Type::Text,
+            format!("estado de turno desconocido: {}", codigo).into(),
+        )
+    })
+}
+
+#[cfg(feature = "storage-sqlite")]
+pub struct SqliteTurnoRepository {
+    conexion: Connection,
+}
+
+#[cfg(feature = "storage-sqlite")]
+impl SqliteTurnoRepository {
+    pub fn new(ruta: &Path) -> Result<Self, DomainError> {
+        Ok(Self {
+            conexion: abrir_conexion(ruta)?,
+        })
+    }
+
+    fn desde_fila(row: &Row) -> rusqlite::Result<Turno> {
+        Ok(Turno {
+            id: leer_uuid(row, "id")?,
+            id_clinica: leer_uuid(row, "id_clinica")?,
+            id_mascota: leer_uuid(row, "id_mascota")?,
+            id_cliente: leer_uuid(row, "id_cliente")?,
+            id_veterinario: leer_uuid(row, "id_veterinario")?,
+            inicio: row.get("inicio")?,
+            fin: row.get("fin")?,
+            motivo: row.get("motivo")?,
+            estado: leer_estado(row)?,
+            motivo_cancelacion: row.get("motivo_cancelacion")?,
+            id_entrada: leer_uuid_opcional(row, "id_entrada")?,
+            version: row.get("version")?,
+            fecha_creacion: row.get("fecha_creacion")?,
+            fecha_actualizacion: row.get("fecha_actualizacion")?,
+            creado_por: row.get("creado_por")?,
+            actualizado_por: row.get("actualizado_por")?,
+        })
+    }
+}
+
+#[cfg(feature = "storage-sqlite")]
+impl TurnoRepository for SqliteTurnoRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<Turno>, DomainError> {
+        self.conexion
+            .query_row(
+                "SELECT * FROM turnos WHERE id = ?1",
+                params![id.to_string()],
+                Self::desde_fila,
+            )
+            .optional()
+            .map_err(DomainError::from)
+    }
+
+    fn listar(&self, consulta: &ConsultaTurnos) -> Result<Pagina<Turno>, DomainError> {
+        listar_pagina(
+            &self.conexion,
+            "turnos",
+            &consulta.filtro.filtros(),
+            consulta.orden.columnas(),
+            consulta,
+            Self::desde_fila,
+        )
+        .map_err(DomainError::from)
+    }
+
+    fn listar_todos(&self, filtro: &FiltroTurnos) -> Result<Vec<Turno>, DomainError> {
+        let filtros = filtro.filtros();
+        self.conexion
+            .prepare(&format!(
+                "SELECT * FROM turnos{} ORDER BY julianday(inicio), id",
+                filtros.sql()
+            ))
+            .and_then(|mut stmt| {
+                stmt.query_map(params_from_iter(filtros.valores()), Self::desde_fila)?
+                    .collect()
+            })
+            .map_err(DomainError::from)
+    }
+
+    fn guardar(&mut self, turno: Turno) -> Result<(), DomainError> {
+        self.conexion
+            .execute(
+                "INSERT INTO turnos
+                    (id, id_clinica, id_mascota, id_cliente, id_veterinario, inicio, fin, motivo, estado,
+                     motivo_cancelacion, id_entrada, version, fecha_creacion, fecha_actualizacion,
+                     creado_por, actualizado_por)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)
+                 ON CONFLICT(id) DO UPDATE SET
+                    id_veterinario = excluded.id_veterinario,
+                    inicio = excluded.inicio,
+                    fin = excluded.fin,
+                    motivo = excluded.motivo,
+                    estado = excluded.estado,
+                    motivo_cancelacion = excluded.motivo_cancelacion,
+                    id_entrada = excluded.id_entrada,
+                    version = excluded.version,
+                    fecha_actualizacion = excluded.fecha_actualizacion,
+                    actualizado_por = excluded.actualizado_por",
+                params![
+                    turno.id.to_string(),
+                    turno.id_clinica.to_string(),
+                    turno.id_mascota.to_string(),
+                    turno.id_cliente.to_string(),
+                    turno.id_veterinario.to_string(),
+                    turno.inicio,
+                    turno.fin,
+                    turno.motivo,
+                    turno.estado.codigo(),
+                    turno.motivo_cancelacion,
+                    turno.id_entrada.map(|id| id.to_string()),
+                    turno.version,
+                    turno.fecha_creacion,
+                    turno.fecha_actualizacion,
+                    turno.creado_por,
+                    turno.actualizado_por,
+                ],
+            )
+            .map(|_| ())
+            .map_err(DomainError::from)
+    }
+
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.conexion
+            .execute("DELETE FROM turnos WHERE id = ?1", params![id.to_string()])
+            .map(|_| ())
+            .map_err(DomainError::from)
+    }
+}
@@ -0,0 +1,377 @@
+use crate::models::Vacunacion;
+use std::collections::HashMap;
+use uuid::Uuid;
+use crate::error::DomainError;
+use super::consulta::{comparar_texto, contiene, paginar, CampoOrden, Consulta, Pagina};
+use chrono::NaiveDate;
+use std::cmp::Ordering;
+
+#[cfg(any(feature = "storage-file", feature = "storage-sqlite"))]
+use std::path::Path;
+#[cfg(feature = "storage-file")]
+use super::file_repository::FileRepository;
+#[cfg(feature = "storage-sqlite")]
+use super::sqlite_repository::{
+    abrir_conexion, leer_uuid, leer_uuid_opcional, listar_pagina, Filtros,
+};
+#[cfg(feature = "storage-sqlite")]
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+#[derive(Debug, Clone, Default)]
+pub struct FiltroVacunaciones {
+    pub id_mascota: Option<Uuid>,
+    // Coincidencia parcial en el nombre de la vacuna, sin distinguir mayúsculas
+    pub vacuna: Option<String>,
+}
+
+impl FiltroVacunaciones {
+    fn acepta(&self, vacunacion: &Vacunacion) -> bool {
+        self.id_mascota.is_none_or(|id| vacunacion.id_mascota == id)
+            && self.vacuna.as_ref().is_none_or(|v| contiene(&vacunacion.vacuna, v))
+    }
+
+    #[cfg(feature = "storage-sqlite")]
+    fn filtros(&self) -> Filtros {
+        let mut filtros = Filtros::default();
+        if let Some(id) = self.id_mascota {
+            filtros.agregar("id_mascota = ?", id.to_string());
+        }
+        if let Some(vacuna) = &self.vacuna {
+            filtros.agregar("instr(lower(vacuna), lower(?)) > 0", vacuna.clone());
+        }
+        filtros
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum OrdenVacunaciones {
+    #[default]
+    FechaAplicacion,
+    ProximaDosis,
+    Vacuna,
+}
+
+impl CampoOrden for OrdenVacunaciones {
+    const NOMBRES: &'static [&'static str] = &["fecha_aplicacion", "proxima_dosis", "vacuna"];
+
+    fn desde_nombre(nombre: &str) -> Option<Self> {
+        match nombre {
+            "fecha_aplicacion" => Some(OrdenVacunaciones::FechaAplicacion),
+            "proxima_dosis" => Some(OrdenVacunaciones::ProximaDosis),
+            "vacuna" => Some(OrdenVacunaciones::Vacuna),
+            _ => None,
+        }
+    }
+}
+
+impl OrdenVacunaciones {
+    fn comparar(self, a: &Vacunacion, b: &Vacunacion) -> Ordering {
+        match self {
+            OrdenVacunaciones::FechaAplicacion => a.fecha_aplicacion.cmp(&b.fecha_aplicacion),
+            // Las que no tienen próxima dosis van al final
+            OrdenVacunaciones::ProximaDosis => a.proxima_dosis.is_none()
+                .cmp(&b.proxima_dosis.is_none())
+                .then(a.proxima_dosis.cmp(&b.proxima_dosis)),
+            OrdenVacunaciones::Vacuna => comparar_texto(&a.vacuna, &b.vacuna),
+        }
+        .then(a.id.cmp(&b.id))
+    }
+
+    #[cfg(feature = "storage-sqlite")]
+    fn columnas(self) -> &'static [&'static str] {
+        match self {
+            OrdenVacunaciones::FechaAplicacion => &["fecha_aplicacion"],
+            OrdenVacunaciones::ProximaDosis => &["coalesce(proxima_dosis, '9999-12-31')"],
+            OrdenVacunaciones::Vacuna => &["vacuna COLLATE NOCASE"],
+        }
+    }
+}
+
+pub type ConsultaVacunaciones = Consulta<FiltroVacunaciones, OrdenVacunaciones>;
+
+// Última aplicación de cada vacuna por mascota cuya próxima dosis vence hasta
+// `hasta`, por fecha de vencimiento
+fn pendientes<'a>(vacunaciones: impl Iterator<Item = &'a Vacunacion>, hasta: NaiveDate) -> Vec<Vacunacion> {
+    let mut ultimas: HashMap<(Uuid, String), &Vacunacion> = HashMap::new();
+    for vacunacion in vacunaciones {
+        ultimas.entry((vacunacion.id_mascota, vacunacion.vacuna.to_lowercase()))
+            .and_modify(|ultima| {
+                let clave = |v: &Vacunacion| (v.fecha_aplicacion, v.fecha_creacion);
+                if clave(vacunacion) > clave(ultima) {
+                    *ultima = vacunacion;
+                }
+            })
+            .or_insert(vacunacion);
+    }
+
+    let mut resultado: Vec<Vacunacion> = ultimas.into_values()
+        .filter(|v| v.proxima_dosis.is_some_and(|fecha| fecha <= hasta))
+        .cloned()
+        .collect();
+    resultado.sort_by(|a, b| OrdenVacunaciones::ProximaDosis.comparar(a, b));
+    resultado
+}
+
+pub trait VacunacionRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<Vacunacion>, DomainError>;
+    fn listar(&self, consulta: &ConsultaVacunaciones) -> Result<Pagina<Vacunacion>, DomainError>;
+    fn listar_por_mascota(&self, id_mascota: Uuid) -> Result<Vec<Vacunacion>, DomainError>;
+    fn pendientes(&self, hasta: NaiveDate) -> Result<Vec<Vacunacion>, DomainError>;
+    fn contar_por_veterinario(&self, id_veterinario: Uuid) -> Result<usize, DomainError>;
+    fn guardar(&mut self, vacunacion: Vacunacion) -> Result<(), DomainError>;
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError>;
+}
+
+pub struct InMemoryVacunacionRepository {
+    vacunaciones: HashMap<Uuid, Vacunacion>,
+}
+
+impl InMemoryVacunacionRepository {
+    pub fn new() -> Self {
+        Self {
+            vacunaciones: HashMap::new(),
+        }
+    }
+}
+
+impl VacunacionRepository for InMemoryVacunacionRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<Vacunacion>, DomainError> {
+        Ok(self.vacunaciones.get(&id).cloned())
+    }
+
+    fn listar(&self, consulta: &ConsultaVacunaciones) -> Result<Pagina<Vacunacion>, DomainError> {
+        Ok(paginar(
+            self.vacunaciones.values(),
+            consulta,
+            |v| consulta.filtro.acepta(v),
+            |a, b| consulta.orden.comparar(a, b),
+        ))
+    }
+
+    fn listar_por_mascota(&self, id_mascota: Uuid) -> Result<Vec<Vacunacion>, DomainError> {
+        Ok(self.vacunaciones.values()
+            .filter(|v| v.id_mascota == id_mascota)
+            .cloned()
+            .collect())
+    }
+
+    fn pendientes(&self, hasta: NaiveDate) -> Result<Vec<Vacunacion>, DomainError> {
+        Ok(pendientes(self.vacunaciones.values(), hasta))
+    }
+
+    fn contar_por_veterinario(&self, id_veterinario: Uuid) -> Result<usize, DomainError> {
+        Ok(self.vacunaciones.values()
+            .filter(|v| v.id_veterinario == Some(id_veterinario))
+            .count())
+    }
+
+    fn guardar(&mut self, vacunacion: Vacunacion) -> Result<(), DomainError> {
+        self.vacunaciones.insert(vacunacion.id, vacunacion);
+        Ok(())
+    }
+
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.vacunaciones.remove(&id);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "storage-file")]
+pub struct FileVacunacionRepository {
+    storage: FileRepository<Vacunacion>,
+}
+
+#[cfg(feature = "storage-file")]
+impl FileVacunacionRepository {
+    pub fn new(data_dir: &Path) -> Result<Self, DomainError> {
+        Ok(Self {
+            storage: FileRepository::open(data_dir.join("vacunaciones.json"))?,
+        })
+    }
+}
+
+#[cfg(feature = "storage-file")]
+impl VacunacionRepository for FileVacunacionRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<Vacunacion>, DomainError> {
+        Ok(self.storage.get(id).cloned())
+    }
+
+    fn listar(&self, consulta: &ConsultaVacunaciones) -> Result<Pagina<Vacunacion>, DomainError> {
+        Ok(paginar(
+            self.storage.records().iter(),
+            consulta,
+            |v| consulta.filtro.acepta(v),
+            |a, b| consulta.orden.comparar(a, b),
+        ))
+    }
+
+    fn listar_por_mascota(&self, id_mascota: Uuid) -> Result<Vec<Vacunacion>, DomainError> {
+        Ok(self.storage.records().iter()
+            .filter(|v| v.id_mascota == id_mascota)
+            .cloned()
+            .collect())
+    }
+
+    fn pendientes(&self, hasta: NaiveDate) -> Result<Vec<Vacunacion>, DomainError> {
+        Ok(pendientes(self.storage.records().iter(), hasta))
+    }
+
+    fn contar_por_veterinario(&self, id_veterinario: Uuid) -> Result<usize, DomainError> {
+        Ok(self.storage.records().iter()
+            .filter(|v| v.id_veterinario == Some(id_veterinario))
+            .count())
+    }
+
+    fn guardar(&mut self, vacunacion: Vacunacion) -> Result<(), DomainError> {
+        self.storage.upsert(vacunacion)
+    }
+
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.storage.delete(id)
+    }
+}
+
+#[cfg(feature = "storage-sqlite")]
+pub struct SqliteVacunacionRepository {
+    conexion: Connection,
+}
+
+#[cfg(feature = "storage-sqlite")]
+impl SqliteVacunacionRepository {
+    pub fn new(ruta: &Path) -> Result<Self, DomainError> {
+        Ok(Self {
+            conexion: abrir_conexion(ruta)?,
+        })
+    }
+
+    fn desde_fila(row: &Row) -> rusqlite::Result<Vacunacion> {
+        Ok(Vacunacion {
+            id: leer_uuid(row, "id")?,
+            id_mascota: leer_uuid(row, "id_mascota")?,
+            vacuna: row.get("vacuna")?,
+            lote: row.get("lote")?,
+            fabricante: row.get("fabricante")?,
+            dosis: row.get("dosis")?,
+            fecha_aplicacion: row.get("fecha_aplicacion")?,
+            proxima_dosis: row.get("proxima_dosis")?,
+            id_veterinario: leer_uuid_opcional(row, "id_veterinario")?,
+            version: row.get("version")?,
+            fecha_creacion: row.get("fecha_creacion")?,
+            fecha_actualizacion: row.get("fecha_actualizacion")?,
+            creado_por: row.get("creado_por")?,
+            actualizado_por: row.get("actualizado_por")?,
+        })
+    }
+
+    fn consultar(&self, sql: &str, valores: impl rusqlite::Params) -> Result<Vec<Vacunacion>, DomainError> {
+        self.conexion
+            .prepare_cached(sql)
+            .and_then(|mut stmt| stmt.query_map(valores, Self::desde_fila)?.collect())
+            .map_err(DomainError::from)
+    }
+}
+
+#[cfg(feature = "storage-sqlite")]
+impl VacunacionRepository for SqliteVacunacionRepository {
+    fn obtener(&self, id: Uuid) -> Result<Option<Vacunacion>, DomainError> {
+        self.conexion
+            .query_row(
+                "SELECT * FROM vacunaciones WHERE id = ?1",
+                params![id.to_string()],
+                Self::desde_fila,
+            )
+            .optional()
+            .map_err(DomainError::from)
+    }
+
+    fn listar(&self, consulta: &ConsultaVacunaciones) -> Result<Pagina<Vacunacion>, DomainError> {
+        listar_pagina(
+            &self.conexion,
+            "vacunaciones",
+            &consulta.filtro.filtros(),
+            consulta.orden.columnas(),
+            consulta,
+            Self::desde_fila,
+        )
+        .map_err(DomainError::from)
+    }
+
+    fn listar_por_mascota(&self, id_mascota: Uuid) -> Result<Vec<Vacunacion>, DomainError> {
+        self.consultar(
+            "SELECT * FROM vacunaciones WHERE id_mascota = ?1",
+            params![id_mascota.to_string()],
+        )
+    }
+
+    fn pendientes(&self, hasta: NaiveDate) -> Result<Vec<Vacunacion>, DomainError> {
+        self.consultar(
+            "SELECT * FROM vacunaciones v
+             WHERE v.proxima_dosis IS NOT NULL AND v.proxima_dosis <= ?1
+               AND NOT EXISTS (
+                   SELECT 1 FROM vacunaciones o
+                   WHERE o.id_mascota = v.id_mascota
+                     AND lower(o.vacuna) = lower(v.vacuna)
+                     AND (o.fecha_aplicacion > v.fecha_aplicacion
+                          OR (o.fecha_aplicacion = v.fecha_aplicacion
+                              AND julianday(o.fecha_creacion) > julianday(v.fecha_creacion))))
+             ORDER BY v.proxima_dosis, v.id",
+            params![hasta],
+        )
+    }
+
+    fn contar_por_veterinario(&self, id_veterinario: Uuid) -> Result<usize, DomainError> {
+        self.conexion.query_row(
+            "SELECT COUNT(*) FROM vacunaciones WHERE id_veterinario = ?1",
+            params![id_veterinario.to_string()],
+            |row| row.get(0),
+        )
+        .map_err(DomainError::from)
+    }
+
+    fn guardar(&mut self, vacunacion: Vacunacion) -> Result<(), DomainError> {
+        self.conexion
+            .execute(
+                "INSERT INTO vacunaciones
+                    (id, id_mascota, vacuna, lote, fabricante, dosis, fecha_aplicacion, proxima_dosis,
+                     id_veterinario, version, fecha_creacion, fecha_actualizacion, creado_por,
+                     actualizado_por)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
+                 ON CONFLICT(id) DO UPDATE SET
+                    vacuna = excluded.vacuna,
+                    lote = excluded.lote,
+                    fabricante = excluded.fabricante,
+                    dosis = excluded.dosis,
+                    fecha_aplicacion = excluded.fecha_aplicacion,
+                    proxima_dosis = excluded.proxima_dosis,
+                    id_veterinario = excluded.id_veterinario,
+                    version = excluded.version,
+                    fecha_actualizacion = excluded.fecha_actualizacion,
+                    actualizado_por = excluded.actualizado_por",
+                params![
+                    vacunacion.id.to_string(),
+                    vacunacion.id_mascota.to_string(),
+                    vacunacion.vacuna,
+                    vacunacion.lote,
+                    vacunacion.fabricante,
+                    vacunacion.dosis,
+                    vacunacion.fecha_aplicacion,
+                    vacunacion.proxima_dosis,
+                    vacunacion.id_veterinario.map(|id| id.to_string()),
+                    vacunacion.version,
+                    vacunacion.fecha_creacion,
+                    vacunacion.fecha_actualizacion,
+                    vacunacion.creado_por,
+                    vacunacion.actualizado_por,
+                ],
+            )
+            .map(|_| ())
+            .map_err(DomainError::from)
+    }
+
+    fn eliminar(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.conexion
+            .execute("DELETE FROM vacunaciones WHERE id = ?1", params![id.to_string()])
+            .map(|_| ())
+            .map_err(DomainError::from)
+    }
+}
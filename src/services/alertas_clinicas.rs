@@ -0,0 +1,95 @@
+use crate::error::DomainError;
+use crate::models::{HistoriaClinica, Mascota, Medicamento, Versionado};
+use crate::models::mascota::{Alergia, Severidad};
+use crate::repositories::mascota_repository::MascotaRepository;
+use crate::repositories::busqueda::normalizar;
+use crate::services::MascotaService;
+use serde::Serialize;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TipoAlertaClinica {
+    Alergia,
+    CondicionCronica,
+}
+
+// Alergia o condición crónica de la mascota, para tener a la vista en la consulta
+#[derive(Debug, Serialize, Clone)]
+pub struct AlertaClinica {
+    pub tipo: TipoAlertaClinica,
+    // Sustancia o condición
+    pub descripcion: String,
+    pub severidad: Severidad,
+    // Reacción de la alergia o notas de la condición
+    pub detalle: Option<String>,
+}
+
+// Historia clínica con las alertas de su mascota. Las alertas se calculan en
+// cada respuesta, así que el ETag lleva también la versión de la mascota.
+#[derive(Debug, Serialize)]
+pub struct HistoriaClinicaConAlertas {
+    #[serde(flatten)]
+    pub historia: HistoriaClinica,
+    pub alertas: Vec<AlertaClinica>,
+    #[serde(skip)]
+    pub version_mascota: Option<u64>,
+}
+
+impl Versionado for HistoriaClinicaConAlertas {
+    fn version(&self) -> u64 {
+        self.historia.version
+    }
+
+    fn version_incluida(&self) -> Option<u64> {
+        self.version_mascota
+    }
+}
+
+// Las más graves primero; a igual severidad, las alergias antes que las condiciones
+pub fn alertas(mascota: &Mascota) -> Vec<AlertaClinica> {
+    let alergias = mascota.alergias.iter().map(|a| AlertaClinica {
+        tipo: TipoAlertaClinica::Alergia,
+        descripcion: a.sustancia.clone(),
+        severidad: a.severidad,
+        detalle: a.reaccion.clone(),
+    });
+    let condiciones = mascota.condiciones_cronicas.iter().map(|c| AlertaClinica {
+        tipo: TipoAlertaClinica::CondicionCronica,
+        descripcion: c.condicion.clone(),
+        severidad: c.severidad,
+        detalle: c.notas.clone(),
+    });
+
+    let mut alertas: Vec<AlertaClinica> = alergias.chain(condiciones).collect();
+    alertas.sort_by_key(|a| std::cmp::Reverse(a.severidad));
+    alertas
+}
+
+pub fn con_alertas<M: MascotaRepository + ?Sized>(
+    mascotas: &MascotaService<M>,
+    historia: HistoriaClinica,
+) -> Result<HistoriaClinicaConAlertas, DomainError> {
+    let mascota = mascotas.obtener_mascota(historia.id_mascota)?;
+    Ok(HistoriaClinicaConAlertas {
+        alertas: mascota.as_ref().map(alertas).unwrap_or_default(),
+        version_mascota: mascota.map(|m| m.version),
+        historia,
+    })
+}
+
+// Alergias de la mascota cuya sustancia aparece en el principio activo o en el
+// nombre comercial del medicamento, sin distinguir mayúsculas ni acentos
+pub fn alergias_a(mascota: &Mascota, medicamento: &Medicamento) -> Vec<Alergia> {
+    let nombres: Vec<String> = std::iter::once(&medicamento.principio_activo)
+        .chain(medicamento.nombre_comercial.as_ref())
+        .map(|nombre| normalizar(nombre))
+        .collect();
+
+    mascota.alergias.iter()
+        .filter(|alergia| {
+            let sustancia = normalizar(&alergia.sustancia);
+            nombres.iter().any(|nombre| nombre.contains(&sustancia))
+        })
+        .cloned()
+        .collect()
+}
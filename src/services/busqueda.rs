@@ -0,0 +1,61 @@
+use crate::models::{Cliente, Mascota};
+use crate::repositories::busqueda::{self, Indexable};
+use crate::repositories::cliente_repository::ClienteRepository;
+use crate::repositories::mascota_repository::MascotaRepository;
+use crate::services::{ClienteService, MascotaService};
+use serde::Serialize;
+use std::cmp::Reverse;
+use crate::error::DomainError;
+
+// Resultado de `/buscar`: clientes y mascotas mezclados, de mayor a menor puntaje
+#[derive(Debug, Serialize)]
+#[serde(tag = "tipo", rename_all = "lowercase")]
+pub enum ResultadoBusqueda {
+    Cliente { puntaje: u32, cliente: Cliente },
+    Mascota { puntaje: u32, mascota: Mascota },
+}
+
+impl ResultadoBusqueda {
+    fn clave_orden(&self) -> (Reverse<u32>, String) {
+        match self {
+            ResultadoBusqueda::Cliente { puntaje, cliente } => {
+                (Reverse(*puntaje), busqueda::normalizar(&cliente.etiqueta()))
+            }
+            ResultadoBusqueda::Mascota { puntaje, mascota } => {
+                (Reverse(*puntaje), busqueda::normalizar(&mascota.etiqueta()))
+            }
+        }
+    }
+}
+
+pub fn buscar<C, M>(
+    clientes: &ClienteService<C>,
+    mascotas: &MascotaService<M>,
+    texto: &str,
+    limite: usize,
+) -> Result<Vec<ResultadoBusqueda>, DomainError>
+where
+    C: ClienteRepository + ?Sized,
+    M: MascotaRepository + ?Sized,
+{
+    let terminos = busqueda::terminos(texto);
+    if terminos.is_empty() {
+        return Err(DomainError::BadRequest(
+            "La búsqueda debe contener al menos una letra o un dígito".to_string(),
+        ));
+    }
+
+    // Cada repositorio devuelve sus mejores `limite`, así que entre ambos
+    // están los mejores `limite` de la mezcla.
+    let mut resultados: Vec<ResultadoBusqueda> = clientes.buscar_clientes(&terminos, limite)?
+        .into_iter()
+        .map(|c| ResultadoBusqueda::Cliente { puntaje: c.puntaje, cliente: c.elemento })
+        .chain(mascotas.buscar_mascotas(&terminos, limite)?
+            .into_iter()
+            .map(|m| ResultadoBusqueda::Mascota { puntaje: m.puntaje, mascota: m.elemento }))
+        .collect();
+
+    resultados.sort_by_cached_key(ResultadoBusqueda::clave_orden);
+    resultados.truncate(limite);
+    Ok(resultados)
+}
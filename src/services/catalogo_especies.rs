@@ -0,0 +1,475 @@
+use crate::models::{Mascota, Medicamento};
+use crate::models::medicamento::RangoDosis;
+use crate::repositories::especie_repository::EspecieRepository;
+use crate::repositories::mascota_repository::MascotaRepository;
+use crate::repositories::medicamento_repository::MedicamentoRepository;
+use crate::services::{EspecieService, MascotaService, MedicamentoService};
+use crate::services::especie_service::SinResolver;
+use crate::services::concurrencia::VersionEsperada;
+use crate::error::{DomainError, FieldError};
+use log::error;
+use serde::Serialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize)]
+pub struct CambioMascota {
+    pub id_mascota: Uuid,
+    pub especie_anterior: String,
+    pub raza_anterior: String,
+    pub especie: String,
+    pub raza: String,
+}
+
+// Texto que no corresponde al catálogo; se agrega al catálogo como alias o se
+// corrige a mano en la mascota
+#[derive(Debug, Serialize)]
+pub struct MascotaSinResolver {
+    pub id_mascota: Uuid,
+    pub especie: String,
+    pub raza: String,
+    // "especie" o "raza"
+    pub campo: &'static str,
+}
+
+// Rango de dosis cuya especie pasa a su código
+#[derive(Debug, Serialize)]
+pub struct CambioRangoDosis {
+    pub id_medicamento: Uuid,
+    pub especie_anterior: String,
+    pub especie: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RangoSinResolver {
+    pub id_medicamento: Uuid,
+    pub especie: String,
+}
+
+// Mascota o medicamento que no se pudo migrar; el resto de la migración sigue
+// y puede volver a ejecutarse después de corregirlo
+#[derive(Debug, Serialize)]
+pub struct FalloMigracion {
+    pub id: Uuid,
+    pub mensaje: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MigracionMascotas {
+    pub revisadas: usize,
+    pub cambios: Vec<CambioMascota>,
+    pub sin_resolver: Vec<MascotaSinResolver>,
+    pub fallidas: Vec<FalloMigracion>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MigracionMedicamentos {
+    pub revisados: usize,
+    pub cambios: Vec<CambioRangoDosis>,
+    pub sin_resolver: Vec<RangoSinResolver>,
+    pub fallidos: Vec<FalloMigracion>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MigracionEspecies {
+    pub aplicada: bool,
+    pub mascotas: MigracionMascotas,
+    pub medicamentos: MigracionMedicamentos,
+}
+
+// Reemplaza la especie de cada rango de dosis por su código del catálogo; los
+// rangos sin especie quedan como vinieron para que los rechace la validación
+// del medicamento
+pub fn resolver_dosis<E>(especies: &EspecieService<E>, dosis: Vec<RangoDosis>) -> Result<Vec<RangoDosis>, DomainError>
+where
+    E: EspecieRepository + ?Sized,
+{
+    let mut errores = Vec::new();
+    let dosis = dosis.into_iter()
+        .enumerate()
+        .map(|(indice, rango)| {
+            if rango.especie.is_empty() {
+                return Ok(rango);
+            }
+            Ok(match especies.codigo_especie(&rango.especie)? {
+                Some(especie) => RangoDosis { especie, ..rango },
+                None => {
+                    errores.push(FieldError {
+                        campo: format!("dosis[{}].especie", indice),
+                        regla: "catalog".to_string(),
+                        mensaje: "no corresponde a ninguna especie del catálogo (ver /especies/opciones)".to_string(),
+                    });
+                    rango
+                }
+            })
+        })
+        .collect::<Result<Vec<_>, DomainError>>()?;
+
+    if errores.is_empty() {
+        Ok(dosis)
+    } else {
+        Err(DomainError::InvalidFields(errores))
+    }
+}
+
+fn fallo(id: Uuid, err: DomainError) -> FalloMigracion {
+    let mensaje = match err {
+        // Como en las respuestas, los detalles internos solo van al log
+        DomainError::Storage(_) | DomainError::Internal(_) => {
+            error!("Migración de especies, {}: {}", id, err);
+            "Error interno del servidor".to_string()
+        }
+        _ => err.to_string(),
+    };
+    FalloMigracion { id, mensaje }
+}
+
+type PlanMascotas = (Vec<(u64, CambioMascota)>, Vec<MascotaSinResolver>);
+
+// Especie y raza de catálogo de cada mascota cuyo texto cambia, con la versión
+// revisada. Si la especie se reconoce pero la raza no, cambia solo la especie.
+fn planificar_mascotas<E>(
+    especies: &EspecieService<E>,
+    mascotas: &[Mascota],
+) -> Result<PlanMascotas, DomainError>
+where
+    E: EspecieRepository + ?Sized,
+{
+    let mut cambios = Vec::new();
+    let mut sin_resolver = Vec::new();
+    for mascota in mascotas {
+        let (especie, raza) = match especies.identificar(&mascota.especie, &mascota.raza)? {
+            Ok(codigos) => codigos,
+            Err(motivo) => {
+                sin_resolver.push(MascotaSinResolver {
+                    id_mascota: mascota.id,
+                    especie: mascota.especie.clone(),
+                    raza: mascota.raza.clone(),
+                    campo: match motivo {
+                        SinResolver::Especie => "especie",
+                        SinResolver::Raza(_) => "raza",
+                    },
+                });
+                match motivo {
+                    SinResolver::Especie => continue,
+                    SinResolver::Raza(especie) => (especie, mascota.raza.clone()),
+                }
+            }
+        };
+
+        if especie == mascota.especie && raza == mascota.raza {
+            continue;
+        }
+        cambios.push((mascota.version, CambioMascota {
+            id_mascota: mascota.id,
+            especie_anterior: mascota.especie.clone(),
+            raza_anterior: mascota.raza.clone(),
+            especie,
+            raza,
+        }));
+    }
+    Ok((cambios, sin_resolver))
+}
+
+// Rangos de dosis de un medicamento con la especie llevada al código del
+// catálogo. Falla si dos rangos terminan en la misma especie ("perro" y
+// "canino"): hay que dejar uno solo a mano.
+fn planificar_dosis<E>(
+    especies: &EspecieService<E>,
+    medicamento: &Medicamento,
+    sin_resolver: &mut Vec<RangoSinResolver>,
+) -> Result<(Vec<RangoDosis>, Vec<CambioRangoDosis>), DomainError>
+where
+    E: EspecieRepository + ?Sized,
+{
+    let mut dosis = medicamento.dosis.clone();
+    let mut cambios = Vec::new();
+    let mut originales: HashMap<String, String> = HashMap::new();
+    for rango in &mut dosis {
+        let anterior = rango.especie.clone();
+        match especies.codigo_especie(&anterior)? {
+            Some(especie) if especie != anterior => {
+                cambios.push(CambioRangoDosis {
+                    id_medicamento: medicamento.id,
+                    especie_anterior: anterior.clone(),
+                    especie: especie.clone(),
+                });
+                rango.especie = especie;
+            }
+            Some(_) => {}
+            None => sin_resolver.push(RangoSinResolver {
+                id_medicamento: medicamento.id,
+                especie: anterior.clone(),
+            }),
+        }
+        if let Some(otro) = originales.insert(rango.especie.clone(), anterior.clone()) {
+            return Err(DomainError::Conflict(format!(
+                "Los rangos de dosis para \"{}\" y \"{}\" del medicamento {} son de la misma especie ({}); deje uno solo",
+                otro, anterior, medicamento.id, rango.especie
+            )));
+        }
+    }
+    Ok((dosis, cambios))
+}
+
+// Primero revisa todo y después aplica solo lo que se pudo resolver, cada
+// cambio sobre la versión revisada. Lo que falla se informa por mascota y no
+// detiene al resto; como lo ya migrado no vuelve a cambiar, la migración puede
+// repetirse hasta que no queden cambios.
+fn migrar_mascotas<E, M>(
+    especies: &EspecieService<E>,
+    mascotas: &mut MascotaService<M>,
+    aplicar: bool,
+    autor: Option<&str>,
+) -> Result<MigracionMascotas, DomainError>
+where
+    E: EspecieRepository + ?Sized,
+    M: MascotaRepository + ?Sized,
+{
+    let mut todas = mascotas.todas_las_mascotas()?;
+    todas.sort_by_key(|m| m.id);
+    let (pendientes, sin_resolver) = planificar_mascotas(especies, &todas)?;
+
+    let mut cambios = Vec::new();
+    let mut fallidas = Vec::new();
+    for (version, cambio) in pendientes {
+        if aplicar {
+            let resultado = mascotas.reclasificar_mascota(
+                cambio.id_mascota,
+                cambio.especie.clone(),
+                cambio.raza.clone(),
+                &VersionEsperada::Alguna(vec![version]),
+                autor,
+            );
+            if let Err(err) = resultado {
+                fallidas.push(fallo(cambio.id_mascota, err));
+                continue;
+            }
+        }
+        cambios.push(cambio);
+    }
+
+    Ok(MigracionMascotas {
+        revisadas: todas.len(),
+        cambios,
+        sin_resolver,
+        fallidas,
+    })
+}
+
+// Como `migrar_mascotas`, para los rangos de dosis de los medicamentos
+// (incluidos los archivados)
+fn migrar_medicamentos<E, D>(
+    especies: &EspecieService<E>,
+    medicamentos: &mut MedicamentoService<D>,
+    aplicar: bool,
+    autor: Option<&str>,
+) -> Result<MigracionMedicamentos, DomainError>
+where
+    E: EspecieRepository + ?Sized,
+    D: MedicamentoRepository + ?Sized,
+{
+    let mut todos = medicamentos.todos_los_medicamentos()?;
+    todos.sort_by_key(|m| m.id);
+
+    let mut pendientes = Vec::new();
+    let mut sin_resolver = Vec::new();
+    let mut fallidos = Vec::new();
+    for medicamento in &todos {
+        match planificar_dosis(especies, medicamento, &mut sin_resolver) {
+            Ok((_, cambios)) if cambios.is_empty() => {}
+            Ok((dosis, cambios)) => pendientes.push((medicamento.id, medicamento.version, dosis, cambios)),
+            Err(err) => fallidos.push(fallo(medicamento.id, err)),
+        }
+    }
+
+    let mut cambios = Vec::new();
+    for (id, version, dosis, cambios_medicamento) in pendientes {
+        if aplicar {
+            if let Err(err) = medicamentos.reclasificar_dosis(id, dosis, &VersionEsperada::Alguna(vec![version]), autor) {
+                fallidos.push(fallo(id, err));
+                continue;
+            }
+        }
+        cambios.extend(cambios_medicamento);
+    }
+
+    Ok(MigracionMedicamentos {
+        revisados: todos.len(),
+        cambios,
+        sin_resolver,
+        fallidos,
+    })
+}
+
+// Lleva al catálogo las especies y razas de texto libre de las mascotas y las
+// especies de los rangos de dosis de los medicamentos. Sin `aplicar` solo
+// informa lo que cambiaría. Si no se puede leer lo que hay que revisar, falla
+// antes de aplicar nada.
+pub fn migrar<E, M, D>(
+    especies: &EspecieService<E>,
+    mascotas: &mut MascotaService<M>,
+    medicamentos: &mut MedicamentoService<D>,
+    aplicar: bool,
+    autor: Option<&str>,
+) -> Result<MigracionEspecies, DomainError>
+where
+    E: EspecieRepository + ?Sized,
+    M: MascotaRepository + ?Sized,
+    D: MedicamentoRepository + ?Sized,
+{
+    Ok(MigracionEspecies {
+        aplicada: aplicar,
+        mascotas: migrar_mascotas(especies, mascotas, aplicar, autor)?,
+        medicamentos: migrar_medicamentos(especies, medicamentos, aplicar, autor)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::mascota::{Alergia, Severidad};
+    use crate::repositories::especie_repository::InMemoryEspecieRepository;
+    use crate::repositories::mascota_repository::InMemoryMascotaRepository;
+    use crate::repositories::medicamento_repository::InMemoryMedicamentoRepository;
+    use crate::services::medicamento_service::DatosMedicamento;
+
+    struct Datos {
+        especies: EspecieService<InMemoryEspecieRepository>,
+        mascotas: MascotaService<InMemoryMascotaRepository>,
+        medicamentos: MedicamentoService<InMemoryMedicamentoRepository>,
+    }
+
+    impl Datos {
+        // Catálogo inicial, sin mascotas ni medicamentos
+        fn new() -> Self {
+            let mut especies = EspecieService::new(Box::new(InMemoryEspecieRepository::new()));
+            especies.cargar_catalogo_inicial().unwrap();
+            Self {
+                especies,
+                mascotas: MascotaService::new(Box::new(InMemoryMascotaRepository::new())),
+                medicamentos: MedicamentoService::new(Box::new(InMemoryMedicamentoRepository::new())),
+            }
+        }
+
+        // Los servicios guardan la especie como venga; el catálogo se aplica en
+        // los controladores
+        fn mascota(&mut self, especie: &str, raza: &str) -> Uuid {
+            self.mascotas.crear_mascota(
+                "Toby".to_string(), especie.to_string(), raza.to_string(), None, Uuid::new_v4(),
+                Vec::new(), Vec::new(), None,
+            ).unwrap().id
+        }
+
+        fn medicamento(&mut self, especies: &[&str]) -> Uuid {
+            let dosis = especies.iter()
+                .map(|especie| RangoDosis { especie: especie.to_string(), minima_mg_kg: 0.1, maxima_mg_kg: 0.2 })
+                .collect();
+            self.medicamentos.crear_medicamento(DatosMedicamento {
+                principio_activo: "Meloxicam".to_string(),
+                nombre_comercial: None,
+                presentacion: "Comprimidos".to_string(),
+                concentracion_mg: 2.0,
+                unidad: "comprimido".to_string(),
+                dosis,
+            }, None).unwrap().id
+        }
+
+        fn migrar(&mut self, aplicar: bool) -> MigracionEspecies {
+            migrar(&self.especies, &mut self.mascotas, &mut self.medicamentos, aplicar, None).unwrap()
+        }
+
+        fn especie_y_raza(&self, id_mascota: Uuid) -> (String, String) {
+            let mascota = self.mascotas.obtener_mascota(id_mascota).unwrap().unwrap();
+            (mascota.especie, mascota.raza)
+        }
+
+        fn especies_dosis(&self, id_medicamento: Uuid) -> Vec<String> {
+            self.medicamentos.obtener_medicamento(id_medicamento).unwrap().unwrap()
+                .dosis.into_iter()
+                .map(|rango| rango.especie)
+                .collect()
+        }
+    }
+
+    #[test]
+    fn sin_aplicar_solo_informa() {
+        let mut datos = Datos::new();
+        let toby = datos.mascota("Dog", "Labrador");
+        let dragon = datos.mascota("Dragón", "");
+        let meloxicam = datos.medicamento(&["Canino"]);
+
+        let migracion = datos.migrar(false);
+        assert!(!migracion.aplicada);
+        assert_eq!(migracion.mascotas.revisadas, 2);
+        assert_eq!(migracion.mascotas.cambios.len(), 1);
+        assert_eq!(migracion.mascotas.cambios[0].especie, "perro");
+        assert_eq!(migracion.mascotas.cambios[0].raza, "labrador_retriever");
+        assert_eq!(migracion.mascotas.sin_resolver.len(), 1);
+        assert_eq!(migracion.mascotas.sin_resolver[0].id_mascota, dragon);
+        assert_eq!(migracion.medicamentos.cambios.len(), 1);
+
+        assert_eq!(datos.especie_y_raza(toby), ("Dog".to_string(), "Labrador".to_string()));
+        assert_eq!(datos.especies_dosis(meloxicam), vec!["Canino"]);
+    }
+
+    #[test]
+    fn aplica_los_cambios_y_se_puede_repetir() {
+        let mut datos = Datos::new();
+        let toby = datos.mascota("Dog", "Labrador");
+        let michi = datos.mascota("felino", "raza inventada");
+        let meloxicam = datos.medicamento(&["Dog", "Gato", "Dragón"]);
+
+        let migracion = datos.migrar(true);
+        assert!(migracion.aplicada);
+        assert_eq!(migracion.mascotas.cambios.len(), 2);
+        assert!(migracion.mascotas.fallidas.is_empty());
+        assert_eq!(datos.especie_y_raza(toby), ("perro".to_string(), "labrador_retriever".to_string()));
+        // Se migra la especie aunque la raza no se reconozca
+        assert_eq!(datos.especie_y_raza(michi), ("gato".to_string(), "raza inventada".to_string()));
+        assert_eq!(datos.especies_dosis(meloxicam), vec!["perro", "gato", "Dragón"]);
+        assert_eq!(migracion.medicamentos.sin_resolver.len(), 1);
+
+        let version = datos.mascotas.obtener_mascota(toby).unwrap().unwrap().version;
+        let repetida = datos.migrar(true);
+        assert!(repetida.mascotas.cambios.is_empty());
+        assert!(repetida.medicamentos.cambios.is_empty());
+        assert!(repetida.mascotas.fallidas.is_empty() && repetida.medicamentos.fallidos.is_empty());
+        assert_eq!(datos.mascotas.obtener_mascota(toby).unwrap().unwrap().version, version);
+    }
+
+    #[test]
+    fn informa_los_rangos_que_terminan_en_la_misma_especie() {
+        let mut datos = Datos::new();
+        let duplicado = datos.medicamento(&["perro", "canino"]);
+        let correcto = datos.medicamento(&["felino"]);
+
+        let migracion = datos.migrar(true);
+        assert_eq!(migracion.medicamentos.fallidos.len(), 1);
+        assert_eq!(migracion.medicamentos.fallidos[0].id, duplicado);
+        assert_eq!(datos.especies_dosis(duplicado), vec!["perro", "canino"]);
+        // El resto se migra igual
+        assert_eq!(datos.especies_dosis(correcto), vec!["gato"]);
+    }
+
+    #[test]
+    fn migra_mascotas_con_datos_anteriores_a_las_validaciones() {
+        // Alergia repetida, que hoy no se aceptaría al modificar la mascota
+        let alergia = |sustancia: &str| Alergia {
+            sustancia: sustancia.to_string(),
+            severidad: Severidad::Leve,
+            reaccion: None,
+        };
+        let mascota = Mascota::new(
+            "Toby".to_string(), "perro".to_string(), "ovejero".to_string(), None, Uuid::new_v4(),
+            vec![alergia("Penicilina"), alergia("penicilina")], Vec::new(), None,
+        );
+        let mut repositorio = InMemoryMascotaRepository::new();
+        repositorio.guardar(mascota.clone()).unwrap();
+        let mut datos = Datos { mascotas: MascotaService::new(Box::new(repositorio)), ..Datos::new() };
+
+        let migracion = datos.migrar(true);
+        assert!(migracion.mascotas.fallidas.is_empty());
+        assert_eq!(datos.especie_y_raza(mascota.id), ("perro".to_string(), "pastor_aleman".to_string()));
+    }
+}
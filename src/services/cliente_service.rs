@@ -1,16 +1,33 @@
-use crate::models::Cliente;
-use crate::repositories::cliente_repository::ClienteRepository;
+use crate::models::{Auditado, Cliente};
+use crate::repositories::cliente_repository::{ClienteRepository, ConsultaClientes};
+use crate::repositories::consulta::Pagina;
+use crate::repositories::busqueda::Coincidencia;
 use uuid::Uuid;
+use crate::error::DomainError;
+use crate::services::concurrencia::VersionEsperada;
+use chrono::Utc;
 
-pub struct ClienteService<T: ClienteRepository> {
-    repository: T,
+// Campos a modificar en una actualización parcial; `None` deja el valor actual
+#[derive(Debug, Default)]
+pub struct CambiosCliente {
+    pub nombre: Option<String>,
+    pub apellido: Option<String>,
+    pub correo: Option<String>,
+    pub telefono: Option<String>,
+    pub direccion: Option<String>,
+    pub id_clinica: Option<Uuid>,
 }
 
-impl<T: ClienteRepository> ClienteService<T> {
-    pub fn new(repository: T) -> Self {
+pub struct ClienteService<T: ClienteRepository + ?Sized> {
+    repository: Box<T>,
+}
+
+impl<T: ClienteRepository + ?Sized> ClienteService<T> {
+    pub fn new(repository: Box<T>) -> Self {
         Self { repository }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn crear_cliente(
         &mut self,
         nombre: String,
@@ -19,20 +36,37 @@ impl<T: ClienteRepository> ClienteService<T> {
         telefono: String,
         direccion: String,
         id_clinica: Uuid,
-    ) -> Result<Cliente, String> {
-        let cliente = Cliente::new(nombre, apellido, correo, telefono, direccion, id_clinica);
+        autor: Option<&str>,
+    ) -> Result<Cliente, DomainError> {
+        let cliente = Cliente::new(nombre, apellido, correo, telefono, direccion, id_clinica, autor);
         self.repository.guardar(cliente.clone())?;
         Ok(cliente)
     }
 
-    pub fn obtener_cliente(&self, id: Uuid) -> Option<&Cliente> {
+    pub fn obtener_cliente(&self, id: Uuid) -> Result<Option<Cliente>, DomainError> {
         self.repository.obtener(id)
     }
 
-    pub fn listar_clientes(&self) -> Vec<&Cliente> {
-        self.repository.listar()
+    // Los clientes archivados no aparecen en los listados salvo que el filtro lo pida.
+    // La pertenencia a una clínica se deriva de `Cliente.id_clinica`, por lo que
+    // un cliente actualizado con otra clínica se mueve automáticamente.
+    pub fn listar_clientes(&self, consulta: &ConsultaClientes) -> Result<Pagina<Cliente>, DomainError> {
+        self.repository.listar(consulta)
+    }
+
+    pub fn buscar_clientes(&self, terminos: &[String], limite: usize) -> Result<Vec<Coincidencia<Cliente>>, DomainError> {
+        self.repository.buscar(terminos, limite)
     }
 
+    // Incluye los archivados: se usa para detectar dependientes antes de eliminar
+    pub fn ids_clientes_clinica(&self, id_clinica: Uuid) -> Result<Vec<Uuid>, DomainError> {
+        Ok(self.repository.listar_por_clinica(id_clinica)?
+            .into_iter()
+            .map(|c| c.id)
+            .collect())
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn actualizar_cliente(
         &mut self,
         id: Uuid,
@@ -42,11 +76,14 @@ impl<T: ClienteRepository> ClienteService<T> {
         telefono: String,
         direccion: String,
         id_clinica: Uuid,
-    ) -> Result<Cliente, String> {
-        let cliente = self.repository.obtener(id)
-            .ok_or_else(|| "El cliente no existe".to_string())?;
+        esperada: &VersionEsperada,
+        autor: Option<&str>,
+    ) -> Result<Cliente, DomainError> {
+        let cliente = self.repository.obtener(id)?
+            .ok_or_else(|| DomainError::NotFound(format!("El cliente {} no existe", id)))?;
+        esperada.comprobar(&format!("El cliente {}", id), cliente.version)?;
 
-        let cliente_actualizado = Cliente {
+        let mut cliente_actualizado = Cliente {
             id: cliente.id,
             nombre,
             apellido,
@@ -54,9 +91,86 @@ impl<T: ClienteRepository> ClienteService<T> {
             telefono,
             direccion,
             id_clinica,
+            ..cliente
         };
+        cliente_actualizado.registrar_cambio(autor);
 
         self.repository.guardar(cliente_actualizado.clone())?;
         Ok(cliente_actualizado)
     }
+
+    pub fn modificar_cliente(
+        &mut self,
+        id: Uuid,
+        cambios: CambiosCliente,
+        esperada: &VersionEsperada,
+        autor: Option<&str>,
+    ) -> Result<Cliente, DomainError> {
+        let mut cliente = self.repository.obtener(id)?
+            .ok_or_else(|| DomainError::NotFound(format!("El cliente {} no existe", id)))?;
+        esperada.comprobar(&format!("El cliente {}", id), cliente.version)?;
+
+        if let Some(nombre) = cambios.nombre {
+            cliente.nombre = nombre;
+        }
+        if let Some(apellido) = cambios.apellido {
+            cliente.apellido = apellido;
+        }
+        if let Some(correo) = cambios.correo {
+            cliente.correo = correo;
+        }
+        if let Some(telefono) = cambios.telefono {
+            cliente.telefono = telefono;
+        }
+        if let Some(direccion) = cambios.direccion {
+            cliente.direccion = direccion;
+        }
+        if let Some(id_clinica) = cambios.id_clinica {
+            cliente.id_clinica = id_clinica;
+        }
+
+        cliente.registrar_cambio(autor);
+        self.repository.guardar(cliente.clone())?;
+        Ok(cliente)
+    }
+
+    pub fn archivar_cliente(
+        &mut self,
+        id: Uuid,
+        esperada: &VersionEsperada,
+        autor: Option<&str>,
+    ) -> Result<Cliente, DomainError> {
+        let cliente = self.repository.obtener(id)?
+            .ok_or_else(|| DomainError::NotFound(format!("El cliente {} no existe", id)))?;
+        esperada.comprobar(&format!("El cliente {}", id), cliente.version)?;
+
+        let mut cliente_archivado = Cliente {
+            archivado: true,
+            fecha_archivado: Some(Utc::now()),
+            ..cliente
+        };
+        cliente_archivado.registrar_cambio(autor);
+
+        self.repository.guardar(cliente_archivado.clone())?;
+        Ok(cliente_archivado)
+    }
+
+    pub fn restaurar_cliente(&mut self, id: Uuid, autor: Option<&str>) -> Result<Cliente, DomainError> {
+        let cliente = self.repository.obtener(id)?
+            .ok_or_else(|| DomainError::NotFound(format!("El cliente {} no existe", id)))?;
+
+        let mut cliente_restaurado = Cliente {
+            archivado: false,
+            fecha_archivado: None,
+            ..cliente
+        };
+        cliente_restaurado.registrar_cambio(autor);
+
+        self.repository.guardar(cliente_restaurado.clone())?;
+        Ok(cliente_restaurado)
+    }
+
+    pub fn eliminar_cliente(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.repository.eliminar(id)
+    }
 }
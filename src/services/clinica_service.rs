@@ -1,13 +1,26 @@
-use crate::models::{Clinica, Cliente};
-use crate::repositories::clinica_repository::ClinicaRepository;
+use crate::models::{Auditado, Clinica};
+use crate::repositories::clinica_repository::{ClinicaRepository, ConsultaClinicas};
+use crate::repositories::consulta::Pagina;
 use uuid::Uuid;
+use crate::error::DomainError;
+use crate::services::concurrencia::VersionEsperada;
+use chrono::Utc;
 
-pub struct ClinicaService<T: ClinicaRepository> {
-    repository: T,
+// Campos a modificar en una actualización parcial; `None` deja el valor actual
+#[derive(Debug, Default)]
+pub struct CambiosClinica {
+    pub nombre: Option<String>,
+    pub direccion: Option<String>,
+    pub telefono: Option<String>,
+    pub correo: Option<String>,
 }
 
-impl<T: ClinicaRepository> ClinicaService<T> {
-    pub fn new(repository: T) -> Self {
+pub struct ClinicaService<T: ClinicaRepository + ?Sized> {
+    repository: Box<T>,
+}
+
+impl<T: ClinicaRepository + ?Sized> ClinicaService<T> {
+    pub fn new(repository: Box<T>) -> Self {
         Self { repository }
     }
 
@@ -17,37 +30,23 @@ impl<T: ClinicaRepository> ClinicaService<T> {
         direccion: String,
         telefono: String,
         correo: String,
-    ) -> Result<Clinica, String> {
-        let clinica = Clinica::new(nombre, direccion, telefono, correo);
+        autor: Option<&str>,
+    ) -> Result<Clinica, DomainError> {
+        let clinica = Clinica::new(nombre, direccion, telefono, correo, autor);
         self.repository.guardar(clinica.clone())?;
         Ok(clinica)
     }
 
-    pub fn obtener_clinica(&self, id: Uuid) -> Option<&Clinica> {
+    pub fn obtener_clinica(&self, id: Uuid) -> Result<Option<Clinica>, DomainError> {
         self.repository.obtener(id)
     }
 
-    pub fn listar_clinicas(&self) -> Vec<&Clinica> {
-        self.repository.listar()
-    }
-
-    pub fn obtener_clientes_clinica(&self, id_clinica: Uuid) -> Vec<&Cliente> {
-        self.repository.obtener_clientes(id_clinica)
-    }
-
-    pub fn agregar_cliente(
-        &mut self,
-        id_clinica: Uuid,
-        cliente: Cliente,
-    ) -> Result<(), String> {
-        // Verificamos que la clínica exista
-        if self.repository.obtener(id_clinica).is_none() {
-            return Err("La clínica no existe".to_string());
-        }
-        
-        self.repository.agregar_cliente(id_clinica, cliente)
+    // Las clínicas archivadas no aparecen en los listados salvo que el filtro lo pida
+    pub fn listar_clinicas(&self, consulta: &ConsultaClinicas) -> Result<Pagina<Clinica>, DomainError> {
+        self.repository.listar(consulta)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn actualizar_clinica(
         &mut self,
         id: Uuid,
@@ -55,19 +54,93 @@ impl<T: ClinicaRepository> ClinicaService<T> {
         direccion: String,
         telefono: String,
         correo: String,
-    ) -> Result<Clinica, String> {
-        let clinica = self.repository.obtener(id)
-            .ok_or_else(|| "La clínica no existe".to_string())?;
+        esperada: &VersionEsperada,
+        autor: Option<&str>,
+    ) -> Result<Clinica, DomainError> {
+        let clinica = self.repository.obtener(id)?
+            .ok_or_else(|| DomainError::NotFound(format!("La clínica {} no existe", id)))?;
+        esperada.comprobar(&format!("La clínica {}", id), clinica.version)?;
 
-        let clinica_actualizada = Clinica {
+        let mut clinica_actualizada = Clinica {
             id: clinica.id,
             nombre,
             direccion,
             telefono,
             correo,
+            ..clinica
         };
+        clinica_actualizada.registrar_cambio(autor);
 
         self.repository.guardar(clinica_actualizada.clone())?;
         Ok(clinica_actualizada)
     }
+
+    pub fn modificar_clinica(
+        &mut self,
+        id: Uuid,
+        cambios: CambiosClinica,
+        esperada: &VersionEsperada,
+        autor: Option<&str>,
+    ) -> Result<Clinica, DomainError> {
+        let mut clinica = self.repository.obtener(id)?
+            .ok_or_else(|| DomainError::NotFound(format!("La clínica {} no existe", id)))?;
+        esperada.comprobar(&format!("La clínica {}", id), clinica.version)?;
+
+        if let Some(nombre) = cambios.nombre {
+            clinica.nombre = nombre;
+        }
+        if let Some(direccion) = cambios.direccion {
+            clinica.direccion = direccion;
+        }
+        if let Some(telefono) = cambios.telefono {
+            clinica.telefono = telefono;
+        }
+        if let Some(correo) = cambios.correo {
+            clinica.correo = correo;
+        }
+
+        clinica.registrar_cambio(autor);
+        self.repository.guardar(clinica.clone())?;
+        Ok(clinica)
+    }
+
+    pub fn archivar_clinica(
+        &mut self,
+        id: Uuid,
+        esperada: &VersionEsperada,
+        autor: Option<&str>,
+    ) -> Result<Clinica, DomainError> {
+        let clinica = self.repository.obtener(id)?
+            .ok_or_else(|| DomainError::NotFound(format!("La clínica {} no existe", id)))?;
+        esperada.comprobar(&format!("La clínica {}", id), clinica.version)?;
+
+        let mut clinica_archivada = Clinica {
+            archivado: true,
+            fecha_archivado: Some(Utc::now()),
+            ..clinica
+        };
+        clinica_archivada.registrar_cambio(autor);
+
+        self.repository.guardar(clinica_archivada.clone())?;
+        Ok(clinica_archivada)
+    }
+
+    pub fn restaurar_clinica(&mut self, id: Uuid, autor: Option<&str>) -> Result<Clinica, DomainError> {
+        let clinica = self.repository.obtener(id)?
+            .ok_or_else(|| DomainError::NotFound(format!("La clínica {} no existe", id)))?;
+
+        let mut clinica_restaurada = Clinica {
+            archivado: false,
+            fecha_archivado: None,
+            ..clinica
+        };
+        clinica_restaurada.registrar_cambio(autor);
+
+        self.repository.guardar(clinica_restaurada.clone())?;
+        Ok(clinica_restaurada)
+    }
+
+    pub fn eliminar_clinica(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.repository.eliminar(id)
+    }
 }
@@ -0,0 +1,23 @@
+use crate::error::DomainError;
+
+// Versión que el cliente espera modificar (control de concurrencia optimista).
+// Se comprueba dentro del servicio, con el estado bloqueado, justo antes de guardar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionEsperada {
+    // `If-Match: *`, o una operación interna (p. ej. una cascada)
+    Cualquiera,
+    Alguna(Vec<u64>),
+}
+
+impl VersionEsperada {
+    pub fn comprobar(&self, descripcion: &str, actual: u64) -> Result<(), DomainError> {
+        match self {
+            VersionEsperada::Cualquiera => Ok(()),
+            VersionEsperada::Alguna(versiones) if versiones.contains(&actual) => Ok(()),
+            VersionEsperada::Alguna(_) => Err(DomainError::PreconditionFailed(format!(
+                "{} cambió desde que se leyó (versión actual {}); vuelva a leerlo",
+                descripcion, actual
+            ))),
+        }
+    }
+}
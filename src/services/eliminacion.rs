@@ -0,0 +1,566 @@
+use crate::repositories::clinica_repository::ClinicaRepository;
+use crate::repositories::cliente_repository::ClienteRepository;
+use crate::repositories::mascota_repository::MascotaRepository;
+use crate::repositories::historia_clinica_repository::HistoriaClinicaRepository;
+use crate::repositories::personal_repository::PersonalRepository;
+use crate::repositories::turno_repository::{FiltroTurnos, TurnoRepository};
+use crate::repositories::vacunacion_repository::VacunacionRepository;
+use crate::repositories::medicamento_repository::MedicamentoRepository;
+use crate::repositories::prescripcion_repository::PrescripcionRepository;
+use crate::repositories::especie_repository::EspecieRepository;
+use crate::services::{
+    ClinicaService, ClienteService, MascotaService, HistoriaClinicaService, PersonalService, TurnoService,
+    VacunacionService, MedicamentoService, PrescripcionService, EspecieService,
+};
+use crate::services::concurrencia::VersionEsperada;
+use uuid::Uuid;
+use crate::error::DomainError;
+
+// Eliminación definitiva. Sin `cascada` se rechaza si la entidad tiene
+// dependientes; con `cascada` se eliminan en orden inverso a las referencias
+// (turnos, vacunaciones, prescripciones, entradas, historias, mascotas,
+// clientes) para no dejar huérfanos.
+// La versión esperada se comprueba solo sobre la entidad pedida, no sobre sus
+// dependientes.
+// El personal no depende de una clínica: al eliminarla solo se la quita de sus clínicas.
+// Una historia con entradas firmadas no se elimina ni en cascada: todo lo que
+// la alcance se rechaza antes de borrar nada, y solo puede archivarse.
+
+fn rechazar_firmadas<H: HistoriaClinicaRepository + ?Sized>(
+    historias: &HistoriaClinicaService<H>,
+    ids_historias: &[Uuid],
+    entidad: &str,
+) -> Result<(), DomainError> {
+    for &id_historia in ids_historias {
+        let firmadas = historias.contar_entradas_firmadas(id_historia)?;
+        if firmadas > 0 {
+            return Err(DomainError::Conflict(format!(
+                "{} tiene {} entradas firmadas en la historia clínica {}; solo puede archivarse",
+                entidad, firmadas, id_historia
+            )));
+        }
+    }
+    Ok(())
+}
+
+pub fn eliminar_historia<H, P>(
+    historias: &mut HistoriaClinicaService<H>,
+    prescripciones: &mut PrescripcionService<P>,
+    id_historia: Uuid,
+    cascada: bool,
+    esperada: &VersionEsperada,
+) -> Result<(), DomainError>
+where
+    H: HistoriaClinicaRepository + ?Sized,
+    P: PrescripcionRepository + ?Sized,
+{
+    let actual = historias.obtener_historia(id_historia)?
+        .ok_or_else(|| DomainError::NotFound(format!("La historia clínica {} no existe", id_historia)))?;
+    esperada.comprobar(&format!("La historia clínica {}", id_historia), actual.version)?;
+
+    let firmadas = historias.contar_entradas_firmadas(id_historia)?;
+    if firmadas > 0 {
+        return Err(DomainError::Conflict(format!(
+            "La historia clínica {} tiene {} entradas firmadas; solo puede archivarse",
+            id_historia, firmadas
+        )));
+    }
+    let entradas = historias.obtener_entradas(id_historia)?.len();
+    if entradas > 0 && !cascada {
+        return Err(DomainError::Conflict(format!(
+            "La historia clínica {} tiene {} entradas; use cascada=true para eliminarlo todo",
+            id_historia, entradas
+        )));
+    }
+
+    eliminar_historia_y_prescripciones(historias, prescripciones, id_historia)
+}
+
+// Las prescripciones apuntan a las entradas, así que se eliminan antes que la historia
+fn eliminar_historia_y_prescripciones<H, P>(
+    historias: &mut HistoriaClinicaService<H>,
+    prescripciones: &mut PrescripcionService<P>,
+    id_historia: Uuid,
+) -> Result<(), DomainError>
+where
+    H: HistoriaClinicaRepository + ?Sized,
+    P: PrescripcionRepository + ?Sized,
+{
+    let ids_entradas: Vec<Uuid> = historias.obtener_entradas(id_historia)?.iter().map(|e| e.id).collect();
+    prescripciones.eliminar_prescripciones_entradas(&ids_entradas)?;
+    historias.eliminar_historia(id_historia)
+}
+
+fn eliminar_turnos<T: TurnoRepository + ?Sized>(
+    turnos: &mut TurnoService<T>,
+    ids_turnos: Vec<Uuid>,
+) -> Result<(), DomainError> {
+    ids_turnos.into_iter().try_for_each(|id| turnos.eliminar_turno(id))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn eliminar_mascota<M, H, T, V, P>(
+    mascotas: &mut MascotaService<M>,
+    historias: &mut HistoriaClinicaService<H>,
+    turnos: &mut TurnoService<T>,
+    vacunaciones: &mut VacunacionService<V>,
+    prescripciones: &mut PrescripcionService<P>,
+    id_mascota: Uuid,
+    cascada: bool,
+    esperada: &VersionEsperada,
+) -> Result<(), DomainError>
+where
+    M: MascotaRepository + ?Sized,
+    H: HistoriaClinicaRepository + ?Sized,
+    T: TurnoRepository + ?Sized,
+    V: VacunacionRepository + ?Sized,
+    P: PrescripcionRepository + ?Sized,
+{
+    let actual = mascotas.obtener_mascota(id_mascota)?
+        .ok_or_else(|| DomainError::NotFound(format!("La mascota {} no existe", id_mascota)))?;
+    esperada.comprobar(&format!("La mascota {}", id_mascota), actual.version)?;
+
+    let ids_historias = historias.ids_historias_mascota(id_mascota)?;
+    rechazar_firmadas(historias, &ids_historias, &format!("La mascota {}", id_mascota))?;
+    let ids_turnos = turnos.ids_turnos(&FiltroTurnos { id_mascota: Some(id_mascota), ..Default::default() })?;
+    let ids_vacunaciones = vacunaciones.ids_vacunaciones_mascota(id_mascota)?;
+    if (!ids_historias.is_empty() || !ids_turnos.is_empty() || !ids_vacunaciones.is_empty()) && !cascada {
+        return Err(DomainError::Conflict(format!(
+            "La mascota {} tiene {} historias clínicas, {} turnos y {} vacunaciones; \
+             use cascada=true para eliminarlo todo",
+            id_mascota, ids_historias.len(), ids_turnos.len(), ids_vacunaciones.len()
+        )));
+    }
+
+    eliminar_turnos(turnos, ids_turnos)?;
+    vacunaciones.eliminar_vacunaciones(ids_vacunaciones)?;
+    for id_historia in ids_historias {
+        eliminar_historia_y_prescripciones(historias, prescripciones, id_historia)?;
+    }
+    mascotas.eliminar_mascota(id_mascota)?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn eliminar_cliente<C, M, H, T, V, P>(
+    clientes: &mut ClienteService<C>,
+    mascotas: &mut MascotaService<M>,
+    historias: &mut HistoriaClinicaService<H>,
+    turnos: &mut TurnoService<T>,
+    vacunaciones: &mut VacunacionService<V>,
+    prescripciones: &mut PrescripcionService<P>,
+    id_cliente: Uuid,
+    cascada: bool,
+    esperada: &VersionEsperada,
+) -> Result<(), DomainError>
+where
+    C: ClienteRepository + ?Sized,
+    M: MascotaRepository + ?Sized,
+    H: HistoriaClinicaRepository + ?Sized,
+    T: TurnoRepository + ?Sized,
+    V: VacunacionRepository + ?Sized,
+    P: PrescripcionRepository + ?Sized,
+{
+    let actual = clientes.obtener_cliente(id_cliente)?
+        .ok_or_else(|| DomainError::NotFound(format!("El cliente {} no existe", id_cliente)))?;
+    esperada.comprobar(&format!("El cliente {}", id_cliente), actual.version)?;
+
+    // Las historias se eliminan con las mascotas del cliente
+    let ids_mascotas = mascotas.ids_mascotas_cliente(id_cliente)?;
+    let mut ids_historias = Vec::new();
+    for &id_mascota in &ids_mascotas {
+        ids_historias.extend(historias.ids_historias_mascota(id_mascota)?);
+    }
+    rechazar_firmadas(historias, &ids_historias, &format!("El cliente {}", id_cliente))?;
+    let ids_turnos = turnos.ids_turnos(&FiltroTurnos { id_cliente: Some(id_cliente), ..Default::default() })?;
+    if (!ids_mascotas.is_empty() || !ids_turnos.is_empty()) && !cascada {
+        return Err(DomainError::Conflict(format!(
+            "El cliente {} tiene {} mascotas y {} turnos; use cascada=true para eliminarlo todo",
+            id_cliente, ids_mascotas.len(), ids_turnos.len()
+        )));
+    }
+
+    // Una historia puede seguir apuntando al cliente si su mascota cambió de
+    // dueño antes de que las historias acompañaran el cambio: pasa al dueño actual
+    for historia in historias.historias_cliente(id_cliente)? {
+        if let Some(mascota) = mascotas.obtener_mascota(historia.id_mascota)? {
+            if mascota.id_cliente != id_cliente {
+                historias.reasignar_cliente(mascota.id, mascota.id_cliente, None)?;
+            }
+        }
+    }
+
+    eliminar_turnos(turnos, ids_turnos)?;
+    for id_mascota in ids_mascotas {
+        eliminar_mascota(
+            mascotas,
+            historias,
+            turnos,
+            vacunaciones,
+            prescripciones,
+            id_mascota,
+            true,
+            &VersionEsperada::Cualquiera,
+        )?;
+    }
+    clientes.eliminar_cliente(id_cliente)?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn eliminar_clinica<L, S, C, M, H, T, V, P>(
+    clinicas: &mut ClinicaService<L>,
+    personal: &mut PersonalService<S>,
+    clientes: &mut ClienteService<C>,
+    mascotas: &mut MascotaService<M>,
+    historias: &mut HistoriaClinicaService<H>,
+    turnos: &mut TurnoService<T>,
+    vacunaciones: &mut VacunacionService<V>,
+    prescripciones: &mut PrescripcionService<P>,
+    id_clinica: Uuid,
+    cascada: bool,
+    esperada: &VersionEsperada,
+) -> Result<(), DomainError>
+where
+    L: ClinicaRepository + ?Sized,
+    S: PersonalRepository + ?Sized,
+    C: ClienteRepository + ?Sized,
+    M: MascotaRepository + ?Sized,
+    H: HistoriaClinicaRepository + ?Sized,
+    T: TurnoRepository + ?Sized,
+    V: VacunacionRepository + ?Sized,
+    P: PrescripcionRepository + ?Sized,
+{
+    let actual = clinicas.obtener_clinica(id_clinica)?
+        .ok_or_else(|| DomainError::NotFound(format!("La clínica {} no existe", id_clinica)))?;
+    esperada.comprobar(&format!("La clínica {}", id_clinica), actual.version)?;
+
+    let ids_clientes = clientes.ids_clientes_clinica(id_clinica)?;
+    let mut ids_historias = Vec::new();
+    for &id_cliente in &ids_clientes {
+        for id_mascota in mascotas.ids_mascotas_cliente(id_cliente)? {
+            ids_historias.extend(historias.ids_historias_mascota(id_mascota)?);
+        }
+    }
+    rechazar_firmadas(historias, &ids_historias, &format!("La clínica {}", id_clinica))?;
+    // Un turno conserva la clínica aunque el cliente se haya cambiado a otra
+    let ids_turnos = turnos.ids_turnos(&FiltroTurnos { id_clinica: Some(id_clinica), ..Default::default() })?;
+    if (!ids_clientes.is_empty() || !ids_turnos.is_empty()) && !cascada {
+        return Err(DomainError::Conflict(format!(
+            "La clínica {} tiene {} clientes y {} turnos; use cascada=true para eliminarlo todo",
+            id_clinica, ids_clientes.len(), ids_turnos.len()
+        )));
+    }
+
+    eliminar_turnos(turnos, ids_turnos)?;
+    for id_cliente in ids_clientes {
+        eliminar_cliente(
+            clientes,
+            mascotas,
+            historias,
+            turnos,
+            vacunaciones,
+            prescripciones,
+            id_cliente,
+            true,
+            &VersionEsperada::Cualquiera,
+        )?;
+    }
+    personal.quitar_clinica(id_clinica)?;
+    clinicas.eliminar_clinica(id_clinica)?;
+    Ok(())
+}
+
+// Quien atendió o firmó alguna entrada, tiene turnos o aplicó vacunas solo
+// puede archivarse: esos registros siguen apuntando a él en el directorio
+pub fn eliminar_personal<P, H, T, V>(
+    personal: &mut PersonalService<P>,
+    historias: &HistoriaClinicaService<H>,
+    turnos: &TurnoService<T>,
+    vacunaciones: &VacunacionService<V>,
+    id_personal: Uuid,
+    esperada: &VersionEsperada,
+) -> Result<(), DomainError>
+where
+    P: PersonalRepository + ?Sized,
+    H: HistoriaClinicaRepository + ?Sized,
+    T: TurnoRepository + ?Sized,
+    V: VacunacionRepository + ?Sized,
+{
+    let actual = personal.obtener_personal(id_personal)?
+        .ok_or_else(|| DomainError::NotFound(format!("El integrante del personal {} no existe", id_personal)))?;
+    esperada.comprobar(&format!("El integrante del personal {}", id_personal), actual.version)?;
+
+    let entradas = historias.contar_entradas_veterinario(id_personal)?;
+    if entradas > 0 {
+        return Err(DomainError::Conflict(format!(
+            "El integrante del personal {} figura en {} entradas de historias clínicas; solo puede archivarse",
+            id_personal, entradas
+        )));
+    }
+    let ids_turnos = turnos.ids_turnos(&FiltroTurnos { id_veterinario: Some(id_personal), ..Default::default() })?;
+    if !ids_turnos.is_empty() {
+        return Err(DomainError::Conflict(format!(
+            "El integrante del personal {} tiene {} turnos; solo puede archivarse",
+            id_personal, ids_turnos.len()
+        )));
+    }
+    let aplicadas = vacunaciones.contar_vacunaciones_veterinario(id_personal)?;
+    if aplicadas > 0 {
+        return Err(DomainError::Conflict(format!(
+            "El integrante del personal {} aplicó {} vacunas; solo puede archivarse",
+            id_personal, aplicadas
+        )));
+    }
+
+    personal.eliminar_personal(id_personal)
+}
+
+// Las prescripciones copian los datos del medicamento, pero siguen apuntando a
+// él: uno ya prescrito solo puede archivarse
+pub fn eliminar_medicamento<D, P>(
+    medicamentos: &mut MedicamentoService<D>,
+    prescripciones: &PrescripcionService<P>,
+    id_medicamento: Uuid,
+    esperada: &VersionEsperada,
+) -> Result<(), DomainError>
+where
+    D: MedicamentoRepository + ?Sized,
+    P: PrescripcionRepository + ?Sized,
+{
+    let actual = medicamentos.obtener_medicamento(id_medicamento)?
+        .ok_or_else(|| DomainError::NotFound(format!("El medicamento {} no existe", id_medicamento)))?;
+    esperada.comprobar(&format!("El medicamento {}", id_medicamento), actual.version)?;
+
+    let prescrito = prescripciones.contar_prescripciones_medicamento(id_medicamento)?;
+    if prescrito > 0 {
+        return Err(DomainError::Conflict(format!(
+            "El medicamento {} figura en {} prescripciones; solo puede archivarse",
+            id_medicamento, prescrito
+        )));
+    }
+
+    medicamentos.eliminar_medicamento(id_medicamento)
+}
+
+pub fn eliminar_especie<E, M, D>(
+    especies: &mut EspecieService<E>,
+    mascotas: &MascotaService<M>,
+    medicamentos: &MedicamentoService<D>,
+    id_especie: Uuid,
+    esperada: &VersionEsperada,
+) -> Result<(), DomainError>
+where
+    E: EspecieRepository + ?Sized,
+    M: MascotaRepository + ?Sized,
+    D: MedicamentoRepository + ?Sized,
+{
+    let actual = especies.obtener_especie(id_especie)?
+        .ok_or_else(|| DomainError::NotFound(format!("La especie {} no existe", id_especie)))?;
+    esperada.comprobar(&format!("La especie {}", id_especie), actual.version)?;
+
+    let registradas = mascotas.todas_las_mascotas()?
+        .iter()
+        .filter(|m| m.especie == actual.codigo)
+        .count();
+    if registradas > 0 {
+        return Err(DomainError::Conflict(format!(
+            "La especie {} tiene {} mascotas registradas; solo puede archivarse",
+            id_especie, registradas
+        )));
+    }
+    let con_dosis = medicamentos.todos_los_medicamentos()?
+        .iter()
+        .filter(|m| m.rango_para(&actual.codigo).is_some())
+        .count();
+    if con_dosis > 0 {
+        return Err(DomainError::Conflict(format!(
+            "La especie {} tiene dosis en {} medicamentos; solo puede archivarse",
+            id_especie, con_dosis
+        )));
+    }
+
+    especies.eliminar_especie(id_especie)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Personal, SignosVitales};
+    use crate::models::personal::RolPersonal;
+    use crate::repositories::cliente_repository::{ConsultaClientes, InMemoryClienteRepository};
+    use crate::repositories::mascota_repository::InMemoryMascotaRepository;
+    use crate::repositories::historia_clinica_repository::InMemoryHistoriaClinicaRepository;
+    use crate::repositories::turno_repository::InMemoryTurnoRepository;
+    use crate::repositories::vacunacion_repository::InMemoryVacunacionRepository;
+    use crate::repositories::prescripcion_repository::InMemoryPrescripcionRepository;
+    use chrono::{TimeZone, Utc};
+
+    struct Servicios {
+        clientes: ClienteService<InMemoryClienteRepository>,
+        mascotas: MascotaService<InMemoryMascotaRepository>,
+        historias: HistoriaClinicaService<InMemoryHistoriaClinicaRepository>,
+        turnos: TurnoService<InMemoryTurnoRepository>,
+        vacunaciones: VacunacionService<InMemoryVacunacionRepository>,
+        prescripciones: PrescripcionService<InMemoryPrescripcionRepository>,
+    }
+
+    // Un cliente con una mascota, su historia con un borrador y un turno
+    struct Escenario {
+        id_cliente: Uuid,
+        id_mascota: Uuid,
+        id_historia: Uuid,
+        id_entrada: Uuid,
+    }
+
+    impl Servicios {
+        fn new() -> Self {
+            Self {
+                clientes: ClienteService::new(Box::new(InMemoryClienteRepository::new())),
+                mascotas: MascotaService::new(Box::new(InMemoryMascotaRepository::new())),
+                historias: HistoriaClinicaService::new(Box::new(InMemoryHistoriaClinicaRepository::new())),
+                turnos: TurnoService::new(Box::new(InMemoryTurnoRepository::new())),
+                vacunaciones: VacunacionService::new(Box::new(InMemoryVacunacionRepository::new())),
+                prescripciones: PrescripcionService::new(Box::new(InMemoryPrescripcionRepository::new())),
+            }
+        }
+
+        fn escenario(&mut self) -> Escenario {
+            let id_clinica = Uuid::new_v4();
+            let cliente = self.clientes.crear_cliente(
+                "Ana".to_string(), "Gomez".to_string(), "ana@example.com".to_string(),
+                "555-0000".to_string(), "Calle 1".to_string(), id_clinica, None,
+            ).unwrap();
+            let mascota = self.mascotas.crear_mascota(
+                "Firulais".to_string(), "perro".to_string(), "Mestizo".to_string(), None,
+                cliente.id, Vec::new(), Vec::new(), None,
+            ).unwrap();
+            let historia = self.historias.crear_historia(mascota.id, cliente.id, None).unwrap();
+            let entrada = self.historias.agregar_entrada(
+                historia.id, "Control".to_string(), "Sano".to_string(), "Ninguno".to_string(),
+                None, None, SignosVitales::default(), None,
+            ).unwrap();
+            self.turnos.crear_turno(
+                id_clinica, mascota.id, cliente.id, Uuid::new_v4(),
+                Utc.with_ymd_and_hms(2025, 5, 12, 10, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2025, 5, 12, 10, 30, 0).unwrap(),
+                "Vacunación".to_string(), None,
+            ).unwrap();
+            Escenario { id_cliente: cliente.id, id_mascota: mascota.id, id_historia: historia.id, id_entrada: entrada.id }
+        }
+
+        fn eliminar_cliente(&mut self, id_cliente: Uuid, cascada: bool, esperada: &VersionEsperada) -> Result<(), DomainError> {
+            eliminar_cliente(
+                &mut self.clientes,
+                &mut self.mascotas,
+                &mut self.historias,
+                &mut self.turnos,
+                &mut self.vacunaciones,
+                &mut self.prescripciones,
+                id_cliente,
+                cascada,
+                esperada,
+            )
+        }
+
+        // Comprueba que no se borró nada del escenario
+        fn sigue_completo(&self, escenario: &Escenario) {
+            assert!(self.clientes.obtener_cliente(escenario.id_cliente).unwrap().is_some());
+            assert!(self.mascotas.obtener_mascota(escenario.id_mascota).unwrap().is_some());
+            assert_eq!(self.historias.obtener_entradas(escenario.id_historia).unwrap().len(), 1);
+            assert_eq!(self.turnos.ids_turnos(&FiltroTurnos::default()).unwrap().len(), 1);
+        }
+    }
+
+    fn veterinario() -> Personal {
+        Personal::new(
+            "Luis".to_string(),
+            "Pérez".to_string(),
+            RolPersonal::Veterinario,
+            Some("MP 1234".to_string()),
+            Vec::new(),
+            Vec::new(),
+            "luis@clinica.com".to_string(),
+            "+5491122334455".to_string(),
+            None,
+        )
+    }
+
+    #[test]
+    fn sin_cascada_rechaza_un_cliente_con_dependientes() {
+        let mut servicios = Servicios::new();
+        let escenario = servicios.escenario();
+
+        assert!(matches!(
+            servicios.eliminar_cliente(escenario.id_cliente, false, &VersionEsperada::Cualquiera),
+            Err(DomainError::Conflict(_))
+        ));
+        servicios.sigue_completo(&escenario);
+    }
+
+    #[test]
+    fn la_cascada_elimina_mascotas_historias_y_turnos() {
+        let mut servicios = Servicios::new();
+        let escenario = servicios.escenario();
+
+        servicios.eliminar_cliente(escenario.id_cliente, true, &VersionEsperada::Cualquiera).unwrap();
+
+        assert!(servicios.clientes.obtener_cliente(escenario.id_cliente).unwrap().is_none());
+        assert!(servicios.mascotas.obtener_mascota(escenario.id_mascota).unwrap().is_none());
+        assert!(servicios.historias.obtener_historia(escenario.id_historia).unwrap().is_none());
+        assert!(servicios.turnos.ids_turnos(&FiltroTurnos::default()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn una_entrada_firmada_impide_la_cascada_sin_borrar_nada() {
+        let mut servicios = Servicios::new();
+        let escenario = servicios.escenario();
+        servicios.historias.firmar_entrada(
+            escenario.id_historia, escenario.id_entrada, &VersionEsperada::Cualquiera, &veterinario(), None,
+        ).unwrap();
+
+        assert!(matches!(
+            servicios.eliminar_cliente(escenario.id_cliente, true, &VersionEsperada::Cualquiera),
+            Err(DomainError::Conflict(_))
+        ));
+        servicios.sigue_completo(&escenario);
+    }
+
+    #[test]
+    fn comprueba_la_version_antes_de_eliminar() {
+        let mut servicios = Servicios::new();
+        let escenario = servicios.escenario();
+
+        assert!(matches!(
+            servicios.eliminar_cliente(escenario.id_cliente, true, &VersionEsperada::Alguna(vec![7])),
+            Err(DomainError::PreconditionFailed(_))
+        ));
+        servicios.sigue_completo(&escenario);
+    }
+
+    #[test]
+    fn archivar_oculta_al_cliente_sin_eliminarlo() {
+        let mut servicios = Servicios::new();
+        let escenario = servicios.escenario();
+        let id_clinica = servicios.clientes.obtener_cliente(escenario.id_cliente).unwrap().unwrap().id_clinica;
+        let consulta = ConsultaClientes {
+            filtro: Default::default(),
+            orden: Default::default(),
+            direccion: Default::default(),
+            pagina: 1,
+            por_pagina: 10,
+        };
+
+        let archivado = servicios.clientes
+            .archivar_cliente(escenario.id_cliente, &VersionEsperada::Cualquiera, None)
+            .unwrap();
+        assert!(archivado.archivado && archivado.fecha_archivado.is_some());
+        assert_eq!(servicios.clientes.listar_clientes(&consulta).unwrap().total, 0);
+        // Sigue contando como dependiente de su clínica
+        assert_eq!(servicios.clientes.ids_clientes_clinica(id_clinica).unwrap(), [escenario.id_cliente]);
+        servicios.sigue_completo(&escenario);
+
+        servicios.clientes.restaurar_cliente(escenario.id_cliente, None).unwrap();
+        assert_eq!(servicios.clientes.listar_clientes(&consulta).unwrap().total, 1);
+    }
+}
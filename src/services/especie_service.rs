@@ -0,0 +1,411 @@
+use crate::models::{Auditado, Especie};
+use crate::models::especie::{Etiquetas, Raza, IDIOMA_PREDETERMINADO};
+use crate::repositories::especie_repository::{ConsultaEspecies, EspecieRepository};
+use crate::repositories::consulta::Pagina;
+use crate::repositories::busqueda::terminos;
+use uuid::Uuid;
+use crate::error::{DomainError, FieldError};
+use crate::services::concurrencia::VersionEsperada;
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::HashMap;
+
+// Forma en que se comparan códigos, etiquetas y alias: sin distinguir
+// mayúsculas ni acentos, y con cualquier separador ("Pastor Alemán" y
+// "pastor_aleman" dan "pastor aleman")
+pub fn clave(texto: &str) -> String {
+    terminos(texto).join(" ")
+}
+
+// Todo lo que identifica a una especie o a una raza, con el campo del que sale
+fn claves<'a>(
+    prefijo: &str,
+    codigo: &'a str,
+    etiquetas: &'a Etiquetas,
+    alias: &'a [String],
+) -> Vec<(String, &'a str)> {
+    let mut claves = vec![(format!("{}codigo", prefijo), codigo)];
+    claves.extend(etiquetas.iter().map(|(idioma, etiqueta)| (format!("{}etiquetas.{}", prefijo, idioma), etiqueta.as_str())));
+    claves.extend(alias.iter().enumerate().map(|(i, alias)| (format!("{}alias[{}]", prefijo, i), alias.as_str())));
+    claves
+}
+
+fn identifica(codigo: &str, etiquetas: &Etiquetas, alias: &[String], buscada: &str) -> bool {
+    claves("", codigo, etiquetas, alias).into_iter().any(|(_, texto)| clave(texto) == buscada)
+}
+
+// Minúsculas ASCII, dígitos y guiones bajos
+fn codigo_valido(codigo: &str) -> bool {
+    !codigo.is_empty()
+        && codigo.len() <= 40
+        && codigo.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+// Código de idioma en minúsculas, con región opcional ("es", "pt-br")
+fn idioma_valido(idioma: &str) -> bool {
+    let mut partes = idioma.split('-');
+    let letras = |parte: &str| (2..=3).contains(&parte.len()) && parte.chars().all(|c| c.is_ascii_lowercase());
+    partes.next().is_some_and(letras) && partes.all(letras)
+}
+
+// Datos de una especie del catálogo, para el alta y el reemplazo
+#[derive(Debug)]
+pub struct DatosEspecie {
+    pub codigo: String,
+    pub etiquetas: Etiquetas,
+    pub alias: Vec<String>,
+    pub razas: Vec<Raza>,
+}
+
+impl DatosEspecie {
+    // Códigos con formato válido, etiqueta en el idioma predeterminado y
+    // ningún nombre repetido: ni entre las razas de la especie ni con otra
+    // especie del catálogo
+    fn validar(&self, otras: &[Especie]) -> Result<(), DomainError> {
+        let mut errores = Vec::new();
+        let mut error = |campo: String, regla: &str, mensaje: String| errores.push(FieldError {
+            campo,
+            regla: regla.to_string(),
+            mensaje,
+        });
+
+        let mut validar_entrada = |prefijo: &str, codigo: &str, etiquetas: &Etiquetas, alias: &[String]| {
+            if !codigo_valido(codigo) {
+                error(
+                    format!("{}codigo", prefijo),
+                    "format",
+                    "debe tener hasta 40 letras minúsculas sin acentos, dígitos o guiones bajos".to_string(),
+                );
+            }
+            if !etiquetas.contains_key(IDIOMA_PREDETERMINADO) {
+                error(
+                    format!("{}etiquetas", prefijo),
+                    "required",
+                    format!("debe incluir la etiqueta en '{}'", IDIOMA_PREDETERMINADO),
+                );
+            }
+            for idioma in etiquetas.keys().filter(|idioma| !idioma_valido(idioma)) {
+                error(
+                    format!("{}etiquetas.{}", prefijo, idioma),
+                    "format",
+                    "el idioma debe ser un código como 'es', 'en' o 'pt-br'".to_string(),
+                );
+            }
+            // El código ya se validó arriba
+            for (campo, texto) in claves(prefijo, codigo, etiquetas, alias).into_iter().skip(1) {
+                if clave(texto).is_empty() {
+                    error(campo, "required", "es obligatorio".to_string());
+                }
+            }
+        };
+
+        validar_entrada("", &self.codigo, &self.etiquetas, &self.alias);
+        for (indice, raza) in self.razas.iter().enumerate() {
+            validar_entrada(&format!("razas[{}].", indice), &raza.codigo, &raza.etiquetas, &raza.alias);
+        }
+
+        // Un mismo nombre puede repetirse dentro de una especie o una raza
+        // ("Gato" como etiqueta en español y en portugués), no entre dos.
+        // Las razas solo se comparan con las de su especie.
+        let mut duenos: HashMap<String, String> = HashMap::new();
+        for (campo, texto) in claves("", &self.codigo, &self.etiquetas, &self.alias) {
+            let buscada = clave(texto);
+            if buscada.is_empty() {
+                continue;
+            }
+            if let Some(otra) = otras.iter().find(|e| identifica(&e.codigo, &e.etiquetas, &e.alias, &buscada)) {
+                error(campo, "unique", format!("ya identifica a la especie {}", otra.codigo));
+            } else {
+                duenos.insert(buscada, String::new());
+            }
+        }
+        for (indice, raza) in self.razas.iter().enumerate() {
+            let prefijo = format!("razas[{}].", indice);
+            for (campo, texto) in claves(&prefijo, &raza.codigo, &raza.etiquetas, &raza.alias) {
+                let buscada = clave(texto);
+                match duenos.get(&buscada) {
+                    _ if buscada.is_empty() => {}
+                    Some(dueno) if *dueno != prefijo => {
+                        error(campo, "unique", "ya identifica a otra raza o a la especie".to_string());
+                    }
+                    Some(_) => {}
+                    None => {
+                        duenos.insert(buscada, prefijo.clone());
+                    }
+                }
+            }
+        }
+
+        if errores.is_empty() {
+            Ok(())
+        } else {
+            Err(DomainError::InvalidFields(errores))
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct OpcionRaza {
+    pub codigo: String,
+    pub etiqueta: String,
+}
+
+// Especie vigente con sus razas, en un idioma, para las listas desplegables
+#[derive(Debug, Serialize)]
+pub struct OpcionEspecie {
+    pub codigo: String,
+    pub etiqueta: String,
+    pub razas: Vec<OpcionRaza>,
+}
+
+// Por qué un texto no corresponde al catálogo
+#[derive(Debug)]
+pub enum SinResolver {
+    Especie,
+    Raza(String),
+}
+
+pub struct EspecieService<T: EspecieRepository + ?Sized> {
+    repository: Box<T>,
+}
+
+impl<T: EspecieRepository + ?Sized> EspecieService<T> {
+    pub fn new(repository: Box<T>) -> Self {
+        Self { repository }
+    }
+
+    fn otras(&self, id: Option<Uuid>) -> Result<Vec<Especie>, DomainError> {
+        Ok(self.repository.listar_todas()?
+            .into_iter()
+            .filter(|e| Some(e.id) != id)
+            .collect())
+    }
+
+    pub fn crear_especie(&mut self, datos: DatosEspecie, autor: Option<&str>) -> Result<Especie, DomainError> {
+        datos.validar(&self.otras(None)?)?;
+
+        let especie = Especie::new(datos.codigo, datos.etiquetas, datos.alias, datos.razas, autor);
+        self.repository.guardar(especie.clone())?;
+        Ok(especie)
+    }
+
+    pub fn obtener_especie(&self, id: Uuid) -> Result<Option<Especie>, DomainError> {
+        self.repository.obtener(id)
+    }
+
+    pub fn listar_especies(&self, consulta: &ConsultaEspecies) -> Result<Pagina<Especie>, DomainError> {
+        self.repository.listar(consulta)
+    }
+
+    // El código no cambia: es lo que guardan las mascotas
+    pub fn actualizar_especie(
+        &mut self,
+        id: Uuid,
+        datos: DatosEspecie,
+        esperada: &VersionEsperada,
+        autor: Option<&str>,
+    ) -> Result<Especie, DomainError> {
+        let actual = self.repository.obtener(id)?
+            .ok_or_else(|| DomainError::NotFound(format!("La especie {} no existe", id)))?;
+        esperada.comprobar(&format!("La especie {}", id), actual.version)?;
+        if datos.codigo != actual.codigo {
+            return Err(DomainError::Conflict(format!(
+                "El código de la especie {} no puede cambiarse (es '{}')", id, actual.codigo
+            )));
+        }
+        datos.validar(&self.otras(Some(id))?)?;
+
+        let mut especie = Especie {
+            etiquetas: datos.etiquetas,
+            alias: datos.alias,
+            razas: datos.razas,
+            ..actual
+        };
+        especie.registrar_cambio(autor);
+
+        self.repository.guardar(especie.clone())?;
+        Ok(especie)
+    }
+
+    pub fn archivar_especie(
+        &mut self,
+        id: Uuid,
+        esperada: &VersionEsperada,
+        autor: Option<&str>,
+    ) -> Result<Especie, DomainError> {
+        let especie = self.repository.obtener(id)?
+            .ok_or_else(|| DomainError::NotFound(format!("La especie {} no existe", id)))?;
+        esperada.comprobar(&format!("La especie {}", id), especie.version)?;
+
+        let mut especie_archivada = Especie {
+            archivado: true,
+            fecha_archivado: Some(Utc::now()),
+            ..especie
+        };
+        especie_archivada.registrar_cambio(autor);
+
+        self.repository.guardar(especie_archivada.clone())?;
+        Ok(especie_archivada)
+    }
+
+    pub fn restaurar_especie(&mut self, id: Uuid, autor: Option<&str>) -> Result<Especie, DomainError> {
+        let especie = self.repository.obtener(id)?
+            .ok_or_else(|| DomainError::NotFound(format!("La especie {} no existe", id)))?;
+
+        let mut especie_restaurada = Especie {
+            archivado: false,
+            fecha_archivado: None,
+            ..especie
+        };
+        especie_restaurada.registrar_cambio(autor);
+
+        self.repository.guardar(especie_restaurada.clone())?;
+        Ok(especie_restaurada)
+    }
+
+    pub fn eliminar_especie(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.repository.eliminar(id)
+    }
+
+    // Especies vigentes ordenadas por su etiqueta en el idioma (sin distinguir
+    // acentos, para que "Hámster" vaya antes que "Hurón"), cada una con sus
+    // razas también ordenadas
+    pub fn opciones(&self, idioma: &str) -> Result<Vec<OpcionEspecie>, DomainError> {
+        let mut opciones: Vec<OpcionEspecie> = self.repository.listar_todas()?
+            .into_iter()
+            .filter(|e| !e.archivado)
+            .map(|e| {
+                let mut razas: Vec<OpcionRaza> = e.razas.iter()
+                    .map(|r| OpcionRaza { codigo: r.codigo.clone(), etiqueta: r.etiqueta(idioma).to_string() })
+                    .collect();
+                razas.sort_by_cached_key(|r| clave(&r.etiqueta));
+                OpcionEspecie {
+                    codigo: e.codigo.clone(),
+                    etiqueta: e.etiqueta(idioma).to_string(),
+                    razas,
+                }
+            })
+            .collect();
+        opciones.sort_by_cached_key(|e| clave(&e.etiqueta));
+        Ok(opciones)
+    }
+
+    fn especie_vigente(&self, especie: &str) -> Result<Option<Especie>, DomainError> {
+        let buscada = clave(especie);
+        Ok(self.repository.listar_todas()?
+            .into_iter()
+            .find(|e| !e.archivado && identifica(&e.codigo, &e.etiquetas, &e.alias, &buscada)))
+    }
+
+    // Código de la especie vigente que nombra el texto, sin mirar razas
+    pub fn codigo_especie(&self, especie: &str) -> Result<Option<String>, DomainError> {
+        Ok(self.especie_vigente(especie)?.map(|e| e.codigo))
+    }
+
+    // Códigos de la especie y la raza que nombran los textos, por código,
+    // etiqueta en cualquier idioma o alias. Solo cuentan las especies vigentes;
+    // si la especie no tiene razas, la raza queda como vino. El error externo
+    // es el del almacenamiento; el interno, lo que no se pudo resolver.
+    pub fn identificar(&self, especie: &str, raza: &str) -> Result<Result<(String, String), SinResolver>, DomainError> {
+        let Some(especie) = self.especie_vigente(especie)? else {
+            return Ok(Err(SinResolver::Especie));
+        };
+
+        if especie.razas.is_empty() {
+            return Ok(Ok((especie.codigo, raza.to_string())));
+        }
+        let buscada = clave(raza);
+        Ok(especie.razas.iter()
+            .find(|r| identifica(&r.codigo, &r.etiquetas, &r.alias, &buscada))
+            .map(|r| (especie.codigo.clone(), r.codigo.clone()))
+            .ok_or(SinResolver::Raza(especie.codigo)))
+    }
+
+    // Como `identificar`, con el error por campo que corresponde a un DTO
+    pub fn resolver(&self, especie: &str, raza: &str) -> Result<(String, String), DomainError> {
+        self.identificar(especie, raza)?.map_err(|sin_resolver| {
+            let (campo, mensaje) = match sin_resolver {
+                SinResolver::Especie => (
+                    "especie",
+                    "no corresponde a ninguna especie del catálogo (ver /especies/opciones)".to_string(),
+                ),
+                SinResolver::Raza(codigo) => (
+                    "raza",
+                    format!("no corresponde a ninguna raza de la especie {} en el catálogo", codigo),
+                ),
+            };
+            DomainError::InvalidFields(vec![FieldError {
+                campo: campo.to_string(),
+                regla: "catalog".to_string(),
+                mensaje,
+            }])
+        })
+    }
+
+    // Carga el catálogo de referencia si está vacío (primer arranque)
+    pub fn cargar_catalogo_inicial(&mut self) -> Result<(), DomainError> {
+        if !self.repository.listar_todas()?.is_empty() {
+            return Ok(());
+        }
+        for datos in catalogo_inicial() {
+            self.crear_especie(datos, None)?;
+        }
+        Ok(())
+    }
+}
+
+fn etiquetas(es: &str, en: &str, pt: &str) -> Etiquetas {
+    [("es", es), ("en", en), ("pt", pt)]
+        .into_iter()
+        .map(|(idioma, etiqueta)| (idioma.to_string(), etiqueta.to_string()))
+        .collect()
+}
+
+fn lista(textos: &[&str]) -> Vec<String> {
+    textos.iter().map(|t| t.to_string()).collect()
+}
+
+fn raza(codigo: &str, es: &str, en: &str, pt: &str, alias: &[&str]) -> Raza {
+    Raza { codigo: codigo.to_string(), etiquetas: etiquetas(es, en, pt), alias: lista(alias) }
+}
+
+fn especie(codigo: &str, es: &str, en: &str, pt: &str, alias: &[&str], razas: Vec<Raza>) -> DatosEspecie {
+    DatosEspecie { codigo: codigo.to_string(), etiquetas: etiquetas(es, en, pt), alias: lista(alias), razas }
+}
+
+// Especies y razas habituales en la clínica; se amplían desde `/especies`
+fn catalogo_inicial() -> Vec<DatosEspecie> {
+    vec![
+        especie("perro", "Perro", "Dog", "Cachorro", &["canino", "can", "perra", "canina"], vec![
+            raza("mestizo", "Mestizo", "Mixed breed", "Sem raça definida", &["mestiza", "cruza", "criollo", "sin raza", "mixed", "srd"]),
+            raza("labrador_retriever", "Labrador Retriever", "Labrador Retriever", "Labrador Retriever", &["labrador"]),
+            raza("golden_retriever", "Golden Retriever", "Golden Retriever", "Golden Retriever", &["golden"]),
+            raza("pastor_aleman", "Pastor Alemán", "German Shepherd", "Pastor-alemão", &["ovejero", "ovejero aleman"]),
+            raza("caniche", "Caniche", "Poodle", "Poodle", &["caniche toy"]),
+            raza("bulldog_frances", "Bulldog Francés", "French Bulldog", "Buldogue francês", &[]),
+            raza("beagle", "Beagle", "Beagle", "Beagle", &[]),
+            raza("chihuahua", "Chihuahua", "Chihuahua", "Chihuahua", &[]),
+            raza("yorkshire_terrier", "Yorkshire Terrier", "Yorkshire Terrier", "Yorkshire Terrier", &["yorkshire", "yorki"]),
+            raza("dachshund", "Dachshund", "Dachshund", "Dachshund", &["salchicha", "teckel"]),
+            raza("border_collie", "Border Collie", "Border Collie", "Border Collie", &[]),
+            raza("boxer", "Bóxer", "Boxer", "Boxer", &[]),
+        ]),
+        especie("gato", "Gato", "Cat", "Gato", &["felino", "gata", "felina"], vec![
+            raza("mestizo", "Mestizo", "Mixed breed", "Sem raça definida", &["mestiza", "comun europeo", "domestico", "comun", "sin raza", "mixed", "srd"]),
+            raza("siames", "Siamés", "Siamese", "Siamês", &[]),
+            raza("persa", "Persa", "Persian", "Persa", &[]),
+            raza("maine_coon", "Maine Coon", "Maine Coon", "Maine Coon", &[]),
+            raza("bengali", "Bengalí", "Bengal", "Bengal", &[]),
+            raza("esfinge", "Esfinge", "Sphynx", "Sphynx", &["sin pelo"]),
+        ]),
+        especie("conejo", "Conejo", "Rabbit", "Coelho", &["coneja"], vec![
+            raza("mestizo", "Mestizo", "Mixed breed", "Sem raça definida", &["mestiza", "cruza", "sin raza", "mixed", "srd"]),
+            raza("belier", "Belier", "Lop", "Lop", &["lop", "holland lop"]),
+            raza("cabeza_de_leon", "Cabeza de león", "Lionhead", "Cabeça de leão", &[]),
+            raza("rex", "Rex", "Rex", "Rex", &[]),
+        ]),
+        especie("huron", "Hurón", "Ferret", "Furão", &[], Vec::new()),
+        especie("cobayo", "Cobayo", "Guinea pig", "Porquinho-da-índia", &["cobaya", "cuy", "cuyo", "conejillo de indias"], Vec::new()),
+        especie("hamster", "Hámster", "Hamster", "Hamster", &[], Vec::new()),
+    ]
+}
@@ -1,35 +1,171 @@
-use crate::models::{HistoriaClinica, EntradaHistoriaClinica};
-use crate::repositories::historia_clinica_repository::HistoriaClinicaRepository;
+use crate::models::{Auditado, HistoriaClinica, EntradaHistoriaClinica, Personal, SignosVitales};
+use crate::models::entrada_historia_clinica::EstadoEntrada;
+use crate::repositories::historia_clinica_repository::{entrada_firmada, ConsultaEntradas, HistoriaClinicaRepository};
+use crate::repositories::consulta::Pagina;
 use uuid::Uuid;
-use chrono::Utc;
+use crate::error::DomainError;
+use crate::services::concurrencia::VersionEsperada;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashSet;
 
-pub struct HistoriaClinicaService<T: HistoriaClinicaRepository> {
-    repository: T,
+// Resultado de recalcular el hash de una entrada firmada
+#[derive(Debug, Serialize)]
+pub struct VerificacionFirma {
+    pub id_entrada: Uuid,
+    pub id_firmante: Option<Uuid>,
+    pub firmada_por: Option<String>,
+    pub fecha_firma: Option<DateTime<Utc>>,
+    pub hash_registrado: Option<String>,
+    pub hash_calculado: String,
+    // El contenido actual es el mismo que se firmó
+    pub integra: bool,
 }
 
-impl<T: HistoriaClinicaRepository> HistoriaClinicaService<T> {
-    pub fn new(repository: T) -> Self {
+fn entrada_legado(id_entrada: Uuid) -> DomainError {
+    DomainError::Conflict(format!(
+        "La entrada {} se cargó antes de la firma de entradas: no tiene firma", id_entrada
+    ))
+}
+
+pub struct HistoriaClinicaService<T: HistoriaClinicaRepository + ?Sized> {
+    repository: Box<T>,
+}
+
+impl<T: HistoriaClinicaRepository + ?Sized> HistoriaClinicaService<T> {
+    pub fn new(repository: Box<T>) -> Self {
         Self { repository }
     }
 
+    // Cada mascota tiene una única historia clínica, aunque esté archivada
     pub fn crear_historia(
         &mut self,
         id_mascota: Uuid,
         id_cliente: Uuid,
-    ) -> Result<HistoriaClinica, String> {
-        let historia = HistoriaClinica::new(id_mascota, id_cliente);
+        autor: Option<&str>,
+    ) -> Result<HistoriaClinica, DomainError> {
+        if let Some(existente) = self.repository.obtener_por_mascota(id_mascota)? {
+            return Err(Self::error_duplicada(&existente));
+        }
+
+        let historia = HistoriaClinica::new(id_mascota, id_cliente, autor);
         self.repository.guardar(historia.clone())?;
         Ok(historia)
     }
 
-    pub fn obtener_historia(&self, id: Uuid) -> Option<&HistoriaClinica> {
+    // Devuelve la historia de la mascota creándola si no existe; el booleano
+    // indica si fue creada en esta llamada.
+    pub fn obtener_o_crear_historia(
+        &mut self,
+        id_mascota: Uuid,
+        id_cliente: Uuid,
+        autor: Option<&str>,
+    ) -> Result<(HistoriaClinica, bool), DomainError> {
+        match self.repository.obtener_por_mascota(id_mascota)? {
+            Some(existente) if existente.archivado => Err(Self::error_duplicada(&existente)),
+            Some(existente) => Ok((existente, false)),
+            None => self.crear_historia(id_mascota, id_cliente, autor).map(|h| (h, true)),
+        }
+    }
+
+    // Unifica las historias duplicadas de una mascota en la más antigua: sus
+    // entradas pasan a la historia conservada y las demás se eliminan. Una
+    // historia con entradas firmadas solo puede archivarse, así que no se fusiona.
+    // Todo se revisa antes de cambiar nada, y las sobrantes se eliminan recién
+    // cuando no les quedan entradas: si algo falla a mitad de camino, no se
+    // pierde nada y la fusión puede repetirse.
+    pub fn fusionar_historias_mascota(
+        &mut self,
+        id_mascota: Uuid,
+        autor: Option<&str>,
+    ) -> Result<HistoriaClinica, DomainError> {
+        let mut historias = self.repository.listar_por_mascota(id_mascota)?.into_iter();
+        let destino = historias.next().ok_or_else(|| {
+            DomainError::NotFound(format!("La mascota {} no tiene historia clínica", id_mascota))
+        })?;
+        let origenes: Vec<HistoriaClinica> = historias.collect();
+        if origenes.is_empty() {
+            return Ok(destino);
+        }
+
+        for origen in &origenes {
+            let firmadas = self.contar_entradas_firmadas(origen.id)?;
+            if firmadas > 0 {
+                return Err(DomainError::Conflict(format!(
+                    "La historia clínica {} tiene {} entradas firmadas; solo puede archivarse",
+                    origen.id, firmadas
+                )));
+            }
+        }
+
+        let mut fusionada = destino.clone();
+        // Basta con una historia activa para que la fusionada siga activa
+        if origenes.iter().any(|origen| !origen.archivado) {
+            fusionada.archivado = false;
+            fusionada.fecha_archivado = None;
+        }
+        fusionada.registrar_cambio(autor);
+        self.repository.guardar(fusionada.clone())?;
+
+        for origen in &origenes {
+            self.repository.reasignar_entradas(origen.id, destino.id)?;
+        }
+        for origen in &origenes {
+            self.repository.eliminar(origen.id)?;
+        }
+        Ok(fusionada)
+    }
+
+    fn error_duplicada(existente: &HistoriaClinica) -> DomainError {
+        let mut motivo = format!(
+            "La mascota {} ya tiene la historia clínica {}",
+            existente.id_mascota, existente.id
+        );
+        if existente.archivado {
+            motivo.push_str(" (archivada; puede restaurarse)");
+        }
+        DomainError::Conflict(motivo)
+    }
+
+    pub fn obtener_historia(&self, id: Uuid) -> Result<Option<HistoriaClinica>, DomainError> {
         self.repository.obtener(id)
     }
 
-    pub fn obtener_historia_mascota(&self, id_mascota: Uuid) -> Option<&HistoriaClinica> {
-        self.repository.obtener_por_mascota(id_mascota)
+    pub fn obtener_historia_mascota(&self, id_mascota: Uuid) -> Result<Option<HistoriaClinica>, DomainError> {
+        Ok(self.repository.obtener_por_mascota(id_mascota)?
+            .filter(|h| !h.archivado))
+    }
+
+    // Incluyen las archivadas: se usan para detectar dependientes antes de eliminar
+    pub fn ids_historias_mascota(&self, id_mascota: Uuid) -> Result<Vec<Uuid>, DomainError> {
+        Ok(self.repository.listar_por_mascota(id_mascota)?
+            .into_iter()
+            .map(|h| h.id)
+            .collect())
     }
 
+    pub fn historias_cliente(&self, id_cliente: Uuid) -> Result<Vec<HistoriaClinica>, DomainError> {
+        self.repository.listar_por_cliente(id_cliente)
+    }
+
+    // La historia acompaña a la mascota cuando cambia de dueño
+    pub fn reasignar_cliente(
+        &mut self,
+        id_mascota: Uuid,
+        id_cliente: Uuid,
+        autor: Option<&str>,
+    ) -> Result<(), DomainError> {
+        for mut historia in self.repository.listar_por_mascota(id_mascota)? {
+            if historia.id_cliente != id_cliente {
+                historia.id_cliente = id_cliente;
+                historia.registrar_cambio(autor);
+                self.repository.guardar(historia)?;
+            }
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn agregar_entrada(
         &mut self,
         id_historia: Uuid,
@@ -37,11 +173,12 @@ impl<T: HistoriaClinicaRepository> HistoriaClinicaService<T> {
         diagnostico: String,
         tratamiento: String,
         notas: Option<String>,
-    ) -> Result<EntradaHistoriaClinica, String> {
-        // Verificar que la historia existe
-        if self.repository.obtener(id_historia).is_none() {
-            return Err("La historia clínica no existe".to_string());
-        }
+        id_veterinario: Option<Uuid>,
+        signos_vitales: SignosVitales,
+        autor: Option<&str>,
+    ) -> Result<EntradaHistoriaClinica, DomainError> {
+        let mut historia = self.repository.obtener(id_historia)?
+            .ok_or_else(|| DomainError::NotFound(format!("La historia clínica {} no existe", id_historia)))?;
 
         let entrada = EntradaHistoriaClinica::new(
             id_historia,
@@ -49,13 +186,532 @@ impl<T: HistoriaClinicaRepository> HistoriaClinicaService<T> {
             diagnostico,
             tratamiento,
             notas,
+            id_veterinario,
+            signos_vitales,
+            autor,
         );
 
         self.repository.agregar_entrada(entrada.clone())?;
+        // Una entrada nueva también cuenta como modificación de la historia
+        historia.registrar_cambio(autor);
+        self.repository.guardar(historia)?;
+        Ok(entrada)
+    }
+
+    pub fn obtener_entrada(&self, id_historia: Uuid, id_entrada: Uuid) -> Result<EntradaHistoriaClinica, DomainError> {
+        self.repository.obtener_entrada(id_entrada)?
+            .filter(|e| e.id_historia_clinica == id_historia)
+            .ok_or_else(|| DomainError::NotFound(format!(
+                "La entrada {} no existe en la historia clínica {}", id_entrada, id_historia
+            )))
+    }
+
+    // Deshace `agregar_entrada` cuando falla lo que venía después; la historia
+    // conserva la versión
+    pub fn descartar_borrador(&mut self, id_historia: Uuid, id_entrada: Uuid) -> Result<(), DomainError> {
+        self.obtener_entrada(id_historia, id_entrada)?;
+        self.repository.eliminar_borrador(id_entrada)
+    }
+
+    // Sin la historia: las prescripciones solo guardan la entrada
+    pub fn obtener_entrada_por_id(&self, id_entrada: Uuid) -> Result<Option<EntradaHistoriaClinica>, DomainError> {
+        self.repository.obtener_entrada(id_entrada)
+    }
+
+    // Agrega una revisión que reemplaza a la entrada; la original no se modifica.
+    // Solo puede enmendarse la revisión vigente de cada entrada.
+    #[allow(clippy::too_many_arguments)]
+    pub fn enmendar_entrada(
+        &mut self,
+        id_historia: Uuid,
+        id_entrada: Uuid,
+        descripcion: String,
+        diagnostico: String,
+        tratamiento: String,
+        notas: Option<String>,
+        id_veterinario: Option<Uuid>,
+        signos_vitales: SignosVitales,
+        motivo: String,
+        autor: Option<&str>,
+    ) -> Result<EntradaHistoriaClinica, DomainError> {
+        let mut historia = self.repository.obtener(id_historia)?
+            .ok_or_else(|| DomainError::NotFound(format!("La historia clínica {} no existe", id_historia)))?;
+        let entrada = self.obtener_entrada(id_historia, id_entrada)?;
+        if entrada.en_borrador() {
+            return Err(DomainError::Conflict(format!(
+                "La entrada {} es un borrador; modifíquela en lugar de enmendarla", id_entrada
+            )));
+        }
+        if let Some(enmienda) = self.repository.obtener_enmienda(id_entrada)? {
+            if enmienda.en_borrador() {
+                return Err(DomainError::Conflict(format!(
+                    "La entrada {} ya tiene la enmienda {} en borrador; modifíquela o descártela",
+                    id_entrada, enmienda.id
+                )));
+            }
+            return Err(DomainError::Conflict(format!(
+                "La entrada {} ya fue enmendada por {}; enmiende la revisión vigente",
+                id_entrada, enmienda.id
+            )));
+        }
+
+        // Sin veterinario, la revisión conserva el de la entrada enmendada
+        let id_veterinario = id_veterinario.or(entrada.id_veterinario);
+        let revision = entrada.enmendar(
+            descripcion, diagnostico, tratamiento, notas, id_veterinario, signos_vitales, motivo, autor,
+        );
+        self.repository.agregar_entrada(revision.clone())?;
+        historia.registrar_cambio(autor);
+        self.repository.guardar(historia)?;
+        Ok(revision)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn modificar_borrador(
+        &mut self,
+        id_historia: Uuid,
+        id_entrada: Uuid,
+        descripcion: String,
+        diagnostico: String,
+        tratamiento: String,
+        notas: Option<String>,
+        id_veterinario: Option<Uuid>,
+        signos_vitales: SignosVitales,
+        esperada: &VersionEsperada,
+        autor: Option<&str>,
+    ) -> Result<EntradaHistoriaClinica, DomainError> {
+        let mut historia = self.repository.obtener(id_historia)?
+            .ok_or_else(|| DomainError::NotFound(format!("La historia clínica {} no existe", id_historia)))?;
+        let mut entrada = self.obtener_entrada(id_historia, id_entrada)?;
+        esperada.comprobar(&format!("La entrada {}", id_entrada), entrada.version)?;
+        if !entrada.en_borrador() {
+            return Err(entrada_firmada(id_entrada));
+        }
+
+        entrada.descripcion = descripcion;
+        entrada.diagnostico = diagnostico;
+        entrada.tratamiento = tratamiento;
+        entrada.notas = notas;
+        entrada.id_veterinario = id_veterinario;
+        entrada.signos_vitales = signos_vitales;
+        entrada.registrar_cambio(autor);
+
+        self.repository.actualizar_borrador(entrada.clone())?;
+        historia.registrar_cambio(autor);
+        self.repository.guardar(historia)?;
+        Ok(entrada)
+    }
+
+    // El firmante es un veterinario del directorio, ya validado, y debe ser el
+    // que atendió; si la entrada no lo indica, pasa a serlo. Se guarda su id y
+    // su nombre, y después de firmar la entrada es de solo lectura.
+    pub fn firmar_entrada(
+        &mut self,
+        id_historia: Uuid,
+        id_entrada: Uuid,
+        esperada: &VersionEsperada,
+        firmante: &Personal,
+        autor: Option<&str>,
+    ) -> Result<EntradaHistoriaClinica, DomainError> {
+        let mut historia = self.repository.obtener(id_historia)?
+            .ok_or_else(|| DomainError::NotFound(format!("La historia clínica {} no existe", id_historia)))?;
+        let mut entrada = self.obtener_entrada(id_historia, id_entrada)?;
+        esperada.comprobar(&format!("La entrada {}", id_entrada), entrada.version)?;
+        match entrada.estado {
+            EstadoEntrada::Borrador => {}
+            EstadoEntrada::Firmada => {
+                return Err(DomainError::Conflict(format!("La entrada {} ya está firmada", id_entrada)));
+            }
+            EstadoEntrada::Legado => return Err(entrada_legado(id_entrada)),
+        }
+        match entrada.id_veterinario {
+            Some(id_veterinario) if id_veterinario != firmante.id => {
+                return Err(DomainError::Validation(format!(
+                    "La entrada {} la atendió el veterinario {}; solo ese veterinario puede firmarla",
+                    id_entrada, id_veterinario
+                )));
+            }
+            Some(_) => {}
+            None => entrada.id_veterinario = Some(firmante.id),
+        }
+
+        entrada.registrar_cambio(autor);
+        entrada.firmar(firmante.id, &format!("{} {}", firmante.nombre, firmante.apellido));
+
+        self.repository.actualizar_borrador(entrada.clone())?;
+        historia.registrar_cambio(autor);
+        self.repository.guardar(historia)?;
         Ok(entrada)
     }
 
-    pub fn obtener_entradas(&self, id_historia: Uuid) -> Vec<&EntradaHistoriaClinica> {
+    pub fn verificar_firma(&self, id_historia: Uuid, id_entrada: Uuid) -> Result<VerificacionFirma, DomainError> {
+        let entrada = self.obtener_entrada(id_historia, id_entrada)?;
+        match entrada.estado {
+            EstadoEntrada::Firmada => {}
+            EstadoEntrada::Borrador => {
+                return Err(DomainError::Conflict(format!("La entrada {} todavía no está firmada", id_entrada)));
+            }
+            EstadoEntrada::Legado => return Err(entrada_legado(id_entrada)),
+        }
+
+        let hash_calculado = entrada.calcular_hash();
+        Ok(VerificacionFirma {
+            id_entrada,
+            id_firmante: entrada.id_firmante,
+            integra: entrada.hash_contenido.as_deref() == Some(hash_calculado.as_str()),
+            firmada_por: entrada.firmada_por,
+            fecha_firma: entrada.fecha_firma,
+            hash_registrado: entrada.hash_contenido,
+            hash_calculado,
+        })
+    }
+
+    // Todas las revisiones de la entrada, desde la original hasta la vigente
+    pub fn listar_revisiones(&self, id_historia: Uuid, id_entrada: Uuid) -> Result<Vec<EntradaHistoriaClinica>, DomainError> {
+        let entrada = self.obtener_entrada(id_historia, id_entrada)?;
+        self.repository.listar_revisiones(entrada.id_original.unwrap_or(entrada.id))
+    }
+
+    pub fn contar_entradas_veterinario(&self, id_veterinario: Uuid) -> Result<usize, DomainError> {
+        self.repository.contar_entradas_veterinario(id_veterinario)
+    }
+
+    pub fn obtener_entradas(&self, id_historia: Uuid) -> Result<Vec<EntradaHistoriaClinica>, DomainError> {
         self.repository.obtener_entradas(id_historia)
     }
+
+    // Firmadas o de legado: las que ya no son borradores
+    pub fn contar_entradas_firmadas(&self, id_historia: Uuid) -> Result<usize, DomainError> {
+        Ok(self.repository.obtener_entradas(id_historia)?
+            .iter()
+            .filter(|e| !e.en_borrador())
+            .count())
+    }
+
+    // Entradas sin las revisiones reemplazadas por una enmienda, por fecha
+    pub fn entradas_vigentes(&self, id_historia: Uuid) -> Result<Vec<EntradaHistoriaClinica>, DomainError> {
+        let mut entradas = self.repository.obtener_entradas(id_historia)?;
+        let enmendadas: HashSet<Uuid> = entradas.iter().filter_map(|e| e.reemplaza_a()).collect();
+        entradas.retain(|e| !enmendadas.contains(&e.id));
+        Ok(entradas)
+    }
+
+    // Peso de la consulta más reciente que lo registró
+    pub fn ultimo_peso(&self, id_historia: Uuid) -> Result<Option<(f64, DateTime<Utc>)>, DomainError> {
+        Ok(self.entradas_vigentes(id_historia)?
+            .iter()
+            .filter_map(|e| e.signos_vitales.peso_kg.map(|peso| (peso, e.fecha, e.fecha_creacion)))
+            .max_by_key(|(_, fecha, creacion)| (*fecha, *creacion))
+            .map(|(peso, fecha, _)| (peso, fecha)))
+    }
+
+    pub fn listar_entradas(
+        &self,
+        id_historia: Uuid,
+        consulta: &ConsultaEntradas,
+    ) -> Result<Pagina<EntradaHistoriaClinica>, DomainError> {
+        if self.repository.obtener(id_historia)?.is_none() {
+            return Err(DomainError::NotFound(format!("La historia clínica {} no existe", id_historia)));
+        }
+        self.repository.listar_entradas(id_historia, consulta)
+    }
+
+    pub fn archivar_historia(
+        &mut self,
+        id: Uuid,
+        esperada: &VersionEsperada,
+        autor: Option<&str>,
+    ) -> Result<HistoriaClinica, DomainError> {
+        let historia = self.repository.obtener(id)?
+            .ok_or_else(|| DomainError::NotFound(format!("La historia clínica {} no existe", id)))?;
+        esperada.comprobar(&format!("La historia clínica {}", id), historia.version)?;
+
+        let mut historia_archivada = HistoriaClinica {
+            archivado: true,
+            fecha_archivado: Some(Utc::now()),
+            ..historia
+        };
+        historia_archivada.registrar_cambio(autor);
+
+        self.repository.guardar(historia_archivada.clone())?;
+        Ok(historia_archivada)
+    }
+
+    pub fn restaurar_historia(&mut self, id: Uuid, autor: Option<&str>) -> Result<HistoriaClinica, DomainError> {
+        let historia = self.repository.obtener(id)?
+            .ok_or_else(|| DomainError::NotFound(format!("La historia clínica {} no existe", id)))?;
+
+        let mut historia_restaurada = HistoriaClinica {
+            archivado: false,
+            fecha_archivado: None,
+            ..historia
+        };
+        historia_restaurada.registrar_cambio(autor);
+
+        self.repository.guardar(historia_restaurada.clone())?;
+        Ok(historia_restaurada)
+    }
+
+    // Elimina la historia junto con sus entradas
+    // El repositorio rechaza la eliminación si la historia tiene entradas firmadas
+    pub fn eliminar_historia(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.repository.eliminar_entradas(id)?;
+        self.repository.eliminar(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::personal::RolPersonal;
+    use crate::repositories::historia_clinica_repository::InMemoryHistoriaClinicaRepository;
+
+    fn veterinario() -> Personal {
+        Personal::new(
+            "Ana".to_string(),
+            "Gómez".to_string(),
+            RolPersonal::Veterinario,
+            Some("MP 1234".to_string()),
+            Vec::new(),
+            Vec::new(),
+            "ana@clinica.com".to_string(),
+            "+5491122334455".to_string(),
+            None,
+        )
+    }
+
+    fn entrada(id_historia: Uuid) -> EntradaHistoriaClinica {
+        EntradaHistoriaClinica::new(
+            id_historia,
+            "Control".to_string(),
+            "Sano".to_string(),
+            "Ninguno".to_string(),
+            None,
+            None,
+            SignosVitales::default(),
+            None,
+        )
+    }
+
+    // Servicio con una historia vacía
+    fn servicio() -> (HistoriaClinicaService<InMemoryHistoriaClinicaRepository>, Uuid) {
+        let mut repositorio = InMemoryHistoriaClinicaRepository::new();
+        let historia = HistoriaClinica::new(Uuid::new_v4(), Uuid::new_v4(), None);
+        let id_historia = historia.id;
+        repositorio.guardar(historia).unwrap();
+        (HistoriaClinicaService::new(Box::new(repositorio)), id_historia)
+    }
+
+    fn borrador(
+        servicio: &mut HistoriaClinicaService<InMemoryHistoriaClinicaRepository>,
+        id_historia: Uuid,
+    ) -> EntradaHistoriaClinica {
+        servicio.agregar_entrada(
+            id_historia, "Control".to_string(), "Sano".to_string(), "Ninguno".to_string(),
+            None, None, SignosVitales::default(), None,
+        ).unwrap()
+    }
+
+    #[test]
+    fn verifica_una_entrada_firmada() {
+        let (mut servicio, id_historia) = servicio();
+        let borrador = borrador(&mut servicio, id_historia);
+        let firmante = veterinario();
+        servicio.firmar_entrada(id_historia, borrador.id, &VersionEsperada::Cualquiera, &firmante, None)
+            .unwrap();
+
+        let verificacion = servicio.verificar_firma(id_historia, borrador.id).unwrap();
+        assert!(verificacion.integra);
+        assert_eq!(verificacion.id_firmante, Some(firmante.id));
+        assert_eq!(verificacion.firmada_por.as_deref(), Some("Ana Gómez"));
+        assert_eq!(verificacion.hash_registrado, Some(verificacion.hash_calculado));
+    }
+
+    #[test]
+    fn detecta_un_contenido_alterado_despues_de_firmar() {
+        let (mut servicio, id_historia) = servicio();
+        // Se guarda directamente en el repositorio, como si se hubiera editado la base
+        let mut alterada = entrada(id_historia);
+        alterada.firmar(Uuid::new_v4(), "Ana Gómez");
+        alterada.diagnostico = "Otitis".to_string();
+        servicio.repository.agregar_entrada(alterada.clone()).unwrap();
+
+        let verificacion = servicio.verificar_firma(id_historia, alterada.id).unwrap();
+        assert!(!verificacion.integra);
+        assert_ne!(verificacion.hash_registrado, Some(verificacion.hash_calculado));
+    }
+
+    #[test]
+    fn no_verifica_borradores_ni_entradas_de_legado() {
+        let (mut servicio, id_historia) = servicio();
+        let borrador = borrador(&mut servicio, id_historia);
+        let legado = EntradaHistoriaClinica { estado: EstadoEntrada::Legado, ..entrada(id_historia) };
+        servicio.repository.agregar_entrada(legado.clone()).unwrap();
+
+        assert!(matches!(servicio.verificar_firma(id_historia, borrador.id), Err(DomainError::Conflict(_))));
+        assert!(matches!(servicio.verificar_firma(id_historia, legado.id), Err(DomainError::Conflict(_))));
+        assert!(matches!(
+            servicio.firmar_entrada(id_historia, legado.id, &VersionEsperada::Cualquiera, &veterinario(), None),
+            Err(DomainError::Conflict(_))
+        ));
+    }
+
+    #[test]
+    fn solo_firma_el_veterinario_que_atendio() {
+        let (mut servicio, id_historia) = servicio();
+        let atendio = veterinario();
+        let borrador = servicio.agregar_entrada(
+            id_historia, "Control".to_string(), "Sano".to_string(), "Ninguno".to_string(),
+            None, Some(atendio.id), SignosVitales::default(), None,
+        ).unwrap();
+
+        assert!(matches!(
+            servicio.firmar_entrada(id_historia, borrador.id, &VersionEsperada::Cualquiera, &veterinario(), None),
+            Err(DomainError::Validation(_))
+        ));
+        let firmada = servicio
+            .firmar_entrada(id_historia, borrador.id, &VersionEsperada::Cualquiera, &atendio, None)
+            .unwrap();
+        assert_eq!(firmada.id_firmante, Some(atendio.id));
+    }
+
+    #[test]
+    fn sin_veterinario_lo_toma_del_firmante() {
+        let (mut servicio, id_historia) = servicio();
+        let borrador = borrador(&mut servicio, id_historia);
+        let firmante = veterinario();
+
+        let firmada = servicio
+            .firmar_entrada(id_historia, borrador.id, &VersionEsperada::Cualquiera, &firmante, None)
+            .unwrap();
+        assert_eq!(firmada.id_veterinario, Some(firmante.id));
+        assert_eq!(firmada.hash_contenido, Some(firmada.calcular_hash()));
+    }
+
+    #[test]
+    fn descarta_borradores_pero_no_entradas_firmadas() {
+        let (mut servicio, id_historia) = servicio();
+        let descartada = borrador(&mut servicio, id_historia);
+        let firmada = borrador(&mut servicio, id_historia);
+        servicio.firmar_entrada(id_historia, firmada.id, &VersionEsperada::Cualquiera, &veterinario(), None)
+            .unwrap();
+
+        servicio.descartar_borrador(id_historia, descartada.id).unwrap();
+        assert!(matches!(servicio.obtener_entrada(id_historia, descartada.id), Err(DomainError::NotFound(_))));
+        assert!(matches!(servicio.descartar_borrador(id_historia, firmada.id), Err(DomainError::Conflict(_))));
+        assert_eq!(servicio.obtener_entradas(id_historia).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn una_enmienda_en_borrador_no_reemplaza_a_la_original() {
+        let (mut servicio, id_historia) = servicio();
+        let original = borrador(&mut servicio, id_historia);
+        let firmante = veterinario();
+        servicio.firmar_entrada(id_historia, original.id, &VersionEsperada::Cualquiera, &firmante, None)
+            .unwrap();
+        let enmienda = servicio.enmendar_entrada(
+            id_historia, original.id, "Control".to_string(), "Otitis".to_string(), "Gotas".to_string(),
+            None, None, SignosVitales::default(), "Diagnóstico corregido".to_string(), None,
+        ).unwrap();
+        let vigentes = |servicio: &HistoriaClinicaService<InMemoryHistoriaClinicaRepository>| {
+            let consulta = ConsultaEntradas {
+                filtro: Default::default(),
+                orden: Default::default(),
+                direccion: Default::default(),
+                pagina: 1,
+                por_pagina: 50,
+            };
+            let listadas: Vec<Uuid> = servicio.listar_entradas(id_historia, &consulta).unwrap()
+                .elementos.iter().map(|e| e.id).collect();
+            let mut ids: Vec<Uuid> = servicio.entradas_vigentes(id_historia).unwrap()
+                .iter().map(|e| e.id).collect();
+            assert_eq!(listadas.len(), ids.len());
+            assert!(listadas.iter().all(|id| ids.contains(id)));
+            ids.sort();
+            ids
+        };
+
+        let mut esperadas = vec![original.id, enmienda.id];
+        esperadas.sort();
+        assert_eq!(vigentes(&servicio), esperadas);
+
+        servicio.firmar_entrada(id_historia, enmienda.id, &VersionEsperada::Cualquiera, &firmante, None)
+            .unwrap();
+        assert_eq!(vigentes(&servicio), vec![enmienda.id]);
+    }
+
+    // Segunda historia de la misma mascota, como las que quedaron de antes de
+    // exigir una sola; `crear_historia` ya no las permite
+    fn duplicada(
+        servicio: &mut HistoriaClinicaService<InMemoryHistoriaClinicaRepository>,
+        id_historia: Uuid,
+    ) -> Uuid {
+        let original = servicio.obtener_historia(id_historia).unwrap().unwrap();
+        let mut duplicada = HistoriaClinica::new(original.id_mascota, original.id_cliente, None);
+        duplicada.fecha_creacion = original.fecha_creacion + chrono::Duration::seconds(1);
+        let id_duplicada = duplicada.id;
+        servicio.repository.guardar(duplicada).unwrap();
+        id_duplicada
+    }
+
+    #[test]
+    fn fusiona_las_historias_duplicadas_en_la_mas_antigua() {
+        let (mut servicio, id_historia) = servicio();
+        let id_mascota = servicio.obtener_historia(id_historia).unwrap().unwrap().id_mascota;
+        servicio.archivar_historia(id_historia, &VersionEsperada::Cualquiera, None).unwrap();
+        let version = servicio.obtener_historia(id_historia).unwrap().unwrap().version;
+        let id_duplicada = duplicada(&mut servicio, id_historia);
+        let movida = borrador(&mut servicio, id_duplicada);
+
+        let fusionada = servicio.fusionar_historias_mascota(id_mascota, None).unwrap();
+        assert_eq!(fusionada.id, id_historia);
+        assert_eq!(fusionada.version, version + 1);
+        assert!(!fusionada.archivado);
+        assert_eq!(servicio.ids_historias_mascota(id_mascota).unwrap(), vec![id_historia]);
+        assert_eq!(servicio.obtener_entrada(id_historia, movida.id).unwrap().id_historia_clinica, id_historia);
+    }
+
+    #[test]
+    fn con_una_sola_historia_no_hay_nada_que_fusionar() {
+        let (mut servicio, id_historia) = servicio();
+        let antes = servicio.obtener_historia(id_historia).unwrap().unwrap();
+
+        let fusionada = servicio.fusionar_historias_mascota(antes.id_mascota, None).unwrap();
+        assert_eq!(fusionada.id, id_historia);
+        assert_eq!(fusionada.version, antes.version);
+        assert_eq!(servicio.obtener_historia(id_historia).unwrap().unwrap().version, antes.version);
+    }
+
+    #[test]
+    fn una_entrada_firmada_impide_la_fusion_sin_cambiar_nada() {
+        let (mut servicio, id_historia) = servicio();
+        let antes = servicio.obtener_historia(id_historia).unwrap().unwrap();
+        let id_duplicada = duplicada(&mut servicio, id_historia);
+        let pendiente = borrador(&mut servicio, id_duplicada);
+        let firmada = borrador(&mut servicio, id_duplicada);
+        servicio.firmar_entrada(id_duplicada, firmada.id, &VersionEsperada::Cualquiera, &veterinario(), None)
+            .unwrap();
+
+        assert!(matches!(
+            servicio.fusionar_historias_mascota(antes.id_mascota, None),
+            Err(DomainError::Conflict(_))
+        ));
+        assert_eq!(servicio.ids_historias_mascota(antes.id_mascota).unwrap(), vec![id_historia, id_duplicada]);
+        assert_eq!(servicio.obtener_historia(id_historia).unwrap().unwrap().version, antes.version);
+        assert!(servicio.obtener_entrada(id_duplicada, pendiente.id).is_ok());
+        assert!(servicio.obtener_entradas(id_historia).unwrap().is_empty());
+    }
+
+    #[test]
+    fn una_entrada_firmada_no_se_vuelve_a_firmar() {
+        let (mut servicio, id_historia) = servicio();
+        let borrador = borrador(&mut servicio, id_historia);
+        servicio.firmar_entrada(id_historia, borrador.id, &VersionEsperada::Cualquiera, &veterinario(), None)
+            .unwrap();
+
+        assert!(matches!(
+            servicio.firmar_entrada(id_historia, borrador.id, &VersionEsperada::Cualquiera, &veterinario(), None),
+            Err(DomainError::Conflict(_))
+        ));
+    }
 }
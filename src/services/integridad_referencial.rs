@@ -0,0 +1,204 @@
+use crate::repositories::clinica_repository::ClinicaRepository;
+use crate::repositories::cliente_repository::ClienteRepository;
+use crate::repositories::mascota_repository::MascotaRepository;
+use crate::repositories::historia_clinica_repository::HistoriaClinicaRepository;
+use crate::repositories::personal_repository::PersonalRepository;
+use crate::models::Personal;
+use crate::models::personal::RolPersonal;
+use crate::services::{ClinicaService, ClienteService, MascotaService, HistoriaClinicaService, PersonalService};
+use uuid::Uuid;
+use crate::error::DomainError;
+
+// Validaciones que cruzan servicios: cada función recibe los servicios de las
+// entidades padre y devuelve `DomainError::Validation` con el motivo por el
+// que la referencia no es válida.
+
+// La clínica existe y está activa: la comprueban clientes, personal y turnos
+pub fn validar_clinica<C: ClinicaRepository + ?Sized>(
+    clinicas: &ClinicaService<C>,
+    id_clinica: Uuid,
+) -> Result<(), DomainError> {
+    let clinica = clinicas.obtener_clinica(id_clinica)?
+        .ok_or_else(|| DomainError::Validation(format!("La clínica {} no existe", id_clinica)))?;
+
+    if clinica.archivado {
+        return Err(DomainError::Validation(format!("La clínica {} está archivada", id_clinica)));
+    }
+    Ok(())
+}
+
+pub fn validar_mascota<C: ClienteRepository + ?Sized>(
+    clientes: &ClienteService<C>,
+    id_cliente: Uuid,
+) -> Result<(), DomainError> {
+    let cliente = clientes.obtener_cliente(id_cliente)?
+        .ok_or_else(|| DomainError::Validation(format!("El cliente {} no existe", id_cliente)))?;
+
+    if cliente.archivado {
+        return Err(DomainError::Validation(format!("El cliente {} está archivado", id_cliente)));
+    }
+    Ok(())
+}
+
+pub fn validar_historia<C, M>(
+    clientes: &ClienteService<C>,
+    mascotas: &MascotaService<M>,
+    id_mascota: Uuid,
+    id_cliente: Uuid,
+) -> Result<(), DomainError>
+where
+    C: ClienteRepository + ?Sized,
+    M: MascotaRepository + ?Sized,
+{
+    validar_mascota(clientes, id_cliente)?;
+
+    let mascota = mascotas.obtener_mascota(id_mascota)?
+        .ok_or_else(|| DomainError::Validation(format!("La mascota {} no existe", id_mascota)))?;
+
+    if mascota.archivado {
+        return Err(DomainError::Validation(format!("La mascota {} está archivada", id_mascota)));
+    }
+
+    if mascota.id_cliente != id_cliente {
+        return Err(DomainError::Validation(format!(
+            "La mascota {} no pertenece al cliente {}",
+            id_mascota, id_cliente
+        )));
+    }
+    Ok(())
+}
+
+// Solo se validan las clínicas que se agregan: quien ya trabajaba en una
+// clínica archivada la conserva
+pub fn validar_personal<C: ClinicaRepository + ?Sized>(
+    clinicas: &ClinicaService<C>,
+    ids_clinicas: &[Uuid],
+) -> Result<(), DomainError> {
+    ids_clinicas.iter().try_for_each(|id| validar_clinica(clinicas, *id))
+}
+
+// Un veterinario del directorio de personal que no está archivado
+pub fn validar_veterinario_activo<P: PersonalRepository + ?Sized>(
+    personal: &PersonalService<P>,
+    id_veterinario: Uuid,
+) -> Result<Personal, DomainError> {
+    let veterinario = personal.obtener_personal(id_veterinario)?
+        .ok_or_else(|| DomainError::Validation(format!("El veterinario {} no existe", id_veterinario)))?;
+
+    if veterinario.archivado {
+        return Err(DomainError::Validation(format!("El veterinario {} está archivado", id_veterinario)));
+    }
+    if veterinario.rol != RolPersonal::Veterinario {
+        return Err(DomainError::Validation(format!(
+            "{} {} no es veterinario", veterinario.nombre, veterinario.apellido
+        )));
+    }
+    Ok(veterinario)
+}
+
+// Un veterinario activo que trabaja en la clínica
+pub fn validar_veterinario_clinica<P: PersonalRepository + ?Sized>(
+    personal: &PersonalService<P>,
+    id_veterinario: Uuid,
+    id_clinica: Uuid,
+) -> Result<Personal, DomainError> {
+    let veterinario = validar_veterinario_activo(personal, id_veterinario)?;
+    if !veterinario.trabaja_en(id_clinica) {
+        return Err(DomainError::Validation(format!(
+            "El veterinario {} no trabaja en la clínica {}", id_veterinario, id_clinica
+        )));
+    }
+    Ok(veterinario)
+}
+
+// Clínica del dueño actual de la mascota de la historia
+pub fn clinica_historia<C, M, H>(
+    clientes: &ClienteService<C>,
+    mascotas: &MascotaService<M>,
+    historias: &HistoriaClinicaService<H>,
+    id_historia: Uuid,
+) -> Result<Uuid, DomainError>
+where
+    C: ClienteRepository + ?Sized,
+    M: MascotaRepository + ?Sized,
+    H: HistoriaClinicaRepository + ?Sized,
+{
+    let historia = historias.obtener_historia(id_historia)?
+        .ok_or_else(|| DomainError::NotFound(format!("La historia clínica {} no existe", id_historia)))?;
+    let mascota = mascotas.obtener_mascota(historia.id_mascota)?
+        .ok_or_else(|| DomainError::Validation(format!("La mascota {} no existe", historia.id_mascota)))?;
+    let cliente = clientes.obtener_cliente(mascota.id_cliente)?
+        .ok_or_else(|| DomainError::Validation(format!("El cliente {} no existe", mascota.id_cliente)))?;
+    Ok(cliente.id_clinica)
+}
+
+// El profesional de una entrada debe ser un veterinario activo de la clínica
+// del dueño actual de la mascota
+pub fn validar_veterinario<P, C, M, H>(
+    personal: &PersonalService<P>,
+    clientes: &ClienteService<C>,
+    mascotas: &MascotaService<M>,
+    historias: &HistoriaClinicaService<H>,
+    id_historia: Uuid,
+    id_veterinario: Uuid,
+) -> Result<Personal, DomainError>
+where
+    P: PersonalRepository + ?Sized,
+    C: ClienteRepository + ?Sized,
+    M: MascotaRepository + ?Sized,
+    H: HistoriaClinicaRepository + ?Sized,
+{
+    let id_clinica = clinica_historia(clientes, mascotas, historias, id_historia)?;
+    validar_veterinario_clinica(personal, id_veterinario, id_clinica)
+}
+
+// Un turno se da a una mascota activa, de un cliente activo de una clínica
+// activa, con un veterinario de esa clínica
+#[allow(clippy::too_many_arguments)]
+pub fn validar_turno<L, P, C, M>(
+    clinicas: &ClinicaService<L>,
+    personal: &PersonalService<P>,
+    clientes: &ClienteService<C>,
+    mascotas: &MascotaService<M>,
+    id_clinica: Uuid,
+    id_cliente: Uuid,
+    id_mascota: Uuid,
+    id_veterinario: Uuid,
+) -> Result<(), DomainError>
+where
+    L: ClinicaRepository + ?Sized,
+    P: PersonalRepository + ?Sized,
+    C: ClienteRepository + ?Sized,
+    M: MascotaRepository + ?Sized,
+{
+    validar_clinica(clinicas, id_clinica)?;
+    validar_historia(clientes, mascotas, id_mascota, id_cliente)?;
+    validar_veterinario_clinica(personal, id_veterinario, id_clinica)?;
+    Ok(())
+}
+
+// Una vacuna se registra a una mascota activa; quien la aplicó, si se indica,
+// debe ser un veterinario de la clínica del dueño
+pub fn validar_vacunacion<P, C, M>(
+    personal: &PersonalService<P>,
+    clientes: &ClienteService<C>,
+    mascotas: &MascotaService<M>,
+    id_mascota: Uuid,
+    id_veterinario: Option<Uuid>,
+) -> Result<(), DomainError>
+where
+    P: PersonalRepository + ?Sized,
+    C: ClienteRepository + ?Sized,
+    M: MascotaRepository + ?Sized,
+{
+    let mascota = mascotas.obtener_mascota(id_mascota)?
+        .ok_or_else(|| DomainError::NotFound(format!("La mascota {} no existe", id_mascota)))?;
+    validar_historia(clientes, mascotas, id_mascota, mascota.id_cliente)?;
+
+    if let Some(id_veterinario) = id_veterinario {
+        let cliente = clientes.obtener_cliente(mascota.id_cliente)?
+            .ok_or_else(|| DomainError::Validation(format!("El cliente {} no existe", mascota.id_cliente)))?;
+        validar_veterinario_clinica(personal, id_veterinario, cliente.id_clinica)?;
+    }
+    Ok(())
+}
@@ -1,17 +1,82 @@
-use crate::models::Mascota;
-use crate::repositories::mascota_repository::MascotaRepository;
-use chrono::NaiveDate;
+use crate::models::{Auditado, Mascota};
+use crate::models::mascota::{Alergia, CondicionCronica};
+use crate::repositories::mascota_repository::{ConsultaMascotas, MascotaRepository};
+use crate::repositories::consulta::Pagina;
+use crate::repositories::busqueda::{normalizar, Coincidencia};
+use chrono::{NaiveDate, Utc};
 use uuid::Uuid;
+use crate::error::{DomainError, FieldError};
+use crate::services::concurrencia::VersionEsperada;
+use std::collections::HashSet;
 
-pub struct MascotaService<T: MascotaRepository> {
-    repository: T,
+// Campos a modificar en una actualización parcial; `None` deja el valor actual.
+// `fecha_nacimiento: Some(None)` la borra.
+#[derive(Debug, Default)]
+pub struct CambiosMascota {
+    pub nombre: Option<String>,
+    pub especie: Option<String>,
+    pub raza: Option<String>,
+    pub fecha_nacimiento: Option<Option<NaiveDate>>,
+    pub id_cliente: Option<Uuid>,
+    pub alergias: Option<Vec<Alergia>>,
+    pub condiciones_cronicas: Option<Vec<CondicionCronica>>,
 }
 
-impl<T: MascotaRepository> MascotaService<T> {
-    pub fn new(repository: T) -> Self {
+// Cada sustancia y cada condición aparecen una sola vez, sin distinguir
+// mayúsculas ni acentos
+fn validar_antecedentes(alergias: &[Alergia], condiciones: &[CondicionCronica]) -> Result<(), DomainError> {
+    let mut errores = Vec::new();
+    let mut error = |campo: String, regla: &str, mensaje: &str| errores.push(FieldError {
+        campo,
+        regla: regla.to_string(),
+        mensaje: mensaje.to_string(),
+    });
+
+    let mut sustancias = HashSet::new();
+    for (indice, alergia) in alergias.iter().enumerate() {
+        if alergia.sustancia.is_empty() {
+            error(format!("alergias[{}].sustancia", indice), "required", "es obligatorio");
+        } else if alergia.sustancia.chars().count() > 120 {
+            error(format!("alergias[{}].sustancia", indice), "length", "admite hasta 120 caracteres");
+        } else if !sustancias.insert(normalizar(&alergia.sustancia)) {
+            error(format!("alergias[{}].sustancia", indice), "unique", "la sustancia ya está registrada");
+        }
+        if alergia.reaccion.as_ref().is_some_and(|r| r.chars().count() > 500) {
+            error(format!("alergias[{}].reaccion", indice), "length", "admite hasta 500 caracteres");
+        }
+    }
+
+    let mut nombres = HashSet::new();
+    for (indice, condicion) in condiciones.iter().enumerate() {
+        if condicion.condicion.is_empty() {
+            error(format!("condiciones_cronicas[{}].condicion", indice), "required", "es obligatorio");
+        } else if condicion.condicion.chars().count() > 120 {
+            error(format!("condiciones_cronicas[{}].condicion", indice), "length", "admite hasta 120 caracteres");
+        } else if !nombres.insert(normalizar(&condicion.condicion)) {
+            error(format!("condiciones_cronicas[{}].condicion", indice), "unique", "la condición ya está registrada");
+        }
+        if condicion.notas.as_ref().is_some_and(|n| n.chars().count() > 500) {
+            error(format!("condiciones_cronicas[{}].notas", indice), "length", "admite hasta 500 caracteres");
+        }
+    }
+
+    if errores.is_empty() {
+        Ok(())
+    } else {
+        Err(DomainError::InvalidFields(errores))
+    }
+}
+
+pub struct MascotaService<T: MascotaRepository + ?Sized> {
+    repository: Box<T>,
+}
+
+impl<T: MascotaRepository + ?Sized> MascotaService<T> {
+    pub fn new(repository: Box<T>) -> Self {
         Self { repository }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn crear_mascota(
         &mut self,
         nombre: String,
@@ -19,24 +84,53 @@ impl<T: MascotaRepository> MascotaService<T> {
         raza: String,
         fecha_nacimiento: Option<NaiveDate>,
         id_cliente: Uuid,
-    ) -> Result<Mascota, String> {
-        let mascota = Mascota::new(nombre, especie, raza, fecha_nacimiento, id_cliente);
+        alergias: Vec<Alergia>,
+        condiciones_cronicas: Vec<CondicionCronica>,
+        autor: Option<&str>,
+    ) -> Result<Mascota, DomainError> {
+        validar_antecedentes(&alergias, &condiciones_cronicas)?;
+
+        let mascota = Mascota::new(
+            nombre,
+            especie,
+            raza,
+            fecha_nacimiento,
+            id_cliente,
+            alergias,
+            condiciones_cronicas,
+            autor,
+        );
         self.repository.guardar(mascota.clone())?;
         Ok(mascota)
     }
 
-    pub fn obtener_mascota(&self, id: Uuid) -> Option<&Mascota> {
+    pub fn obtener_mascota(&self, id: Uuid) -> Result<Option<Mascota>, DomainError> {
         self.repository.obtener(id)
     }
 
-    pub fn listar_mascotas(&self) -> Vec<&Mascota> {
-        self.repository.listar()
+    // Las mascotas archivadas no aparecen en los listados salvo que el filtro lo pida
+    pub fn listar_mascotas(&self, consulta: &ConsultaMascotas) -> Result<Pagina<Mascota>, DomainError> {
+        self.repository.listar(consulta)
+    }
+
+    pub fn buscar_mascotas(&self, terminos: &[String], limite: usize) -> Result<Vec<Coincidencia<Mascota>>, DomainError> {
+        self.repository.buscar(terminos, limite)
+    }
+
+    // Incluye las archivadas
+    pub fn todas_las_mascotas(&self) -> Result<Vec<Mascota>, DomainError> {
+        self.repository.listar_todas()
     }
 
-    pub fn listar_mascotas_cliente(&self, id_cliente: Uuid) -> Vec<&Mascota> {
-        self.repository.listar_por_cliente(id_cliente)
+    // Incluye las archivadas: se usa para detectar dependientes antes de eliminar
+    pub fn ids_mascotas_cliente(&self, id_cliente: Uuid) -> Result<Vec<Uuid>, DomainError> {
+        Ok(self.repository.listar_por_cliente(id_cliente)?
+            .into_iter()
+            .map(|m| m.id)
+            .collect())
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn actualizar_mascota(
         &mut self,
         id: Uuid,
@@ -45,20 +139,131 @@ impl<T: MascotaRepository> MascotaService<T> {
         raza: String,
         fecha_nacimiento: Option<NaiveDate>,
         id_cliente: Uuid,
-    ) -> Result<Mascota, String> {
-        let mascota = self.repository.obtener(id)
-            .ok_or_else(|| "La mascota no existe".to_string())?;
+        alergias: Vec<Alergia>,
+        condiciones_cronicas: Vec<CondicionCronica>,
+        esperada: &VersionEsperada,
+        autor: Option<&str>,
+    ) -> Result<Mascota, DomainError> {
+        let mascota = self.repository.obtener(id)?
+            .ok_or_else(|| DomainError::NotFound(format!("La mascota {} no existe", id)))?;
+        esperada.comprobar(&format!("La mascota {}", id), mascota.version)?;
+        validar_antecedentes(&alergias, &condiciones_cronicas)?;
 
-        let mascota_actualizada = Mascota {
+        let mut mascota_actualizada = Mascota {
             id: mascota.id,
             nombre,
             especie,
             raza,
             fecha_nacimiento,
             id_cliente,
+            alergias,
+            condiciones_cronicas,
+            ..mascota
         };
+        mascota_actualizada.registrar_cambio(autor);
 
         self.repository.guardar(mascota_actualizada.clone())?;
         Ok(mascota_actualizada)
     }
+
+    pub fn modificar_mascota(
+        &mut self,
+        id: Uuid,
+        cambios: CambiosMascota,
+        esperada: &VersionEsperada,
+        autor: Option<&str>,
+    ) -> Result<Mascota, DomainError> {
+        let mut mascota = self.repository.obtener(id)?
+            .ok_or_else(|| DomainError::NotFound(format!("La mascota {} no existe", id)))?;
+        esperada.comprobar(&format!("La mascota {}", id), mascota.version)?;
+
+        if let Some(nombre) = cambios.nombre {
+            mascota.nombre = nombre;
+        }
+        if let Some(especie) = cambios.especie {
+            mascota.especie = especie;
+        }
+        if let Some(raza) = cambios.raza {
+            mascota.raza = raza;
+        }
+        if let Some(fecha_nacimiento) = cambios.fecha_nacimiento {
+            mascota.fecha_nacimiento = fecha_nacimiento;
+        }
+        if let Some(id_cliente) = cambios.id_cliente {
+            mascota.id_cliente = id_cliente;
+        }
+        if let Some(alergias) = cambios.alergias {
+            mascota.alergias = alergias;
+        }
+        if let Some(condiciones_cronicas) = cambios.condiciones_cronicas {
+            mascota.condiciones_cronicas = condiciones_cronicas;
+        }
+        validar_antecedentes(&mascota.alergias, &mascota.condiciones_cronicas)?;
+
+        mascota.registrar_cambio(autor);
+        self.repository.guardar(mascota.clone())?;
+        Ok(mascota)
+    }
+
+    // Solo cambia la especie y la raza, sin volver a validar el resto: la
+    // migración al catálogo no debe fallar por datos cargados antes de las
+    // validaciones actuales
+    pub fn reclasificar_mascota(
+        &mut self,
+        id: Uuid,
+        especie: String,
+        raza: String,
+        esperada: &VersionEsperada,
+        autor: Option<&str>,
+    ) -> Result<Mascota, DomainError> {
+        let mut mascota = self.repository.obtener(id)?
+            .ok_or_else(|| DomainError::NotFound(format!("La mascota {} no existe", id)))?;
+        esperada.comprobar(&format!("La mascota {}", id), mascota.version)?;
+
+        mascota.especie = especie;
+        mascota.raza = raza;
+        mascota.registrar_cambio(autor);
+        self.repository.guardar(mascota.clone())?;
+        Ok(mascota)
+    }
+
+    pub fn archivar_mascota(
+        &mut self,
+        id: Uuid,
+        esperada: &VersionEsperada,
+        autor: Option<&str>,
+    ) -> Result<Mascota, DomainError> {
+        let mascota = self.repository.obtener(id)?
+            .ok_or_else(|| DomainError::NotFound(format!("La mascota {} no existe", id)))?;
+        esperada.comprobar(&format!("La mascota {}", id), mascota.version)?;
+
+        let mut mascota_archivada = Mascota {
+            archivado: true,
+            fecha_archivado: Some(Utc::now()),
+            ..mascota
+        };
+        mascota_archivada.registrar_cambio(autor);
+
+        self.repository.guardar(mascota_archivada.clone())?;
+        Ok(mascota_archivada)
+    }
+
+    pub fn restaurar_mascota(&mut self, id: Uuid, autor: Option<&str>) -> Result<Mascota, DomainError> {
+        let mascota = self.repository.obtener(id)?
+            .ok_or_else(|| DomainError::NotFound(format!("La mascota {} no existe", id)))?;
+
+        let mut mascota_restaurada = Mascota {
+            archivado: false,
+            fecha_archivado: None,
+            ..mascota
+        };
+        mascota_restaurada.registrar_cambio(autor);
+
+        self.repository.guardar(mascota_restaurada.clone())?;
+        Ok(mascota_restaurada)
+    }
+
+    pub fn eliminar_mascota(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.repository.eliminar(id)
+    }
 }
@@ -0,0 +1,184 @@
+use crate::models::{Auditado, Medicamento};
+use crate::models::medicamento::RangoDosis;
+use crate::repositories::medicamento_repository::{ConsultaMedicamentos, MedicamentoRepository};
+use crate::repositories::consulta::Pagina;
+use uuid::Uuid;
+use crate::error::{DomainError, FieldError};
+use crate::services::concurrencia::VersionEsperada;
+use chrono::Utc;
+use std::collections::HashSet;
+
+// Datos de un medicamento del catálogo, para el alta y el reemplazo
+#[derive(Debug)]
+pub struct DatosMedicamento {
+    pub principio_activo: String,
+    pub nombre_comercial: Option<String>,
+    pub presentacion: String,
+    pub concentracion_mg: f64,
+    pub unidad: String,
+    pub dosis: Vec<RangoDosis>,
+}
+
+impl DatosMedicamento {
+    // Cada especie aparece una sola vez, con una dosis mínima positiva y no
+    // mayor que la máxima
+    fn validar(&self) -> Result<(), DomainError> {
+        let mut errores = Vec::new();
+        let mut especies = HashSet::new();
+        let mut error = |campo: String, regla: &str, mensaje: &str| errores.push(FieldError {
+            campo,
+            regla: regla.to_string(),
+            mensaje: mensaje.to_string(),
+        });
+
+        for (indice, rango) in self.dosis.iter().enumerate() {
+            if rango.especie.is_empty() {
+                error(format!("dosis[{}].especie", indice), "required", "es obligatorio");
+            } else if !especies.insert(rango.especie.as_str()) {
+                error(format!("dosis[{}].especie", indice), "unique", "la especie ya tiene un rango");
+            }
+            if rango.minima_mg_kg <= 0.0 {
+                error(format!("dosis[{}].minima_mg_kg", indice), "range", "debe ser mayor que 0");
+            }
+            if rango.maxima_mg_kg < rango.minima_mg_kg {
+                error(format!("dosis[{}].maxima_mg_kg", indice), "range", "no puede ser menor que la mínima");
+            }
+        }
+
+        if errores.is_empty() {
+            Ok(())
+        } else {
+            Err(DomainError::InvalidFields(errores))
+        }
+    }
+}
+
+pub struct MedicamentoService<T: MedicamentoRepository + ?Sized> {
+    repository: Box<T>,
+}
+
+impl<T: MedicamentoRepository + ?Sized> MedicamentoService<T> {
+    pub fn new(repository: Box<T>) -> Self {
+        Self { repository }
+    }
+
+    pub fn crear_medicamento(
+        &mut self,
+        datos: DatosMedicamento,
+        autor: Option<&str>,
+    ) -> Result<Medicamento, DomainError> {
+        datos.validar()?;
+
+        let medicamento = Medicamento::new(
+            datos.principio_activo,
+            datos.nombre_comercial,
+            datos.presentacion,
+            datos.concentracion_mg,
+            datos.unidad,
+            datos.dosis,
+            autor,
+        );
+        self.repository.guardar(medicamento.clone())?;
+        Ok(medicamento)
+    }
+
+    pub fn obtener_medicamento(&self, id: Uuid) -> Result<Option<Medicamento>, DomainError> {
+        self.repository.obtener(id)
+    }
+
+    pub fn listar_medicamentos(&self, consulta: &ConsultaMedicamentos) -> Result<Pagina<Medicamento>, DomainError> {
+        self.repository.listar(consulta)
+    }
+
+    // Incluye los archivados
+    pub fn todos_los_medicamentos(&self) -> Result<Vec<Medicamento>, DomainError> {
+        self.repository.listar_todos()
+    }
+
+    // Las prescripciones ya emitidas conservan los datos con que se calcularon
+    pub fn actualizar_medicamento(
+        &mut self,
+        id: Uuid,
+        datos: DatosMedicamento,
+        esperada: &VersionEsperada,
+        autor: Option<&str>,
+    ) -> Result<Medicamento, DomainError> {
+        let actual = self.repository.obtener(id)?
+            .ok_or_else(|| DomainError::NotFound(format!("El medicamento {} no existe", id)))?;
+        esperada.comprobar(&format!("El medicamento {}", id), actual.version)?;
+        datos.validar()?;
+
+        let mut medicamento = Medicamento {
+            principio_activo: datos.principio_activo,
+            nombre_comercial: datos.nombre_comercial,
+            presentacion: datos.presentacion,
+            concentracion_mg: datos.concentracion_mg,
+            unidad: datos.unidad,
+            dosis: datos.dosis,
+            ..actual
+        };
+        medicamento.registrar_cambio(autor);
+
+        self.repository.guardar(medicamento.clone())?;
+        Ok(medicamento)
+    }
+
+    // Reemplaza los rangos de dosis sin volver a validar el resto; lo usa la
+    // migración al catálogo, que ya comprobó que no se repiten especies
+    pub fn reclasificar_dosis(
+        &mut self,
+        id: Uuid,
+        dosis: Vec<RangoDosis>,
+        esperada: &VersionEsperada,
+        autor: Option<&str>,
+    ) -> Result<Medicamento, DomainError> {
+        let mut medicamento = self.repository.obtener(id)?
+            .ok_or_else(|| DomainError::NotFound(format!("El medicamento {} no existe", id)))?;
+        esperada.comprobar(&format!("El medicamento {}", id), medicamento.version)?;
+
+        medicamento.dosis = dosis;
+        medicamento.registrar_cambio(autor);
+        self.repository.guardar(medicamento.clone())?;
+        Ok(medicamento)
+    }
+
+    pub fn archivar_medicamento(
+        &mut self,
+        id: Uuid,
+        esperada: &VersionEsperada,
+        autor: Option<&str>,
+    ) -> Result<Medicamento, DomainError> {
+        let medicamento = self.repository.obtener(id)?
+            .ok_or_else(|| DomainError::NotFound(format!("El medicamento {} no existe", id)))?;
+        esperada.comprobar(&format!("El medicamento {}", id), medicamento.version)?;
+
+        let mut medicamento_archivado = Medicamento {
+            archivado: true,
+            fecha_archivado: Some(Utc::now()),
+            ..medicamento
+        };
+        medicamento_archivado.registrar_cambio(autor);
+
+        self.repository.guardar(medicamento_archivado.clone())?;
+        Ok(medicamento_archivado)
+    }
+
+    pub fn restaurar_medicamento(&mut self, id: Uuid, autor: Option<&str>) -> Result<Medicamento, DomainError> {
+        let medicamento = self.repository.obtener(id)?
+            .ok_or_else(|| DomainError::NotFound(format!("El medicamento {} no existe", id)))?;
+
+        let mut medicamento_restaurado = Medicamento {
+            archivado: false,
+            fecha_archivado: None,
+            ..medicamento
+        };
+        medicamento_restaurado.registrar_cambio(autor);
+
+        self.repository.guardar(medicamento_restaurado.clone())?;
+        Ok(medicamento_restaurado)
+    }
+
+    pub fn eliminar_medicamento(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.repository.eliminar(id)
+    }
+}
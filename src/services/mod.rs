@@ -2,8 +2,29 @@ pub mod clinica_service;
 pub mod cliente_service;
 pub mod mascota_service;
 pub mod historia_clinica_service;
+pub mod personal_service;
+pub mod turno_service;
+pub mod vacunacion_service;
+pub mod medicamento_service;
+pub mod prescripcion_service;
+pub mod especie_service;
+pub mod integridad_referencial;
+pub mod eliminacion;
+pub mod busqueda;
+pub mod vencimientos;
+pub mod recetas;
+pub mod signos_vitales;
+pub mod alertas_clinicas;
+pub mod catalogo_especies;
+pub mod concurrencia;
 
 pub use clinica_service::ClinicaService;
 pub use cliente_service::ClienteService;
 pub use mascota_service::MascotaService;
 pub use historia_clinica_service::HistoriaClinicaService;
+pub use personal_service::PersonalService;
+pub use turno_service::TurnoService;
+pub use vacunacion_service::VacunacionService;
+pub use medicamento_service::MedicamentoService;
+pub use prescripcion_service::PrescripcionService;
+pub use especie_service::EspecieService;
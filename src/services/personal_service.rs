@@ -0,0 +1,203 @@
+use crate::models::{Auditado, Personal};
+use crate::models::personal::RolPersonal;
+use crate::repositories::personal_repository::{ConsultaPersonal, PersonalRepository};
+use crate::repositories::consulta::Pagina;
+use uuid::Uuid;
+use crate::error::{DomainError, FieldError};
+use crate::services::concurrencia::VersionEsperada;
+use chrono::Utc;
+
+// Datos completos de un integrante del personal, para el alta y el reemplazo
+#[derive(Debug)]
+pub struct DatosPersonal {
+    pub nombre: String,
+    pub apellido: String,
+    pub rol: RolPersonal,
+    pub matricula: Option<String>,
+    pub especialidades: Vec<String>,
+    pub clinicas: Vec<Uuid>,
+    pub correo: String,
+    pub telefono: String,
+}
+
+// Campos a modificar en una actualización parcial; `None` deja el valor actual
+#[derive(Debug, Default)]
+pub struct CambiosPersonal {
+    pub nombre: Option<String>,
+    pub apellido: Option<String>,
+    pub rol: Option<RolPersonal>,
+    pub matricula: Option<Option<String>>,
+    pub especialidades: Option<Vec<String>>,
+    pub clinicas: Option<Vec<Uuid>>,
+    pub correo: Option<String>,
+    pub telefono: Option<String>,
+}
+
+pub struct PersonalService<T: PersonalRepository + ?Sized> {
+    repository: Box<T>,
+}
+
+impl<T: PersonalRepository + ?Sized> PersonalService<T> {
+    pub fn new(repository: Box<T>) -> Self {
+        Self { repository }
+    }
+
+    // Los veterinarios deben tener matrícula, y una matrícula no puede repetirse
+    fn validar_matricula(&self, personal: &Personal) -> Result<(), DomainError> {
+        let Some(matricula) = &personal.matricula else {
+            if personal.rol == RolPersonal::Veterinario {
+                return Err(DomainError::InvalidFields(vec![FieldError {
+                    campo: "matricula".to_string(),
+                    regla: "required".to_string(),
+                    mensaje: "es obligatoria para los veterinarios".to_string(),
+                }]));
+            }
+            return Ok(());
+        };
+
+        match self.repository.obtener_por_matricula(matricula)? {
+            Some(otro) if otro.id != personal.id => Err(DomainError::Conflict(format!(
+                "La matrícula {} ya está registrada para {} {}",
+                matricula, otro.nombre, otro.apellido
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn crear_personal(&mut self, datos: DatosPersonal, autor: Option<&str>) -> Result<Personal, DomainError> {
+        let personal = Personal::new(
+            datos.nombre,
+            datos.apellido,
+            datos.rol,
+            datos.matricula,
+            datos.especialidades,
+            datos.clinicas,
+            datos.correo,
+            datos.telefono,
+            autor,
+        );
+        self.validar_matricula(&personal)?;
+
+        self.repository.guardar(personal.clone())?;
+        Ok(personal)
+    }
+
+    pub fn obtener_personal(&self, id: Uuid) -> Result<Option<Personal>, DomainError> {
+        self.repository.obtener(id)
+    }
+
+    pub fn listar_personal(&self, consulta: &ConsultaPersonal) -> Result<Pagina<Personal>, DomainError> {
+        self.repository.listar(consulta)
+    }
+
+    pub fn actualizar_personal(
+        &mut self,
+        id: Uuid,
+        datos: DatosPersonal,
+        esperada: &VersionEsperada,
+        autor: Option<&str>,
+    ) -> Result<Personal, DomainError> {
+        self.modificar_personal(id, CambiosPersonal {
+            nombre: Some(datos.nombre),
+            apellido: Some(datos.apellido),
+            rol: Some(datos.rol),
+            matricula: Some(datos.matricula),
+            especialidades: Some(datos.especialidades),
+            clinicas: Some(datos.clinicas),
+            correo: Some(datos.correo),
+            telefono: Some(datos.telefono),
+        }, esperada, autor)
+    }
+
+    pub fn modificar_personal(
+        &mut self,
+        id: Uuid,
+        cambios: CambiosPersonal,
+        esperada: &VersionEsperada,
+        autor: Option<&str>,
+    ) -> Result<Personal, DomainError> {
+        let mut personal = self.repository.obtener(id)?
+            .ok_or_else(|| DomainError::NotFound(format!("El integrante del personal {} no existe", id)))?;
+        esperada.comprobar(&format!("El integrante del personal {}", id), personal.version)?;
+
+        if let Some(nombre) = cambios.nombre {
+            personal.nombre = nombre;
+        }
+        if let Some(apellido) = cambios.apellido {
+            personal.apellido = apellido;
+        }
+        if let Some(rol) = cambios.rol {
+            personal.rol = rol;
+        }
+        if let Some(matricula) = cambios.matricula {
+            personal.matricula = matricula;
+        }
+        if let Some(especialidades) = cambios.especialidades {
+            personal.especialidades = especialidades;
+        }
+        if let Some(clinicas) = cambios.clinicas {
+            personal.clinicas = clinicas;
+        }
+        if let Some(correo) = cambios.correo {
+            personal.correo = correo;
+        }
+        if let Some(telefono) = cambios.telefono {
+            personal.telefono = telefono;
+        }
+        self.validar_matricula(&personal)?;
+
+        personal.registrar_cambio(autor);
+        self.repository.guardar(personal.clone())?;
+        Ok(personal)
+    }
+
+    pub fn archivar_personal(
+        &mut self,
+        id: Uuid,
+        esperada: &VersionEsperada,
+        autor: Option<&str>,
+    ) -> Result<Personal, DomainError> {
+        let personal = self.repository.obtener(id)?
+            .ok_or_else(|| DomainError::NotFound(format!("El integrante del personal {} no existe", id)))?;
+        esperada.comprobar(&format!("El integrante del personal {}", id), personal.version)?;
+
+        let mut personal_archivado = Personal {
+            archivado: true,
+            fecha_archivado: Some(Utc::now()),
+            ..personal
+        };
+        personal_archivado.registrar_cambio(autor);
+
+        self.repository.guardar(personal_archivado.clone())?;
+        Ok(personal_archivado)
+    }
+
+    pub fn restaurar_personal(&mut self, id: Uuid, autor: Option<&str>) -> Result<Personal, DomainError> {
+        let personal = self.repository.obtener(id)?
+            .ok_or_else(|| DomainError::NotFound(format!("El integrante del personal {} no existe", id)))?;
+
+        let mut personal_restaurado = Personal {
+            archivado: false,
+            fecha_archivado: None,
+            ..personal
+        };
+        personal_restaurado.registrar_cambio(autor);
+
+        self.repository.guardar(personal_restaurado.clone())?;
+        Ok(personal_restaurado)
+    }
+
+    // Quita la clínica de quienes trabajan en ella; se usa al eliminarla
+    pub fn quitar_clinica(&mut self, id_clinica: Uuid) -> Result<(), DomainError> {
+        for mut personal in self.repository.listar_por_clinica(id_clinica)? {
+            personal.clinicas.retain(|id| *id != id_clinica);
+            personal.registrar_cambio(None);
+            self.repository.guardar(personal)?;
+        }
+        Ok(())
+    }
+
+    pub fn eliminar_personal(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.repository.eliminar(id)
+    }
+}
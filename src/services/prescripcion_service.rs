@@ -0,0 +1,112 @@
+use crate::models::{Medicamento, Prescripcion};
+use crate::models::mascota::Alergia;
+use crate::repositories::prescripcion_repository::{
+    ConsultaPrescripciones, FiltroPrescripciones, PrescripcionRepository,
+};
+use crate::repositories::consulta::Pagina;
+use uuid::Uuid;
+use crate::error::DomainError;
+use crate::services::concurrencia::VersionEsperada;
+
+// Lo que indica el veterinario; el resto sale del catálogo y del peso
+#[derive(Debug)]
+pub struct DatosPrescripcion {
+    pub id_medicamento: Uuid,
+    pub dosis_mg_kg: f64,
+    pub frecuencia_horas: u32,
+    pub duracion_dias: u32,
+    pub indicaciones: Option<String>,
+    // Obligatorio si la mascota es alérgica al medicamento
+    pub motivo_excepcion: Option<String>,
+}
+
+pub struct PrescripcionService<T: PrescripcionRepository + ?Sized> {
+    repository: Box<T>,
+}
+
+impl<T: PrescripcionRepository + ?Sized> PrescripcionService<T> {
+    pub fn new(repository: Box<T>) -> Self {
+        Self { repository }
+    }
+
+    fn obtener_de_entrada(&self, id_entrada: Uuid, id: Uuid) -> Result<Prescripcion, DomainError> {
+        self.repository.obtener(id)?
+            .filter(|p| p.id_entrada == id_entrada)
+            .ok_or_else(|| DomainError::NotFound(format!(
+                "La prescripción {} no existe en la entrada {}", id, id_entrada
+            )))
+    }
+
+    // Las alergias ya vienen cotejadas: el motivo de excepción solo se guarda
+    // si hay alguna
+    #[allow(clippy::too_many_arguments)]
+    pub fn prescribir(
+        &mut self,
+        id_mascota: Uuid,
+        especie: &str,
+        id_entrada: Uuid,
+        medicamento: &Medicamento,
+        peso_kg: f64,
+        alergias: Vec<Alergia>,
+        datos: DatosPrescripcion,
+        autor: Option<&str>,
+    ) -> Result<Prescripcion, DomainError> {
+        let motivo_excepcion = datos.motivo_excepcion.filter(|_| !alergias.is_empty());
+        let prescripcion = Prescripcion {
+            alergias,
+            motivo_excepcion,
+            ..Prescripcion::new(
+                id_mascota,
+                id_entrada,
+                medicamento,
+                especie,
+                peso_kg,
+                datos.dosis_mg_kg,
+                datos.frecuencia_horas,
+                datos.duracion_dias,
+                datos.indicaciones,
+                autor,
+            )
+        };
+        self.repository.guardar(prescripcion.clone())?;
+        Ok(prescripcion)
+    }
+
+    pub fn obtener_prescripcion(&self, id_entrada: Uuid, id: Uuid) -> Result<Prescripcion, DomainError> {
+        self.obtener_de_entrada(id_entrada, id)
+    }
+
+    pub fn listar_prescripciones(&self, consulta: &ConsultaPrescripciones) -> Result<Pagina<Prescripcion>, DomainError> {
+        self.repository.listar(consulta)
+    }
+
+    // Todas las que cumplen el filtro, por fecha
+    pub fn prescripciones(&self, filtro: &FiltroPrescripciones) -> Result<Vec<Prescripcion>, DomainError> {
+        self.repository.listar_todas(filtro)
+    }
+
+    pub fn contar_prescripciones_medicamento(&self, id_medicamento: Uuid) -> Result<usize, DomainError> {
+        self.repository.contar_por_medicamento(id_medicamento)
+    }
+
+    pub fn eliminar_prescripcion(
+        &mut self,
+        id_entrada: Uuid,
+        id: Uuid,
+        esperada: &VersionEsperada,
+    ) -> Result<(), DomainError> {
+        let actual = self.obtener_de_entrada(id_entrada, id)?;
+        esperada.comprobar(&format!("La prescripción {}", id), actual.version)?;
+
+        self.repository.eliminar(id)
+    }
+
+    // Sin comprobaciones: se usa al eliminar la historia clínica de las entradas
+    pub fn eliminar_prescripciones_entradas(&mut self, ids_entradas: &[Uuid]) -> Result<(), DomainError> {
+        ids_entradas.iter().try_for_each(|&id_entrada| {
+            self.repository.listar_todas(&FiltroPrescripciones { id_entrada: Some(id_entrada), ..Default::default() })?
+                .into_iter()
+                .try_for_each(|p| self.repository.eliminar(p.id))
+        })
+    }
+}
@@ -0,0 +1,369 @@
+use crate::models::{Clinica, Cliente, Mascota, Personal, Prescripcion};
+use crate::repositories::clinica_repository::ClinicaRepository;
+use crate::repositories::cliente_repository::ClienteRepository;
+use crate::repositories::mascota_repository::MascotaRepository;
+use crate::repositories::historia_clinica_repository::HistoriaClinicaRepository;
+use crate::repositories::personal_repository::PersonalRepository;
+use crate::repositories::medicamento_repository::MedicamentoRepository;
+use crate::repositories::prescripcion_repository::{FiltroPrescripciones, PrescripcionRepository};
+use crate::services::{
+    ClinicaService, ClienteService, MascotaService, HistoriaClinicaService, PersonalService, MedicamentoService,
+    PrescripcionService,
+};
+use crate::services::prescripcion_service::DatosPrescripcion;
+use crate::services::alertas_clinicas;
+use crate::services::concurrencia::VersionEsperada;
+use crate::error::DomainError;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+// Entrada en la que se prescribe; las firmadas ya no admiten cambios
+fn entrada_en_borrador<H: HistoriaClinicaRepository + ?Sized>(
+    historias: &HistoriaClinicaService<H>,
+    id_historia: Uuid,
+    id_entrada: Uuid,
+) -> Result<(), DomainError> {
+    let entrada = historias.obtener_entrada(id_historia, id_entrada)?;
+    if !entrada.en_borrador() {
+        return Err(DomainError::Conflict(format!(
+            "La entrada {} está firmada; las prescripciones solo se modifican en borrador",
+            id_entrada
+        )));
+    }
+    Ok(())
+}
+
+// Peso más reciente entre todas las historias de la mascota
+fn ultimo_peso<H: HistoriaClinicaRepository + ?Sized>(
+    historias: &HistoriaClinicaService<H>,
+    id_mascota: Uuid,
+) -> Result<Option<f64>, DomainError> {
+    let mut pesos = Vec::new();
+    for id_historia in historias.ids_historias_mascota(id_mascota)? {
+        pesos.extend(historias.ultimo_peso(id_historia)?);
+    }
+    Ok(pesos.into_iter()
+        .max_by_key(|(_, fecha)| *fecha)
+        .map(|(peso, _)| peso))
+}
+
+// Prescribe en una entrada en borrador. La dosis se calcula con el último
+// peso registrado de la mascota y se compara con el rango de su especie; una
+// dosis fuera de rango se acepta con una advertencia. Un medicamento al que la
+// mascota es alérgica se rechaza salvo que se indique el motivo de la excepción.
+#[allow(clippy::too_many_arguments)]
+pub fn prescribir<M, H, D, P>(
+    mascotas: &MascotaService<M>,
+    historias: &HistoriaClinicaService<H>,
+    medicamentos: &MedicamentoService<D>,
+    prescripciones: &mut PrescripcionService<P>,
+    id_historia: Uuid,
+    id_entrada: Uuid,
+    datos: DatosPrescripcion,
+    autor: Option<&str>,
+) -> Result<Prescripcion, DomainError>
+where
+    M: MascotaRepository + ?Sized,
+    H: HistoriaClinicaRepository + ?Sized,
+    D: MedicamentoRepository + ?Sized,
+    P: PrescripcionRepository + ?Sized,
+{
+    entrada_en_borrador(historias, id_historia, id_entrada)?;
+    let historia = historias.obtener_historia(id_historia)?
+        .ok_or_else(|| DomainError::NotFound(format!("La historia clínica {} no existe", id_historia)))?;
+    let mascota = mascotas.obtener_mascota(historia.id_mascota)?
+        .ok_or_else(|| DomainError::NotFound(format!("La mascota {} no existe", historia.id_mascota)))?;
+
+    let medicamento = medicamentos.obtener_medicamento(datos.id_medicamento)?
+        .ok_or_else(|| DomainError::Validation(format!("El medicamento {} no existe", datos.id_medicamento)))?;
+    if medicamento.archivado {
+        return Err(DomainError::Validation(format!(
+            "El medicamento {} está archivado", medicamento.id
+        )));
+    }
+    let peso_kg = ultimo_peso(historias, mascota.id)?.ok_or_else(|| DomainError::Validation(format!(
+        "La mascota {} no tiene peso registrado; indique peso_kg en una entrada de su historia clínica",
+        mascota.id
+    )))?;
+
+    let alergias = alertas_clinicas::alergias_a(&mascota, &medicamento);
+    if !alergias.is_empty() && datos.motivo_excepcion.is_none() {
+        let sustancias: Vec<String> = alergias.iter()
+            .map(|a| format!("{} ({})", a.sustancia, a.severidad.codigo()))
+            .collect();
+        return Err(DomainError::Conflict(format!(
+            "La mascota {} es alérgica a {}; indique motivo_excepcion para prescribir {} de todos modos",
+            mascota.id, sustancias.join(", "), medicamento.principio_activo
+        )));
+    }
+
+    prescripciones.prescribir(
+        mascota.id,
+        &mascota.especie,
+        id_entrada,
+        &medicamento,
+        peso_kg,
+        alergias,
+        datos,
+        autor,
+    )
+}
+
+pub fn prescripciones_entrada<H, P>(
+    historias: &HistoriaClinicaService<H>,
+    prescripciones: &PrescripcionService<P>,
+    id_historia: Uuid,
+    id_entrada: Uuid,
+) -> Result<Vec<Prescripcion>, DomainError>
+where
+    H: HistoriaClinicaRepository + ?Sized,
+    P: PrescripcionRepository + ?Sized,
+{
+    historias.obtener_entrada(id_historia, id_entrada)?;
+    prescripciones.prescripciones(&FiltroPrescripciones { id_entrada: Some(id_entrada), ..Default::default() })
+}
+
+pub fn eliminar_prescripcion<H, P>(
+    historias: &HistoriaClinicaService<H>,
+    prescripciones: &mut PrescripcionService<P>,
+    id_historia: Uuid,
+    id_entrada: Uuid,
+    id: Uuid,
+    esperada: &VersionEsperada,
+) -> Result<(), DomainError>
+where
+    H: HistoriaClinicaRepository + ?Sized,
+    P: PrescripcionRepository + ?Sized,
+{
+    entrada_en_borrador(historias, id_historia, id_entrada)?;
+    prescripciones.eliminar_prescripcion(id_entrada, id, esperada)
+}
+
+#[derive(Debug, Serialize)]
+pub struct ItemReceta {
+    pub prescripcion: Prescripcion,
+    pub fecha_consulta: DateTime<Utc>,
+    pub veterinario: Option<Personal>,
+}
+
+// Receta imprimible: las prescripciones de la mascota con los datos de la
+// clínica, el dueño y quien atendió cada consulta
+#[derive(Debug, Serialize)]
+pub struct Receta {
+    pub clinica: Clinica,
+    pub cliente: Cliente,
+    pub mascota: Mascota,
+    pub items: Vec<ItemReceta>,
+    pub fecha_emision: DateTime<Utc>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn receta<L, S, C, M, H, P>(
+    clinicas: &ClinicaService<L>,
+    personal: &PersonalService<S>,
+    clientes: &ClienteService<C>,
+    mascotas: &MascotaService<M>,
+    historias: &HistoriaClinicaService<H>,
+    prescripciones: &PrescripcionService<P>,
+    id_mascota: Uuid,
+    filtro: FiltroPrescripciones,
+) -> Result<Receta, DomainError>
+where
+    L: ClinicaRepository + ?Sized,
+    S: PersonalRepository + ?Sized,
+    C: ClienteRepository + ?Sized,
+    M: MascotaRepository + ?Sized,
+    H: HistoriaClinicaRepository + ?Sized,
+    P: PrescripcionRepository + ?Sized,
+{
+    let mascota = mascotas.obtener_mascota(id_mascota)?
+        .ok_or_else(|| DomainError::NotFound(format!("La mascota {} no existe", id_mascota)))?;
+    let cliente = clientes.obtener_cliente(mascota.id_cliente)?
+        .ok_or_else(|| DomainError::NotFound(format!("El cliente {} no existe", mascota.id_cliente)))?;
+    let clinica = clinicas.obtener_clinica(cliente.id_clinica)?
+        .ok_or_else(|| DomainError::NotFound(format!("La clínica {} no existe", cliente.id_clinica)))?;
+
+    let filtro = FiltroPrescripciones { id_mascota: Some(id_mascota), ..filtro };
+    let mut items = Vec::new();
+    for prescripcion in prescripciones.prescripciones(&filtro)? {
+        let Some(entrada) = historias.obtener_entrada_por_id(prescripcion.id_entrada)? else {
+            continue;
+        };
+        let veterinario = match entrada.id_veterinario {
+            Some(id) => personal.obtener_personal(id)?,
+            None => None,
+        };
+        items.push(ItemReceta {
+            fecha_consulta: entrada.fecha,
+            veterinario,
+            prescripcion,
+        });
+    }
+
+    Ok(Receta {
+        clinica,
+        cliente,
+        mascota,
+        items,
+        fecha_emision: Utc::now(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::mascota::{Alergia, Severidad};
+    use crate::models::medicamento::RangoDosis;
+    use crate::models::personal::RolPersonal;
+    use crate::models::SignosVitales;
+    use crate::repositories::historia_clinica_repository::InMemoryHistoriaClinicaRepository;
+    use crate::repositories::mascota_repository::InMemoryMascotaRepository;
+    use crate::repositories::medicamento_repository::InMemoryMedicamentoRepository;
+    use crate::repositories::prescripcion_repository::InMemoryPrescripcionRepository;
+    use crate::services::medicamento_service::DatosMedicamento;
+
+    // Un perro alérgico a la penicilina con su historia y dos medicamentos
+    struct Consultorio {
+        mascotas: MascotaService<InMemoryMascotaRepository>,
+        historias: HistoriaClinicaService<InMemoryHistoriaClinicaRepository>,
+        medicamentos: MedicamentoService<InMemoryMedicamentoRepository>,
+        prescripciones: PrescripcionService<InMemoryPrescripcionRepository>,
+        id_historia: Uuid,
+        id_meloxicam: Uuid,
+        id_penicilina: Uuid,
+    }
+
+    impl Consultorio {
+        fn new() -> Self {
+            let mut mascotas = MascotaService::new(Box::new(InMemoryMascotaRepository::new()));
+            let mut historias = HistoriaClinicaService::new(Box::new(InMemoryHistoriaClinicaRepository::new()));
+            let mut medicamentos = MedicamentoService::new(Box::new(InMemoryMedicamentoRepository::new()));
+
+            let id_cliente = Uuid::new_v4();
+            let alergia = Alergia {
+                sustancia: "Penicilina".to_string(),
+                severidad: Severidad::Grave,
+                reaccion: None,
+            };
+            let mascota = mascotas.crear_mascota(
+                "Toby".to_string(), "perro".to_string(), "Mestizo".to_string(), None, id_cliente,
+                vec![alergia], Vec::new(), None,
+            ).unwrap();
+            let historia = historias.crear_historia(mascota.id, id_cliente, None).unwrap();
+
+            let mut medicamento = |principio_activo: &str, minima_mg_kg: f64, maxima_mg_kg: f64| {
+                medicamentos.crear_medicamento(DatosMedicamento {
+                    principio_activo: principio_activo.to_string(),
+                    nombre_comercial: None,
+                    presentacion: "Comprimidos".to_string(),
+                    concentracion_mg: 2.0,
+                    unidad: "comprimido".to_string(),
+                    dosis: vec![RangoDosis { especie: "perro".to_string(), minima_mg_kg, maxima_mg_kg }],
+                }, None).unwrap().id
+            };
+            let id_meloxicam = medicamento("Meloxicam", 0.1, 0.2);
+            let id_penicilina = medicamento("Penicilina G", 10.0, 20.0);
+
+            Self {
+                mascotas,
+                historias,
+                medicamentos,
+                prescripciones: PrescripcionService::new(Box::new(InMemoryPrescripcionRepository::new())),
+                id_historia: historia.id,
+                id_meloxicam,
+                id_penicilina,
+            }
+        }
+
+        fn consulta(&mut self, peso_kg: Option<f64>) -> Uuid {
+            self.historias.agregar_entrada(
+                self.id_historia, "Control".to_string(), "Sano".to_string(), "Ninguno".to_string(),
+                None, None, SignosVitales { peso_kg, ..Default::default() }, None,
+            ).unwrap().id
+        }
+
+        fn prescribir(&mut self, id_entrada: Uuid, datos: DatosPrescripcion) -> Result<Prescripcion, DomainError> {
+            prescribir(
+                &self.mascotas, &self.historias, &self.medicamentos, &mut self.prescripciones,
+                self.id_historia, id_entrada, datos, None,
+            )
+        }
+    }
+
+    fn datos(id_medicamento: Uuid, dosis_mg_kg: f64) -> DatosPrescripcion {
+        DatosPrescripcion {
+            id_medicamento,
+            dosis_mg_kg,
+            frecuencia_horas: 24,
+            duracion_dias: 5,
+            indicaciones: None,
+            motivo_excepcion: None,
+        }
+    }
+
+    #[test]
+    fn calcula_la_dosis_con_el_ultimo_peso() {
+        let mut consultorio = Consultorio::new();
+        consultorio.consulta(Some(8.0));
+        let id_entrada = consultorio.consulta(Some(10.0));
+
+        let prescripcion = consultorio.prescribir(id_entrada, datos(consultorio.id_meloxicam, 0.1)).unwrap();
+        assert_eq!(prescripcion.peso_kg, 10.0);
+        assert_eq!(prescripcion.dosis_mg, 1.0);
+        assert_eq!(prescripcion.cantidad, 0.5);
+        assert!(prescripcion.advertencia.is_none());
+    }
+
+    #[test]
+    fn acepta_una_dosis_fuera_de_rango_con_advertencia() {
+        let mut consultorio = Consultorio::new();
+        let id_entrada = consultorio.consulta(Some(10.0));
+
+        let prescripcion = consultorio.prescribir(id_entrada, datos(consultorio.id_meloxicam, 0.5)).unwrap();
+        assert_eq!(prescripcion.dosis_mg, 5.0);
+        assert!(prescripcion.advertencia.unwrap().contains("fuera del rango"));
+    }
+
+    #[test]
+    fn sin_peso_registrado_no_prescribe() {
+        let mut consultorio = Consultorio::new();
+        let id_entrada = consultorio.consulta(None);
+
+        let error = consultorio.prescribir(id_entrada, datos(consultorio.id_meloxicam, 0.1)).unwrap_err();
+        assert!(matches!(error, DomainError::Validation(_)));
+    }
+
+    #[test]
+    fn no_prescribe_en_una_entrada_firmada() {
+        let mut consultorio = Consultorio::new();
+        let id_entrada = consultorio.consulta(Some(10.0));
+        let firmante = Personal::new(
+            "Ana".to_string(), "Gómez".to_string(), RolPersonal::Veterinario, Some("MP 1234".to_string()),
+            Vec::new(), Vec::new(), "ana@clinica.com".to_string(), "+5491122334455".to_string(), None,
+        );
+        consultorio.historias
+            .firmar_entrada(consultorio.id_historia, id_entrada, &VersionEsperada::Cualquiera, &firmante, None)
+            .unwrap();
+
+        let error = consultorio.prescribir(id_entrada, datos(consultorio.id_meloxicam, 0.1)).unwrap_err();
+        assert!(matches!(error, DomainError::Conflict(_)));
+    }
+
+    #[test]
+    fn una_alergia_exige_motivo_de_excepcion() {
+        let mut consultorio = Consultorio::new();
+        let id_entrada = consultorio.consulta(Some(10.0));
+
+        let error = consultorio.prescribir(id_entrada, datos(consultorio.id_penicilina, 15.0)).unwrap_err();
+        assert!(matches!(error, DomainError::Conflict(_)));
+
+        let con_motivo = DatosPrescripcion {
+            motivo_excepcion: Some("Sin alternativa disponible".to_string()),
+            ..datos(consultorio.id_penicilina, 15.0)
+        };
+        let prescripcion = consultorio.prescribir(id_entrada, con_motivo).unwrap();
+        assert_eq!(prescripcion.alergias.len(), 1);
+        assert_eq!(prescripcion.motivo_excepcion.as_deref(), Some("Sin alternativa disponible"));
+    }
+}
@@ -0,0 +1,177 @@
+use crate::models::SignosVitales;
+use crate::models::signos_vitales::{AlertaSignoVital, RangosReferencia};
+use crate::repositories::mascota_repository::MascotaRepository;
+use crate::repositories::historia_clinica_repository::HistoriaClinicaRepository;
+use crate::services::{MascotaService, HistoriaClinicaService};
+use crate::error::DomainError;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+// Por debajo de esta variación (2 % del promedio en todo el período) la serie
+// se considera estable
+const UMBRAL_TENDENCIA: f64 = 0.02;
+
+// Signos de una consulta, con los que quedaron fuera del rango de la especie
+#[derive(Debug, Serialize)]
+pub struct MedicionSignosVitales {
+    pub id_entrada: Uuid,
+    pub fecha: DateTime<Utc>,
+    #[serde(flatten)]
+    pub signos_vitales: SignosVitales,
+    pub alertas: Vec<AlertaSignoVital>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Tendencia {
+    Ascendente,
+    Descendente,
+    Estable,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EstadisticaSignoVital {
+    pub mediciones: usize,
+    pub ultimo: f64,
+    pub minimo: f64,
+    pub maximo: f64,
+    pub promedio: f64,
+    // Valor de la consulta anterior que lo midió y variación porcentual
+    // respecto de ella; faltan si hay una sola medición
+    pub anterior: Option<f64>,
+    pub variacion_porcentual: Option<f64>,
+    // Según la recta de mínimos cuadrados sobre el tiempo
+    pub tendencia: Option<Tendencia>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EstadisticasSignosVitales {
+    pub peso_kg: Option<EstadisticaSignoVital>,
+    pub temperatura_c: Option<EstadisticaSignoVital>,
+    pub frecuencia_cardiaca: Option<EstadisticaSignoVital>,
+    pub frecuencia_respiratoria: Option<EstadisticaSignoVital>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SerieSignosVitales {
+    pub id_mascota: Uuid,
+    pub especie: String,
+    // Falta si la especie no tiene valores de referencia
+    pub rangos_referencia: Option<RangosReferencia>,
+    pub mediciones: Vec<MedicionSignosVitales>,
+    pub estadisticas: EstadisticasSignosVitales,
+}
+
+fn redondear(valor: f64) -> f64 {
+    (valor * 100.0).round() / 100.0
+}
+
+fn tendencia(puntos: &[(DateTime<Utc>, f64)], promedio: f64) -> Tendencia {
+    let (primero, _) = puntos[0];
+    let dias: Vec<f64> = puntos.iter()
+        .map(|(fecha, _)| (*fecha - primero).num_seconds() as f64 / 86_400.0)
+        .collect();
+    let dias_promedio = dias.iter().sum::<f64>() / dias.len() as f64;
+    let covarianza: f64 = dias.iter().zip(puntos)
+        .map(|(d, (_, v))| (d - dias_promedio) * (v - promedio))
+        .sum();
+    let varianza: f64 = dias.iter().map(|d| (d - dias_promedio).powi(2)).sum();
+
+    // Todas el mismo día: se compara la última con la primera
+    let cambio = if varianza > 0.0 {
+        covarianza / varianza * (dias[dias.len() - 1] - dias[0])
+    } else {
+        puntos[puntos.len() - 1].1 - puntos[0].1
+    };
+
+    if promedio == 0.0 || (cambio / promedio).abs() < UMBRAL_TENDENCIA {
+        Tendencia::Estable
+    } else if cambio > 0.0 {
+        Tendencia::Ascendente
+    } else {
+        Tendencia::Descendente
+    }
+}
+
+// Puntos en orden cronológico
+fn estadistica(puntos: Vec<(DateTime<Utc>, f64)>) -> Option<EstadisticaSignoVital> {
+    let (_, ultimo) = *puntos.last()?;
+    let valores = puntos.iter().map(|(_, v)| *v);
+    let promedio = valores.clone().sum::<f64>() / puntos.len() as f64;
+    let anterior = puntos.len().checked_sub(2).map(|i| puntos[i].1);
+
+    Some(EstadisticaSignoVital {
+        mediciones: puntos.len(),
+        ultimo,
+        minimo: valores.clone().fold(f64::INFINITY, f64::min),
+        maximo: valores.fold(f64::NEG_INFINITY, f64::max),
+        promedio: redondear(promedio),
+        anterior,
+        variacion_porcentual: anterior
+            .filter(|a| *a != 0.0)
+            .map(|a| redondear((ultimo - a) / a * 100.0)),
+        tendencia: (puntos.len() > 1).then(|| tendencia(&puntos, promedio)),
+    })
+}
+
+fn serie_de<F>(mediciones: &[MedicionSignosVitales], valor: F) -> Option<EstadisticaSignoVital>
+where
+    F: Fn(&SignosVitales) -> Option<f64>,
+{
+    estadistica(mediciones.iter()
+        .filter_map(|m| valor(&m.signos_vitales).map(|v| (m.fecha, v)))
+        .collect())
+}
+
+// Signos vitales de las consultas de la mascota en el período, de la más
+// antigua a la más reciente. Cuenta la revisión vigente de cada entrada, de
+// todas sus historias clínicas.
+pub fn serie<M, H>(
+    mascotas: &MascotaService<M>,
+    historias: &HistoriaClinicaService<H>,
+    id_mascota: Uuid,
+    desde: Option<DateTime<Utc>>,
+    hasta: Option<DateTime<Utc>>,
+) -> Result<SerieSignosVitales, DomainError>
+where
+    M: MascotaRepository + ?Sized,
+    H: HistoriaClinicaRepository + ?Sized,
+{
+    let mascota = mascotas.obtener_mascota(id_mascota)?
+        .ok_or_else(|| DomainError::NotFound(format!("La mascota {} no existe", id_mascota)))?;
+    let rangos = RangosReferencia::para_especie(&mascota.especie);
+
+    let mut entradas = Vec::new();
+    for id_historia in historias.ids_historias_mascota(id_mascota)? {
+        entradas.extend(historias.entradas_vigentes(id_historia)?
+            .into_iter()
+            .filter(|e| !e.signos_vitales.vacios())
+            .filter(|e| desde.is_none_or(|d| e.fecha >= d) && hasta.is_none_or(|h| e.fecha <= h)));
+    }
+    entradas.sort_by_key(|e| (e.fecha, e.fecha_creacion));
+
+    let mediciones: Vec<MedicionSignosVitales> = entradas.into_iter()
+        .map(|e| MedicionSignosVitales {
+            id_entrada: e.id,
+            fecha: e.fecha,
+            alertas: rangos.map(|r| r.evaluar(&e.signos_vitales)).unwrap_or_default(),
+            signos_vitales: e.signos_vitales,
+        })
+        .collect();
+
+    let estadisticas = EstadisticasSignosVitales {
+        peso_kg: serie_de(&mediciones, |s| s.peso_kg),
+        temperatura_c: serie_de(&mediciones, |s| s.temperatura_c),
+        frecuencia_cardiaca: serie_de(&mediciones, |s| s.frecuencia_cardiaca.map(f64::from)),
+        frecuencia_respiratoria: serie_de(&mediciones, |s| s.frecuencia_respiratoria.map(f64::from)),
+    };
+
+    Ok(SerieSignosVitales {
+        id_mascota,
+        especie: mascota.especie,
+        rangos_referencia: rangos,
+        mediciones,
+        estadisticas,
+    })
+}
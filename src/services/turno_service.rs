@@ -0,0 +1,308 @@
+use crate::models::{Auditado, Turno};
+use crate::models::turno::EstadoTurno;
+use crate::repositories::turno_repository::{ConsultaTurnos, FiltroTurnos, TurnoRepository};
+use crate::repositories::consulta::Pagina;
+use uuid::Uuid;
+use crate::error::{DomainError, FieldError};
+use crate::services::concurrencia::VersionEsperada;
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use serde::Serialize;
+
+// Duración máxima de un turno; más larga suele ser un error de carga
+const DURACION_MAXIMA_HORAS: i64 = 12;
+
+fn validar_horario(inicio: DateTime<Utc>, fin: DateTime<Utc>) -> Result<(), DomainError> {
+    let mensaje = if fin <= inicio {
+        "debe ser posterior al inicio"
+    } else if fin - inicio > Duration::hours(DURACION_MAXIMA_HORAS) {
+        "el turno no puede durar más de 12 horas"
+    } else {
+        return Ok(());
+    };
+    Err(DomainError::InvalidFields(vec![FieldError {
+        campo: "fin".to_string(),
+        regla: "range".to_string(),
+        mensaje: mensaje.to_string(),
+    }]))
+}
+
+// Turnos de una clínica en un día (UTC), por hora de inicio
+#[derive(Debug, Serialize)]
+pub struct Agenda {
+    pub id_clinica: Uuid,
+    pub fecha: NaiveDate,
+    pub turnos: Vec<Turno>,
+}
+
+pub struct TurnoService<T: TurnoRepository + ?Sized> {
+    repository: Box<T>,
+}
+
+impl<T: TurnoRepository + ?Sized> TurnoService<T> {
+    pub fn new(repository: Box<T>) -> Self {
+        Self { repository }
+    }
+
+    // Un veterinario no puede tener dos turnos activos superpuestos
+    fn validar_disponibilidad(
+        &self,
+        id_veterinario: Uuid,
+        inicio: DateTime<Utc>,
+        fin: DateTime<Utc>,
+        excluir: Option<Uuid>,
+    ) -> Result<(), DomainError> {
+        let filtro = FiltroTurnos {
+            id_veterinario: Some(id_veterinario),
+            desde: Some(inicio),
+            hasta: Some(fin),
+            solo_activos: true,
+            ..Default::default()
+        };
+        let superpuesto = self.repository.listar_todos(&filtro)?
+            .into_iter()
+            .find(|t| Some(t.id) != excluir && t.se_superpone(inicio, fin));
+
+        match superpuesto {
+            Some(turno) => Err(DomainError::Conflict(format!(
+                "El veterinario {} ya tiene el turno {} de {} a {}",
+                id_veterinario, turno.id, turno.inicio.to_rfc3339(), turno.fin.to_rfc3339()
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn crear_turno(
+        &mut self,
+        id_clinica: Uuid,
+        id_mascota: Uuid,
+        id_cliente: Uuid,
+        id_veterinario: Uuid,
+        inicio: DateTime<Utc>,
+        fin: DateTime<Utc>,
+        motivo: String,
+        autor: Option<&str>,
+    ) -> Result<Turno, DomainError> {
+        validar_horario(inicio, fin)?;
+        self.validar_disponibilidad(id_veterinario, inicio, fin, None)?;
+
+        let turno = Turno::new(id_clinica, id_mascota, id_cliente, id_veterinario, inicio, fin, motivo, autor);
+        self.repository.guardar(turno.clone())?;
+        Ok(turno)
+    }
+
+    pub fn obtener_turno(&self, id: Uuid) -> Result<Option<Turno>, DomainError> {
+        self.repository.obtener(id)
+    }
+
+    pub fn listar_turnos(&self, consulta: &ConsultaTurnos) -> Result<Pagina<Turno>, DomainError> {
+        self.repository.listar(consulta)
+    }
+
+    pub fn agenda(
+        &self,
+        id_clinica: Uuid,
+        fecha: NaiveDate,
+        id_veterinario: Option<Uuid>,
+        incluir_cancelados: bool,
+    ) -> Result<Agenda, DomainError> {
+        let inicio = fecha.and_time(NaiveTime::MIN).and_utc();
+        let filtro = FiltroTurnos {
+            id_clinica: Some(id_clinica),
+            id_veterinario,
+            desde: Some(inicio),
+            hasta: Some(inicio + Duration::days(1)),
+            solo_activos: !incluir_cancelados,
+            ..Default::default()
+        };
+        // `hasta` es inclusivo: se descartan los que empiezan a medianoche del día siguiente
+        let turnos = self.repository.listar_todos(&filtro)?
+            .into_iter()
+            .filter(|t| t.se_superpone(inicio, inicio + Duration::days(1)))
+            .collect();
+
+        Ok(Agenda { id_clinica, fecha, turnos })
+    }
+
+    pub fn ids_turnos(&self, filtro: &FiltroTurnos) -> Result<Vec<Uuid>, DomainError> {
+        Ok(self.repository.listar_todos(filtro)?
+            .into_iter()
+            .map(|t| t.id)
+            .collect())
+    }
+
+    // Turno actual, si está en la versión esperada y admite pasar a `nuevo`
+    pub fn comprobar_transicion(
+        &self,
+        id: Uuid,
+        nuevo: EstadoTurno,
+        esperada: &VersionEsperada,
+    ) -> Result<Turno, DomainError> {
+        let turno = self.repository.obtener(id)?
+            .ok_or_else(|| DomainError::NotFound(format!("El turno {} no existe", id)))?;
+        esperada.comprobar(&format!("El turno {}", id), turno.version)?;
+
+        if !turno.estado.admite(nuevo) {
+            return Err(DomainError::Conflict(format!(
+                "El turno {} está {} y no puede pasar a {}",
+                id, turno.estado.codigo(), nuevo.codigo()
+            )));
+        }
+        Ok(turno)
+    }
+
+    // Solo se reprograman los turnos reservados o confirmados; vuelven a
+    // quedar reservados hasta que se confirmen de nuevo
+    pub fn reprogramar_turno(
+        &mut self,
+        id: Uuid,
+        inicio: DateTime<Utc>,
+        fin: DateTime<Utc>,
+        id_veterinario: Option<Uuid>,
+        esperada: &VersionEsperada,
+        autor: Option<&str>,
+    ) -> Result<Turno, DomainError> {
+        let mut turno = self.repository.obtener(id)?
+            .ok_or_else(|| DomainError::NotFound(format!("El turno {} no existe", id)))?;
+        esperada.comprobar(&format!("El turno {}", id), turno.version)?;
+
+        if !matches!(turno.estado, EstadoTurno::Reservado | EstadoTurno::Confirmado) {
+            return Err(DomainError::Conflict(format!(
+                "El turno {} está {} y ya no puede reprogramarse", id, turno.estado.codigo()
+            )));
+        }
+        validar_horario(inicio, fin)?;
+        let id_veterinario = id_veterinario.unwrap_or(turno.id_veterinario);
+        self.validar_disponibilidad(id_veterinario, inicio, fin, Some(id))?;
+
+        turno.inicio = inicio;
+        turno.fin = fin;
+        turno.id_veterinario = id_veterinario;
+        turno.estado = EstadoTurno::Reservado;
+        turno.registrar_cambio(autor);
+
+        self.repository.guardar(turno.clone())?;
+        Ok(turno)
+    }
+
+    pub fn cambiar_estado(
+        &mut self,
+        id: Uuid,
+        nuevo: EstadoTurno,
+        esperada: &VersionEsperada,
+        autor: Option<&str>,
+    ) -> Result<Turno, DomainError> {
+        let mut turno = self.comprobar_transicion(id, nuevo, esperada)?;
+
+        turno.estado = nuevo;
+        turno.registrar_cambio(autor);
+        self.repository.guardar(turno.clone())?;
+        Ok(turno)
+    }
+
+    pub fn cancelar_turno(
+        &mut self,
+        id: Uuid,
+        motivo: Option<String>,
+        esperada: &VersionEsperada,
+        autor: Option<&str>,
+    ) -> Result<Turno, DomainError> {
+        let mut turno = self.comprobar_transicion(id, EstadoTurno::Cancelado, esperada)?;
+
+        turno.estado = EstadoTurno::Cancelado;
+        turno.motivo_cancelacion = motivo;
+        turno.registrar_cambio(autor);
+        self.repository.guardar(turno.clone())?;
+        Ok(turno)
+    }
+
+    // `id_entrada` es la entrada de historia clínica abierta para la atención, si se abrió
+    pub fn completar_turno(
+        &mut self,
+        id: Uuid,
+        id_entrada: Option<Uuid>,
+        esperada: &VersionEsperada,
+        autor: Option<&str>,
+    ) -> Result<Turno, DomainError> {
+        let mut turno = self.comprobar_transicion(id, EstadoTurno::Completado, esperada)?;
+
+        turno.estado = EstadoTurno::Completado;
+        turno.id_entrada = id_entrada;
+        turno.registrar_cambio(autor);
+        self.repository.guardar(turno.clone())?;
+        Ok(turno)
+    }
+
+    pub fn eliminar_turno(&mut self, id: Uuid) -> Result<(), DomainError> {
+        self.repository.eliminar(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::turno_repository::InMemoryTurnoRepository;
+    use chrono::TimeZone;
+
+    fn hora(h: u32, m: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 5, 12, h, m, 0).unwrap()
+    }
+
+    fn servicio() -> TurnoService<InMemoryTurnoRepository> {
+        TurnoService::new(Box::new(InMemoryTurnoRepository::new()))
+    }
+
+    fn reservar(
+        servicio: &mut TurnoService<InMemoryTurnoRepository>,
+        id_veterinario: Uuid,
+        inicio: DateTime<Utc>,
+        fin: DateTime<Utc>,
+    ) -> Result<Turno, DomainError> {
+        servicio.crear_turno(
+            Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4(), id_veterinario,
+            inicio, fin, "Control".to_string(), None,
+        )
+    }
+
+    #[test]
+    fn rechaza_un_turno_superpuesto_del_mismo_veterinario() {
+        let mut servicio = servicio();
+        let id_veterinario = Uuid::new_v4();
+        reservar(&mut servicio, id_veterinario, hora(10, 0), hora(10, 30)).unwrap();
+
+        assert!(matches!(
+            reservar(&mut servicio, id_veterinario, hora(10, 15), hora(10, 45)),
+            Err(DomainError::Conflict(_))
+        ));
+        assert!(reservar(&mut servicio, id_veterinario, hora(10, 30), hora(11, 0)).is_ok());
+        assert!(reservar(&mut servicio, Uuid::new_v4(), hora(10, 15), hora(10, 45)).is_ok());
+    }
+
+    #[test]
+    fn los_turnos_cancelados_liberan_el_horario() {
+        let mut servicio = servicio();
+        let id_veterinario = Uuid::new_v4();
+        let turno = reservar(&mut servicio, id_veterinario, hora(10, 0), hora(10, 30)).unwrap();
+        servicio.cancelar_turno(turno.id, None, &VersionEsperada::Cualquiera, None).unwrap();
+
+        assert!(reservar(&mut servicio, id_veterinario, hora(10, 0), hora(10, 30)).is_ok());
+    }
+
+    #[test]
+    fn reprogramar_no_choca_con_el_mismo_turno() {
+        let mut servicio = servicio();
+        let id_veterinario = Uuid::new_v4();
+        let turno = reservar(&mut servicio, id_veterinario, hora(10, 0), hora(10, 30)).unwrap();
+        reservar(&mut servicio, id_veterinario, hora(11, 0), hora(11, 30)).unwrap();
+
+        assert!(servicio.reprogramar_turno(
+            turno.id, hora(10, 15), hora(10, 45), None, &VersionEsperada::Cualquiera, None,
+        ).is_ok());
+        assert!(matches!(
+            servicio.reprogramar_turno(
+                turno.id, hora(10, 45), hora(11, 15), None, &VersionEsperada::Cualquiera, None,
+            ),
+            Err(DomainError::Conflict(_))
+        ));
+    }
+}
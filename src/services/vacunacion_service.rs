@@ -0,0 +1,142 @@
+use crate::models::{Auditado, Vacunacion};
+use crate::repositories::vacunacion_repository::{ConsultaVacunaciones, VacunacionRepository};
+use crate::repositories::consulta::Pagina;
+use uuid::Uuid;
+use crate::error::{DomainError, FieldError};
+use crate::services::concurrencia::VersionEsperada;
+use chrono::NaiveDate;
+
+// Datos de una aplicación, para el alta y el reemplazo
+#[derive(Debug)]
+pub struct DatosVacunacion {
+    pub vacuna: String,
+    pub lote: String,
+    pub fabricante: String,
+    pub dosis: String,
+    pub fecha_aplicacion: NaiveDate,
+    pub proxima_dosis: Option<NaiveDate>,
+    pub id_veterinario: Option<Uuid>,
+}
+
+impl DatosVacunacion {
+    fn validar(&self) -> Result<(), DomainError> {
+        if self.proxima_dosis.is_some_and(|proxima| proxima <= self.fecha_aplicacion) {
+            return Err(DomainError::InvalidFields(vec![FieldError {
+                campo: "proxima_dosis".to_string(),
+                regla: "range".to_string(),
+                mensaje: "debe ser posterior a la fecha de aplicación".to_string(),
+            }]));
+        }
+        Ok(())
+    }
+}
+
+pub struct VacunacionService<T: VacunacionRepository + ?Sized> {
+    repository: Box<T>,
+}
+
+impl<T: VacunacionRepository + ?Sized> VacunacionService<T> {
+    pub fn new(repository: Box<T>) -> Self {
+        Self { repository }
+    }
+
+    fn obtener_de_mascota(&self, id_mascota: Uuid, id: Uuid) -> Result<Vacunacion, DomainError> {
+        self.repository.obtener(id)?
+            .filter(|v| v.id_mascota == id_mascota)
+            .ok_or_else(|| DomainError::NotFound(format!(
+                "La vacunación {} no existe para la mascota {}", id, id_mascota
+            )))
+    }
+
+    pub fn registrar_vacunacion(
+        &mut self,
+        id_mascota: Uuid,
+        datos: DatosVacunacion,
+        autor: Option<&str>,
+    ) -> Result<Vacunacion, DomainError> {
+        datos.validar()?;
+
+        let vacunacion = Vacunacion::new(
+            id_mascota,
+            datos.vacuna,
+            datos.lote,
+            datos.fabricante,
+            datos.dosis,
+            datos.fecha_aplicacion,
+            datos.proxima_dosis,
+            datos.id_veterinario,
+            autor,
+        );
+        self.repository.guardar(vacunacion.clone())?;
+        Ok(vacunacion)
+    }
+
+    pub fn obtener_vacunacion(&self, id_mascota: Uuid, id: Uuid) -> Result<Vacunacion, DomainError> {
+        self.obtener_de_mascota(id_mascota, id)
+    }
+
+    pub fn listar_vacunaciones(&self, consulta: &ConsultaVacunaciones) -> Result<Pagina<Vacunacion>, DomainError> {
+        self.repository.listar(consulta)
+    }
+
+    // Última aplicación de cada vacuna por mascota con la próxima dosis hasta `hasta`
+    pub fn vacunaciones_pendientes(&self, hasta: NaiveDate) -> Result<Vec<Vacunacion>, DomainError> {
+        self.repository.pendientes(hasta)
+    }
+
+    pub fn ids_vacunaciones_mascota(&self, id_mascota: Uuid) -> Result<Vec<Uuid>, DomainError> {
+        Ok(self.repository.listar_por_mascota(id_mascota)?
+            .into_iter()
+            .map(|v| v.id)
+            .collect())
+    }
+
+    pub fn contar_vacunaciones_veterinario(&self, id_veterinario: Uuid) -> Result<usize, DomainError> {
+        self.repository.contar_por_veterinario(id_veterinario)
+    }
+
+    pub fn actualizar_vacunacion(
+        &mut self,
+        id_mascota: Uuid,
+        id: Uuid,
+        datos: DatosVacunacion,
+        esperada: &VersionEsperada,
+        autor: Option<&str>,
+    ) -> Result<Vacunacion, DomainError> {
+        let actual = self.obtener_de_mascota(id_mascota, id)?;
+        esperada.comprobar(&format!("La vacunación {}", id), actual.version)?;
+        datos.validar()?;
+
+        let mut vacunacion = Vacunacion {
+            vacuna: datos.vacuna,
+            lote: datos.lote,
+            fabricante: datos.fabricante,
+            dosis: datos.dosis,
+            fecha_aplicacion: datos.fecha_aplicacion,
+            proxima_dosis: datos.proxima_dosis,
+            id_veterinario: datos.id_veterinario,
+            ..actual
+        };
+        vacunacion.registrar_cambio(autor);
+
+        self.repository.guardar(vacunacion.clone())?;
+        Ok(vacunacion)
+    }
+
+    pub fn eliminar_vacunacion(
+        &mut self,
+        id_mascota: Uuid,
+        id: Uuid,
+        esperada: &VersionEsperada,
+    ) -> Result<(), DomainError> {
+        let actual = self.obtener_de_mascota(id_mascota, id)?;
+        esperada.comprobar(&format!("La vacunación {}", id), actual.version)?;
+
+        self.repository.eliminar(id)
+    }
+
+    // Sin comprobaciones: se usa al eliminar la mascota
+    pub fn eliminar_vacunaciones(&mut self, ids: Vec<Uuid>) -> Result<(), DomainError> {
+        ids.into_iter().try_for_each(|id| self.repository.eliminar(id))
+    }
+}
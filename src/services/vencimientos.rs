@@ -0,0 +1,177 @@
+use crate::error::DomainError;
+use crate::models::Vacunacion;
+use crate::repositories::cliente_repository::ClienteRepository;
+use crate::repositories::mascota_repository::MascotaRepository;
+use crate::repositories::vacunacion_repository::VacunacionRepository;
+use crate::services::{ClienteService, MascotaService, VacunacionService};
+use chrono::{Duration, NaiveDate};
+use serde::Serialize;
+use uuid::Uuid;
+
+// Fila del informe de vacunas por vencer, con lo necesario para avisarle al dueño
+#[derive(Debug, Serialize)]
+pub struct VacunaPendiente {
+    pub vacunacion: Vacunacion,
+    pub mascota: String,
+    pub especie: String,
+    pub id_cliente: Uuid,
+    pub cliente: String,
+    pub telefono: String,
+    pub correo: String,
+    // Negativo si la dosis ya venció
+    pub dias_restantes: i64,
+    pub vencida: bool,
+}
+
+// Vacunas de las mascotas activas de la clínica cuya próxima dosis vence en
+// los próximos `dias` días, incluidas las ya vencidas. Solo cuenta la última
+// aplicación de cada vacuna: un refuerzo ya aplicado no vuelve a avisarse.
+pub fn vacunas_pendientes<C, M, V>(
+    clientes: &ClienteService<C>,
+    mascotas: &MascotaService<M>,
+    vacunaciones: &VacunacionService<V>,
+    id_clinica: Uuid,
+    hoy: NaiveDate,
+    dias: u32,
+) -> Result<Vec<VacunaPendiente>, DomainError>
+where
+    C: ClienteRepository + ?Sized,
+    M: MascotaRepository + ?Sized,
+    V: VacunacionRepository + ?Sized,
+{
+    let mut pendientes = Vec::new();
+    for vacunacion in vacunaciones.vacunaciones_pendientes(hoy + Duration::days(dias as i64))? {
+        let Some(mascota) = mascotas.obtener_mascota(vacunacion.id_mascota)?.filter(|m| !m.archivado) else {
+            continue;
+        };
+        let Some(cliente) = clientes.obtener_cliente(mascota.id_cliente)?
+            .filter(|c| !c.archivado && c.id_clinica == id_clinica) else {
+            continue;
+        };
+        let Some(proxima_dosis) = vacunacion.proxima_dosis else {
+            continue;
+        };
+        let dias_restantes = (proxima_dosis - hoy).num_days();
+
+        pendientes.push(VacunaPendiente {
+            vacunacion,
+            mascota: mascota.nombre,
+            especie: mascota.especie,
+            id_cliente: cliente.id,
+            cliente: format!("{} {}", cliente.nombre, cliente.apellido),
+            telefono: cliente.telefono,
+            correo: cliente.correo,
+            dias_restantes,
+            vencida: dias_restantes < 0,
+        });
+    }
+    Ok(pendientes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::cliente_repository::InMemoryClienteRepository;
+    use crate::repositories::mascota_repository::InMemoryMascotaRepository;
+    use crate::repositories::vacunacion_repository::InMemoryVacunacionRepository;
+    use crate::services::concurrencia::VersionEsperada;
+    use crate::services::vacunacion_service::DatosVacunacion;
+
+    struct Servicios {
+        clientes: ClienteService<InMemoryClienteRepository>,
+        mascotas: MascotaService<InMemoryMascotaRepository>,
+        vacunaciones: VacunacionService<InMemoryVacunacionRepository>,
+    }
+
+    fn fecha(anio: i32, mes: u32, dia: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(anio, mes, dia).unwrap()
+    }
+
+    fn hoy() -> NaiveDate {
+        fecha(2025, 6, 1)
+    }
+
+    impl Servicios {
+        fn new() -> Self {
+            Self {
+                clientes: ClienteService::new(Box::new(InMemoryClienteRepository::new())),
+                mascotas: MascotaService::new(Box::new(InMemoryMascotaRepository::new())),
+                vacunaciones: VacunacionService::new(Box::new(InMemoryVacunacionRepository::new())),
+            }
+        }
+
+        // Mascota de un cliente de la clínica
+        fn mascota(&mut self, id_clinica: Uuid) -> Uuid {
+            let cliente = self.clientes.crear_cliente(
+                "Ana".to_string(), "Gomez".to_string(), "ana@example.com".to_string(),
+                "555-0000".to_string(), "Calle 1".to_string(), id_clinica, None,
+            ).unwrap();
+            self.mascotas.crear_mascota(
+                "Firulais".to_string(), "perro".to_string(), "mestizo".to_string(), None,
+                cliente.id, Vec::new(), Vec::new(), None,
+            ).unwrap().id
+        }
+
+        fn vacunar(&mut self, id_mascota: Uuid, vacuna: &str, aplicada: NaiveDate, proxima: NaiveDate) {
+            self.vacunaciones.registrar_vacunacion(id_mascota, DatosVacunacion {
+                vacuna: vacuna.to_string(),
+                lote: "L-1".to_string(),
+                fabricante: "Vetlab".to_string(),
+                dosis: "1 ml".to_string(),
+                fecha_aplicacion: aplicada,
+                proxima_dosis: Some(proxima),
+                id_veterinario: None,
+            }, None).unwrap();
+        }
+
+        // Vacuna, días restantes y si está vencida, en el orden del informe
+        fn pendientes(&self, id_clinica: Uuid, dias: u32) -> Vec<(String, i64, bool)> {
+            vacunas_pendientes(&self.clientes, &self.mascotas, &self.vacunaciones, id_clinica, hoy(), dias)
+                .unwrap()
+                .into_iter()
+                .map(|p| (p.vacunacion.vacuna, p.dias_restantes, p.vencida))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn calcula_los_dias_restantes_dentro_de_la_ventana() {
+        let mut servicios = Servicios::new();
+        let id_clinica = Uuid::new_v4();
+        let id_mascota = servicios.mascota(id_clinica);
+        servicios.vacunar(id_mascota, "Rabia", fecha(2024, 5, 25), fecha(2025, 5, 25));
+        servicios.vacunar(id_mascota, "Quíntuple", fecha(2024, 6, 11), fecha(2025, 6, 11));
+        servicios.vacunar(id_mascota, "Leptospirosis", fecha(2024, 7, 1), fecha(2025, 7, 1));
+        servicios.vacunar(id_mascota, "Tos de las perreras", fecha(2024, 7, 2), fecha(2025, 7, 2));
+
+        assert_eq!(servicios.pendientes(id_clinica, 30), [
+            ("Rabia".to_string(), -7, true),
+            ("Quíntuple".to_string(), 10, false),
+            ("Leptospirosis".to_string(), 30, false),
+        ]);
+    }
+
+    #[test]
+    fn un_refuerzo_aplicado_deja_de_avisarse() {
+        let mut servicios = Servicios::new();
+        let id_clinica = Uuid::new_v4();
+        let id_mascota = servicios.mascota(id_clinica);
+        servicios.vacunar(id_mascota, "Rabia", fecha(2024, 5, 25), fecha(2025, 5, 25));
+        servicios.vacunar(id_mascota, "rabia", fecha(2025, 5, 28), fecha(2026, 5, 28));
+
+        assert!(servicios.pendientes(id_clinica, 30).is_empty());
+    }
+
+    #[test]
+    fn solo_incluye_mascotas_activas_de_la_clinica() {
+        let mut servicios = Servicios::new();
+        let id_clinica = Uuid::new_v4();
+        let archivada = servicios.mascota(id_clinica);
+        let de_otra_clinica = servicios.mascota(Uuid::new_v4());
+        servicios.vacunar(archivada, "Rabia", fecha(2024, 6, 5), fecha(2025, 6, 5));
+        servicios.vacunar(de_otra_clinica, "Rabia", fecha(2024, 6, 5), fecha(2025, 6, 5));
+        servicios.mascotas.archivar_mascota(archivada, &VersionEsperada::Cualquiera, None).unwrap();
+
+        assert!(servicios.pendientes(id_clinica, 30).is_empty());
+    }
+}